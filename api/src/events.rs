@@ -0,0 +1,124 @@
+//! Forwarding of server events to plugin handlers.
+
+use feather_server_types::*;
+use fecs::{EventHandlers, World};
+
+/// Defines an event handler forwarding each of the
+/// given server events to plugin handlers.
+macro_rules! plugin_events {
+    ($($event:ident => $handler:ident,)*) => {
+        $(
+            #[fecs::event_handler]
+            pub fn $handler(
+                event: &$event,
+                game: &mut Game,
+                world: &mut World,
+                #[default] events: &PluginEvents,
+            ) {
+                events.trigger(event, game, world);
+            }
+        )*
+
+        /// Adds the handlers forwarding server events to plugins.
+        pub fn add_event_handlers(handlers: EventHandlers) -> EventHandlers {
+            handlers
+                $(.with($handler))*
+        }
+    }
+}
+
+plugin_events! {
+    BlockUpdateEvent => on_block_update_call_plugins,
+    EntitySpawnEvent => on_entity_spawn_call_plugins,
+    EntityDespawnEvent => on_entity_despawn_call_plugins,
+    EntityDeathEvent => on_entity_death_call_plugins,
+    PlayerPreJoinEvent => on_player_pre_join_call_plugins,
+    PlayerJoinEvent => on_player_join_call_plugins,
+    PlayerLeaveEvent => on_player_leave_call_plugins,
+    EntityLandEvent => on_entity_land_call_plugins,
+    ItemDropEvent => on_item_drop_call_plugins,
+    ItemCollectEvent => on_item_collect_call_plugins,
+    InventoryUpdateEvent => on_inventory_update_call_plugins,
+    ItemDamageEvent => on_item_damage_call_plugins,
+    WindowOpenEvent => on_window_open_call_plugins,
+    WindowCloseEvent => on_window_close_call_plugins,
+    HealthUpdateEvent => on_health_update_call_plugins,
+    PlayerAnimationEvent => on_player_animation_call_plugins,
+    ChatEvent => on_chat_call_plugins,
+    ChunkCrossEvent => on_chunk_cross_call_plugins,
+    ChunkLoadEvent => on_chunk_load_call_plugins,
+    ChunkUnloadEvent => on_chunk_unload_call_plugins,
+    WeatherChangeEvent => on_weather_change_call_plugins,
+    GamemodeUpdateEvent => on_gamemode_update_call_plugins,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_test_framework::Test;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    struct TestPreEvent {
+        value: u32,
+        cancelled: bool,
+    }
+
+    impl Cancellable for TestPreEvent {
+        fn is_cancelled(&self) -> bool {
+            self.cancelled
+        }
+
+        fn set_cancelled(&mut self, cancelled: bool) {
+            self.cancelled = cancelled;
+        }
+    }
+
+    impl PluginEvent for TestPreEvent {}
+
+    #[test]
+    fn trigger() {
+        let calls = Arc::new(AtomicU32::new(0));
+
+        let mut events = PluginEvents::default();
+        let calls2 = Arc::clone(&calls);
+        events.subscribe(move |event: &WeatherChangeEvent, _game, _world| {
+            assert_eq!(event.duration, 100);
+            calls2.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let mut test = Test::new().with_resource(events);
+        test.handle(
+            WeatherChangeEvent {
+                from: Weather::Clear,
+                to: Weather::Rain,
+                duration: 100,
+            },
+            on_weather_change_call_plugins,
+        );
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn trigger_pre() {
+        let mut events = PluginEvents::default();
+        events.subscribe_pre(|event: &mut TestPreEvent, _game, _world| {
+            event.value += 1;
+            if event.value > 1 {
+                event.set_cancelled(true);
+            }
+        });
+
+        let mut test = Test::new();
+        let mut event = TestPreEvent {
+            value: 0,
+            cancelled: false,
+        };
+        assert!(events.trigger_pre(&mut event, &mut test.game, &mut test.world));
+        assert_eq!(event.value, 1);
+
+        assert!(!events.trigger_pre(&mut event, &mut test.game, &mut test.world));
+        assert_eq!(event.value, 2);
+    }
+}
@@ -1 +1,75 @@
+#![forbid(unsafe_code)]
 
+//! Feather's plugin API.
+//!
+//! Plugins implement the [`Plugin`](trait.Plugin.html) trait and
+//! register themselves with the [`plugin!`](macro.plugin.html) macro.
+//! When enabled, a plugin receives a [`PluginContext`](struct.PluginContext.html)
+//! through which it can subscribe to events, register commands and
+//! schedule tasks. Event handlers, commands and tasks all receive
+//! the `Game` and `World`, giving access to the full server state.
+//!
+//! Plugins are linked into the server, so a plugin crate must be
+//! added as a dependency of `feather-server` to be loaded.
+//!
+//! ```ignore
+//! use feather_api::{plugin, Plugin, PluginContext};
+//! use feather_api::types::{MessageReceiver, PlayerJoinEvent};
+//!
+//! struct Greeter;
+//!
+//! impl Plugin for Greeter {
+//!     fn name(&self) -> &str {
+//!         "greeter"
+//!     }
+//!
+//!     fn version(&self) -> &str {
+//!         env!("CARGO_PKG_VERSION")
+//!     }
+//!
+//!     fn enable(&mut self, ctx: &mut PluginContext) -> anyhow::Result<()> {
+//!         ctx.on(|event: &PlayerJoinEvent, _game, world| {
+//!             if let Some(mut receiver) = world.try_get_mut::<MessageReceiver>(event.player) {
+//!                 receiver.send("Welcome!");
+//!             }
+//!         });
+//!         Ok(())
+//!     }
+//! }
+//!
+//! plugin!(Greeter);
+//! ```
+
+mod events;
+mod plugin;
+mod scheduler;
+
+pub use events::add_event_handlers;
+pub use plugin::{
+    disable_plugins, enable_plugins, Plugin, PluginContext, PluginRegistration, Plugins,
+};
+pub use scheduler::{run_plugin_tasks, Scheduler, TaskId};
+
+pub use feather_server_commands as commands;
+/// Defines a command, to be registered with `PluginContext::register_command`.
+///
+/// The generated code refers to the `feather_server_commands` and
+/// `lieutenant` crates, which plugins defining commands must depend on.
+pub use feather_server_commands::command;
+pub use feather_server_types as types;
+pub use feather_server_types::{PluginEvent, PluginEvents};
+pub use fecs::World;
+
+#[doc(hidden)]
+pub use inventory;
+
+/// Registers a plugin, which is created from the given
+/// expression and enabled when the server starts.
+#[macro_export]
+macro_rules! plugin {
+    ($plugin:expr) => {
+        $crate::inventory::submit!($crate::PluginRegistration::new(|| {
+            Box::new($plugin) as Box<dyn $crate::Plugin>
+        }));
+    };
+}
@@ -0,0 +1,368 @@
+//! Plugins and their lifecycle.
+
+use crate::{PluginEvent, PluginEvents, Scheduler};
+use feather_server_commands::{CommandState, DeclaredCommand, RawCommandHandler};
+use feather_server_types::{Cancellable, Game, Permission};
+use fecs::{OwnedResources, ResourcesProvider, World};
+use std::sync::Arc;
+
+/// A plugin, which extends the server with event
+/// handlers, commands and scheduled tasks.
+pub trait Plugin: Send + Sync + 'static {
+    /// Returns the name of the plugin, used in log messages.
+    fn name(&self) -> &str;
+
+    /// Returns the version of the plugin, reported
+    /// to server lists along with its name.
+    fn version(&self) -> &str;
+
+    /// Called when the server starts. Event handlers,
+    /// commands and tasks should be registered here.
+    ///
+    /// If an error is returned, the plugin is not
+    /// enabled and `disable` will not be called.
+    fn enable(&mut self, ctx: &mut PluginContext) -> anyhow::Result<()>;
+
+    /// Called when the server shuts down, before
+    /// players are disconnected and the world is saved.
+    fn disable(&mut self, _game: &mut Game, _world: &mut World) {}
+}
+
+/// Registration of a plugin, created by the `plugin!` macro.
+pub struct PluginRegistration {
+    create: fn() -> Box<dyn Plugin>,
+}
+
+impl PluginRegistration {
+    pub fn new(create: fn() -> Box<dyn Plugin>) -> Self {
+        Self { create }
+    }
+}
+
+inventory::collect!(PluginRegistration);
+
+/// Passed to `Plugin::enable` to register event
+/// handlers, commands and tasks.
+///
+/// Event handlers and commands are only added to the server
+/// once the plugin has been enabled successfully, so that
+/// the plugin may trigger events while it is being enabled.
+pub struct PluginContext<'a> {
+    pub game: &'a mut Game,
+    pub world: &'a mut World,
+    events: PluginEvents,
+    commands: CommandState,
+    scheduler: &'a Scheduler,
+}
+
+impl<'a> PluginContext<'a> {
+    /// Calls `handler` whenever an event of type `E` is triggered,
+    /// after the server's own handlers.
+    pub fn on<E, F>(&mut self, handler: F)
+    where
+        E: PluginEvent,
+        F: Fn(&E, &mut Game, &mut World) + Send + Sync + 'static,
+    {
+        self.events.subscribe(handler);
+    }
+
+    /// Calls `handler` whenever the pre-event `E` is triggered, before
+    /// the action it announces is performed. The handler may modify
+    /// the event or cancel the action through `Cancellable`.
+    pub fn on_pre<E, F>(&mut self, handler: F)
+    where
+        E: PluginEvent + Cancellable,
+        F: Fn(&mut E, &mut Game, &mut World) + Send + Sync + 'static,
+    {
+        self.events.subscribe_pre(handler);
+    }
+
+    /// Registers a command defined with `#[command]`:
+    ///
+    /// ```ignore
+    /// const HEAL: Permission = Permission::new("myplugin.command.heal", 2);
+    ///
+    /// #[command(usage = "heal", permission = HEAL)]
+    /// fn heal(ctx: &mut CommandCtx) -> anyhow::Result<Option<String>> {
+    ///     // ...
+    /// }
+    ///
+    /// ctx.register_command::<heal>()?;
+    /// ```
+    pub fn register_command<C>(&mut self) -> anyhow::Result<()>
+    where
+        C: DeclaredCommand,
+    {
+        self.game.resources.get::<CommandState>().check::<C>()?;
+        self.commands.register::<C>()
+    }
+
+    /// Registers a command whose arguments are parsed by `handler`,
+    /// for commands which are only known at runtime.
+    ///
+    /// See `CommandState::register_raw`.
+    pub fn register_raw_command(
+        &mut self,
+        name: &str,
+        permission: Permission,
+        handler: RawCommandHandler,
+    ) -> anyhow::Result<()> {
+        self.check_raw_command(name)?;
+        self.commands.register_raw(name, permission, handler)
+    }
+
+    /// Checks that a command named `name` can be
+    /// registered with `register_raw_command`.
+    pub fn check_raw_command(&self, name: &str) -> anyhow::Result<()> {
+        self.game.resources.get::<CommandState>().check_raw(name)?;
+        self.commands.check_raw(name)
+    }
+
+    /// Returns the scheduler, used to run tasks after a delay
+    /// or at an interval.
+    ///
+    /// After the plugin is enabled, the scheduler can
+    /// be accessed through `Game::resources`.
+    pub fn scheduler(&self) -> &Scheduler {
+        self.scheduler
+    }
+}
+
+/// Resource storing the enabled plugins.
+#[derive(Default)]
+pub struct Plugins {
+    plugins: Vec<Box<dyn Plugin>>,
+    /// Plugins added with `with`, which have yet to be enabled.
+    pending: Vec<Box<dyn Plugin>>,
+}
+
+impl Plugins {
+    /// Adds a plugin to be enabled by `enable_plugins`, in
+    /// addition to the plugins registered with `plugin!`.
+    pub fn with(mut self, plugin: impl Plugin) -> Self {
+        self.pending.push(Box::new(plugin));
+        self
+    }
+}
+
+/// Creates and enables the plugins registered with `plugin!`,
+/// followed by those added with `Plugins::with`.
+///
+/// Must be called once the `Plugins`, `PluginEvents`, `Scheduler`
+/// and `CommandState` resources have been inserted.
+pub fn enable_plugins(resources: &OwnedResources, world: &mut World) {
+    enable_all(&mut resources.get_mut::<Game>(), world);
+}
+
+/// Enables the plugins, accessing the resources through `game`.
+fn enable_all(game: &mut Game, world: &mut World) {
+    let resources = Arc::clone(&game.resources);
+
+    let registered = inventory::iter::<PluginRegistration>
+        .into_iter()
+        .map(|registration| (registration.create)());
+    let pending = std::mem::take(&mut resources.get_mut::<Plugins>().pending);
+
+    for mut plugin in registered.chain(pending) {
+        match enable_plugin(&mut *plugin, game, world) {
+            Ok(()) => {
+                log::info!("Enabled plugin {} {}", plugin.name(), plugin.version());
+                game.plugins
+                    .write()
+                    .unwrap()
+                    .push((plugin.name().to_owned(), plugin.version().to_owned()));
+                resources.get_mut::<Plugins>().plugins.push(plugin);
+            }
+            Err(e) => log::error!("Failed to enable plugin {}: {:?}", plugin.name(), e),
+        }
+    }
+}
+
+/// Enables a plugin. Its event handlers and commands are
+/// added to the `PluginEvents` and `CommandState` resources
+/// only if it was enabled successfully.
+///
+/// The resources are not borrowed while `Plugin::enable`
+/// runs, since the events it triggers are passed to
+/// the handlers of the plugins enabled before it.
+fn enable_plugin(
+    plugin: &mut dyn Plugin,
+    game: &mut Game,
+    world: &mut World,
+) -> anyhow::Result<()> {
+    let resources = Arc::clone(&game.resources);
+    let scheduler = resources.get::<Scheduler>();
+
+    let mut ctx = PluginContext {
+        game,
+        world,
+        events: PluginEvents::default(),
+        commands: CommandState::empty(),
+        scheduler: &*scheduler,
+    };
+    plugin.enable(&mut ctx)?;
+
+    let PluginContext {
+        game,
+        world,
+        events,
+        commands,
+        ..
+    } = ctx;
+    let merged = resources.get_mut::<CommandState>().merge(commands);
+    if let Err(e) = merged {
+        plugin.disable(game, world);
+        return Err(e);
+    }
+    resources.get_mut::<PluginEvents>().merge(events);
+
+    Ok(())
+}
+
+/// Disables all enabled plugins, in the reverse order
+/// of the order they were enabled in.
+pub fn disable_plugins(resources: &OwnedResources, world: &mut World) {
+    let mut plugins = resources.get_mut::<Plugins>();
+    let mut game = resources.get_mut::<Game>();
+
+    while let Some(mut plugin) = plugins.plugins.pop() {
+        plugin.disable(&mut game, world);
+        game.plugins.write().unwrap().pop();
+        log::info!("Disabled plugin {}", plugin.name());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::on_weather_change_call_plugins;
+    use feather_server_types::{Weather, WeatherChangeEvent};
+    use feather_test_framework::Test;
+    use fecs::EventHandlers;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn weather_change() -> WeatherChangeEvent {
+        WeatherChangeEvent {
+            from: Weather::Clear,
+            to: Weather::Rain,
+            duration: 100,
+        }
+    }
+
+    /// Plugin which counts weather changes and
+    /// changes the weather while being enabled.
+    struct WeatherPlugin {
+        changes: Arc<AtomicU32>,
+    }
+
+    impl Plugin for WeatherPlugin {
+        fn name(&self) -> &str {
+            "weather"
+        }
+
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+
+        fn enable(&mut self, ctx: &mut PluginContext) -> anyhow::Result<()> {
+            let changes = Arc::clone(&self.changes);
+            ctx.on(move |_: &WeatherChangeEvent, _game, _world| {
+                changes.fetch_add(1, Ordering::SeqCst);
+            });
+
+            ctx.game.handle(ctx.world, weather_change());
+            Ok(())
+        }
+    }
+
+    fn test_with(plugins: Plugins) -> Test {
+        let mut test = Test::new()
+            .with_resource(plugins)
+            .with_resource(PluginEvents::default())
+            .with_resource(CommandState::empty())
+            .with_resource(Scheduler::default());
+
+        let mut handlers = EventHandlers::new().with(on_weather_change_call_plugins);
+        handlers.set_up(
+            Arc::get_mut(&mut test.game.resources).unwrap(),
+            &mut test.world,
+        );
+        test.game.event_handlers = Arc::new(handlers);
+        test
+    }
+
+    /// Plugin which registers a handler and a command, then fails.
+    struct FailingPlugin {
+        changes: Arc<AtomicU32>,
+    }
+
+    impl Plugin for FailingPlugin {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+
+        fn enable(&mut self, ctx: &mut PluginContext) -> anyhow::Result<()> {
+            let changes = Arc::clone(&self.changes);
+            ctx.on(move |_: &WeatherChangeEvent, _game, _world| {
+                changes.fetch_add(1, Ordering::SeqCst);
+            });
+            ctx.register_raw_command(
+                "fail",
+                Permission::new("failing.command.fail", 0),
+                Box::new(|_, _| Ok(None)),
+            )?;
+
+            anyhow::bail!("failed to enable")
+        }
+    }
+
+    #[test]
+    fn failed_plugin_registers_nothing() {
+        let changes = Arc::new(AtomicU32::new(0));
+        let mut test = test_with(Plugins::default().with(FailingPlugin {
+            changes: Arc::clone(&changes),
+        }));
+
+        enable_all(&mut test.game, &mut test.world);
+        assert!(test.game.plugins.read().unwrap().is_empty());
+        assert!(test
+            .game
+            .resources
+            .get::<CommandState>()
+            .permission("fail")
+            .is_none());
+
+        test.game.handle(&mut test.world, weather_change());
+        assert_eq!(changes.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn handle_events_while_enabling() {
+        let first = Arc::new(AtomicU32::new(0));
+        let second = Arc::new(AtomicU32::new(0));
+        let mut test = test_with(
+            Plugins::default()
+                .with(WeatherPlugin {
+                    changes: Arc::clone(&first),
+                })
+                .with(WeatherPlugin {
+                    changes: Arc::clone(&second),
+                }),
+        );
+
+        enable_all(&mut test.game, &mut test.world);
+        // Only the first plugin was enabled when the second
+        // one changed the weather.
+        assert_eq!(first.load(Ordering::SeqCst), 1);
+        assert_eq!(second.load(Ordering::SeqCst), 0);
+        assert_eq!(test.game.plugins.read().unwrap().len(), 2);
+
+        test.game.handle(&mut test.world, weather_change());
+        assert_eq!(first.load(Ordering::SeqCst), 2);
+        assert_eq!(second.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,218 @@
+//! Tasks scheduled by plugins to run on the server thread.
+
+use feather_server_types::Game;
+use fecs::World;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// ID of a scheduled task, which can be used to cancel it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TaskId(u64);
+
+enum TaskFn {
+    Once(Box<dyn FnOnce(&mut Game, &mut World) + Send>),
+    Repeating(Box<dyn FnMut(&mut Game, &mut World) + Send>),
+}
+
+struct ScheduledTask {
+    id: TaskId,
+    /// Number of ticks remaining until the task runs.
+    remaining: u64,
+    /// Number of ticks between runs of a repeating task.
+    interval: u64,
+    f: TaskFn,
+}
+
+/// Resource allowing plugins to run tasks on
+/// the server thread after a number of ticks.
+#[derive(Default)]
+pub struct Scheduler {
+    tasks: Mutex<Vec<ScheduledTask>>,
+    /// Tasks cancelled while not in `tasks`, such as
+    /// while running, which must not run again.
+    cancelled: Mutex<Vec<TaskId>>,
+    next_id: AtomicU64,
+}
+
+impl Scheduler {
+    /// Runs `task` once, after `delay` ticks. A delay
+    /// of 0 runs the task at the end of the current tick.
+    pub fn schedule(
+        &self,
+        delay: u64,
+        task: impl FnOnce(&mut Game, &mut World) + Send + 'static,
+    ) -> TaskId {
+        self.push(delay, 0, TaskFn::Once(Box::new(task)))
+    }
+
+    /// Runs `task` every `interval` ticks until it is cancelled,
+    /// starting `interval` ticks from now.
+    ///
+    /// # Panics
+    /// Panics if `interval` is 0.
+    pub fn schedule_repeating(
+        &self,
+        interval: u64,
+        task: impl FnMut(&mut Game, &mut World) + Send + 'static,
+    ) -> TaskId {
+        assert!(interval > 0, "repeating task interval must be positive");
+        self.push(interval - 1, interval, TaskFn::Repeating(Box::new(task)))
+    }
+
+    /// Cancels a task. Has no effect if the task
+    /// has already run or been cancelled.
+    pub fn cancel(&self, id: TaskId) {
+        let mut tasks = self.tasks.lock();
+        let len = tasks.len();
+        tasks.retain(|task| task.id != id);
+        if tasks.len() == len {
+            self.cancelled.lock().push(id);
+        }
+    }
+
+    fn is_cancelled(&self, id: TaskId) -> bool {
+        self.cancelled.lock().contains(&id)
+    }
+
+    fn push(&self, delay: u64, interval: u64, f: TaskFn) -> TaskId {
+        let id = TaskId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.tasks.lock().push(ScheduledTask {
+            id,
+            remaining: delay,
+            interval,
+            f,
+        });
+        id
+    }
+}
+
+/// System which runs the scheduled tasks which are due.
+#[fecs::system]
+pub fn run_plugin_tasks(game: &mut Game, world: &mut World, #[default] scheduler: &Scheduler) {
+    // Take the due tasks out of the list, so tasks
+    // can schedule or cancel tasks while running.
+    // Tasks run in the order they were scheduled.
+    let due = {
+        let mut tasks = scheduler.tasks.lock();
+        let (due, mut pending): (Vec<_>, Vec<_>) =
+            tasks.drain(..).partition(|task| task.remaining == 0);
+        for task in &mut pending {
+            task.remaining -= 1;
+        }
+        *tasks = pending;
+        due
+    };
+
+    for task in due {
+        // Cancelled by a task which ran before
+        if scheduler.is_cancelled(task.id) {
+            continue;
+        }
+
+        match task.f {
+            TaskFn::Once(f) => f(game, world),
+            TaskFn::Repeating(mut f) => {
+                f(game, world);
+                if !scheduler.is_cancelled(task.id) {
+                    scheduler.tasks.lock().push(ScheduledTask {
+                        id: task.id,
+                        remaining: task.interval - 1,
+                        interval: task.interval,
+                        f: TaskFn::Repeating(f),
+                    });
+                }
+            }
+        }
+    }
+
+    // The IDs are not reused, so the
+    // cancelled tasks are gone for good.
+    scheduler.cancelled.lock().clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_test_framework::Test;
+    use fecs::ResourcesProvider;
+    use std::sync::atomic::AtomicU32;
+    use std::sync::Arc;
+
+    #[test]
+    fn schedule() {
+        let runs = Arc::new(AtomicU32::new(0));
+
+        let scheduler = Scheduler::default();
+        let runs2 = Arc::clone(&runs);
+        scheduler.schedule(2, move |_, _| {
+            runs2.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let mut test = Test::new().with_resource(scheduler);
+        test.run(run_plugin_tasks).run(run_plugin_tasks);
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+
+        test.run(run_plugin_tasks).run(run_plugin_tasks);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn schedule_repeating() {
+        let runs = Arc::new(AtomicU32::new(0));
+
+        let scheduler = Scheduler::default();
+        let runs2 = Arc::clone(&runs);
+        let id = scheduler.schedule_repeating(2, move |_, _| {
+            runs2.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let mut test = Test::new().with_resource(scheduler);
+        for _ in 0..6 {
+            test.run(run_plugin_tasks);
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+
+        test.game.resources.get::<Scheduler>().cancel(id);
+        for _ in 0..6 {
+            test.run(run_plugin_tasks);
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn cancel_while_running() {
+        let runs = Arc::new(AtomicU32::new(0));
+        let scheduler = Scheduler::default();
+
+        // A task which cancels itself on its second run
+        let runs2 = Arc::clone(&runs);
+        let id = Arc::new(Mutex::new(None));
+        let id2 = Arc::clone(&id);
+        *id.lock() = Some(scheduler.schedule_repeating(1, move |game, _| {
+            if runs2.fetch_add(1, Ordering::SeqCst) == 1 {
+                let id = id2.lock().unwrap();
+                game.resources.get::<Scheduler>().cancel(id);
+            }
+        }));
+
+        // A task which cancels another one due in the same tick
+        let other_runs = Arc::new(AtomicU32::new(0));
+        let other_runs2 = Arc::clone(&other_runs);
+        let other = Arc::new(Mutex::new(None));
+        let other2 = Arc::clone(&other);
+        scheduler.schedule(1, move |game, _| {
+            let other = other2.lock().unwrap();
+            game.resources.get::<Scheduler>().cancel(other);
+        });
+        *other.lock() = Some(scheduler.schedule(1, move |_, _| {
+            other_runs2.fetch_add(1, Ordering::SeqCst);
+        }));
+
+        let mut test = Test::new().with_resource(scheduler);
+        for _ in 0..6 {
+            test.run(run_plugin_tasks);
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert_eq!(other_runs.load(Ordering::SeqCst), 0);
+    }
+}
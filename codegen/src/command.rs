@@ -0,0 +1,118 @@
+//! The `#[command]` attribute, which defines a command along
+//! with the description of it sent to clients and the
+//! permission required to run it.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, Error, Expr, ExprLit, FnArg, Ident, ItemFn, Lit, Token};
+
+/// An argument of the attribute, such as `usage = "stop"`
+/// or `permission = STOP`.
+struct AttributeArg {
+    name: Ident,
+    value: Expr,
+}
+
+impl Parse for AttributeArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let value = input.parse()?;
+        Ok(Self { name, value })
+    }
+}
+
+pub fn command(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = match Punctuated::<AttributeArg, Token![,]>::parse_terminated.parse(args) {
+        Ok(args) => args,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let function = parse_macro_input!(input as ItemFn);
+
+    match expand(args, function) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(
+    args: Punctuated<AttributeArg, Token![,]>,
+    mut function: ItemFn,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut usage = None;
+    let mut permission = None;
+    for arg in args {
+        if arg.name == "usage" {
+            match arg.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(lit), ..
+                }) => usage = Some(lit),
+                value => return Err(Error::new_spanned(value, "expected a string literal")),
+            }
+        } else if arg.name == "permission" {
+            permission = Some(arg.value);
+        } else {
+            return Err(Error::new_spanned(arg.name, "unknown command attribute"));
+        }
+    }
+    let usage = usage.ok_or_else(|| Error::new(Span::call_site(), "missing `usage`"))?;
+    let permission =
+        permission.ok_or_else(|| Error::new(Span::call_site(), "missing `permission`"))?;
+
+    // The first parameter is the context; the rest are the arguments.
+    let arguments = function
+        .sig
+        .inputs
+        .iter()
+        .skip(1)
+        .map(|input| match input {
+            FnArg::Typed(pat) => Ok(pat.ty.clone()),
+            FnArg::Receiver(receiver) => {
+                Err(Error::new_spanned(receiver, "commands cannot take `self`"))
+            }
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let name = function.sig.ident.clone();
+    let vis = function.vis.clone();
+    let module = Ident::new(&format!("__command_{}", name), name.span());
+    function.vis = syn::parse_quote!(pub);
+
+    // The function is passed to lieutenant in a module of its own,
+    // so that `name` can refer to our declaration instead.
+    Ok(quote! {
+        #[allow(non_camel_case_types)]
+        #vis struct #name;
+
+        impl feather_server_commands::DeclaredCommand for #name {
+            const USAGE: &'static str = #usage;
+            const PERMISSION: feather_server_commands::Permission = #permission;
+
+            fn arguments() -> Vec<feather_server_commands::ArgumentSpec> {
+                vec![#(feather_server_commands::ArgumentSpec::of::<#arguments>()),*]
+            }
+
+            fn register(
+                dispatcher: &mut feather_server_commands::lieutenant::CommandDispatcher<
+                    feather_server_commands::CommandCtx,
+                >,
+            ) -> Result<(), String> {
+                dispatcher
+                    .register(#module::#name)
+                    .map_err(|e| format!("{:?}", e))
+            }
+        }
+
+        #[doc(hidden)]
+        #[allow(non_snake_case)]
+        mod #module {
+            use super::*;
+
+            #[feather_server_commands::lieutenant::command(usage = #usage)]
+            #function
+        }
+    })
+}
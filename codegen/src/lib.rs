@@ -3,6 +3,7 @@ extern crate proc_macro;
 #[macro_use]
 extern crate strum_macros;
 
+mod command;
 mod entity_metadata;
 
 use heck::SnakeCase;
@@ -169,6 +170,10 @@ pub fn derive_packet(_item: TokenStream) -> TokenStream {
             write = quote! {
                 buf.#write_fn_ident(&self.#field_name);
             };
+        } else if *parameter_type == PacketParameterType::Slot {
+            write = quote! {
+                buf.#write_fn_ident(self.#field_name.as_ref());
+            };
         } else {
             write = quote! {
                 buf.#write_fn_ident(self.#field_name);
@@ -284,3 +289,14 @@ pub fn derive_to_snake_case(input: TokenStream) -> TokenStream {
 pub fn entity_metadata(input: TokenStream) -> TokenStream {
     entity_metadata::entity_metadata(input)
 }
+
+/// Defines a command for `feather-server-commands`.
+///
+/// Takes the same `usage` as lieutenant's `#[command]`, along with the
+/// `permission` required to run the command. In addition to the command,
+/// this implements `DeclaredCommand` for a type with the function's name,
+/// which describes the command's arguments to clients.
+#[proc_macro_attribute]
+pub fn command(args: TokenStream, input: TokenStream) -> TokenStream {
+    command::command(args, input)
+}
@@ -1,83 +1,277 @@
+use crate::serialization_helper::nbt_value;
 use arrayvec::ArrayVec;
-use feather_items::{Item, ItemStack};
+use feather_items::{BookContents, Enchantment, Item, ItemMeta, ItemStack};
+use feather_text::ansi::to_plain;
+use feather_text::Text;
 use feather_util::{vec3, Position, Vec3d};
-use serde::ser::Error;
-use serde::{Deserialize, Serialize, Serializer};
+use nbt::Value;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use thiserror::Error;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-pub enum EntityDataKind {
-    Item,
-    Arrow,
-    Cow,
-    Pig,
-    Chicken,
-    Sheep,
-    Horse,
-    Llama,
-    Mooshroom,
-    Rabbit,
-    Squid,
-    Donkey,
-    Unknown,
-}
-
-impl<'a> From<&'a EntityData> for EntityDataKind {
-    fn from(data: &'a EntityData) -> Self {
-        match data {
-            EntityData::Arrow(_) => EntityDataKind::Arrow,
-            EntityData::Item(_) => EntityDataKind::Item,
-            EntityData::Cow(_) => EntityDataKind::Cow,
-            EntityData::Pig(_) => EntityDataKind::Pig,
-            EntityData::Chicken(_) => EntityDataKind::Chicken,
-            EntityData::Sheep(_) => EntityDataKind::Sheep,
-            EntityData::Horse(_) => EntityDataKind::Horse,
-            EntityData::Llama(_) => EntityDataKind::Llama,
-            EntityData::Mooshroom(_) => EntityDataKind::Mooshroom,
-            EntityData::Rabbit(_) => EntityDataKind::Rabbit,
-            EntityData::Squid(_) => EntityDataKind::Squid,
-            EntityData::Donkey(_) => EntityDataKind::Donkey,
-            EntityData::Unknown => EntityDataKind::Unknown,
+/// Defines `EntityDataKind` and `EntityData`, with a variant
+/// for each entity identifier. Mobs all use `MobData`.
+macro_rules! entity_data {
+    (
+        objects { $($kind:ident($data:ty) = $id:literal,)* }
+        mobs { $($mob:ident = $mob_id:literal,)* }
+    ) => {
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+        pub enum EntityDataKind {
+            $($kind,)*
+            $($mob,)*
+            Unknown,
         }
-    }
+
+        impl EntityDataKind {
+            /// Returns the kind of entity with the given
+            /// identifier, e.g. `minecraft:cow`.
+            pub fn from_id(id: &str) -> Self {
+                match id {
+                    $($id => EntityDataKind::$kind,)*
+                    $($mob_id => EntityDataKind::$mob,)*
+                    _ => EntityDataKind::Unknown,
+                }
+            }
+
+            /// Returns the identifier of this kind of entity,
+            /// or `None` if it is unknown.
+            pub fn id(self) -> Option<&'static str> {
+                match self {
+                    $(EntityDataKind::$kind => Some($id),)*
+                    $(EntityDataKind::$mob => Some($mob_id),)*
+                    EntityDataKind::Unknown => None,
+                }
+            }
+
+            /// Returns whether this kind of entity is a mob.
+            pub fn is_mob(self) -> bool {
+                match self {
+                    $(EntityDataKind::$mob => true,)*
+                    _ => false,
+                }
+            }
+        }
+
+        impl<'a> From<&'a EntityData> for EntityDataKind {
+            fn from(data: &'a EntityData) -> Self {
+                match data {
+                    $(EntityData::$kind(_) => EntityDataKind::$kind,)*
+                    $(EntityData::$mob(_) => EntityDataKind::$mob,)*
+                    EntityData::Unknown(_) => EntityDataKind::Unknown,
+                }
+            }
+        }
+
+        /// An entity stored in a chunk, tagged by its `id`.
+        #[derive(Debug, Clone)]
+        pub enum EntityData {
+            $($kind($data),)*
+            $($mob(MobData),)*
+            /// Entity of an unknown kind, or which could not be parsed,
+            /// stored as its NBT compound so it is saved unchanged.
+            Unknown(HashMap<String, Value>),
+        }
+
+        impl EntityData {
+            /// Reads an entity from its NBT compound.
+            pub fn from_compound(mut compound: HashMap<String, Value>) -> Self {
+                let id = match compound.remove("id") {
+                    Some(Value::String(id)) => id,
+                    Some(id) => {
+                        compound.insert(String::from("id"), id);
+                        return EntityData::Unknown(compound);
+                    }
+                    None => return EntityData::Unknown(compound),
+                };
+
+                let data = match EntityDataKind::from_id(&id) {
+                    $(EntityDataKind::$kind => {
+                        nbt_value::from_compound(&compound).map(EntityData::$kind).ok()
+                    })*
+                    $(EntityDataKind::$mob => {
+                        nbt_value::from_compound(&compound).map(EntityData::$mob).ok()
+                    })*
+                    EntityDataKind::Unknown => None,
+                };
+
+                data.unwrap_or_else(|| {
+                    compound.insert(String::from("id"), Value::String(id));
+                    EntityData::Unknown(compound)
+                })
+            }
+
+            /// Creates the data of a mob of the given kind,
+            /// or returns `None` if the kind is not a mob.
+            pub fn from_mob(kind: EntityDataKind, mob: MobData) -> Option<Self> {
+                match kind {
+                    $(EntityDataKind::$mob => Some(EntityData::$mob(mob)),)*
+                    _ => None,
+                }
+            }
+
+            /// Returns the data of this entity if it is a mob.
+            pub fn into_mob(self) -> Option<MobData> {
+                match self {
+                    $(EntityData::$mob(mob) => Some(mob),)*
+                    _ => None,
+                }
+            }
+
+            /// Returns the common tags of this entity.
+            /// Those of unknown entities are parsed, so
+            /// `None` is returned if they are invalid.
+            pub fn base(&self) -> Option<Cow<BaseEntityData>> {
+                match self {
+                    $(EntityData::$kind(data) => Some(Cow::Borrowed(data.base())),)*
+                    $(EntityData::$mob(mob) => Some(Cow::Borrowed(&mob.base)),)*
+                    EntityData::Unknown(compound) => {
+                        nbt_value::from_compound(compound).ok().map(Cow::Owned)
+                    }
+                }
+            }
+        }
+
+        impl Serialize for EntityData {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                match self {
+                    $(EntityData::$kind(data) => Tagged { id: $id, data }.serialize(serializer),)*
+                    $(EntityData::$mob(data) => Tagged { id: $mob_id, data }.serialize(serializer),)*
+                    EntityData::Unknown(compound) => {
+                        nbt_value::serialize_compound(compound, serializer)
+                    }
+                }
+            }
+        }
+    };
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "id")]
-pub enum EntityData {
-    #[serde(rename = "minecraft:item")]
-    Item(ItemEntityData),
-    #[serde(rename = "minecraft:arrow")]
-    Arrow(ArrowEntityData),
-    #[serde(rename = "minecraft:cow")]
-    Cow(AnimalData),
-    #[serde(rename = "minecraft:pig")]
-    Pig(AnimalData),
-    #[serde(rename = "minecraft:chicken")]
-    Chicken(AnimalData),
-    #[serde(rename = "minecraft:sheep")]
-    Sheep(AnimalData),
-    #[serde(rename = "minecraft:horse")]
-    Horse(AnimalData),
-    #[serde(rename = "minecraft:llama")]
-    Llama(AnimalData),
-    #[serde(rename = "minecraft:mooshroom")]
-    Mooshroom(AnimalData),
-    #[serde(rename = "minecraft:rabbit}")]
-    Rabbit(AnimalData),
-    #[serde(rename = "minecraft:squid")]
-    Squid(AnimalData),
-    #[serde(rename = "minecraft:donkey")]
-    Donkey(AnimalData),
-
-    /// Fallback type for unknown entities
-    #[serde(other, serialize_with = "EntityData::serialize_unknown")]
-    Unknown,
+entity_data! {
+    objects {
+        AreaEffectCloud(ObjectData) = "minecraft:area_effect_cloud",
+        ArmorStand(ObjectData) = "minecraft:armor_stand",
+        Arrow(ArrowEntityData) = "minecraft:arrow",
+        Boat(ObjectData) = "minecraft:boat",
+        ChestMinecart(ObjectData) = "minecraft:chest_minecart",
+        CommandBlockMinecart(ObjectData) = "minecraft:command_block_minecart",
+        DragonFireball(ObjectData) = "minecraft:dragon_fireball",
+        Egg(ObjectData) = "minecraft:egg",
+        EndCrystal(ObjectData) = "minecraft:end_crystal",
+        EnderPearl(ObjectData) = "minecraft:ender_pearl",
+        EvokerFangs(ObjectData) = "minecraft:evoker_fangs",
+        ExperienceBottle(ObjectData) = "minecraft:experience_bottle",
+        ExperienceOrb(ObjectData) = "minecraft:experience_orb",
+        EyeOfEnder(ObjectData) = "minecraft:eye_of_ender",
+        FallingBlock(ObjectData) = "minecraft:falling_block",
+        Fireball(ObjectData) = "minecraft:fireball",
+        FireworkRocket(ObjectData) = "minecraft:firework_rocket",
+        FurnaceMinecart(ObjectData) = "minecraft:furnace_minecart",
+        HopperMinecart(ObjectData) = "minecraft:hopper_minecart",
+        Item(ItemEntityData) = "minecraft:item",
+        ItemFrame(ObjectData) = "minecraft:item_frame",
+        LeashKnot(ObjectData) = "minecraft:leash_knot",
+        LlamaSpit(ObjectData) = "minecraft:llama_spit",
+        Minecart(ObjectData) = "minecraft:minecart",
+        Painting(ObjectData) = "minecraft:painting",
+        Potion(ObjectData) = "minecraft:potion",
+        ShulkerBullet(ObjectData) = "minecraft:shulker_bullet",
+        SmallFireball(ObjectData) = "minecraft:small_fireball",
+        Snowball(ObjectData) = "minecraft:snowball",
+        SpawnerMinecart(ObjectData) = "minecraft:spawner_minecart",
+        SpectralArrow(ObjectData) = "minecraft:spectral_arrow",
+        Tnt(ObjectData) = "minecraft:tnt",
+        TntMinecart(ObjectData) = "minecraft:tnt_minecart",
+        Trident(ObjectData) = "minecraft:trident",
+        WitherSkull(ObjectData) = "minecraft:wither_skull",
+    }
+    mobs {
+        Bat = "minecraft:bat",
+        Blaze = "minecraft:blaze",
+        CaveSpider = "minecraft:cave_spider",
+        Chicken = "minecraft:chicken",
+        Cod = "minecraft:cod",
+        Cow = "minecraft:cow",
+        Creeper = "minecraft:creeper",
+        Dolphin = "minecraft:dolphin",
+        Donkey = "minecraft:donkey",
+        Drowned = "minecraft:drowned",
+        ElderGuardian = "minecraft:elder_guardian",
+        EnderDragon = "minecraft:ender_dragon",
+        Enderman = "minecraft:enderman",
+        Endermite = "minecraft:endermite",
+        Evoker = "minecraft:evoker",
+        Ghast = "minecraft:ghast",
+        Giant = "minecraft:giant",
+        Guardian = "minecraft:guardian",
+        Horse = "minecraft:horse",
+        Husk = "minecraft:husk",
+        Illusioner = "minecraft:illusioner",
+        IronGolem = "minecraft:iron_golem",
+        Llama = "minecraft:llama",
+        MagmaCube = "minecraft:magma_cube",
+        Mooshroom = "minecraft:mooshroom",
+        Mule = "minecraft:mule",
+        Ocelot = "minecraft:ocelot",
+        Parrot = "minecraft:parrot",
+        Phantom = "minecraft:phantom",
+        Pig = "minecraft:pig",
+        PolarBear = "minecraft:polar_bear",
+        Pufferfish = "minecraft:pufferfish",
+        Rabbit = "minecraft:rabbit",
+        Salmon = "minecraft:salmon",
+        Sheep = "minecraft:sheep",
+        Shulker = "minecraft:shulker",
+        Silverfish = "minecraft:silverfish",
+        Skeleton = "minecraft:skeleton",
+        SkeletonHorse = "minecraft:skeleton_horse",
+        Slime = "minecraft:slime",
+        SnowGolem = "minecraft:snow_golem",
+        Spider = "minecraft:spider",
+        Squid = "minecraft:squid",
+        Stray = "minecraft:stray",
+        TropicalFish = "minecraft:tropical_fish",
+        Turtle = "minecraft:turtle",
+        Vex = "minecraft:vex",
+        Villager = "minecraft:villager",
+        Vindicator = "minecraft:vindicator",
+        Witch = "minecraft:witch",
+        Wither = "minecraft:wither",
+        WitherSkeleton = "minecraft:wither_skeleton",
+        Wolf = "minecraft:wolf",
+        Zombie = "minecraft:zombie",
+        ZombieHorse = "minecraft:zombie_horse",
+        ZombiePigman = "minecraft:zombie_pigman",
+        ZombieVillager = "minecraft:zombie_villager",
+    }
 }
 
 impl EntityData {
-    pub(crate) fn serialize_unknown<S: Serializer>(_serializer: S) -> Result<S::Ok, S::Error> {
-        Err(S::Error::custom("cannot serialize unknown entities"))
+    /// Reads the position and rotation of this entity.
+    pub fn read_position(&self) -> Result<Position, EntityLoadError> {
+        self.base()
+            .ok_or(EntityLoadError::MissingData)?
+            .read_position()
+    }
+}
+
+/// Serializes entity data along with its identifier.
+#[derive(Serialize)]
+struct Tagged<'a, T> {
+    id: &'a str,
+    #[serde(flatten)]
+    data: &'a T,
+}
+
+impl<'de> Deserialize<'de> for EntityData {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        nbt_value::deserialize_compound(deserializer).map(EntityData::from_compound)
     }
 }
 
@@ -168,6 +362,82 @@ impl Default for AnimalData {
     }
 }
 
+/// Data for a mob. Tags other than the common
+/// ones and its health are kept in `other`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MobData {
+    #[serde(flatten)]
+    pub base: BaseEntityData,
+    #[serde(rename = "Health")]
+    pub health: f32,
+    #[serde(
+        flatten,
+        serialize_with = "nbt_value::serialize_compound",
+        deserialize_with = "nbt_value::deserialize_compound"
+    )]
+    pub other: HashMap<String, Value>,
+}
+
+impl MobData {
+    /// Creates a `MobData` without other tags.
+    pub fn new(base: BaseEntityData, health: f32) -> Self {
+        Self {
+            base,
+            health,
+            other: HashMap::new(),
+        }
+    }
+
+    /// Returns the `PersistenceRequired` tag, which
+    /// prevents the mob from despawning.
+    pub fn persistence_required(&self) -> bool {
+        match self.other.get("PersistenceRequired") {
+            Some(Value::Byte(x)) => *x != 0,
+            _ => false,
+        }
+    }
+
+    /// Sets the `PersistenceRequired` tag.
+    pub fn set_persistence_required(&mut self, persistent: bool) {
+        self.other.insert(
+            String::from("PersistenceRequired"),
+            Value::Byte(persistent as i8),
+        );
+    }
+
+    /// Returns whether the mob has a custom name,
+    /// e.g. from a name tag.
+    pub fn has_custom_name(&self) -> bool {
+        self.other.contains_key("CustomName")
+    }
+}
+
+impl Default for MobData {
+    fn default() -> Self {
+        Self::new(Default::default(), 20.0)
+    }
+}
+
+/// Data for an entity other than a mob. Tags
+/// other than the common ones are kept in `other`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ObjectData {
+    #[serde(flatten)]
+    pub base: BaseEntityData,
+    #[serde(
+        flatten,
+        serialize_with = "nbt_value::serialize_compound",
+        deserialize_with = "nbt_value::deserialize_compound"
+    )]
+    pub other: HashMap<String, Value>,
+}
+
+impl ObjectData {
+    fn base(&self) -> &BaseEntityData {
+        &self.base
+    }
+}
+
 /// Represents a single item, without slot information.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ItemData {
@@ -227,21 +497,256 @@ where
 }
 
 /// Represents NBT tags on an item.
-#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct ItemNbt {
-    #[serde(rename = "Damage")]
     pub damage: Option<i32>,
-    // TODO enchantments, display name, ...
+    pub meta: ItemMeta,
 }
 
 impl ItemNbt {
     /// Create an `ItemStack` of the specified item and amount, setting any nbt present.
     pub fn item_stack(nbt: &Option<Self>, item: Item, amount: u8) -> ItemStack {
+        let meta = nbt
+            .as_ref()
+            .filter(|n| !n.meta.is_empty())
+            .map(|n| Box::new(n.meta.clone()));
         ItemStack {
             ty: item,
             amount,
             damage: nbt.as_ref().map(|n| n.damage).flatten(),
+            meta,
+        }
+    }
+
+    /// Reads item tags from an NBT compound. Tags which
+    /// are not understood are kept in `ItemMeta::other`.
+    pub fn from_compound(mut compound: HashMap<String, Value>) -> Self {
+        let damage = take(&mut compound, "Damage", as_int);
+
+        let unbreakable = take(&mut compound, "Unbreakable", as_int).map_or(false, |x| x != 0);
+        let repair_cost = take(&mut compound, "RepairCost", as_int);
+        let custom_model_data = take(&mut compound, "CustomModelData", as_int);
+        let enchantments = take(&mut compound, "Enchantments", as_enchantments).unwrap_or_default();
+        let stored_enchantments =
+            take(&mut compound, "StoredEnchantments", as_enchantments).unwrap_or_default();
+
+        let pages = take(&mut compound, "pages", as_string_list);
+        let title = take(&mut compound, "title", as_string);
+        let author = take(&mut compound, "author", as_string);
+        let generation = take(&mut compound, "generation", as_int);
+        let resolved = take(&mut compound, "resolved", as_int);
+        let book = if pages.is_some()
+            || title.is_some()
+            || author.is_some()
+            || generation.is_some()
+            || resolved.is_some()
+        {
+            Some(BookContents {
+                pages: pages.unwrap_or_default(),
+                title,
+                author,
+                generation,
+                resolved: resolved.map_or(false, |x| x != 0),
+            })
+        } else {
+            None
+        };
+
+        let mut display_name = None;
+        let mut lore = vec![];
+        if let Some(Value::Compound(mut display)) = compound.get("display").cloned() {
+            compound.remove("display");
+            display_name = take(&mut display, "Name", as_string).map(|name| {
+                // Names are JSON text, but may have been set by
+                // tools which write plain strings.
+                serde_json::from_str(&name).unwrap_or_else(|_| Text::from(name))
+            });
+            lore = take(&mut display, "Lore", as_string_list)
+                .unwrap_or_default()
+                .into_iter()
+                .map(Text::from)
+                .collect();
+
+            // Keep other display tags, e.g. the color of leather armor
+            if !display.is_empty() {
+                compound.insert(String::from("display"), Value::Compound(display));
+            }
+        }
+
+        let meta = ItemMeta {
+            display_name,
+            lore,
+            enchantments,
+            stored_enchantments,
+            unbreakable,
+            repair_cost,
+            custom_model_data,
+            book,
+            other: compound,
+        };
+
+        Self { damage, meta }
+    }
+
+    /// Converts these tags into an NBT compound.
+    pub fn to_compound(&self) -> HashMap<String, Value> {
+        let meta = &self.meta;
+        let mut compound = meta.other.clone();
+
+        if let Some(damage) = self.damage {
+            compound.insert(String::from("Damage"), Value::Int(damage));
+        }
+        if meta.unbreakable {
+            compound.insert(String::from("Unbreakable"), Value::Byte(1));
+        }
+        if let Some(repair_cost) = meta.repair_cost {
+            compound.insert(String::from("RepairCost"), Value::Int(repair_cost));
+        }
+        if let Some(custom_model_data) = meta.custom_model_data {
+            compound.insert(
+                String::from("CustomModelData"),
+                Value::Int(custom_model_data),
+            );
+        }
+        if !meta.enchantments.is_empty() {
+            compound.insert(
+                String::from("Enchantments"),
+                enchantments_to_nbt(&meta.enchantments),
+            );
+        }
+        if !meta.stored_enchantments.is_empty() {
+            compound.insert(
+                String::from("StoredEnchantments"),
+                enchantments_to_nbt(&meta.stored_enchantments),
+            );
+        }
+
+        if let Some(book) = &meta.book {
+            compound.insert(
+                String::from("pages"),
+                Value::List(book.pages.iter().cloned().map(Value::String).collect()),
+            );
+            if let Some(title) = &book.title {
+                compound.insert(String::from("title"), Value::String(title.clone()));
+            }
+            if let Some(author) = &book.author {
+                compound.insert(String::from("author"), Value::String(author.clone()));
+            }
+            if let Some(generation) = book.generation {
+                compound.insert(String::from("generation"), Value::Int(generation));
+            }
+            if book.resolved {
+                compound.insert(String::from("resolved"), Value::Byte(1));
+            }
+        }
+
+        let mut display = match compound.remove("display") {
+            Some(Value::Compound(display)) => display,
+            Some(other) => {
+                compound.insert(String::from("display"), other);
+                HashMap::new()
+            }
+            None => HashMap::new(),
+        };
+        if let Some(name) = &meta.display_name {
+            display.insert(String::from("Name"), Value::String(name.to_string()));
+        }
+        if !meta.lore.is_empty() {
+            // Lore lines are plain strings in 1.13 rather than JSON text.
+            let lore = meta
+                .lore
+                .iter()
+                .map(|line| Value::String(to_plain(line)))
+                .collect();
+            display.insert(String::from("Lore"), Value::List(lore));
+        }
+        if !display.is_empty() {
+            compound.insert(String::from("display"), Value::Compound(display));
         }
+
+        compound
+    }
+}
+
+/// Removes the tag `key` from `compound` if `parse` accepts it.
+fn take<T>(
+    compound: &mut HashMap<String, Value>,
+    key: &str,
+    parse: impl FnOnce(&Value) -> Option<T>,
+) -> Option<T> {
+    let parsed = compound.get(key).and_then(parse)?;
+    compound.remove(key);
+    Some(parsed)
+}
+
+fn as_int(value: &Value) -> Option<i32> {
+    match value {
+        Value::Byte(x) => Some(*x as i32),
+        Value::Short(x) => Some(*x as i32),
+        Value::Int(x) => Some(*x),
+        _ => None,
+    }
+}
+
+fn as_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+fn as_string_list(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::List(list) => list.iter().map(as_string).collect(),
+        _ => None,
+    }
+}
+
+fn as_enchantments(value: &Value) -> Option<Vec<Enchantment>> {
+    match value {
+        Value::List(list) => list
+            .iter()
+            .map(|enchantment| match enchantment {
+                Value::Compound(enchantment) => Some(Enchantment::new(
+                    as_string(enchantment.get("id")?)?,
+                    as_int(enchantment.get("lvl")?)? as i16,
+                )),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+fn enchantments_to_nbt(enchantments: &[Enchantment]) -> Value {
+    Value::List(
+        enchantments
+            .iter()
+            .map(|enchantment| {
+                let mut compound = HashMap::new();
+                compound.insert(String::from("id"), Value::String(enchantment.id.clone()));
+                compound.insert(String::from("lvl"), Value::Short(enchantment.level));
+                Value::Compound(compound)
+            })
+            .collect(),
+    )
+}
+
+impl Serialize for ItemNbt {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        nbt_value::Tag(&Value::Compound(self.to_compound())).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ItemNbt {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        nbt_value::deserialize_compound(deserializer).map(ItemNbt::from_compound)
     }
 }
 
@@ -253,6 +758,7 @@ where
         let stack = s.borrow();
         Self {
             damage: stack.damage,
+            meta: stack.meta.as_deref().cloned().unwrap_or_default(),
         }
     }
 }
@@ -273,6 +779,19 @@ pub struct ItemEntityData {
     pub item: ItemData,
     #[serde(rename = "Health")]
     pub health: i16,
+    /// Tags which are not modelled above, such as `Owner`.
+    #[serde(
+        flatten,
+        serialize_with = "nbt_value::serialize_compound",
+        deserialize_with = "nbt_value::deserialize_compound"
+    )]
+    pub other: HashMap<String, Value>,
+}
+
+impl ItemEntityData {
+    fn base(&self) -> &BaseEntityData {
+        &self.entity
+    }
 }
 
 /// Data for an Arrow entity (`minecraft:arrow`).
@@ -288,12 +807,27 @@ pub struct ArrowEntityData {
     // See: https://github.com/PistonDevelopers/hematite_nbt/issues/43
     #[serde(rename = "crit")]
     pub critical: i8,
+    /// Tags which are not modelled above, such as `pickup`.
+    #[serde(
+        flatten,
+        serialize_with = "nbt_value::serialize_compound",
+        deserialize_with = "nbt_value::deserialize_compound"
+    )]
+    pub other: HashMap<String, Value>,
+}
+
+impl ArrowEntityData {
+    fn base(&self) -> &BaseEntityData {
+        &self.entity
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use feather_text::TextComponentBuilder;
     use feather_util::position;
+    use flate2::read::ZlibDecoder;
 
     #[test]
     fn test_read_position() {
@@ -335,4 +869,226 @@ mod tests {
         assert_eq!(data.read_position(), Ok(pos));
         assert_eq!(data.read_velocity(), Ok(vel));
     }
+
+    #[derive(Serialize, Deserialize)]
+    struct Entities {
+        #[serde(rename = "Entities")]
+        entities: Vec<EntityData>,
+    }
+
+    fn roundtrip(entity: EntityData) -> EntityData {
+        let mut buf = vec![];
+        nbt::to_zlib_writer(
+            &mut buf,
+            &Entities {
+                entities: vec![entity],
+            },
+            None,
+        )
+        .unwrap();
+        // Read back as chunks are, through `Value`
+        let compound = crate::region::read_compound(ZlibDecoder::new(buf.as_slice())).unwrap();
+        let mut loaded: Entities = nbt_value::from_compound(&compound).unwrap();
+        loaded.entities.remove(0)
+    }
+
+    /// Item tags of a firework, which contain an int array.
+    fn firework_item() -> Value {
+        let mut explosion = HashMap::new();
+        explosion.insert(String::from("Colors"), Value::IntArray(vec![11_743_532]));
+        let mut fireworks = HashMap::new();
+        fireworks.insert(
+            String::from("Explosions"),
+            Value::List(vec![Value::Compound(explosion)]),
+        );
+        let mut tag = HashMap::new();
+        tag.insert(String::from("Fireworks"), Value::Compound(fireworks));
+
+        let mut item = HashMap::new();
+        item.insert(
+            String::from("id"),
+            Value::String(String::from("minecraft:firework_rocket")),
+        );
+        item.insert(String::from("Count"), Value::Byte(1));
+        item.insert(String::from("tag"), Value::Compound(tag));
+        Value::Compound(item)
+    }
+
+    fn compound(id: &str) -> HashMap<String, Value> {
+        let mut compound = HashMap::new();
+        compound.insert(String::from("id"), Value::String(id.to_owned()));
+        compound.insert(
+            String::from("Pos"),
+            Value::List(vec![
+                Value::Double(1.0),
+                Value::Double(64.0),
+                Value::Double(2.0),
+            ]),
+        );
+        compound.insert(
+            String::from("Rotation"),
+            Value::List(vec![Value::Float(90.0), Value::Float(0.0)]),
+        );
+        compound.insert(
+            String::from("Motion"),
+            Value::List(vec![Value::Double(0.0); 3]),
+        );
+        compound
+    }
+
+    #[test]
+    fn test_unknown_entity_roundtrip() {
+        let mut tags = compound("examplemod:ghost");
+        tags.insert(String::from("Invisible"), Value::Byte(1));
+        tags.insert(String::from("Age"), Value::Short(7));
+        let mut owner = HashMap::new();
+        owner.insert(String::from("UUIDMost"), Value::Long(-5));
+        tags.insert(String::from("Owner"), Value::Compound(owner));
+
+        let entity = EntityData::from_compound(tags.clone());
+        assert_eq!(EntityDataKind::from(&entity), EntityDataKind::Unknown);
+        assert_eq!(
+            entity.read_position(),
+            Ok(position!(1.0, 64.0, 2.0, 90.0, 0.0))
+        );
+
+        match roundtrip(entity) {
+            EntityData::Unknown(loaded) => assert_eq!(loaded, tags),
+            other => panic!("expected unknown entity, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_mob_roundtrip() {
+        let mut tags = compound("minecraft:sheep");
+        tags.insert(String::from("Health"), Value::Float(8.0));
+        tags.insert(String::from("Color"), Value::Byte(3));
+
+        let sheep = match roundtrip(EntityData::from_compound(tags)) {
+            EntityData::Sheep(sheep) => sheep,
+            other => panic!("expected sheep, got {:?}", other),
+        };
+        assert!((sheep.health - 8.0).abs() < std::f32::EPSILON);
+        assert_eq!(
+            sheep.base.read_position(),
+            Ok(position!(1.0, 64.0, 2.0, 90.0, 0.0))
+        );
+        assert_eq!(sheep.other.len(), 1);
+        assert_eq!(sheep.other.get("Color"), Some(&Value::Byte(3)));
+    }
+
+    #[test]
+    fn test_mob_arrays_roundtrip() {
+        let mut tags = compound("minecraft:zombie");
+        tags.insert(String::from("Health"), Value::Float(20.0));
+        tags.insert(
+            String::from("HandItems"),
+            Value::List(vec![firework_item(), Value::Compound(HashMap::new())]),
+        );
+
+        let zombie = match roundtrip(EntityData::from_compound(tags.clone())) {
+            EntityData::Zombie(zombie) => zombie,
+            other => panic!("expected zombie, got {:?}", other),
+        };
+        assert_eq!(zombie.other.get("HandItems"), tags.get("HandItems"));
+    }
+
+    #[test]
+    fn test_object_arrays_roundtrip() {
+        let mut tags = compound("minecraft:firework_rocket");
+        tags.insert(String::from("FireworksItem"), firework_item());
+        tags.insert(String::from("LifeTime"), Value::Int(30));
+
+        let rocket = match roundtrip(EntityData::from_compound(tags.clone())) {
+            EntityData::FireworkRocket(rocket) => rocket,
+            other => panic!("expected firework rocket, got {:?}", other),
+        };
+        assert_eq!(rocket.other.len(), 2);
+        assert_eq!(rocket.other.get("FireworksItem"), tags.get("FireworksItem"));
+    }
+
+    #[test]
+    fn test_item_and_arrow_roundtrip() {
+        let mut tags = compound("minecraft:item");
+        tags.insert(String::from("Age"), Value::Short(10));
+        tags.insert(String::from("PickupDelay"), Value::Short(0));
+        tags.insert(String::from("Health"), Value::Short(5));
+        let mut stack = HashMap::new();
+        stack.insert(
+            String::from("id"),
+            Value::String(String::from("minecraft:stone")),
+        );
+        stack.insert(String::from("Count"), Value::Byte(3));
+        tags.insert(String::from("Item"), Value::Compound(stack));
+        tags.insert(String::from("UUIDMost"), Value::Long(-5));
+        tags.insert(String::from("UUIDLeast"), Value::Long(7));
+
+        let item = match roundtrip(EntityData::from_compound(tags.clone())) {
+            EntityData::Item(item) => item,
+            other => panic!("expected item, got {:?}", other),
+        };
+        assert_eq!(item.age, 10);
+        assert_eq!(item.other.len(), 2);
+        assert_eq!(item.other.get("UUIDMost"), tags.get("UUIDMost"));
+
+        let mut tags = compound("minecraft:arrow");
+        tags.insert(String::from("crit"), Value::Byte(1));
+        tags.insert(String::from("pickup"), Value::Byte(1));
+        tags.insert(String::from("inGround"), Value::Byte(0));
+        tags.insert(String::from("damage"), Value::Double(2.0));
+
+        let arrow = match roundtrip(EntityData::from_compound(tags.clone())) {
+            EntityData::Arrow(arrow) => arrow,
+            other => panic!("expected arrow, got {:?}", other),
+        };
+        assert_eq!(arrow.critical, 1);
+        assert_eq!(arrow.other.len(), 3);
+        assert_eq!(arrow.other.get("damage"), tags.get("damage"));
+    }
+
+    #[test]
+    fn test_lore_is_plain_text() {
+        let mut nbt = ItemNbt::default();
+        nbt.meta.lore = vec![
+            Text::from("Plain"),
+            Text::from("Rare").gold().bold() + Text::from(" item"),
+        ];
+
+        let display = match nbt.to_compound().remove("display") {
+            Some(Value::Compound(display)) => display,
+            other => panic!("expected display compound, got {:?}", other),
+        };
+        assert_eq!(
+            display.get("Lore"),
+            Some(&Value::List(vec![
+                Value::String(String::from("Plain")),
+                Value::String(String::from("Rare item")),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_invalid_entity_is_kept() {
+        // Mobs need a health tag.
+        let tags = compound("minecraft:cow");
+        let entity = EntityData::from_compound(tags.clone());
+        match entity {
+            EntityData::Unknown(loaded) => assert_eq!(loaded, tags),
+            other => panic!("expected unknown entity, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_entity_ids() {
+        assert_eq!(
+            EntityDataKind::from_id("minecraft:rabbit"),
+            EntityDataKind::Rabbit
+        );
+        assert_eq!(EntityDataKind::Rabbit.id(), Some("minecraft:rabbit"));
+        assert_eq!(
+            EntityDataKind::from_id("minecraft:player"),
+            EntityDataKind::Unknown
+        );
+        assert_eq!(EntityDataKind::Unknown.id(), None);
+    }
 }
@@ -1,9 +1,12 @@
 use crate::entity::{AnimalData, ItemNbt};
+use crate::region;
+use crate::serialization_helper::nbt_value;
 use feather_inventory::player_constants::{
     HOTBAR_SIZE, INVENTORY_SIZE, SLOT_ARMOR_MAX, SLOT_ARMOR_MIN, SLOT_HOTBAR_OFFSET,
     SLOT_INVENTORY_OFFSET, SLOT_OFFHAND,
 };
 use feather_items::{Item, ItemStack};
+use flate2::read::GzDecoder;
 use nbt::Value;
 use serde::{Deserialize, Serialize};
 use std::{
@@ -27,10 +30,33 @@ pub struct PlayerData {
     pub inventory: Vec<InventorySlot>,
     #[serde(rename = "SelectedItemSlot")]
     pub held_item: i32,
+    #[serde(rename = "recipeBook", default)]
+    pub recipe_book: RecipeBookNbt,
 }
 
-/// Represents a single inventory slot (including position index).
+/// A player's recipe book: the recipes they have
+/// unlocked and the state of the recipe book GUI.
 #[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RecipeBookNbt {
+    /// IDs of unlocked recipes.
+    #[serde(default)]
+    pub recipes: Vec<String>,
+    /// IDs of unlocked recipes which have not
+    /// yet been viewed, highlighted as new.
+    #[serde(rename = "toBeDisplayed", default)]
+    pub to_be_displayed: Vec<String>,
+    #[serde(rename = "isGuiOpen", default)]
+    pub gui_open: bool,
+    #[serde(rename = "isFilteringCraftable", default)]
+    pub filtering_craftable: bool,
+    #[serde(rename = "isFurnaceGuiOpen", default)]
+    pub furnace_gui_open: bool,
+    #[serde(rename = "isFurnaceFilteringCraftable", default)]
+    pub furnace_filtering_craftable: bool,
+}
+
+/// Represents a single inventory slot (including position index).
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct InventorySlot {
     #[serde(rename = "Count")]
     pub count: i8,
@@ -65,7 +91,7 @@ impl InventorySlot {
 
     /// Converts an `ItemStack` and inventory position index into an `InventorySlot`.
     pub fn from_inventory_index(slot: i8, stack: ItemStack) -> Self {
-        let nbt = stack.into();
+        let nbt = ItemNbt::from(&stack);
         let nbt = if nbt == Default::default() {
             None
         } else {
@@ -107,12 +133,7 @@ impl InventorySlot {
         compound.insert(String::from("id"), Value::String(self.item));
         compound.insert(String::from("Slot"), Value::Byte(self.slot));
 
-        let mut tags_compound = HashMap::new();
-        if let Some(nbt) = self.nbt {
-            if let Some(damage) = nbt.damage {
-                tags_compound.insert(String::from("Damage"), Value::Int(damage));
-            }
-        }
+        let tags_compound = self.nbt.map(|nbt| nbt.to_compound()).unwrap_or_default();
         compound.insert(String::from("tag"), Value::Compound(tags_compound));
         Value::Compound(compound)
     }
@@ -135,13 +156,16 @@ impl From<&InventorySlot> for ItemStack {
     }
 }
 
-async fn load_from_file<R: AsyncRead + Unpin>(mut reader: R) -> Result<PlayerData, nbt::Error> {
+async fn load_from_file<R: AsyncRead + Unpin>(mut reader: R) -> Result<PlayerData, anyhow::Error> {
     let mut buf = vec![];
     tokio::io::copy(&mut reader, &mut buf).await?;
-    nbt::from_gzip_reader(buf.as_slice())
+
+    // Read through `Value` so that arrays in item tags are kept.
+    let compound = region::read_compound(GzDecoder::new(buf.as_slice()))?;
+    Ok(nbt_value::from_compound(&compound)?)
 }
 
-pub async fn load_player_data(world_dir: &Path, uuid: Uuid) -> Result<PlayerData, nbt::Error> {
+pub async fn load_player_data(world_dir: &Path, uuid: Uuid) -> Result<PlayerData, anyhow::Error> {
     let file_path = file_path(world_dir, uuid);
     let file = tokio::fs::File::open(file_path).await?;
     let data = load_from_file(file).await?;
@@ -179,6 +203,8 @@ mod tests {
     use feather_inventory::player_constants::{
         SLOT_ARMOR_CHEST, SLOT_ARMOR_FEET, SLOT_ARMOR_HEAD, SLOT_ARMOR_LEGS,
     };
+    use feather_items::Enchantment;
+    use feather_text::Text;
     use feather_util::Gamemode;
     use std::collections::HashMap;
     use std::io::Cursor;
@@ -190,7 +216,54 @@ mod tests {
         let player = load_from_file(cursor).await.unwrap();
         assert_eq!(player.gamemode, i32::from(Gamemode::Creative.id()));
         assert_eq!(player.inventory[0].item, "minecraft:diamond_shovel");
-        assert_eq!(player.inventory[0].nbt, Some(ItemNbt { damage: Some(3) }));
+        assert_eq!(
+            player.inventory[0].nbt,
+            Some(ItemNbt {
+                damage: Some(3),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recipe_book_roundtrip() {
+        let data = PlayerData {
+            recipe_book: RecipeBookNbt {
+                recipes: vec![String::from("minecraft:crafting_table")],
+                to_be_displayed: vec![String::from("minecraft:crafting_table")],
+                gui_open: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let mut buf = vec![];
+        save_to_file(&mut buf, &data).await.unwrap();
+        let loaded = load_from_file(Cursor::new(buf)).await.unwrap();
+        assert_eq!(loaded.recipe_book, data.recipe_book);
+    }
+
+    #[tokio::test]
+    async fn test_item_meta_roundtrip() {
+        let mut stack = ItemStack::new(Item::DiamondSword, 1);
+        stack.damage = Some(10);
+        let meta = stack.meta_mut();
+        meta.display_name = Some(Text::from("Excalibur"));
+        meta.lore.push(Text::from("Pulled from a stone"));
+        meta.enchantments
+            .push(Enchantment::new("minecraft:sharpness", 5));
+        meta.unbreakable = true;
+        meta.other.insert(String::from("HideFlags"), Value::Int(1));
+
+        let data = PlayerData {
+            inventory: vec![InventorySlot::from_inventory_index(0, stack.clone())],
+            ..Default::default()
+        };
+
+        let mut buf = vec![];
+        save_to_file(&mut buf, &data).await.unwrap();
+        let loaded = load_from_file(Cursor::new(buf)).await.unwrap();
+        assert_eq!(ItemStack::from(&loaded.inventory[0]), stack);
     }
 
     #[test]
@@ -213,7 +286,10 @@ mod tests {
             count: 1,
             slot: 2,
             item: String::from(Item::DiamondAxe.identifier()),
-            nbt: Some(ItemNbt { damage: Some(42) }),
+            nbt: Some(ItemNbt {
+                damage: Some(42),
+                ..Default::default()
+            }),
         };
 
         let item_stack: ItemStack = slot.into();
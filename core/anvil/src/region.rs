@@ -1,7 +1,7 @@
 //! This module implements the loading and saving
 //! of Anvil region files.
 
-use super::serialization_helper::packed_u9;
+use super::serialization_helper::{nbt_value, packed_u9};
 use crate::{block_entity::BlockEntityData, entity::EntityData};
 use bitvec::{bitvec, vec::BitVec};
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
@@ -9,9 +9,11 @@ use feather_biomes::Biome;
 use feather_blocks::BlockId;
 use feather_chunk::{BitArray, Chunk, ChunkSection};
 use feather_util::ChunkPosition;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use nbt::Value;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{self, Display, Formatter};
 use std::fs::{File, OpenOptions};
 use std::io::prelude::*;
@@ -31,24 +33,27 @@ const DATA_VERSION: i32 = 1631;
 const SECTOR_BYTES: usize = 4096;
 
 /// Represents the data for a chunk after the "Chunk [x, y]" tag.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Debug)]
 #[serde(rename_all = "PascalCase")]
 pub struct ChunkRoot {
     level: ChunkLevel,
     data_version: i32,
+    /// Tags not modelled above, written back unchanged.
+    #[serde(flatten, serialize_with = "nbt_value::serialize_compound")]
+    other: HashMap<String, Value>,
 }
 
 /// Represents the level data for a chunk.
-#[derive(Serialize, Deserialize, Debug)]
+///
+/// Only the data Feather keeps in its own chunks is modelled; everything
+/// else, such as structures and pending post-processing, is kept in `other`.
+#[derive(Serialize, Debug)]
 #[serde(rename_all = "PascalCase")]
 pub struct ChunkLevel {
-    // TODO heightmaps, etc.
     #[serde(rename = "xPos")]
     x_pos: i32,
     #[serde(rename = "zPos")]
     z_pos: i32,
-    last_update: i64,
-    inhabited_time: i64,
     sections: Vec<LevelSection>,
     #[serde(serialize_with = "nbt::i32_array")]
     biomes: Vec<i32>,
@@ -56,17 +61,144 @@ pub struct ChunkLevel {
     #[serde(rename = "TileEntities")]
     block_entities: Vec<BlockEntityData>,
     heightmaps: Heightmaps,
-    #[serde(rename = "ToBeTicked")]
-    awaiting_block_updates: Vec<Vec<i16>>,
-    #[serde(rename = "LiquidsToBeTicked")]
-    awaiting_liquid_updates: Vec<Vec<i16>>,
-    post_processing: Vec<Vec<i16>>,
     #[serde(rename = "TileTicks")]
     scheduled_block_updates: Vec<ScheduledBlockUpdate>,
     #[serde(rename = "LiquidTicks")]
     scheduled_liquid_updates: Vec<ScheduledBlockUpdate>,
-    #[serde(rename = "Status")]
-    worldgen_status: Cow<'static, str>,
+    /// Tags not modelled above, written back unchanged.
+    #[serde(flatten, serialize_with = "nbt_value::serialize_compound")]
+    other: HashMap<String, Value>,
+}
+
+impl ChunkRoot {
+    /// Reads the root compound of a chunk.
+    ///
+    /// The chunk is parsed from `Value`s rather than directly with
+    /// serde, since the deserializer reads arrays as lists, which
+    /// vanilla would not accept when the tags are written back.
+    fn from_compound(mut compound: HashMap<String, Value>) -> Result<Self, Error> {
+        // Check the data version first, as the level
+        // of other versions likely fails to parse.
+        let data_version = take_tag(&mut compound, "DataVersion")?;
+        if data_version != DATA_VERSION {
+            return Err(Error::UnsupportedDataVersion(data_version));
+        }
+
+        let level = match compound.remove("Level") {
+            Some(Value::Compound(level)) => ChunkLevel::from_compound(level)?,
+            _ => return Err(Error::InvalidTag("Level", "expected a compound".to_owned())),
+        };
+
+        Ok(Self {
+            level,
+            data_version,
+            other: compound,
+        })
+    }
+}
+
+impl ChunkLevel {
+    fn from_compound(mut compound: HashMap<String, Value>) -> Result<Self, Error> {
+        Ok(Self {
+            x_pos: take_tag(&mut compound, "xPos")?,
+            z_pos: take_tag(&mut compound, "zPos")?,
+            sections: take_tag(&mut compound, "Sections")?,
+            biomes: take_tag(&mut compound, "Biomes")?,
+            // Entities are kept verbatim if Feather does not know them,
+            // so they are parsed from their compounds directly.
+            entities: take_compounds(&mut compound, "Entities")?
+                .into_iter()
+                .map(EntityData::from_compound)
+                .collect(),
+            block_entities: take_tag(&mut compound, "TileEntities")?,
+            heightmaps: take_tag(&mut compound, "Heightmaps")?,
+            // Chunks which are not fully generated have no scheduled updates.
+            scheduled_block_updates: take_optional_tag(&mut compound, "TileTicks")?,
+            scheduled_liquid_updates: take_optional_tag(&mut compound, "LiquidTicks")?,
+            other: compound,
+        })
+    }
+}
+
+/// Removes the tag `name` from a compound and deserializes it.
+fn take_tag<T>(compound: &mut HashMap<String, Value>, name: &'static str) -> Result<T, Error>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let value = compound
+        .remove(name)
+        .ok_or_else(|| Error::InvalidTag(name, "missing".to_owned()))?;
+    nbt_value::from_value(&value).map_err(|e| Error::InvalidTag(name, e.to_string()))
+}
+
+/// Like `take_tag`, but returns the default value if the tag is missing.
+fn take_optional_tag<T>(
+    compound: &mut HashMap<String, Value>,
+    name: &'static str,
+) -> Result<T, Error>
+where
+    T: for<'de> Deserialize<'de> + Default,
+{
+    if compound.contains_key(name) {
+        take_tag(compound, name)
+    } else {
+        Ok(T::default())
+    }
+}
+
+/// Removes the list of compounds `name` from a compound.
+fn take_compounds(
+    compound: &mut HashMap<String, Value>,
+    name: &'static str,
+) -> Result<Vec<HashMap<String, Value>>, Error> {
+    let invalid = || Error::InvalidTag(name, "expected a list of compounds".to_owned());
+    match compound.remove(name) {
+        Some(Value::List(list)) => list
+            .into_iter()
+            .map(|value| match value {
+                Value::Compound(compound) => Ok(compound),
+                _ => Err(invalid()),
+            })
+            .collect(),
+        Some(_) => Err(invalid()),
+        None => Err(Error::InvalidTag(name, "missing".to_owned())),
+    }
+}
+
+/// The tags of a chunk which Feather does not model,
+/// such as structures, carving masks and post-processing.
+///
+/// These are returned when a chunk is loaded and should be
+/// passed back when it is saved, so that vanilla finds
+/// them unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkTags {
+    /// Unmodelled tags of the root compound.
+    root: HashMap<String, Value>,
+    /// Unmodelled tags of the `Level` compound.
+    level: HashMap<String, Value>,
+}
+
+impl ChunkTags {
+    /// Returns the tags of the `Level` compound to save,
+    /// with defaults for the tags vanilla expects to be present,
+    /// which chunks generated by Feather lack.
+    fn level_tags(&self) -> HashMap<String, Value> {
+        let mut level = self.level.clone();
+        let sixteen_lists = || Value::List(vec![Value::List(vec![]); 16]);
+        let defaults = vec![
+            ("LastUpdate", Value::Long(0)),
+            ("InhabitedTime", Value::Long(0)),
+            ("Status", Value::String("postprocessed".to_owned())),
+            ("PostProcessing", sixteen_lists()),
+            ("ToBeTicked", sixteen_lists()),
+            ("LiquidsToBeTicked", sixteen_lists()),
+        ];
+        for (name, value) in defaults {
+            level.entry(name.to_owned()).or_insert(value);
+        }
+        level
+    }
 }
 
 /// Represents the heightmap data of a chunk.
@@ -134,18 +266,19 @@ pub struct LevelProperties {
 }
 
 /// Represents a block update scheduled for a specific time.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ScheduledBlockUpdate {
-    /// The identifier of the type of this block
+    /// The identifier of the type of this block,
+    /// or of the fluid for liquid updates
     #[serde(rename = "i")]
-    name: Cow<'static, str>,
+    pub name: Cow<'static, str>,
     /// When this update should be executed in ticks from current time, can be negative if overdue
     #[serde(rename = "t")]
-    ticks_from_now: i32,
+    pub ticks_from_now: i32,
     /// Lower priority is handled first when happening on the same tick
-    #[serde(rename = "t")]
-    priority: i32,
-    // TODO are these global or chunk coordinates?
+    #[serde(rename = "p", default)]
+    pub priority: i32,
+    // Coordinates are global, not relative to the chunk.
     /// X coordinate
     pub x: i32,
     /// Y coordinate
@@ -181,10 +314,20 @@ impl RegionHandle {
     /// # Panics
     /// Panics if the specified chunk position is not within this
     /// region file.
-    pub fn load_chunk(
-        &mut self,
-        mut pos: ChunkPosition,
-    ) -> Result<(Chunk, Vec<EntityData>, Vec<BlockEntityData>), Error> {
+    pub fn load_chunk(&mut self, pos: ChunkPosition) -> Result<ChunkData, Error> {
+        self.read_chunk(pos)?.decode()
+    }
+
+    /// Reads the still-encoded data of the chunk at the given
+    /// position (global, not region-relative).
+    ///
+    /// Unlike `load_chunk`, this does not decode the chunk, so the
+    /// handle can be released before the more expensive decoding.
+    ///
+    /// # Panics
+    /// Panics if the specified chunk position is not within this
+    /// region file.
+    pub fn read_chunk(&mut self, mut pos: ChunkPosition) -> Result<RawChunk, Error> {
         // Get a copy of the original position before clipping
         let original_pos = pos;
         // Clip chunk position to region-local coordinates.
@@ -222,57 +365,22 @@ impl RegionHandle {
         }
 
         // Read `len` bytes into memory.
-        let mut buf = vec![0u8; len as usize];
-        self.file.read_exact(&mut buf).map_err(Error::Io)?;
-
-        // The compression type is indicated by a byte.
-        // 1 corresponds to gzip compression, while 2
-        // corresponds to zlib.
-        let compression_type = buf[0];
-
-        // Parse NBT data
-        let cursor = Cursor::new(&buf[1..]);
-        let root: ChunkRoot = match compression_type {
-            1 => nbt::from_gzip_reader(cursor).map_err(Error::Nbt)?,
-            2 => nbt::from_zlib_reader(cursor).map_err(Error::Nbt)?,
-            _ => return Err(Error::InvalidCompression(compression_type)),
-        };
-
-        // Check data version
-        if root.data_version != DATA_VERSION {
-            return Err(Error::UnsupportedDataVersion(root.data_version));
-        }
-
-        let level = &root.level;
-
-        let mut chunk = Chunk::new(original_pos);
-
-        // Read sections
-        for section in &level.sections {
-            read_section_into_chunk(section, &mut chunk)?;
-        }
+        let mut data = vec![0u8; len as usize];
+        self.file.read_exact(&mut data).map_err(Error::Io)?;
 
-        // Read biomes
-        if level.biomes.len() != 256 {
-            return Err(Error::IndexOutOfBounds);
-        }
-        for index in 0..256 {
-            let id = level.biomes[index];
-            chunk.biomes_mut()[index] =
-                Biome::from_protocol_id(id).ok_or_else(|| Error::InvalidBiomeId(id))?;
-        }
-
-        // Chunk was not modified, but it thinks it was: disable this
-        chunk.check_modified();
-
-        chunk.recalculate_heightmap();
-
-        Ok((chunk, level.entities.clone(), level.block_entities.clone()))
+        Ok(RawChunk {
+            pos: original_pos,
+            data,
+        })
     }
 
     /// Saves the given chunk to this region file. The header will be updated
     /// accordingly and saved as well.
     ///
+    /// `tags` are the tags the chunk was loaded with, which
+    /// are written back unchanged; chunks which were
+    /// not loaded from a region file have none.
+    ///
     /// Behavior may be unexpected if this region file does not contain the given
     /// chunk position.
     pub fn save_chunk(
@@ -280,9 +388,21 @@ impl RegionHandle {
         chunk: &Chunk,
         entities: &[EntityData],
         block_entities: &[BlockEntityData],
+        scheduled_updates: &ScheduledUpdates,
+        tags: &ChunkTags,
     ) -> Result<(), Error> {
-        let chunk_pos = chunk.position();
+        // Write chunk to `ChunkRoot` tag.
+        let root = chunk_to_chunk_root(chunk, entities, block_entities, scheduled_updates, tags);
 
+        self.write_chunk(chunk.position(), &root)
+    }
+
+    /// Writes the NBT data of the chunk at the given position.
+    fn write_chunk(
+        &mut self,
+        chunk_pos: ChunkPosition,
+        root: &impl Serialize,
+    ) -> Result<(), Error> {
         let (local_x, local_z) = (chunk_pos.x % 32, chunk_pos.z % 32);
 
         // Find position in header and deallocate it if it currently exists.
@@ -293,14 +413,11 @@ impl RegionHandle {
             self.allocator.free(location.0);
         }
 
-        // Write chunk to `ChunkRoot` tag.
-        let root = chunk_to_chunk_root(chunk, entities, block_entities);
-
         // Write to intermediate buffer, because we need to know the length.
         let mut buf = Vec::with_capacity(4096);
         buf.write_u8(2).map_err(Error::Io)?; // Compression type: zlib
 
-        nbt::to_zlib_writer(&mut buf, &root, None).map_err(Error::Nbt)?;
+        nbt::to_zlib_writer(&mut buf, root, None).map_err(Error::Nbt)?;
 
         let total_len = buf.len() + 4; // 4 bytes for length header
 
@@ -333,6 +450,13 @@ impl RegionHandle {
         Ok(())
     }
 
+    /// Writes the header and flushes the file to disk.
+    /// Should be called before the handle is closed.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.save_header().map_err(Error::Io)?;
+        self.file.sync_data().map_err(Error::Io)
+    }
+
     fn save_header(&mut self) -> Result<(), io::Error> {
         self.file.seek(SeekFrom::Start(0))?;
 
@@ -340,6 +464,110 @@ impl RegionHandle {
     }
 }
 
+/// A chunk decoded from a region file, along with its associated data.
+pub struct ChunkData {
+    pub chunk: Chunk,
+    pub entities: Vec<EntityData>,
+    pub block_entities: Vec<BlockEntityData>,
+    pub scheduled_updates: ScheduledUpdates,
+    pub tags: ChunkTags,
+}
+
+/// The block and liquid updates scheduled in a chunk,
+/// saved as `TileTicks` and `LiquidTicks`.
+#[derive(Debug, Clone, Default)]
+pub struct ScheduledUpdates {
+    pub blocks: Vec<ScheduledBlockUpdate>,
+    pub liquids: Vec<ScheduledBlockUpdate>,
+}
+
+/// The encoded data of a chunk, read from a region file.
+pub struct RawChunk {
+    /// The position of the chunk.
+    pos: ChunkPosition,
+    /// The compression type byte followed by the compressed NBT data.
+    data: Vec<u8>,
+}
+
+impl RawChunk {
+    /// Decodes the chunk along with its associated data.
+    pub fn decode(&self) -> Result<ChunkData, Error> {
+        // The compression type is indicated by a byte.
+        // 1 corresponds to gzip compression, while 2
+        // corresponds to zlib.
+        let compression_type = self.data[0];
+
+        // Parse NBT data
+        let cursor = Cursor::new(&self.data[1..]);
+        let compound = match compression_type {
+            1 => read_compound(GzDecoder::new(cursor))?,
+            2 => read_compound(ZlibDecoder::new(cursor))?,
+            _ => return Err(Error::InvalidCompression(compression_type)),
+        };
+        let root = ChunkRoot::from_compound(compound)?;
+
+        let level = root.level;
+
+        let mut chunk = Chunk::new(self.pos);
+
+        // Read sections
+        for section in &level.sections {
+            read_section_into_chunk(section, &mut chunk)?;
+        }
+
+        // Read biomes
+        if level.biomes.len() != 256 {
+            return Err(Error::IndexOutOfBounds);
+        }
+        for index in 0..256 {
+            let id = level.biomes[index];
+            chunk.biomes_mut()[index] =
+                Biome::from_protocol_id(id).ok_or_else(|| Error::InvalidBiomeId(id))?;
+        }
+
+        // Chunk was not modified, but it thinks it was: disable this
+        chunk.check_modified();
+
+        chunk.recalculate_heightmap();
+
+        Ok(ChunkData {
+            chunk,
+            entities: level.entities,
+            block_entities: level.block_entities,
+            scheduled_updates: ScheduledUpdates {
+                blocks: level.scheduled_block_updates,
+                liquids: level.scheduled_liquid_updates,
+            },
+            tags: ChunkTags {
+                root: root.other,
+                level: level.other,
+            },
+        })
+    }
+}
+
+/// Reads the root compound of NBT data, keeping the exact
+/// types of its tags.
+pub(crate) fn read_compound(mut reader: impl Read) -> Result<HashMap<String, Value>, Error> {
+    let id = reader.read_u8().map_err(Error::Io)?;
+    if id != 0x0a {
+        return Err(Error::MissingRootTag);
+    }
+
+    // Skip the name of the root tag, which is unused.
+    let name_len = reader.read_u16::<BigEndian>().map_err(Error::Io)?;
+    io::copy(
+        &mut reader.by_ref().take(u64::from(name_len)),
+        &mut io::sink(),
+    )
+    .map_err(Error::Io)?;
+
+    match Value::from_reader(id, &mut reader).map_err(Error::Nbt)? {
+        Value::Compound(compound) => Ok(compound),
+        _ => Err(Error::MissingRootTag),
+    }
+}
+
 fn read_section_into_chunk(section: &LevelSection, chunk: &mut Chunk) -> Result<(), Error> {
     let data = &section.states;
 
@@ -417,13 +645,13 @@ fn chunk_to_chunk_root(
     chunk: &Chunk,
     entities: &[EntityData],
     block_entities: &[BlockEntityData],
+    scheduled_updates: &ScheduledUpdates,
+    tags: &ChunkTags,
 ) -> ChunkRoot {
     ChunkRoot {
         level: ChunkLevel {
             x_pos: chunk.position().x,
             z_pos: chunk.position().z,
-            last_update: 0,    // TODO
-            inhabited_time: 0, // TODO
             block_entities: block_entities.into(),
             sections: chunk
                 .sections()
@@ -470,14 +698,12 @@ fn chunk_to_chunk_root(
                     .map(|h| h.world_surface())
                     .collect(),
             },
-            awaiting_block_updates: vec![vec![]; 16], // TODO
-            awaiting_liquid_updates: vec![vec![]; 16], // TODO
-            scheduled_block_updates: vec![],          // TODO
-            scheduled_liquid_updates: vec![],
-            post_processing: vec![vec![]; 16],
-            worldgen_status: "postprocessed".into(),
+            scheduled_block_updates: scheduled_updates.blocks.clone(),
+            scheduled_liquid_updates: scheduled_updates.liquids.clone(),
+            other: tags.level_tags(),
         },
         data_version: DATA_VERSION,
+        other: tags.root.clone(),
     }
 }
 
@@ -633,6 +859,8 @@ pub enum Error {
     IndexOutOfBounds,
     /// Invalid biome ID
     InvalidBiomeId(i32),
+    /// A tag of the chunk was missing or invalid
+    InvalidTag(&'static str, String),
 }
 
 impl Display for Error {
@@ -654,6 +882,7 @@ impl Display for Error {
             Error::MissingRootTag => f.write_str("Chunk is missing a root NBT tag")?,
             Error::IndexOutOfBounds => f.write_str("Section index out of bounds")?,
             Error::InvalidBiomeId(id) => write!(f, "Invalid biome ID {}", id)?,
+            Error::InvalidTag(name, msg) => write!(f, "Chunk tag {} is invalid: {}", name, msg)?,
         }
 
         Ok(())
@@ -942,4 +1171,338 @@ mod tests {
             }
         );
     }
+
+    fn compound(tags: Vec<(&str, Value)>) -> Value {
+        Value::Compound(
+            tags.into_iter()
+                .map(|(name, value)| (name.to_owned(), value))
+                .collect(),
+        )
+    }
+
+    fn level(root: &HashMap<String, Value>) -> &HashMap<String, Value> {
+        match &root["Level"] {
+            Value::Compound(level) => level,
+            _ => panic!("level is not a compound"),
+        }
+    }
+
+    /// Returns a chunk of stone as vanilla would save it, including
+    /// tags which Feather does not model.
+    fn vanilla_chunk(pos: ChunkPosition) -> HashMap<String, Value> {
+        let section = compound(vec![
+            ("Y", Value::Byte(0)),
+            (
+                "Palette",
+                Value::List(vec![compound(vec![(
+                    "Name",
+                    Value::String("minecraft:stone".to_owned()),
+                )])]),
+            ),
+            ("BlockStates", Value::LongArray(vec![0; 256])),
+            ("BlockLight", Value::ByteArray(vec![0; 2048])),
+            ("SkyLight", Value::ByteArray(vec![-1; 2048])),
+        ]);
+        let mut post_processing = vec![Value::List(vec![]); 16];
+        post_processing[0] = Value::List(vec![Value::Short(17)]);
+
+        let level = compound(vec![
+            ("xPos", Value::Int(pos.x)),
+            ("zPos", Value::Int(pos.z)),
+            ("LastUpdate", Value::Long(1234)),
+            ("InhabitedTime", Value::Long(56)),
+            ("Status", Value::String("fullchunk".to_owned())),
+            ("Sections", Value::List(vec![section])),
+            ("Biomes", Value::IntArray(vec![1; 256])),
+            ("Entities", Value::List(vanilla_entities(pos))),
+            ("TileEntities", Value::List(vec![])),
+            ("Heightmaps", compound(vec![])),
+            ("TileTicks", Value::List(vec![])),
+            ("ToBeTicked", Value::List(vec![Value::List(vec![]); 16])),
+            (
+                "LiquidsToBeTicked",
+                Value::List(vec![Value::List(vec![]); 16]),
+            ),
+            (
+                "Structures",
+                compound(vec![
+                    (
+                        "References",
+                        compound(vec![("Mineshaft", Value::LongArray(vec![4_294_967_297]))]),
+                    ),
+                    (
+                        "Starts",
+                        compound(vec![(
+                            "Mineshaft",
+                            compound(vec![("id", Value::String("INVALID".to_owned()))]),
+                        )]),
+                    ),
+                ]),
+            ),
+            (
+                "CarvingMasks",
+                compound(vec![
+                    ("AIR", Value::ByteArray(vec![1, 2, 3])),
+                    ("LIQUID", Value::ByteArray(vec![])),
+                ]),
+            ),
+            ("PostProcessing", Value::List(post_processing)),
+            (
+                "LiquidTicks",
+                Value::List(vec![compound(vec![
+                    ("i", Value::String("minecraft:water".to_owned())),
+                    ("x", Value::Int(pos.x * 16)),
+                    ("y", Value::Int(64)),
+                    ("z", Value::Int(pos.z * 16)),
+                    ("t", Value::Int(3)),
+                    ("p", Value::Int(0)),
+                ])]),
+            ),
+        ]);
+
+        match compound(vec![
+            ("Level", level),
+            ("DataVersion", Value::Int(DATA_VERSION)),
+            ("ForgeCaps", compound(vec![("test", Value::Byte(1))])),
+        ]) {
+            Value::Compound(root) => root,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns a zombie holding a firework and a firework
+    /// rocket, whose item tags contain int arrays.
+    fn vanilla_entities(pos: ChunkPosition) -> Vec<Value> {
+        let firework = compound(vec![
+            ("id", Value::String("minecraft:firework_rocket".to_owned())),
+            ("Count", Value::Byte(1)),
+            (
+                "tag",
+                compound(vec![(
+                    "Fireworks",
+                    compound(vec![
+                        ("Flight", Value::Byte(2)),
+                        (
+                            "Explosions",
+                            Value::List(vec![compound(vec![
+                                ("Type", Value::Byte(1)),
+                                ("Colors", Value::IntArray(vec![11_743_532, 3_887_386])),
+                                ("FadeColors", Value::IntArray(vec![])),
+                            ])]),
+                        ),
+                    ]),
+                )]),
+            ),
+        ]);
+        let base = |id: &str| {
+            vec![
+                ("id", Value::String(id.to_owned())),
+                (
+                    "Pos",
+                    Value::List(vec![
+                        Value::Double(f64::from(pos.x * 16) + 0.5),
+                        Value::Double(64.0),
+                        Value::Double(f64::from(pos.z * 16) + 0.5),
+                    ]),
+                ),
+                (
+                    "Rotation",
+                    Value::List(vec![Value::Float(90.0), Value::Float(0.0)]),
+                ),
+                ("Motion", Value::List(vec![Value::Double(0.0); 3])),
+                ("UUIDMost", Value::Long(-1_234_567)),
+                ("UUIDLeast", Value::Long(7_654_321)),
+            ]
+        };
+
+        let mut zombie = base("minecraft:zombie");
+        zombie.extend(vec![
+            ("Health", Value::Float(20.0)),
+            ("PersistenceRequired", Value::Byte(0)),
+            (
+                "HandItems",
+                Value::List(vec![firework.clone(), compound(vec![])]),
+            ),
+            ("HandDropChances", Value::List(vec![Value::Float(0.085); 2])),
+        ]);
+
+        let mut rocket = base("minecraft:firework_rocket");
+        rocket.extend(vec![
+            ("FireworksItem", firework),
+            ("Life", Value::Int(3)),
+            ("LifeTime", Value::Int(30)),
+        ]);
+
+        vec![compound(zombie), compound(rocket)]
+    }
+
+    /// A world directory which is removed when dropped.
+    struct TempWorld(PathBuf);
+
+    impl TempWorld {
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("feather-anvil-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            TempWorld(dir)
+        }
+    }
+
+    impl Drop for TempWorld {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Returns the root compound of a chunk without the sections and
+    /// heightmaps, which Feather encodes itself. Everything else
+    /// should be saved exactly as it was loaded.
+    fn without_blocks(root: &HashMap<String, Value>) -> HashMap<String, Value> {
+        let mut root = root.clone();
+        if let Some(Value::Compound(level)) = root.get_mut("Level") {
+            level.remove("Sections");
+            level.remove("Heightmaps");
+        }
+        root
+    }
+
+    fn read_root(region: &mut RegionHandle, pos: ChunkPosition) -> HashMap<String, Value> {
+        let raw = region.read_chunk(pos).unwrap();
+        read_compound(ZlibDecoder::new(&raw.data[1..])).unwrap()
+    }
+
+    #[test]
+    fn test_region_fixture_roundtrip() {
+        // A region file with chunks in the format of vanilla 1.13.2,
+        // with terrain, entities, a chest, scheduled ticks, structures,
+        // carving masks and post-processing. It was written to match
+        // vanilla's output rather than saved by a vanilla server.
+        let fixture = include_bytes!("r.0.0.mca");
+        let world = TempWorld::new("fixture");
+        let rpos = RegionPosition::from_chunk(ChunkPosition::new(0, 0));
+        create_region_dir(&world.0).unwrap();
+        fs::write(region_file_path(&world.0, rpos), &fixture[..]).unwrap();
+        let mut region = load_region(&world.0, rpos).unwrap();
+
+        let saved_world = TempWorld::new("fixture-saved");
+        let mut saved_region = create_region(&saved_world.0, rpos).unwrap();
+
+        let mut chunks = 0;
+        for x in 0..32 {
+            for z in 0..32 {
+                let pos = ChunkPosition::new(x, z);
+                let data = match region.load_chunk(pos) {
+                    Ok(data) => data,
+                    Err(Error::ChunkNotExist) => continue,
+                    Err(e) => panic!("failed to load chunk {}: {}", pos, e),
+                };
+                chunks += 1;
+
+                saved_region
+                    .save_chunk(
+                        &data.chunk,
+                        &data.entities,
+                        &data.block_entities,
+                        &data.scheduled_updates,
+                        &data.tags,
+                    )
+                    .unwrap();
+                assert_eq!(
+                    without_blocks(&read_root(&mut saved_region, pos)),
+                    without_blocks(&read_root(&mut region, pos)),
+                    "chunk {} was not saved as loaded",
+                    pos
+                );
+
+                let saved = saved_region.load_chunk(pos).unwrap();
+                for y in 0..256 {
+                    for (x, z) in (0..16).flat_map(|x| (0..16).map(move |z| (x, z))) {
+                        assert_eq!(
+                            saved.chunk.block_at(x, y, z),
+                            data.chunk.block_at(x, y, z),
+                            "block {} {} {} of chunk {} was not saved as loaded",
+                            x,
+                            y,
+                            z,
+                            pos
+                        );
+                    }
+                }
+            }
+        }
+        assert_eq!(chunks, 4);
+    }
+
+    #[test]
+    fn test_chunk_roundtrip() {
+        let world = TempWorld::new("tags");
+        let pos = ChunkPosition::new(33, -2);
+        let mut region = create_region(&world.0, RegionPosition::from_chunk(pos)).unwrap();
+
+        let original = vanilla_chunk(pos);
+        region
+            .write_chunk(pos, &nbt_value::Tag(&Value::Compound(original.clone())))
+            .unwrap();
+
+        let data = region.load_chunk(pos).unwrap();
+        assert_eq!(data.chunk.block_at(0, 0, 0), BlockId::stone());
+        assert_eq!(data.scheduled_updates.liquids.len(), 1);
+        region
+            .save_chunk(
+                &data.chunk,
+                &data.entities,
+                &data.block_entities,
+                &data.scheduled_updates,
+                &data.tags,
+            )
+            .unwrap();
+
+        let raw = region.read_chunk(pos).unwrap();
+        let saved = read_compound(ZlibDecoder::new(&raw.data[1..])).unwrap();
+        assert_eq!(saved.get("ForgeCaps"), original.get("ForgeCaps"));
+        for name in &[
+            "LastUpdate",
+            "InhabitedTime",
+            "Status",
+            "Structures",
+            "CarvingMasks",
+            "PostProcessing",
+            "LiquidTicks",
+        ] {
+            assert_eq!(
+                level(&saved).get(*name),
+                level(&original).get(*name),
+                "{} was not kept",
+                name
+            );
+        }
+
+        assert_eq!(without_blocks(&saved), without_blocks(&original));
+
+        // The saved chunk can be loaded again.
+        let data = region.load_chunk(pos).unwrap();
+        assert_eq!(data.chunk.block_at(15, 15, 15), BlockId::stone());
+    }
+
+    #[test]
+    fn test_default_level_tags() {
+        let level = ChunkTags::default().level_tags();
+        assert_eq!(
+            level.get("Status"),
+            Some(&Value::String("postprocessed".to_owned()))
+        );
+        assert_eq!(
+            level.get("PostProcessing"),
+            Some(&Value::List(vec![Value::List(vec![]); 16]))
+        );
+
+        // Tags a chunk was loaded with are not replaced.
+        let mut tags = ChunkTags::default();
+        tags.level
+            .insert("Status".to_owned(), Value::String("carved".to_owned()));
+        assert_eq!(
+            tags.level_tags().get("Status"),
+            Some(&Value::String("carved".to_owned()))
+        );
+    }
 }
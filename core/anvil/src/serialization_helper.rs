@@ -109,12 +109,404 @@ pub mod packed_u9 {
     }
 }
 
+/// Type-preserving (de)serialization of arbitrary NBT values.
+///
+/// The serde implementations of `nbt::Value` are untagged, so integers
+/// are read back as the smallest type which fits them and arrays are
+/// written as lists.
+///
+/// The NBT deserializer does not expose the type of arrays, so values
+/// should be deserialized from a `Value` with `from_value` or
+/// `from_compound`. Those present arrays to `deserialize_any` as a map
+/// with a single marker key, which is kept when serde buffers values,
+/// e.g. for flattened fields. Typed fields still read arrays as
+/// sequences, unless they are buffered.
+pub mod nbt_value {
+    use nbt::Value;
+    use serde::de::value::{Error as ValueError, MapDeserializer, SeqDeserializer};
+    use serde::de::Error as DeError;
+    use serde::de::{IntoDeserializer, MapAccess, SeqAccess, Visitor};
+    use serde::export::Formatter;
+    use serde::{forward_to_deserialize_any, Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::HashMap;
+    use std::iter;
+
+    /// Marker keys of arrays visited as maps.
+    const BYTE_ARRAY: &str = "__feather_nbt_byte_array__";
+    const INT_ARRAY: &str = "__feather_nbt_int_array__";
+    const LONG_ARRAY: &str = "__feather_nbt_long_array__";
+
+    /// Serializes the wrapped value as the NBT tag of the same type.
+    pub struct Tag<'a>(pub &'a Value);
+
+    impl Serialize for Tag<'_> {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            match self.0 {
+                Value::Byte(x) => serializer.serialize_i8(*x),
+                Value::Short(x) => serializer.serialize_i16(*x),
+                Value::Int(x) => serializer.serialize_i32(*x),
+                Value::Long(x) => serializer.serialize_i64(*x),
+                Value::Float(x) => serializer.serialize_f32(*x),
+                Value::Double(x) => serializer.serialize_f64(*x),
+                Value::String(x) => serializer.serialize_str(x),
+                Value::ByteArray(x) => nbt::i8_array(x.iter().copied(), serializer),
+                Value::IntArray(x) => nbt::i32_array(x.iter().copied(), serializer),
+                Value::LongArray(x) => nbt::i64_array(x.iter().copied(), serializer),
+                Value::List(x) => serializer.collect_seq(x.iter().map(Tag)),
+                Value::Compound(x) => serializer.collect_map(x.iter().map(|(k, v)| (k, Tag(v)))),
+            }
+        }
+    }
+
+    /// Deserializes any NBT tag into the `Value` of the same type.
+    pub struct OwnedTag(pub Value);
+
+    impl<'de> Deserialize<'de> for OwnedTag {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserializer.deserialize_any(TagVisitor).map(OwnedTag)
+        }
+    }
+
+    /// Deserializes an NBT compound, preserving the types of its values.
+    pub fn deserialize_compound<'de, D>(deserializer: D) -> Result<HashMap<String, Value>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match deserializer.deserialize_map(TagVisitor)? {
+            Value::Compound(compound) => Ok(compound),
+            _ => Err(D::Error::custom("expected a compound")),
+        }
+    }
+
+    /// Serializes an NBT compound, preserving the types of its values.
+    pub fn serialize_compound<S>(
+        compound: &HashMap<String, Value>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(compound.iter().map(|(k, v)| (k, Tag(v))))
+    }
+
+    /// Deserializes a `T` from an NBT value, as it would
+    /// be deserialized from the NBT the value was read from.
+    pub fn from_value<'de, T>(value: &'de Value) -> Result<T, ValueError>
+    where
+        T: Deserialize<'de>,
+    {
+        T::deserialize(ValueDeserializer(value))
+    }
+
+    /// Deserializes a `T` from an NBT compound, as with `from_value`.
+    pub fn from_compound<'de, T>(compound: &'de HashMap<String, Value>) -> Result<T, ValueError>
+    where
+        T: Deserialize<'de>,
+    {
+        T::deserialize(MapDeserializer::new(
+            compound
+                .iter()
+                .map(|(k, v)| (k.as_str(), ValueDeserializer(v))),
+        ))
+    }
+
+    struct ValueDeserializer<'de>(&'de Value);
+
+    impl<'de> IntoDeserializer<'de, ValueError> for ValueDeserializer<'de> {
+        type Deserializer = Self;
+
+        fn into_deserializer(self) -> Self {
+            self
+        }
+    }
+
+    impl<'de> Deserializer<'de> for ValueDeserializer<'de> {
+        type Error = ValueError;
+
+        fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, ValueError>
+        where
+            V: Visitor<'de>,
+        {
+            match self.0 {
+                Value::Byte(x) => visitor.visit_i8(*x),
+                Value::Short(x) => visitor.visit_i16(*x),
+                Value::Int(x) => visitor.visit_i32(*x),
+                Value::Long(x) => visitor.visit_i64(*x),
+                Value::Float(x) => visitor.visit_f32(*x),
+                Value::Double(x) => visitor.visit_f64(*x),
+                Value::String(x) => visitor.visit_borrowed_str(x),
+                Value::ByteArray(_) => self.visit_array(BYTE_ARRAY, visitor),
+                Value::IntArray(_) => self.visit_array(INT_ARRAY, visitor),
+                Value::LongArray(_) => self.visit_array(LONG_ARRAY, visitor),
+                Value::List(x) => {
+                    visitor.visit_seq(SeqDeserializer::new(x.iter().map(ValueDeserializer)))
+                }
+                Value::Compound(x) => visitor.visit_map(MapDeserializer::new(
+                    x.iter().map(|(k, v)| (k.as_str(), ValueDeserializer(v))),
+                )),
+            }
+        }
+
+        fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, ValueError>
+        where
+            V: Visitor<'de>,
+        {
+            match self.0 {
+                Value::ByteArray(_) | Value::IntArray(_) | Value::LongArray(_) => {
+                    ArrayElements(self.0).deserialize_any(visitor)
+                }
+                _ => self.deserialize_any(visitor),
+            }
+        }
+
+        fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, ValueError>
+        where
+            V: Visitor<'de>,
+        {
+            self.deserialize_seq(visitor)
+        }
+
+        fn deserialize_tuple_struct<V>(
+            self,
+            _name: &'static str,
+            _len: usize,
+            visitor: V,
+        ) -> Result<V::Value, ValueError>
+        where
+            V: Visitor<'de>,
+        {
+            self.deserialize_seq(visitor)
+        }
+
+        // NBT has no booleans, so they are stored as bytes.
+        fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, ValueError>
+        where
+            V: Visitor<'de>,
+        {
+            match self.0 {
+                Value::Byte(x) => visitor.visit_bool(*x != 0),
+                _ => self.deserialize_any(visitor),
+            }
+        }
+
+        // Missing tags are `None`, so any value present is `Some`.
+        fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, ValueError>
+        where
+            V: Visitor<'de>,
+        {
+            visitor.visit_some(self)
+        }
+
+        fn deserialize_newtype_struct<V>(
+            self,
+            _name: &'static str,
+            visitor: V,
+        ) -> Result<V::Value, ValueError>
+        where
+            V: Visitor<'de>,
+        {
+            visitor.visit_newtype_struct(self)
+        }
+
+        forward_to_deserialize_any! {
+            i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes byte_buf
+            unit unit_struct map struct enum identifier ignored_any
+        }
+    }
+
+    impl<'de> ValueDeserializer<'de> {
+        /// Visits an array as a map from `key` to its elements.
+        fn visit_array<V>(self, key: &'static str, visitor: V) -> Result<V::Value, ValueError>
+        where
+            V: Visitor<'de>,
+        {
+            visitor.visit_map(MapDeserializer::new(iter::once((
+                key,
+                ArrayElements(self.0),
+            ))))
+        }
+    }
+
+    /// Deserializes the elements of an array as a sequence.
+    struct ArrayElements<'de>(&'de Value);
+
+    impl<'de> IntoDeserializer<'de, ValueError> for ArrayElements<'de> {
+        type Deserializer = Self;
+
+        fn into_deserializer(self) -> Self {
+            self
+        }
+    }
+
+    impl<'de> Deserializer<'de> for ArrayElements<'de> {
+        type Error = ValueError;
+
+        fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, ValueError>
+        where
+            V: Visitor<'de>,
+        {
+            match self.0 {
+                Value::ByteArray(x) => visitor.visit_seq(SeqDeserializer::new(x.iter().copied())),
+                Value::IntArray(x) => visitor.visit_seq(SeqDeserializer::new(x.iter().copied())),
+                Value::LongArray(x) => visitor.visit_seq(SeqDeserializer::new(x.iter().copied())),
+                _ => Err(ValueError::custom("expected an array")),
+            }
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes byte_buf
+            option unit unit_struct newtype_struct seq tuple tuple_struct map struct
+            enum identifier ignored_any
+        }
+    }
+
+    struct TagVisitor;
+
+    impl<'de> Visitor<'de> for TagVisitor {
+        type Value = Value;
+
+        fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+            formatter.write_str("an NBT tag")
+        }
+
+        fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+            Ok(Value::Byte(v as i8))
+        }
+
+        fn visit_i8<E>(self, v: i8) -> Result<Value, E> {
+            Ok(Value::Byte(v))
+        }
+
+        fn visit_i16<E>(self, v: i16) -> Result<Value, E> {
+            Ok(Value::Short(v))
+        }
+
+        fn visit_i32<E>(self, v: i32) -> Result<Value, E> {
+            Ok(Value::Int(v))
+        }
+
+        fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+            Ok(Value::Long(v))
+        }
+
+        fn visit_f32<E>(self, v: f32) -> Result<Value, E> {
+            Ok(Value::Float(v))
+        }
+
+        fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+            Ok(Value::Double(v))
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+            Ok(Value::String(v.to_owned()))
+        }
+
+        fn visit_string<E>(self, v: String) -> Result<Value, E> {
+            Ok(Value::String(v))
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let mut list = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(OwnedTag(value)) = seq.next_element()? {
+                list.push(value);
+            }
+            Ok(Value::List(list))
+        }
+
+        fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserializer.deserialize_any(self)
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let mut compound = HashMap::with_capacity(map.size_hint().unwrap_or(0));
+            let mut key = map.next_key::<String>()?;
+
+            // Arrays visited by `ValueDeserializer`
+            match key.as_deref() {
+                Some(BYTE_ARRAY) => return Ok(Value::ByteArray(map.next_value()?)),
+                Some(INT_ARRAY) => return Ok(Value::IntArray(map.next_value()?)),
+                Some(LONG_ARRAY) => return Ok(Value::LongArray(map.next_value()?)),
+                _ => (),
+            }
+
+            while let Some(name) = key {
+                let OwnedTag(value) = map.next_value()?;
+                compound.insert(name, value);
+                key = map.next_key()?;
+            }
+            Ok(Value::Compound(compound))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use core::iter;
+    use nbt::Value;
     use serde::{Deserialize, Serialize};
     use serde_test::Token;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_nbt_value_keeps_arrays() {
+        let mut inner = HashMap::new();
+        inner.insert(String::from("Colors"), Value::IntArray(vec![1, 2, 3]));
+        inner.insert(String::from("Bytes"), Value::ByteArray(vec![-1, 0]));
+        let mut compound = HashMap::new();
+        compound.insert(
+            String::from("Explosions"),
+            Value::List(vec![Value::Compound(inner)]),
+        );
+        compound.insert(
+            String::from("Longs"),
+            Value::LongArray(vec![i64::max_value()]),
+        );
+        compound.insert(String::from("List"), Value::List(vec![Value::Int(4)]));
+
+        let nbt_value::OwnedTag(value) =
+            nbt_value::from_value(&Value::Compound(compound.clone())).unwrap();
+        assert_eq!(value, Value::Compound(compound.clone()));
+
+        // Buffered values, as for flattened fields
+        #[derive(Deserialize)]
+        struct Flattened {
+            #[serde(rename = "List")]
+            list: Vec<i32>,
+            #[serde(flatten, deserialize_with = "nbt_value::deserialize_compound")]
+            other: HashMap<String, Value>,
+        }
+
+        let flattened: Flattened = nbt_value::from_compound(&compound).unwrap();
+        assert_eq!(flattened.list, vec![4]);
+        compound.remove("List");
+        assert_eq!(flattened.other, compound);
+
+        // Typed fields read arrays as sequences
+        #[derive(Deserialize)]
+        struct Typed {
+            #[serde(rename = "Longs")]
+            longs: Vec<i64>,
+        }
+
+        let typed: Typed = nbt_value::from_compound(&compound).unwrap();
+        assert_eq!(typed.longs, vec![i64::max_value()]);
+    }
 
     #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
     struct TestPackedU9 {
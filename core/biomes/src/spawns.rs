@@ -0,0 +1,345 @@
+//! The mobs which spawn naturally in each biome.
+
+use crate::Biome;
+
+/// Categories of naturally spawning mobs. Each category
+/// has its own spawn conditions and mob cap.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SpawnCategory {
+    /// Hostile mobs, which spawn in the dark.
+    Monster,
+    /// Passive land animals, which spawn on grass.
+    Creature,
+    /// Bats, which spawn in the dark.
+    Ambient,
+    /// Squid, fish and dolphins, which spawn in water.
+    WaterCreature,
+}
+
+impl SpawnCategory {
+    /// Returns all categories.
+    pub fn values() -> [SpawnCategory; 4] {
+        [
+            SpawnCategory::Monster,
+            SpawnCategory::Creature,
+            SpawnCategory::Ambient,
+            SpawnCategory::WaterCreature,
+        ]
+    }
+
+    /// Returns the maximum number of mobs of this category
+    /// for 289 chunks (a 17x17 square) around players.
+    pub fn mob_cap(self) -> usize {
+        match self {
+            SpawnCategory::Monster => 70,
+            SpawnCategory::Creature => 10,
+            SpawnCategory::Ambient => 15,
+            SpawnCategory::WaterCreature => 5,
+        }
+    }
+
+    /// Returns whether mobs of this category are
+    /// peaceful animals, which only spawn occasionally.
+    pub fn is_peaceful(self) -> bool {
+        self == SpawnCategory::Creature
+    }
+}
+
+/// An entry of a biome's spawn list.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SpawnEntry {
+    /// The identifier of the mob, e.g. `minecraft:zombie`.
+    pub mob: &'static str,
+    /// The weight of this entry relative to the
+    /// other entries of the same category.
+    pub weight: u32,
+    /// The minimum number of mobs spawned in a group.
+    pub min_group: u32,
+    /// The maximum number of mobs spawned in a group.
+    pub max_group: u32,
+}
+
+const fn entry(mob: &'static str, weight: u32, min_group: u32, max_group: u32) -> SpawnEntry {
+    SpawnEntry {
+        mob,
+        weight,
+        min_group,
+        max_group,
+    }
+}
+
+const DEFAULT_MONSTERS: &[SpawnEntry] = &[
+    entry("minecraft:spider", 100, 4, 4),
+    entry("minecraft:zombie", 95, 4, 4),
+    entry("minecraft:zombie_villager", 5, 1, 1),
+    entry("minecraft:skeleton", 100, 4, 4),
+    entry("minecraft:creeper", 100, 4, 4),
+    entry("minecraft:slime", 100, 4, 4),
+    entry("minecraft:enderman", 10, 1, 4),
+    entry("minecraft:witch", 5, 1, 1),
+];
+
+const DESERT_MONSTERS: &[SpawnEntry] = &[
+    entry("minecraft:spider", 100, 4, 4),
+    entry("minecraft:zombie", 19, 4, 4),
+    entry("minecraft:zombie_villager", 1, 1, 1),
+    entry("minecraft:husk", 80, 4, 4),
+    entry("minecraft:skeleton", 100, 4, 4),
+    entry("minecraft:creeper", 100, 4, 4),
+    entry("minecraft:slime", 100, 4, 4),
+    entry("minecraft:enderman", 10, 1, 4),
+    entry("minecraft:witch", 5, 1, 1),
+];
+
+const SNOWY_MONSTERS: &[SpawnEntry] = &[
+    entry("minecraft:spider", 100, 4, 4),
+    entry("minecraft:zombie", 95, 4, 4),
+    entry("minecraft:zombie_villager", 5, 1, 1),
+    entry("minecraft:skeleton", 20, 4, 4),
+    entry("minecraft:stray", 80, 4, 4),
+    entry("minecraft:creeper", 100, 4, 4),
+    entry("minecraft:slime", 100, 4, 4),
+    entry("minecraft:enderman", 10, 1, 4),
+    entry("minecraft:witch", 5, 1, 1),
+];
+
+const NETHER_MONSTERS: &[SpawnEntry] = &[
+    entry("minecraft:ghast", 50, 4, 4),
+    entry("minecraft:zombie_pigman", 100, 4, 4),
+    entry("minecraft:magma_cube", 2, 4, 4),
+    entry("minecraft:enderman", 1, 4, 4),
+];
+
+const END_MONSTERS: &[SpawnEntry] = &[entry("minecraft:enderman", 10, 4, 4)];
+
+const DEFAULT_CREATURES: &[SpawnEntry] = &[
+    entry("minecraft:sheep", 12, 4, 4),
+    entry("minecraft:pig", 10, 4, 4),
+    entry("minecraft:chicken", 10, 4, 4),
+    entry("minecraft:cow", 8, 4, 4),
+];
+
+const PLAINS_CREATURES: &[SpawnEntry] = &[
+    entry("minecraft:horse", 5, 2, 6),
+    entry("minecraft:donkey", 1, 1, 3),
+];
+
+const SAVANNA_CREATURES: &[SpawnEntry] = &[
+    entry("minecraft:horse", 1, 2, 6),
+    entry("minecraft:donkey", 1, 1, 1),
+];
+
+const FOREST_CREATURES: &[SpawnEntry] = &[entry("minecraft:wolf", 5, 4, 4)];
+
+const FLOWER_FOREST_CREATURES: &[SpawnEntry] = &[
+    entry("minecraft:wolf", 5, 4, 4),
+    entry("minecraft:rabbit", 4, 2, 3),
+];
+
+const TAIGA_CREATURES: &[SpawnEntry] = &[
+    entry("minecraft:wolf", 8, 4, 4),
+    entry("minecraft:rabbit", 4, 2, 3),
+];
+
+const JUNGLE_CREATURES: &[SpawnEntry] = &[
+    entry("minecraft:parrot", 40, 1, 2),
+    entry("minecraft:ocelot", 2, 1, 1),
+];
+
+const MOUNTAIN_CREATURES: &[SpawnEntry] = &[entry("minecraft:llama", 5, 4, 6)];
+
+const DEFAULT_AMBIENT: &[SpawnEntry] = &[entry("minecraft:bat", 10, 8, 8)];
+
+impl Biome {
+    /// Returns the mobs of the given category which
+    /// spawn naturally in this biome.
+    pub fn spawn_entries(self, category: SpawnCategory) -> Vec<SpawnEntry> {
+        match category {
+            SpawnCategory::Monster => self.monsters(),
+            SpawnCategory::Creature => self.creatures(),
+            SpawnCategory::Ambient => self.ambient(),
+            SpawnCategory::WaterCreature => self.water_creatures(),
+        }
+    }
+
+    fn monsters(self) -> Vec<SpawnEntry> {
+        use Biome::*;
+        let mut entries = match self {
+            Desert | DesertHills | DesertLakes => DESERT_MONSTERS.to_vec(),
+            SnowyTundra | IceSpikes | SnowyMountains => SNOWY_MONSTERS.to_vec(),
+            Nether => NETHER_MONSTERS.to_vec(),
+            TheEnd | EndBarrens | EndHighlands | EndMidlands | SmallEndIslands => {
+                END_MONSTERS.to_vec()
+            }
+            MushroomFields | MushroomFieldShore | TheVoid => vec![],
+            _ => DEFAULT_MONSTERS.to_vec(),
+        };
+
+        match self {
+            Swamp | SwampHills => entries.push(entry("minecraft:slime", 1, 1, 1)),
+            River | FrozenRiver => entries.push(entry("minecraft:drowned", 100, 1, 1)),
+            _ if self.is_ocean() => entries.push(entry("minecraft:drowned", 5, 1, 1)),
+            _ => (),
+        }
+
+        entries
+    }
+
+    fn creatures(self) -> Vec<SpawnEntry> {
+        use Biome::*;
+        match self {
+            Desert | DesertHills | DesertLakes => vec![entry("minecraft:rabbit", 4, 2, 3)],
+            SnowyTundra | IceSpikes | SnowyMountains => vec![
+                entry("minecraft:rabbit", 10, 2, 3),
+                entry("minecraft:polar_bear", 1, 1, 2),
+            ],
+            FrozenOcean | DeepFrozenOcean => vec![entry("minecraft:polar_bear", 1, 1, 2)],
+            Beach => vec![entry("minecraft:turtle", 5, 2, 5)],
+            MushroomFields | MushroomFieldShore => vec![entry("minecraft:mooshroom", 8, 4, 8)],
+            _ if self.is_ocean() => vec![],
+            River | FrozenRiver | SnowyBeach | StoneShore => vec![],
+            Nether | TheEnd | EndBarrens | EndHighlands | EndMidlands | SmallEndIslands
+            | TheVoid => vec![],
+            _ => {
+                let extra: &[SpawnEntry] = match self {
+                    Plains | SunflowerPlains => PLAINS_CREATURES,
+                    Savanna | SavannaPlateau | ShatteredSavanna | ShatteredSavannaPlateau => {
+                        SAVANNA_CREATURES
+                    }
+                    Forest | WoodedHills => FOREST_CREATURES,
+                    FlowerForest => FLOWER_FOREST_CREATURES,
+                    Taiga
+                    | TaigaHills
+                    | TaigaMountains
+                    | GiantTreeTaiga
+                    | GiantTreeTaigaHills
+                    | GiantSpruceTaiga
+                    | GiantSpruceTaigaHills
+                    | SnowyTaiga
+                    | SnowyTaigaHills
+                    | SnowyTaigaMountains => TAIGA_CREATURES,
+                    Jungle | JungleHills | JungleEdge | ModifiedJungle | ModifiedJungleEdge => {
+                        JUNGLE_CREATURES
+                    }
+                    Mountains
+                    | WoodedMountains
+                    | GravellyMountains
+                    | ModifiedGravellyMountains
+                    | MountainEdge => MOUNTAIN_CREATURES,
+                    _ => &[],
+                };
+                let mut entries = DEFAULT_CREATURES.to_vec();
+                entries.extend_from_slice(extra);
+                entries
+            }
+        }
+    }
+
+    fn ambient(self) -> Vec<SpawnEntry> {
+        use Biome::*;
+        match self {
+            Nether | TheEnd | EndBarrens | EndHighlands | EndMidlands | SmallEndIslands
+            | TheVoid => vec![],
+            _ => DEFAULT_AMBIENT.to_vec(),
+        }
+    }
+
+    fn water_creatures(self) -> Vec<SpawnEntry> {
+        use Biome::*;
+        match self {
+            Ocean | DeepOcean => vec![
+                entry("minecraft:squid", 1, 1, 4),
+                entry("minecraft:cod", 10, 3, 6),
+                entry("minecraft:dolphin", 1, 1, 2),
+            ],
+            WarmOcean | DeepWarmOcean => vec![
+                entry("minecraft:squid", 10, 4, 4),
+                entry("minecraft:pufferfish", 15, 1, 3),
+                entry("minecraft:tropical_fish", 25, 8, 8),
+                entry("minecraft:dolphin", 2, 1, 2),
+            ],
+            LukewarmOcean | DeepLukewarmOcean => vec![
+                entry("minecraft:squid", 10, 1, 4),
+                entry("minecraft:cod", 15, 3, 6),
+                entry("minecraft:pufferfish", 5, 1, 3),
+                entry("minecraft:tropical_fish", 25, 8, 8),
+                entry("minecraft:dolphin", 2, 1, 2),
+            ],
+            ColdOcean | DeepColdOcean => vec![
+                entry("minecraft:squid", 3, 1, 4),
+                entry("minecraft:cod", 15, 3, 6),
+                entry("minecraft:salmon", 15, 1, 5),
+            ],
+            FrozenOcean | DeepFrozenOcean => vec![
+                entry("minecraft:squid", 1, 1, 4),
+                entry("minecraft:salmon", 15, 1, 5),
+            ],
+            River | FrozenRiver => vec![
+                entry("minecraft:squid", 2, 1, 4),
+                entry("minecraft:salmon", 5, 1, 5),
+            ],
+            Nether | TheEnd | EndBarrens | EndHighlands | EndMidlands | SmallEndIslands
+            | TheVoid => vec![],
+            _ => vec![entry("minecraft:squid", 10, 4, 4)],
+        }
+    }
+
+    /// Returns whether this biome is one of the oceans.
+    pub fn is_ocean(self) -> bool {
+        use Biome::*;
+        matches!(
+            self,
+            Ocean
+                | DeepOcean
+                | WarmOcean
+                | DeepWarmOcean
+                | LukewarmOcean
+                | DeepLukewarmOcean
+                | ColdOcean
+                | DeepColdOcean
+                | FrozenOcean
+                | DeepFrozenOcean
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawn_entries() {
+        let plains = Biome::Plains.spawn_entries(SpawnCategory::Creature);
+        assert!(plains.iter().any(|entry| entry.mob == "minecraft:horse"));
+        assert!(plains.iter().any(|entry| entry.mob == "minecraft:cow"));
+
+        let desert = Biome::Desert.spawn_entries(SpawnCategory::Monster);
+        assert!(desert.iter().any(|entry| entry.mob == "minecraft:husk"));
+
+        assert!(Biome::MushroomFields
+            .spawn_entries(SpawnCategory::Monster)
+            .is_empty());
+        assert!(Biome::Nether
+            .spawn_entries(SpawnCategory::Ambient)
+            .is_empty());
+        assert!(Biome::Ocean
+            .spawn_entries(SpawnCategory::Monster)
+            .iter()
+            .any(|entry| entry.mob == "minecraft:drowned"));
+    }
+
+    #[test]
+    fn groups_are_valid() {
+        use strum::IntoEnumIterator;
+
+        for biome in Biome::iter() {
+            for category in &SpawnCategory::values() {
+                for entry in biome.spawn_entries(*category) {
+                    assert!(entry.weight > 0);
+                    assert!(entry.min_group >= 1);
+                    assert!(entry.min_group <= entry.max_group);
+                }
+            }
+        }
+    }
+}
@@ -108,7 +108,7 @@ impl ToMetaEntry for bool {
 
 impl ToMetaEntry for Slot {
     fn to_meta_entry(&self) -> MetaEntry {
-        MetaEntry::Slot(*self)
+        MetaEntry::Slot(self.clone())
     }
 }
 
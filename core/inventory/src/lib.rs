@@ -157,7 +157,7 @@ impl Inventory {
     pub fn crafting_table() -> Self {
         let slots = btreemap! {
             Area::CraftingInput => empty(9),
-            Area::CraftingOutput => empty(9),
+            Area::CraftingOutput => empty(1),
         };
 
         Self { slots }
@@ -176,7 +176,9 @@ impl Inventory {
     /// Returns the item at the given
     /// index inside some area.
     pub fn item_at(&self, area: Area, index: usize) -> Result<Slot, Error> {
-        self.slot(area, index).map(RwLock::read).map(|guard| *guard)
+        self.slot(area, index)
+            .map(RwLock::read)
+            .map(|guard| guard.clone())
     }
 
     /// Returns a mutable guard for an item
@@ -190,13 +192,12 @@ impl Inventory {
     /// Returns the old item in the slot.
     pub fn set_item_at(&self, area: Area, index: usize, stack: ItemStack) -> Result<Slot, Error> {
         let mut slot = self.item_at_mut(area, index)?;
-        let old = *slot;
-        *slot = if stack.amount == 0 {
+        let new = if stack.amount == 0 {
             Slot::None
         } else {
             Slot::Some(stack)
         };
-        Ok(old)
+        Ok(std::mem::replace(&mut *slot, new))
     }
 
     /// Removes the item at the given position. Returns
@@ -207,6 +208,15 @@ impl Inventory {
         Ok(item.take())
     }
 
+    /// Returns the items in the given area, in index order.
+    pub fn items_in_area(&self, area: Area) -> Result<Vec<Slot>, Error> {
+        Ok(self
+            .slots(area)?
+            .iter()
+            .map(|slot| slot.read().clone())
+            .collect())
+    }
+
     /// Returns an iterator over mutable references to all
     /// items in this inventory.
     pub fn iter_mut(&self) -> impl Iterator<Item = RwLockWriteGuard<Slot>> {
@@ -221,7 +231,7 @@ impl Inventory {
         self.slots
             .iter()
             .flat_map(|(area, slots)| std::iter::repeat(*area).zip(slots.iter().enumerate()))
-            .map(|(area, (index, slot))| (SlotIndex { area, slot: index }, *slot.read()))
+            .map(|(area, (index, slot))| (SlotIndex { area, slot: index }, slot.read().clone()))
     }
 
     /// Returns an iterator over the areas in this inventory.
@@ -242,7 +252,7 @@ impl Inventory {
         // First, look for slots already having the type.
         for (area, slot) in COLLECT_SEARCH_ORDER.iter() {
             if let Some(slot_item) = self.item_at(*area, *slot).expect("index out of bounds") {
-                if slot_item.eq_ignore_amount(item) {
+                if slot_item.eq_ignore_amount(&item) {
                     self.add_to_stack(
                         &mut item,
                         slot_item,
@@ -279,7 +289,7 @@ impl Inventory {
             }
 
             if let Some(slot_item) = slot_item {
-                if slot_item.eq_ignore_amount(item) {
+                if slot_item.eq_ignore_amount(&item) {
                     self.add_to_stack(
                         &mut item,
                         slot_item,
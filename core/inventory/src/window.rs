@@ -133,6 +133,17 @@ impl Window {
         }
     }
 
+    /// Creates a new `Window` for an opened crafting table.
+    ///
+    /// `table` is the entity holding the crafting grid.
+    pub fn crafting_table(player: Entity, table: Entity) -> Self {
+        Self {
+            protocol_to_slot: crafting_table_to_slot,
+            slot_to_protocol: crafting_table_from_slot,
+            inventories: smallvec![player, table],
+        }
+    }
+
     /// Returns the entities other than the player
     /// which this window wraps over. For example,
     /// for `Window::chest(),` this will return the chest.
@@ -212,11 +223,8 @@ impl<'a> WindowAccessor<'a> {
     /// Returns the old slot.
     pub fn set_slot_at(&self, index: usize, slot: Slot) -> Result<Slot, crate::Error> {
         self.with_inv(index, |inv, idx| {
-            inv.item_at_mut(idx.area, idx.slot).map(|mut guard| {
-                let old = *guard;
-                *guard = slot;
-                old
-            })
+            inv.item_at_mut(idx.area, idx.slot)
+                .map(|mut guard| std::mem::replace(&mut *guard, slot))
         })
     }
 
@@ -321,6 +329,28 @@ fn large_chest_from_slot(slot: Index) -> usize {
     }
 }
 
+/// https://wiki.vg/Inventory#Crafting_table
+fn crafting_table_to_slot(x: usize) -> Option<Index> {
+    Some(match x {
+        0 => index(1, Area::CraftingOutput, 0),
+        1..=9 => index(1, Area::CraftingInput, x - 1),
+        10..=36 => index(0, Area::Main, x - 10),
+        37..=45 => index(0, Area::Hotbar, x - 37),
+        _ => return None,
+    })
+}
+
+fn crafting_table_from_slot(slot: Index) -> usize {
+    use Area::*;
+    match slot.area {
+        CraftingOutput => 0,
+        CraftingInput => slot.slot + 1,
+        Main => slot.slot + 10,
+        Hotbar => slot.slot + 37,
+        x => panic!("unreachable area {:?} for crafting table window", x),
+    }
+}
+
 fn index(inventory: usize, area: Area, slot: usize) -> Index {
     Index {
         inventory,
@@ -343,6 +373,16 @@ mod tests {
         (0..62).for_each(|i| assert_eq!(i, chest_from_slot(chest_to_slot(i).unwrap())));
     }
 
+    #[test]
+    fn crafting_table_roundtrip() {
+        (0..=45).for_each(|i| {
+            assert_eq!(
+                i,
+                crafting_table_from_slot(crafting_table_to_slot(i).unwrap())
+            )
+        });
+    }
+
     #[test]
     fn large_chest_roundtrip() {
         (0..89).for_each(|i| assert_eq!(i, large_chest_from_slot(large_chest_to_slot(i).unwrap())));
@@ -1,11 +1,14 @@
 #![forbid(unsafe_code, warnings)]
 
 pub use feather_definitions::Item;
+pub use meta::{BookContents, Enchantment, ItemMeta};
+
+mod meta;
 
 /// Represents an item stack.
 ///
 /// An item stack includes a type, an amount, and a bunch of properties (enchantments, etc.)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ItemStack {
     /// The type of this item.
     pub ty: Item,
@@ -13,7 +16,9 @@ pub struct ItemStack {
     pub amount: u8,
     /// Amount of damage taken on tools/equipment (how much durability expended).
     pub damage: Option<i32>,
-    // TODO enchantments, more
+    /// Enchantments, custom name and other properties. Boxed
+    /// since most items have none.
+    pub meta: Option<Box<ItemMeta>>,
 }
 
 impl Default for ItemStack {
@@ -28,18 +33,27 @@ impl ItemStack {
             ty,
             amount,
             damage: None,
+            meta: None,
         }
     }
 
     /// Create a copy of the `ItemStack` which has the specified amount of items.
-    pub fn of_amount(self, amount: u8) -> Self {
-        let mut s = self;
+    pub fn of_amount(&self, amount: u8) -> Self {
+        let mut s = self.clone();
         s.amount = amount;
         s
     }
 
-    pub fn eq_ignore_amount(self, other: Self) -> bool {
-        self.of_amount(0) == other.of_amount(0)
+    /// Returns whether two stacks are equal apart from their
+    /// amounts, i.e. whether they can be merged into one stack.
+    pub fn eq_ignore_amount(&self, other: &Self) -> bool {
+        self.ty == other.ty && self.damage == other.damage && self.meta == other.meta
+    }
+
+    /// Returns this stack's meta, inserting empty
+    /// meta if it has none.
+    pub fn meta_mut(&mut self) -> &mut ItemMeta {
+        self.meta.get_or_insert_with(Default::default)
     }
 }
 
@@ -53,4 +67,18 @@ mod tests {
         assert_eq!(item.vanilla_id(), 0);
         assert_eq!(Item::from_vanilla_id(0), Some(item));
     }
+
+    #[test]
+    fn test_eq_ignore_amount() {
+        let sword = ItemStack::new(Item::DiamondSword, 1);
+        let mut enchanted = sword.clone();
+        enchanted
+            .meta_mut()
+            .enchantments
+            .push(Enchantment::new("minecraft:sharpness", 5));
+
+        assert!(sword.eq_ignore_amount(&sword.of_amount(2)));
+        assert!(!sword.eq_ignore_amount(&enchanted));
+        assert!(enchanted.eq_ignore_amount(&enchanted.of_amount(3)));
+    }
 }
@@ -0,0 +1,76 @@
+//! Item properties stored in an item's NBT tag.
+//!
+//! See https://minecraft.gamepedia.com/Player.dat_format#Item_structure
+
+use feather_text::Text;
+use std::collections::HashMap;
+
+/// Properties of an item stack beyond its type, amount
+/// and damage: custom names, enchantments, book contents, etc.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ItemMeta {
+    /// Custom name of the item, e.g. as set using an anvil.
+    pub display_name: Option<Text>,
+    /// Lines of text shown below the item name.
+    pub lore: Vec<Text>,
+    pub enchantments: Vec<Enchantment>,
+    /// Enchantments held by an enchanted book, which
+    /// can be applied to other items using an anvil.
+    pub stored_enchantments: Vec<Enchantment>,
+    /// Whether the item never loses durability.
+    pub unbreakable: bool,
+    /// Number of experience levels added to the cost of
+    /// repairing or combining this item in an anvil.
+    pub repair_cost: Option<i32>,
+    /// Custom model index used by resource packs.
+    pub custom_model_data: Option<i32>,
+    /// Contents of a writable or written book.
+    pub book: Option<BookContents>,
+    /// Tags which the server does not interpret. These
+    /// are preserved so that items are saved and sent
+    /// to clients unchanged.
+    pub other: HashMap<String, nbt::Value>,
+}
+
+impl ItemMeta {
+    /// Returns whether this meta has no properties set,
+    /// i.e. whether it is equal to the default meta.
+    pub fn is_empty(&self) -> bool {
+        *self == ItemMeta::default()
+    }
+}
+
+/// An enchantment on an item.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Enchantment {
+    /// Namespaced ID of the enchantment, e.g. "minecraft:sharpness".
+    pub id: String,
+    pub level: i16,
+}
+
+impl Enchantment {
+    pub fn new(id: impl Into<String>, level: i16) -> Self {
+        Self {
+            id: id.into(),
+            level,
+        }
+    }
+}
+
+/// Contents of a book and quill or written book.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BookContents {
+    /// The book's pages. These are plain strings for books
+    /// and quills and JSON text for written books.
+    pub pages: Vec<String>,
+    /// Title of a written book.
+    pub title: Option<String>,
+    /// Author of a written book.
+    pub author: Option<String>,
+    /// Copy tier of a written book: 0 for the original,
+    /// 1 for a copy of the original, etc.
+    pub generation: Option<i32>,
+    /// Whether selectors and scores in a written book's
+    /// pages have been resolved.
+    pub resolved: bool,
+}
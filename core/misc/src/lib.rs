@@ -4,7 +4,7 @@ use ordinalizer::Ordinal;
 
 /// This is an enum over the kinds of particles
 /// listed on [the Particle data type](https://wiki.vg/index.php?title=Protocol&diff=14889&oldid=14881#Particle).
-#[derive(Copy, Clone, Debug, PartialEq, Ordinal)]
+#[derive(Clone, Debug, PartialEq, Ordinal)]
 pub enum ParticleData {
     AmbientEntityEffect,
     AngryVillager,
@@ -0,0 +1,363 @@
+//! Generates the tables used to translate IDs between 1.13.2,
+//! the server's native protocol version, and 1.14.4.
+//!
+//! The tables are computed from the vanilla reports of both
+//! versions and dumped into `${OUT_DIR}/v1_14_4.rs` for inclusion
+//! in `feather-network`.
+
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufReader, Write};
+
+/// Blocks and items which were renamed in 1.14.
+const RENAMES: &[(&str, &str)] = &[
+    ("minecraft:sign", "minecraft:oak_sign"),
+    ("minecraft:wall_sign", "minecraft:oak_wall_sign"),
+    ("minecraft:stone_slab", "minecraft:smooth_stone_slab"),
+    ("minecraft:rose_red", "minecraft:red_dye"),
+    ("minecraft:dandelion_yellow", "minecraft:yellow_dye"),
+    ("minecraft:cactus_green", "minecraft:green_dye"),
+];
+
+/// The 1.13.2 entity type registry, indexed by protocol ID.
+/// (The 1.13.2 reports do not include registries.)
+const ENTITY_TYPES_1_13_2: &[&str] = &[
+    "area_effect_cloud",
+    "armor_stand",
+    "arrow",
+    "bat",
+    "blaze",
+    "boat",
+    "cave_spider",
+    "chicken",
+    "cod",
+    "cow",
+    "creeper",
+    "donkey",
+    "dolphin",
+    "dragon_fireball",
+    "drowned",
+    "elder_guardian",
+    "end_crystal",
+    "ender_dragon",
+    "enderman",
+    "endermite",
+    "evoker_fangs",
+    "evoker",
+    "experience_orb",
+    "eye_of_ender",
+    "falling_block",
+    "firework_rocket",
+    "ghast",
+    "giant",
+    "guardian",
+    "horse",
+    "husk",
+    "illusioner",
+    "item",
+    "item_frame",
+    "fireball",
+    "leash_knot",
+    "llama",
+    "llama_spit",
+    "magma_cube",
+    "minecart",
+    "chest_minecart",
+    "command_block_minecart",
+    "furnace_minecart",
+    "hopper_minecart",
+    "spawner_minecart",
+    "tnt_minecart",
+    "mule",
+    "mooshroom",
+    "ocelot",
+    "painting",
+    "parrot",
+    "pig",
+    "pufferfish",
+    "zombie_pigman",
+    "polar_bear",
+    "tnt",
+    "rabbit",
+    "salmon",
+    "sheep",
+    "shulker",
+    "shulker_bullet",
+    "silverfish",
+    "skeleton",
+    "skeleton_horse",
+    "slime",
+    "small_fireball",
+    "snow_golem",
+    "snowball",
+    "spectral_arrow",
+    "spider",
+    "squid",
+    "stray",
+    "tropical_fish",
+    "turtle",
+    "egg",
+    "ender_pearl",
+    "experience_bottle",
+    "potion",
+    "vex",
+    "villager",
+    "iron_golem",
+    "vindicator",
+    "witch",
+    "wither",
+    "wither_skeleton",
+    "wither_skull",
+    "wolf",
+    "zombie",
+    "zombie_horse",
+    "zombie_villager",
+    "phantom",
+    "lightning_bolt",
+    "player",
+    "fishing_bobber",
+    "trident",
+];
+
+#[derive(Debug, Deserialize)]
+struct BlockReport {
+    states: Vec<BlockState>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockState {
+    id: u16,
+    #[serde(default)]
+    properties: BTreeMap<String, String>,
+    #[serde(default)]
+    default: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryEntry {
+    protocol_id: u16,
+}
+
+#[derive(Debug, Deserialize)]
+struct Registry {
+    entries: HashMap<String, RegistryEntry>,
+}
+
+fn main() {
+    if let Err(e) = run() {
+        panic!("{:?}", e);
+    }
+
+    println!(
+        "cargo:rerun-if-changed={}",
+        concat!(env!("CARGO_MANIFEST_DIR"), "/build.rs")
+    );
+}
+
+fn run() -> anyhow::Result<()> {
+    let reports_1_13 = format!(
+        "{}/minecraft/generated/reports",
+        feather_data::minecraft::PATH
+    );
+    let reports_1_14 = format!(
+        "{}/minecraft-1.14/generated/reports",
+        feather_data::minecraft::PATH
+    );
+
+    let blocks_1_13: HashMap<String, BlockReport> = load(&format!("{}/blocks.json", reports_1_13))?;
+    let items_1_13: HashMap<String, RegistryEntry> = load(&format!("{}/items.json", reports_1_13))?;
+    let blocks_1_14: HashMap<String, BlockReport> = load(&format!("{}/blocks.json", reports_1_14))?;
+    let registries_1_14: HashMap<String, Registry> =
+        load(&format!("{}/registries.json", reports_1_14))?;
+    let registry = |name: &str| {
+        registries_1_14
+            .get(name)
+            .with_context(|| format!("missing registry `{}` in 1.14 reports", name))
+    };
+
+    let items = items(&items_1_13, registry("minecraft:item")?);
+
+    let mut out = String::from("// This file is @generated by build.rs\n\n");
+    write_table(
+        &mut out,
+        "BLOCK_STATES",
+        &block_states(&blocks_1_13, &blocks_1_14),
+    );
+    write_table(
+        &mut out,
+        "BLOCKS",
+        &blocks(&blocks_1_13, registry("minecraft:block")?),
+    );
+    write_table(&mut out, "ITEMS", &items);
+    write_table(&mut out, "ITEMS_REVERSE", &invert(&items));
+    write_table(
+        &mut out,
+        "ENTITY_TYPES",
+        &entity_types(registry("minecraft:entity_type")?),
+    );
+    write_menus(&mut out, registry("minecraft:menu")?);
+
+    let path = format!("{}/v1_14_4.rs", env::var("OUT_DIR")?);
+    File::create(&path)
+        .and_then(|mut file| file.write_all(out.as_bytes()))
+        .with_context(|| format!("failed to write `{}`", path))?;
+
+    Ok(())
+}
+
+fn load<T: DeserializeOwned>(path: &str) -> anyhow::Result<T> {
+    let file = File::open(path).with_context(|| format!("failed to open `{}`", path))?;
+    serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("failed to parse `{}`", path))
+}
+
+fn rename(name: &str) -> &str {
+    RENAMES
+        .iter()
+        .find(|(old, _)| *old == name)
+        .map_or(name, |(_, new)| new)
+}
+
+/// Maps each 1.13.2 block state ID to the 1.14.4 state
+/// of the same block with the same property values.
+///
+/// Properties which did not exist in 1.13.2 take their default
+/// value. States without a counterpart map to the default state.
+fn block_states(
+    old: &HashMap<String, BlockReport>,
+    new: &HashMap<String, BlockReport>,
+) -> Vec<u16> {
+    let len = old
+        .values()
+        .flat_map(|block| &block.states)
+        .map(|state| state.id as usize + 1)
+        .max()
+        .unwrap_or(0);
+    let mut table = vec![0; len];
+
+    for (name, block) in old {
+        let new_block = match new.get(rename(name)) {
+            Some(new_block) => new_block,
+            None => continue,
+        };
+        let default = new_block
+            .states
+            .iter()
+            .find(|state| state.default)
+            .unwrap_or(&new_block.states[0]);
+
+        for state in &block.states {
+            let translated = new_block
+                .states
+                .iter()
+                .find(|candidate| {
+                    candidate.properties.iter().all(|(property, value)| {
+                        state
+                            .properties
+                            .get(property)
+                            .or_else(|| default.properties.get(property))
+                            == Some(value)
+                    })
+                })
+                .unwrap_or(default);
+            table[state.id as usize] = translated.id;
+        }
+    }
+
+    table
+}
+
+/// Maps 1.13.2 block IDs (not state IDs) to 1.14.4 block IDs.
+///
+/// Block IDs are assigned in the order of the blocks' state IDs.
+fn blocks(old: &HashMap<String, BlockReport>, new: &Registry) -> Vec<u16> {
+    let mut old: Vec<(&str, u16)> = old
+        .iter()
+        .map(|(name, block)| {
+            let first_state = block.states.iter().map(|state| state.id).min();
+            (name.as_str(), first_state.unwrap_or(0))
+        })
+        .collect();
+    old.sort_by_key(|(_, first_state)| *first_state);
+
+    old.into_iter()
+        .map(|(name, _)| {
+            new.entries
+                .get(rename(name))
+                .map_or(0, |entry| entry.protocol_id)
+        })
+        .collect()
+}
+
+/// Maps 1.13.2 item IDs to 1.14.4 item IDs.
+fn items(old: &HashMap<String, RegistryEntry>, new: &Registry) -> Vec<u16> {
+    let len = old
+        .values()
+        .map(|entry| entry.protocol_id as usize + 1)
+        .max()
+        .unwrap_or(0);
+    let mut table = vec![0; len];
+
+    for (name, entry) in old {
+        if let Some(new_entry) = new.entries.get(rename(name)) {
+            table[entry.protocol_id as usize] = new_entry.protocol_id;
+        }
+    }
+
+    table
+}
+
+/// Inverts a table; IDs without a counterpart map to 0.
+fn invert(table: &[u16]) -> Vec<u16> {
+    let len = table.iter().map(|id| *id as usize + 1).max().unwrap_or(0);
+    let mut inverted = vec![0; len];
+
+    for (old, new) in table.iter().enumerate() {
+        if *new != 0 {
+            inverted[*new as usize] = old as u16;
+        }
+    }
+
+    inverted
+}
+
+/// Maps 1.13.2 entity type IDs to 1.14.4 entity type IDs.
+fn entity_types(new: &Registry) -> Vec<u16> {
+    ENTITY_TYPES_1_13_2
+        .iter()
+        .map(|name| {
+            new.entries
+                .get(&format!("minecraft:{}", name))
+                .map_or(0, |entry| entry.protocol_id)
+        })
+        .collect()
+}
+
+fn write_table(out: &mut String, name: &str, table: &[u16]) {
+    writeln!(out, "pub static {}: [u16; {}] = [", name, table.len()).unwrap();
+    for chunk in table.chunks(16) {
+        let line: Vec<String> = chunk.iter().map(u16::to_string).collect();
+        writeln!(out, "    {},", line.join(", ")).unwrap();
+    }
+    out.push_str("];\n\n");
+}
+
+fn write_menus(out: &mut String, menus: &Registry) {
+    let mut menus: Vec<(&String, u16)> = menus
+        .entries
+        .iter()
+        .map(|(name, entry)| (name, entry.protocol_id))
+        .collect();
+    menus.sort_by_key(|(_, id)| *id);
+
+    writeln!(out, "pub static MENUS: [(&str, u16); {}] = [", menus.len()).unwrap();
+    for (name, id) in menus {
+        writeln!(out, "    ({:?}, {}),", name, id).unwrap();
+    }
+    out.push_str("];\n");
+}
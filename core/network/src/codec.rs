@@ -1,9 +1,10 @@
 use crate::bytes_ext::TryGetError;
 use crate::mctypes::{McTypeRead, McTypeWrite};
-use crate::packet::{PacketDirection, PacketId, PacketStage};
-use crate::{Packet, PacketType};
+use crate::packet::{AsAny, PacketDirection, PacketId, PacketStage};
+use crate::packets::Handshake;
+use crate::version::Translator;
+use crate::{Packet, PacketType, ProtocolVersion};
 use aes::Aes128;
-use bytes::buf::BufMutExt;
 use bytes::{Buf, BytesMut};
 use cfb8::stream_cipher::{NewStreamCipher, StreamCipher};
 use cfb8::Cfb8;
@@ -51,8 +52,16 @@ pub struct MinecraftCodec {
     /// Cached buffer into which we write decompressed
     /// data. Using this avoids reallocations.
     decompressed_buffer: Vec<u8>,
+    /// Cached buffer into which we write compressed
+    /// data. Using this avoids reallocations.
+    compressed_buffer: Vec<u8>,
     /// Index into `src` of next byte to decrypt.
     decrypt_index: usize,
+    /// Translates packets to and from the
+    /// protocol version of the connection.
+    translator: Translator,
+    /// Cached buffer of frames produced by the translator.
+    frames: Vec<BytesMut>,
 }
 
 impl MinecraftCodec {
@@ -65,7 +74,10 @@ impl MinecraftCodec {
             compression_threshold: None,
             header_buffer: BytesMut::with_capacity(HEADER_SIZE),
             decompressed_buffer: vec![],
+            compressed_buffer: vec![],
             decrypt_index: 0,
+            translator: Translator::new(ProtocolVersion::default()),
+            frames: vec![],
         }
     }
 
@@ -86,100 +98,84 @@ impl MinecraftCodec {
         log::trace!("Setting packet stage to {:?}", stage);
         self.stage = stage;
     }
-}
-
-impl Encoder<Box<dyn Packet>> for MinecraftCodec {
-    type Error = anyhow::Error;
-
-    fn encode(&mut self, packet: Box<dyn Packet>, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        // Reserve space for the packet header (at most 2 * 5 bytes, for length + data length).
-        // `header` will contain the first 10 bytes of the buffer, while `dst`
-        // still contains the rest.
-        // "Data length" refers to the uncompressed size of the packet.
-        // Since we cannot know the size of the header in advance, thanks to varints,
-        // we reserve the maximum size and copy the header in with a correct offset.
-        assert!(dst.is_empty());
-        dst.reserve(HEADER_SIZE);
 
-        // Zero out the header.
-        dst.extend_from_slice(&[0u8; HEADER_SIZE]);
-
-        let mut header = dst.split_to(HEADER_SIZE);
-        assert!(dst.is_empty());
+    /// Returns the protocol version used by this codec.
+    pub fn version(&self) -> ProtocolVersion {
+        self.translator.version()
+    }
 
-        // Write raw packet data to `dst`.
-        let ty = packet.ty();
-        log::trace!("Sending packet with type {:?}", ty);
-        dst.push_var_int(ty.get_id().0 as i32);
-        packet.write_to(dst);
+    /// Sets the protocol version used by this codec.
+    ///
+    /// This is done automatically when a handshake is decoded.
+    pub fn set_version(&mut self, version: ProtocolVersion) {
+        log::trace!("Setting protocol version to {}", version.name());
+        self.translator = Translator::new(version);
+    }
 
+    /// Writes a frame (packet ID and data) to `dst`, preceded
+    /// by the packet header and compressed if necessary.
+    fn write_frame(&mut self, frame: BytesMut, dst: &mut BytesMut) {
         // If compression is enabled, we follow a more complex course of action:
-        // * Write the raw packet data to `dst`.
-        // * If the data is less than the compression threshold, proceed as usual.
-        // * Otherwise, we move forward into the buffer, allocating
-        // another header and then writing the compressed bytes
-        // to the capacity after that.
-        let data_len: Option<usize> = if let Some(threshold) = self.compression_threshold {
-            let data_len = dst.len();
-            if data_len >= threshold {
-                // Allocate new header
-                dst.reserve(HEADER_SIZE);
-
-                let uncompressed = dst.split_to(data_len);
-                dst.extend_from_slice(&[0u8; HEADER_SIZE]);
-                header = dst.split_to(HEADER_SIZE);
-
-                assert!(dst.is_empty());
-                // Compress data into `compressed`.
-                let mut encoder = ZlibEncoder::new(dst.writer(), Compression::default());
-                encoder.write_all(uncompressed.as_ref()).unwrap();
-
-                Some(data_len)
+        // * If the data is less than the compression threshold, proceed as usual,
+        // with a data length of 0 to indicate the packet is not compressed.
+        // * Otherwise, compress the data into `self.compressed_buffer`
+        // and write the uncompressed size as the data length.
+        // "Data length" refers to the uncompressed size of the packet.
+        let (data_len, data): (Option<usize>, &[u8]) =
+            if let Some(threshold) = self.compression_threshold {
+                if frame.len() >= threshold {
+                    self.compressed_buffer.clear();
+                    let mut encoder =
+                        ZlibEncoder::new(&mut self.compressed_buffer, Compression::default());
+                    encoder.write_all(frame.as_ref()).unwrap();
+                    encoder.finish().unwrap();
+
+                    (Some(frame.len()), &self.compressed_buffer[..])
+                } else {
+                    (Some(0), &frame[..]) // Not compressed
+                }
             } else {
-                Some(0) // Not compressed
-            }
-        } else {
-            None
-        };
+                (None, &frame[..])
+            };
 
-        // Figure out the length of `data_length` encoded.
-        let length_of_data_length = match data_len {
-            Some(data_len) => {
-                let mut temp = BytesMut::with_capacity(MAX_VAR_INT_SIZE);
-                temp.push_var_int(data_len as i32);
-                temp.len()
-            }
-            None => 0,
-        };
-
-        // Write header. We first write to a temporary buffer,
-        // then copy this to the correct position in `header`,
-        // trimming off the unused bytes.
-        self.header_buffer
-            .push_var_int((length_of_data_length + dst.len()) as i32);
+        // Write header. We first write to a temporary buffer
+        // to find the length of `data_length` encoded.
         if let Some(data_len) = data_len {
             self.header_buffer.push_var_int(data_len as i32);
         }
+        let length = self.header_buffer.len() + data.len();
 
-        // Offset into `header` to write to.
-        let header_offset = HEADER_SIZE - self.header_buffer.len();
-        // Discard unused header bytes.
-        header.advance(header_offset);
-        header.clear();
-
-        // Write into header.
-        header.extend_from_slice(&self.header_buffer);
+        dst.reserve(HEADER_SIZE + data.len());
+        dst.push_var_int(length as i32);
+        dst.extend_from_slice(&self.header_buffer);
+        dst.extend_from_slice(data);
         self.header_buffer.clear();
+    }
+}
 
-        // Finally, merge `header` and `dst`.
-        std::mem::swap(dst, &mut header);
-        dst.unsplit(header);
+impl Encoder<Box<dyn Packet>> for MinecraftCodec {
+    type Error = anyhow::Error;
 
-        // If encryption is enabled, encrypt data in place.
-        if let Some(crypter) = self.encrypter.as_mut() {
-            crypter.encrypt(dst);
+    fn encode(&mut self, packet: Box<dyn Packet>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        log::trace!("Sending packet with type {:?}", packet.ty());
+
+        // The translator may produce several frames for
+        // a single packet, each containing the packet ID and data.
+        let mut frames = std::mem::take(&mut self.frames);
+        self.translator.write_packet(packet.as_ref(), &mut frames);
+
+        for frame in frames.drain(..) {
+            let start = dst.len();
+            self.write_frame(frame, dst);
+
+            // If encryption is enabled, encrypt the new data in place.
+            if let Some(crypter) = self.encrypter.as_mut() {
+                crypter.encrypt(&mut dst[start..]);
+            }
         }
 
+        self.frames = frames;
+
         Ok(())
     }
 }
@@ -251,7 +247,8 @@ impl Decoder for MinecraftCodec {
         let id = cursor.try_get_var_int()? as u32;
         // If we don't know this packet type, skip the packet.
         let packet_type = {
-            match PacketType::get_from_id(PacketId(id, self.incoming_direction, self.stage)) {
+            let packet_id = PacketId(id, self.incoming_direction, self.stage);
+            match PacketType::get_from_id(packet_id, self.version()) {
                 Ok(ty) => ty,
                 Err(_) => {
                     // Advance buffer and stop.
@@ -265,11 +262,16 @@ impl Decoder for MinecraftCodec {
 
         log::trace!("Decoding packet with type {:?}", packet_type);
 
-        let mut packet = packet_type.get_implementation();
-        packet.read_from(&mut cursor)?;
+        let packet = self.translator.read_packet(packet_type, &mut cursor)?;
 
         log::trace!("Received packet with type {:?}", packet_type);
 
+        // The handshake determines the protocol version of the connection.
+        if let Some(handshake) = packet.as_any().downcast_ref::<Handshake>() {
+            let version = ProtocolVersion::from_id(handshake.protocol_version).unwrap_or_default();
+            self.set_version(version);
+        }
+
         src.advance(length);
         self.decrypt_index = src.len();
 
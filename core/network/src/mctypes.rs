@@ -34,7 +34,7 @@ pub trait McTypeWrite {
 
     fn push_nbt<T: Serialize>(&mut self, x: &T);
 
-    fn push_slot(&mut self, slot: Option<ItemStack>);
+    fn push_slot(&mut self, slot: Option<&ItemStack>);
 }
 
 /// Identifies a type from which Minecraft-specified
@@ -115,10 +115,10 @@ impl McTypeWrite for BytesMut {
         self.extend_from_slice(&temp);
     }
 
-    fn push_slot(&mut self, slot: Option<ItemStack>) {
+    fn push_slot(&mut self, slot: Option<&ItemStack>) {
         self.push_bool(slot.is_some());
 
-        if let Some(slot) = slot.as_ref() {
+        if let Some(slot) = slot {
             self.push_var_int(slot.ty.vanilla_id() as i32);
             self.push_i8(slot.amount as i8);
             let tags: ItemNbt = slot.into();
@@ -223,11 +223,7 @@ impl<B: Buf + Read> McTypeRead for B {
         let amount = self.try_get_i8()? as u8;
         let nbt: Option<ItemNbt> = self.try_get_nbt().ok();
 
-        Ok(Some(ItemStack {
-            ty,
-            amount,
-            damage: nbt.map(|t| t.damage).flatten(),
-        }))
+        Ok(Some(ItemNbt::item_stack(&nbt, ty, amount)))
     }
 }
 
@@ -276,7 +272,7 @@ where
     }
 }
 
-fn write_entry_to_buf<B>(entry: &MetaEntry, buf: &mut B)
+pub(crate) fn write_entry_to_buf<B>(entry: &MetaEntry, buf: &mut B)
 where
     B: BytesMutExt + McTypeWrite,
 {
@@ -297,7 +293,7 @@ where
             }
         }
         MetaEntry::Slot(slot) => {
-            buf.push_slot(*slot);
+            buf.push_slot(slot.as_ref());
         }
         MetaEntry::Boolean(x) => buf.push_bool(*x),
         MetaEntry::Rotation(x, y, z) => {
@@ -384,6 +380,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use feather_items::Enchantment;
     use std::io::Cursor;
 
     #[test]
@@ -393,4 +390,27 @@ mod tests {
         buf.extend_from_slice(&[0xff, 0x01]);
         assert_eq!(Cursor::new(&buf).try_get_var_int(), Ok(255));
     }
+
+    #[test]
+    fn test_slot_roundtrip() {
+        let mut stack = ItemStack::new(Item::EnchantedBook, 1);
+        stack
+            .meta_mut()
+            .stored_enchantments
+            .push(Enchantment::new("minecraft:mending", 1));
+        stack.meta_mut().repair_cost = Some(1);
+
+        let mut buf = BytesMut::new();
+        buf.push_slot(Some(&stack));
+        buf.push_slot(Some(&ItemStack::new(Item::Stone, 64)));
+        buf.push_slot(None);
+
+        let mut cursor = Cursor::new(&buf);
+        assert_eq!(cursor.try_get_slot(), Ok(Some(stack)));
+        assert_eq!(
+            cursor.try_get_slot(),
+            Ok(Some(ItemStack::new(Item::Stone, 64)))
+        );
+        assert_eq!(cursor.try_get_slot(), Ok(None));
+    }
 }
@@ -3,6 +3,7 @@ use std::any::Any;
 use std::io::Cursor;
 
 use crate::packets::IMPL_MAP;
+use crate::ProtocolVersion;
 use ahash::AHashMap;
 use num_derive::{FromPrimitive, ToPrimitive};
 use once_cell::sync::Lazy;
@@ -215,13 +216,18 @@ pub enum PacketType {
     EntityEffect,
     DeclareRecipes,
     Tags,
+    // Added in 1.14
+    UpdateLight,
+    UpdateViewPosition,
 
     // Status
     Response,
     Pong,
 }
 
-static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
+/// Packet IDs for 1.13.2. Other protocol versions
+/// have their own mappings in the `version` module.
+pub(crate) static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
     let mut m = AHashMap::new();
 
     m.insert(
@@ -501,6 +507,14 @@ static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
         PacketId(0x0E, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::ChatMessageClientbound,
     );
+    m.insert(
+        PacketId(0x10, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::TabCompleteClientbound,
+    );
+    m.insert(
+        PacketId(0x11, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::DeclareCommands,
+    );
 
     m.insert(
         PacketId(0x17, PacketDirection::Clientbound, PacketStage::Play),
@@ -587,6 +601,11 @@ static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
         PacketType::EntityLook,
     );
 
+    m.insert(
+        PacketId(0x2D, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::CraftRecipeResponse,
+    );
+
     m.insert(
         PacketId(0x30, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::PlayerInfo,
@@ -597,6 +616,11 @@ static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
         PacketType::PlayerPositionAndLookClientbound,
     );
 
+    m.insert(
+        PacketId(0x34, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::UnlockRecipes,
+    );
+
     m.insert(
         PacketId(0x35, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::DestroyEntities,
@@ -662,6 +686,11 @@ static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
         PacketType::EntityTeleport,
     );
 
+    m.insert(
+        PacketId(0x54, PacketDirection::Clientbound, PacketStage::Play),
+        PacketType::DeclareRecipes,
+    );
+
     m.insert(
         PacketId(0x55, PacketDirection::Clientbound, PacketStage::Play),
         PacketType::Tags,
@@ -670,23 +699,29 @@ static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
     m
 });
 
-static PACKET_TYPE_MAPPINGS: Lazy<AHashMap<PacketType, PacketId>> = Lazy::new(|| {
+pub(crate) static PACKET_TYPE_MAPPINGS: Lazy<AHashMap<PacketType, PacketId>> =
+    Lazy::new(|| invert_mappings(&PACKET_ID_MAPPINGS));
+
+/// Inverts a packet ID mapping.
+pub(crate) fn invert_mappings(
+    mappings: &AHashMap<PacketId, PacketType>,
+) -> AHashMap<PacketType, PacketId> {
     let mut m = AHashMap::new();
 
-    for (key, val) in PACKET_ID_MAPPINGS.clone().into_iter() {
-        m.insert(val, key);
+    for (key, val) in mappings.iter() {
+        m.insert(*val, *key);
     }
 
     m
-});
+}
 
 impl PacketType {
-    pub fn get_from_id(id: PacketId) -> Result<PacketType, ()> {
-        PACKET_ID_MAPPINGS.get(&id).copied().ok_or(())
+    pub fn get_from_id(id: PacketId, version: ProtocolVersion) -> Result<PacketType, ()> {
+        version.packet_ids().get(&id).copied().ok_or(())
     }
 
-    pub fn get_id(self) -> PacketId {
-        *PACKET_TYPE_MAPPINGS.get(&self).unwrap_or_else(|| panic!("failed to find packet ID for packet type {:?} (try inserting it into the ID map in core/network/packet.rs)", self))
+    pub fn get_id(self, version: ProtocolVersion) -> PacketId {
+        *version.packet_types().get(&self).unwrap_or_else(|| panic!("failed to find packet ID for packet type {:?} in protocol {} (try inserting it into the ID map in core/network/packet.rs or core/network/version)", self, version.name()))
     }
 
     pub fn get_implementation(self) -> Box<dyn Packet> {
@@ -5,7 +5,7 @@ use crate::{Packet, PacketType};
 use ahash::AHashMap;
 use bytes::{Buf, BufMut, BytesMut};
 use feather_blocks::{FacingCardinal, FacingCardinalAndDown, FacingCubic};
-use feather_chunk::Chunk;
+use feather_chunk::{BitArray, Chunk};
 use feather_codegen::{AsAny, Packet};
 use feather_entity_metadata::EntityMetadata;
 use feather_items::ItemStack;
@@ -60,6 +60,10 @@ pub static IMPL_MAP: Lazy<AHashMap<PacketType, PacketBuilder>> = Lazy::new(|| {
         PacketType::EncryptionResponse,
         PacketBuilder::with(|| Box::new(EncryptionResponse::default())),
     );
+    m.insert(
+        PacketType::LoginPluginResponse,
+        PacketBuilder::with(|| Box::new(LoginPluginResponse::default())),
+    );
 
     m.insert(
         PacketType::Request,
@@ -265,6 +269,7 @@ pub static IMPL_MAP: Lazy<AHashMap<PacketType, PacketBuilder>> = Lazy::new(|| {
         EncryptionRequest,
         LoginSuccess,
         SetCompression,
+        LoginPluginRequest,
         SpawnObject,
         SpawnExperienceOrb,
         SpawnGlobalEntity,
@@ -280,6 +285,8 @@ pub static IMPL_MAP: Lazy<AHashMap<PacketType, PacketBuilder>> = Lazy::new(|| {
         BossBar,
         ServerDifficulty,
         ChatMessageClientbound,
+        TabCompleteClientbound,
+        DeclareCommands,
         OpenWindow,
         WindowItems,
         WindowProperty,
@@ -308,6 +315,7 @@ pub static IMPL_MAP: Lazy<AHashMap<PacketType, PacketBuilder>> = Lazy::new(|| {
         PlayerInfo,
         PlayerPositionAndLookClientbound,
         UseBed,
+        UnlockRecipes,
         DestroyEntities,
         RemoveEntityEffect,
         ResourcePackSend,
@@ -321,7 +329,10 @@ pub static IMPL_MAP: Lazy<AHashMap<PacketType, PacketBuilder>> = Lazy::new(|| {
         TimeUpdate,
         CollectItem,
         EntityTeleport,
+        DeclareRecipes,
         Tags,
+        UpdateLight,
+        UpdateViewPosition,
         Response,
         Pong,
     );
@@ -351,6 +362,8 @@ pub enum Error {
     InsufficientArrayLength,
     #[error("invalid handshake next state {0}")]
     InvalidHandshakeState(i32),
+    #[error("invalid recipe book data type {0}")]
+    InvalidRecipeBookDataType(i32),
 }
 
 // SERVERBOUND
@@ -476,6 +489,48 @@ impl Packet for EncryptionResponse {
     }
 }
 
+#[derive(Default, AsAny, Clone)]
+pub struct LoginPluginResponse {
+    pub message_id: VarInt,
+    pub successful: bool,
+    /// The rest of the packet; only present if `successful` is `true`.
+    pub data: Vec<u8>,
+}
+
+impl Packet for LoginPluginResponse {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.message_id = buf.try_get_var_int()?;
+        self.successful = buf.try_get_bool()?;
+
+        let mut data = Vec::with_capacity(buf.remaining());
+        buf.read_to_end(&mut data)?;
+        self.data = data;
+
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_var_int(self.message_id);
+        buf.push_bool(self.successful);
+        buf.extend_from_slice(&self.data);
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::LoginPluginResponse
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::LoginPluginResponse
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 #[derive(Default, AsAny, Packet, Clone)]
 pub struct Request {}
 
@@ -883,10 +938,81 @@ pub struct SteerVehicle {
     pub flags: u8,
 }
 
-#[derive(Default, AsAny, Packet, Clone)]
+#[derive(Default, AsAny, Clone)]
 pub struct RecipeBookData {
-    pub ty: VarInt,
-    // TODO
+    pub data: RecipeBookDataType,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecipeBookDataType {
+    /// The player viewed a recipe in the recipe book.
+    DisplayedRecipe(String),
+    /// The player opened, closed or filtered the recipe book.
+    RecipeBookStates {
+        crafting_book_open: bool,
+        crafting_filter_active: bool,
+        smelting_book_open: bool,
+        smelting_filter_active: bool,
+    },
+}
+
+impl Default for RecipeBookDataType {
+    fn default() -> Self {
+        RecipeBookDataType::DisplayedRecipe(String::new())
+    }
+}
+
+impl Packet for RecipeBookData {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.data = match buf.try_get_var_int()? {
+            0 => RecipeBookDataType::DisplayedRecipe(buf.try_get_string()?),
+            1 => RecipeBookDataType::RecipeBookStates {
+                crafting_book_open: buf.try_get_bool()?,
+                crafting_filter_active: buf.try_get_bool()?,
+                smelting_book_open: buf.try_get_bool()?,
+                smelting_filter_active: buf.try_get_bool()?,
+            },
+            x => return Err(Error::InvalidRecipeBookDataType(x).into()),
+        };
+
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        match &self.data {
+            RecipeBookDataType::DisplayedRecipe(recipe_id) => {
+                buf.push_var_int(0);
+                buf.push_string(recipe_id);
+            }
+            RecipeBookDataType::RecipeBookStates {
+                crafting_book_open,
+                crafting_filter_active,
+                smelting_book_open,
+                smelting_filter_active,
+            } => {
+                buf.push_var_int(1);
+                buf.push_bool(*crafting_book_open);
+                buf.push_bool(*crafting_filter_active);
+                buf.push_bool(*smelting_book_open);
+                buf.push_bool(*smelting_filter_active);
+            }
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::RecipeBookData
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::RecipeBookData
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
 }
 
 #[derive(Default, AsAny, Packet, Clone)]
@@ -1202,6 +1328,48 @@ pub struct LoginSuccess {
     pub username: String,
 }
 
+#[derive(Default, AsAny, Clone)]
+pub struct LoginPluginRequest {
+    pub message_id: VarInt,
+    pub channel: String,
+    /// The rest of the packet.
+    pub data: Vec<u8>,
+}
+
+impl Packet for LoginPluginRequest {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.message_id = buf.try_get_var_int()?;
+        self.channel = buf.try_get_string()?;
+
+        let mut data = Vec::with_capacity(buf.remaining());
+        buf.read_to_end(&mut data)?;
+        self.data = data;
+
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_var_int(self.message_id);
+        buf.push_string(&self.channel);
+        buf.extend_from_slice(&self.data);
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::LoginPluginRequest
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::LoginPluginRequest
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 #[derive(Default, AsAny, Packet, Clone)]
 pub struct SetCompression {
     pub threshold: VarInt,
@@ -1542,8 +1710,279 @@ pub struct ChatMessageClientbound {
 }
 
 // TODO MultiBlockChange
-// TODO TabCompleteClientbound
-// TODO DeclareCommands
+#[derive(Default, AsAny, Clone)]
+pub struct TabCompleteClientbound {
+    pub transaction_id: VarInt,
+    pub start: VarInt,
+    pub length: VarInt,
+    pub matches: Vec<TabCompleteMatch>,
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct TabCompleteMatch {
+    pub text: String,
+    pub tooltip: Option<String>, // Chat
+}
+
+impl Packet for TabCompleteClientbound {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.transaction_id = buf.try_get_var_int()?;
+        self.start = buf.try_get_var_int()?;
+        self.length = buf.try_get_var_int()?;
+
+        let count = buf.try_get_var_int()?;
+        self.matches = Vec::with_capacity(count.max(0) as usize);
+        for _ in 0..count {
+            let text = buf.try_get_string()?;
+            let tooltip = if buf.try_get_bool()? {
+                Some(buf.try_get_string()?)
+            } else {
+                None
+            };
+            self.matches.push(TabCompleteMatch { text, tooltip });
+        }
+
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_var_int(self.transaction_id);
+        buf.push_var_int(self.start);
+        buf.push_var_int(self.length);
+
+        buf.push_var_int(self.matches.len() as i32);
+        for m in &self.matches {
+            buf.push_string(&m.text);
+            buf.push_bool(m.tooltip.is_some());
+            if let Some(tooltip) = m.tooltip.as_ref() {
+                buf.push_string(tooltip);
+            }
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::TabCompleteClientbound
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::TabCompleteClientbound
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+#[derive(Default, AsAny, Clone)]
+pub struct DeclareCommands {
+    pub nodes: Vec<CommandNode>,
+    pub root_index: VarInt,
+}
+
+/// A node in the command graph sent in `DeclareCommands`.
+#[derive(Clone, Debug)]
+pub struct CommandNode {
+    pub node_type: CommandNodeType,
+    /// Whether the command is complete at this node.
+    pub executable: bool,
+    /// Indices of the child nodes.
+    pub children: Vec<VarInt>,
+    pub redirect_node: Option<VarInt>,
+    /// Name of a literal or argument node; ignored for the root.
+    pub name: String,
+    /// Present for argument nodes only.
+    pub parser: Option<CommandParser>,
+    /// Identifier of the suggestions provider for argument nodes,
+    /// e.g. `minecraft:ask_server`.
+    pub suggestions_type: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandNodeType {
+    Root,
+    Literal,
+    Argument,
+}
+
+/// Parser used by the client to parse, validate and
+/// highlight an argument node.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CommandParser {
+    Bool,
+    Double { min: Option<f64>, max: Option<f64> },
+    Float { min: Option<f32>, max: Option<f32> },
+    Integer { min: Option<i32>, max: Option<i32> },
+    String(StringParserMode),
+    Entity { single: bool, only_players: bool },
+    GameProfile,
+    BlockPos,
+    Vec3,
+    Vec2,
+    BlockState,
+    ItemStack,
+    Message,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StringParserMode {
+    SingleWord,
+    QuotablePhrase,
+    GreedyPhrase,
+}
+
+impl CommandParser {
+    /// Returns the identifier of this parser.
+    pub fn identifier(&self) -> &'static str {
+        match self {
+            CommandParser::Bool => "brigadier:bool",
+            CommandParser::Double { .. } => "brigadier:double",
+            CommandParser::Float { .. } => "brigadier:float",
+            CommandParser::Integer { .. } => "brigadier:integer",
+            CommandParser::String(_) => "brigadier:string",
+            CommandParser::Entity { .. } => "minecraft:entity",
+            CommandParser::GameProfile => "minecraft:game_profile",
+            CommandParser::BlockPos => "minecraft:block_pos",
+            CommandParser::Vec3 => "minecraft:vec3",
+            CommandParser::Vec2 => "minecraft:vec2",
+            CommandParser::BlockState => "minecraft:block_state",
+            CommandParser::ItemStack => "minecraft:item_stack",
+            CommandParser::Message => "minecraft:message",
+        }
+    }
+
+    fn write_properties(&self, buf: &mut BytesMut) {
+        match *self {
+            CommandParser::Double { min, max } => {
+                buf.push_u8(range_flags(min.is_some(), max.is_some()));
+                if let Some(min) = min {
+                    buf.push_f64(min);
+                }
+                if let Some(max) = max {
+                    buf.push_f64(max);
+                }
+            }
+            CommandParser::Float { min, max } => {
+                buf.push_u8(range_flags(min.is_some(), max.is_some()));
+                if let Some(min) = min {
+                    buf.push_f32(min);
+                }
+                if let Some(max) = max {
+                    buf.push_f32(max);
+                }
+            }
+            CommandParser::Integer { min, max } => {
+                buf.push_u8(range_flags(min.is_some(), max.is_some()));
+                if let Some(min) = min {
+                    buf.push_i32(min);
+                }
+                if let Some(max) = max {
+                    buf.push_i32(max);
+                }
+            }
+            CommandParser::String(mode) => {
+                buf.push_var_int(match mode {
+                    StringParserMode::SingleWord => 0,
+                    StringParserMode::QuotablePhrase => 1,
+                    StringParserMode::GreedyPhrase => 2,
+                });
+            }
+            CommandParser::Entity {
+                single,
+                only_players,
+            } => {
+                let mut flags = 0;
+                if single {
+                    flags |= 0x01;
+                }
+                if only_players {
+                    flags |= 0x02;
+                }
+                buf.push_u8(flags);
+            }
+            _ => (),
+        }
+    }
+}
+
+fn range_flags(has_min: bool, has_max: bool) -> u8 {
+    let mut flags = 0;
+    if has_min {
+        flags |= 0x01;
+    }
+    if has_max {
+        flags |= 0x02;
+    }
+    flags
+}
+
+impl Packet for DeclareCommands {
+    fn read_from(&mut self, _buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        unimplemented!()
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_var_int(self.nodes.len() as i32);
+        for node in &self.nodes {
+            let mut flags = match node.node_type {
+                CommandNodeType::Root => 0,
+                CommandNodeType::Literal => 1,
+                CommandNodeType::Argument => 2,
+            };
+            if node.executable {
+                flags |= 0x04;
+            }
+            if node.redirect_node.is_some() {
+                flags |= 0x08;
+            }
+            if node.suggestions_type.is_some() {
+                flags |= 0x10;
+            }
+            buf.push_u8(flags);
+
+            buf.push_var_int(node.children.len() as i32);
+            for child in &node.children {
+                buf.push_var_int(*child);
+            }
+
+            if let Some(redirect) = node.redirect_node {
+                buf.push_var_int(redirect);
+            }
+
+            if node.node_type != CommandNodeType::Root {
+                buf.push_string(&node.name);
+            }
+
+            if node.node_type == CommandNodeType::Argument {
+                let parser = node.parser.expect("argument command node without a parser");
+                buf.push_string(parser.identifier());
+                parser.write_properties(buf);
+
+                if let Some(suggestions_type) = node.suggestions_type.as_ref() {
+                    buf.push_string(suggestions_type);
+                }
+            }
+        }
+        buf.push_var_int(self.root_index);
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::DeclareCommands
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::DeclareCommands
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
 
 #[derive(Default, AsAny, Packet, Clone)]
 pub struct ConfirmTransactionClientbound {
@@ -1627,7 +2066,7 @@ impl Packet for WindowItems {
         buf.push_i16(self.slots.len() as i16);
 
         for slot in &self.slots {
-            buf.push_slot(*slot);
+            buf.push_slot(slot.as_ref());
         }
     }
 
@@ -1954,7 +2393,7 @@ impl Packet for Particle {
         buf.push_f32(self.offset_z);
         buf.push_f32(self.particle_data);
         buf.push_i32(self.particle_count);
-        match self.data {
+        match &self.data {
             ParticleData::Block(id) => {
                 buf.push_var_int(id.vanilla_id() as i32);
             }
@@ -1964,15 +2403,15 @@ impl Packet for Particle {
                 blue,
                 scale,
             } => {
-                buf.push_f32(red);
-                buf.push_f32(green);
-                buf.push_f32(blue);
-                buf.push_f32(scale);
+                buf.push_f32(*red);
+                buf.push_f32(*green);
+                buf.push_f32(*blue);
+                buf.push_f32(*scale);
             }
             ParticleData::FallingDust(id) => {
                 buf.push_var_int(id.vanilla_id() as i32);
             }
-            ParticleData::Item(stack) => buf.push_slot(stack),
+            ParticleData::Item(stack) => buf.push_slot(stack.as_ref()),
             _ => (),
         }
     }
@@ -1993,7 +2432,7 @@ impl Packet for Particle {
     }
 }
 
-#[derive(Default, AsAny, Packet, Clone, Debug)]
+#[derive(Default, AsAny, Clone, Debug)]
 pub struct JoinGame {
     pub entity_id: i32,
     pub gamemode: u8,
@@ -2002,6 +2441,47 @@ pub struct JoinGame {
     pub max_players: u8,
     pub level_type: String,
     pub reduced_debug_info: bool,
+    /// Only sent to 1.14+ clients, which
+    /// no longer receive the difficulty.
+    pub view_distance: u8,
+}
+
+impl Packet for JoinGame {
+    fn read_from(&mut self, buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        self.entity_id = buf.try_get_i32()?;
+        self.gamemode = buf.try_get_u8()?;
+        self.dimension = buf.try_get_i32()?;
+        self.difficulty = buf.try_get_u8()?;
+        self.max_players = buf.try_get_u8()?;
+        self.level_type = buf.try_get_string()?;
+        self.reduced_debug_info = buf.try_get_bool()?;
+        Ok(())
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_i32(self.entity_id);
+        buf.push_u8(self.gamemode);
+        buf.push_i32(self.dimension);
+        buf.push_u8(self.difficulty);
+        buf.push_u8(self.max_players);
+        buf.push_string(&self.level_type);
+        buf.push_bool(self.reduced_debug_info);
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::JoinGame
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::JoinGame
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
 }
 
 // TODO MapData
@@ -2274,7 +2754,78 @@ pub struct UseBed {
     pub location: BlockPosition,
 }
 
-// TODO Unlock Recipes
+#[derive(Default, AsAny, Clone)]
+pub struct UnlockRecipes {
+    pub action: UnlockRecipesAction,
+    pub crafting_book_open: bool,
+    pub crafting_filter_active: bool,
+    pub smelting_book_open: bool,
+    pub smelting_filter_active: bool,
+    pub recipe_ids: Vec<String>,
+    /// Recipes to highlight as new. Only sent
+    /// with `UnlockRecipesAction::Init`.
+    pub displayed_recipe_ids: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnlockRecipesAction {
+    /// Sets the full list of known recipes. Sent on join.
+    Init,
+    /// Adds recipes, showing a "new recipe" notification.
+    Add,
+    Remove,
+}
+
+impl Default for UnlockRecipesAction {
+    fn default() -> Self {
+        UnlockRecipesAction::Init
+    }
+}
+
+impl Packet for UnlockRecipes {
+    fn read_from(&mut self, _buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        unimplemented!()
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_var_int(match self.action {
+            UnlockRecipesAction::Init => 0,
+            UnlockRecipesAction::Add => 1,
+            UnlockRecipesAction::Remove => 2,
+        });
+        buf.push_bool(self.crafting_book_open);
+        buf.push_bool(self.crafting_filter_active);
+        buf.push_bool(self.smelting_book_open);
+        buf.push_bool(self.smelting_filter_active);
+
+        buf.push_var_int(self.recipe_ids.len() as i32);
+        for id in &self.recipe_ids {
+            buf.push_string(id);
+        }
+
+        if self.action == UnlockRecipesAction::Init {
+            buf.push_var_int(self.displayed_recipe_ids.len() as i32);
+            for id in &self.displayed_recipe_ids {
+                buf.push_string(id);
+            }
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::UnlockRecipes
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::UnlockRecipes
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
 
 #[derive(Default, AsAny, Clone)]
 pub struct DestroyEntities {
@@ -2429,6 +2980,127 @@ pub struct EntityTeleport {
     pub on_ground: bool,
 }
 
+#[derive(Default, AsAny, Clone)]
+pub struct DeclareRecipes {
+    pub recipes: Vec<DeclaredRecipe>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DeclaredRecipe {
+    pub id: String,
+    pub data: DeclaredRecipeData,
+}
+
+/// An ingredient of a declared recipe. Contains
+/// each item accepted by the ingredient.
+pub type DeclaredIngredient = Vec<ItemStack>;
+
+#[derive(Clone, Debug)]
+pub enum DeclaredRecipeData {
+    Shapeless {
+        group: String,
+        ingredients: Vec<DeclaredIngredient>,
+        result: ItemStack,
+    },
+    Shaped {
+        width: VarInt,
+        height: VarInt,
+        group: String,
+        /// Ingredients row by row; empty
+        /// ingredients must be empty slots.
+        ingredients: Vec<DeclaredIngredient>,
+        result: ItemStack,
+    },
+    Smelting {
+        group: String,
+        ingredient: DeclaredIngredient,
+        result: ItemStack,
+        experience: f32,
+        cooking_time: VarInt,
+    },
+}
+
+fn push_ingredient(buf: &mut BytesMut, ingredient: &[ItemStack]) {
+    buf.push_var_int(ingredient.len() as i32);
+    for stack in ingredient {
+        buf.push_slot(Some(stack));
+    }
+}
+
+impl Packet for DeclareRecipes {
+    fn read_from(&mut self, _buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        unimplemented!()
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        buf.push_var_int(self.recipes.len() as i32);
+        for recipe in &self.recipes {
+            buf.push_string(&recipe.id);
+
+            match &recipe.data {
+                DeclaredRecipeData::Shapeless {
+                    group,
+                    ingredients,
+                    result,
+                } => {
+                    buf.push_string("crafting_shapeless");
+                    buf.push_string(group);
+                    buf.push_var_int(ingredients.len() as i32);
+                    for ingredient in ingredients {
+                        push_ingredient(buf, ingredient);
+                    }
+                    buf.push_slot(Some(result));
+                }
+                DeclaredRecipeData::Shaped {
+                    width,
+                    height,
+                    group,
+                    ingredients,
+                    result,
+                } => {
+                    buf.push_string("crafting_shaped");
+                    buf.push_var_int(*width);
+                    buf.push_var_int(*height);
+                    buf.push_string(group);
+                    for ingredient in ingredients {
+                        push_ingredient(buf, ingredient);
+                    }
+                    buf.push_slot(Some(result));
+                }
+                DeclaredRecipeData::Smelting {
+                    group,
+                    ingredient,
+                    result,
+                    experience,
+                    cooking_time,
+                } => {
+                    buf.push_string("smelting");
+                    buf.push_string(group);
+                    push_ingredient(buf, ingredient);
+                    buf.push_slot(Some(result));
+                    buf.push_f32(*experience);
+                    buf.push_var_int(*cooking_time);
+                }
+            }
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::DeclareRecipes
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::DeclareRecipes
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
 #[derive(Default, AsAny, Clone)]
 pub struct Tags {
     pub block_tags: Vec<(String, Vec<VarInt>)>,
@@ -2469,3 +3141,73 @@ impl Packet for Tags {
         box_clone_impl!(self);
     }
 }
+
+/// Sends the light of a chunk to 1.14+ clients,
+/// which no longer receive it as part of `ChunkData`.
+#[derive(Default, AsAny, Clone)]
+pub struct UpdateLight {
+    pub chunk: Arc<RwLock<Chunk>>,
+}
+
+fn push_light(buf: &mut BytesMut, light: &BitArray) {
+    let data = light.inner();
+    buf.push_var_int((data.len() * 8) as i32);
+    buf.reserve(data.len() * 8);
+    for val in data {
+        buf.put_u64_le(*val);
+    }
+}
+
+impl Packet for UpdateLight {
+    fn read_from(&mut self, _buf: &mut Cursor<&[u8]>) -> anyhow::Result<()> {
+        unimplemented!()
+    }
+
+    fn write_to(&self, buf: &mut BytesMut) {
+        let chunk = self.chunk.read();
+        buf.push_var_int(chunk.position().x);
+        buf.push_var_int(chunk.position().z);
+
+        // Bit 0 of the masks is the section below the world,
+        // so the section at Y coordinate `y` uses bit `y + 1`.
+        let sections = chunk.sections();
+        let mask = sections
+            .iter()
+            .enumerate()
+            .filter(|(_, section)| section.is_some())
+            .fold(0, |mask, (y, _)| mask | 1 << (y + 1));
+
+        buf.push_var_int(mask); // Sky light
+        buf.push_var_int(mask); // Block light
+        buf.push_var_int(0); // Empty sky light
+        buf.push_var_int(0); // Empty block light
+
+        for section in sections.iter().flatten() {
+            push_light(buf, section.sky_light());
+        }
+        for section in sections.iter().flatten() {
+            push_light(buf, section.block_light());
+        }
+    }
+
+    fn ty(&self) -> PacketType {
+        PacketType::UpdateLight
+    }
+
+    fn ty_sized() -> PacketType
+    where
+        Self: Sized,
+    {
+        PacketType::UpdateLight
+    }
+
+    fn box_clone(&self) -> Box<dyn Packet> {
+        box_clone_impl!(self);
+    }
+}
+
+#[derive(Default, AsAny, Packet, Clone)]
+pub struct UpdateViewPosition {
+    pub chunk_x: VarInt,
+    pub chunk_z: VarInt,
+}
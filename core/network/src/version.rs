@@ -0,0 +1,191 @@
+//! Support for multiple protocol versions.
+//!
+//! Packets are always written and read in the layout of 1.13.2,
+//! the server's native protocol version. For clients on other
+//! versions, a `Translator` rewrites packets at the codec boundary,
+//! translating packet IDs, field layouts, block states, items,
+//! and chunk formats.
+
+use crate::mctypes::McTypeWrite;
+use crate::packet::{PacketId, PACKET_ID_MAPPINGS, PACKET_TYPE_MAPPINGS};
+use crate::{Packet, PacketType};
+use ahash::AHashMap;
+use bytes::BytesMut;
+use std::io::Cursor;
+
+mod v1_14_4;
+
+/// A protocol version supported by the server.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ProtocolVersion {
+    V1_13_2,
+    V1_14_4,
+}
+
+impl ProtocolVersion {
+    /// Returns all supported protocol versions.
+    pub fn values() -> &'static [ProtocolVersion] {
+        &[ProtocolVersion::V1_13_2, ProtocolVersion::V1_14_4]
+    }
+
+    /// Returns the protocol version with the given
+    /// protocol number, or `None` if it is not supported.
+    pub fn from_id(id: u32) -> Option<Self> {
+        Self::values()
+            .iter()
+            .copied()
+            .find(|version| version.id() == id)
+    }
+
+    /// Returns the protocol number of this version,
+    /// as sent in the handshake.
+    pub fn id(self) -> u32 {
+        match self {
+            ProtocolVersion::V1_13_2 => 404,
+            ProtocolVersion::V1_14_4 => 498,
+        }
+    }
+
+    /// Returns the name of the Minecraft version.
+    pub fn name(self) -> &'static str {
+        match self {
+            ProtocolVersion::V1_13_2 => "1.13.2",
+            ProtocolVersion::V1_14_4 => "1.14.4",
+        }
+    }
+
+    pub(crate) fn packet_ids(self) -> &'static AHashMap<PacketId, PacketType> {
+        match self {
+            ProtocolVersion::V1_13_2 => &PACKET_ID_MAPPINGS,
+            ProtocolVersion::V1_14_4 => &v1_14_4::PACKET_ID_MAPPINGS,
+        }
+    }
+
+    pub(crate) fn packet_types(self) -> &'static AHashMap<PacketType, PacketId> {
+        match self {
+            ProtocolVersion::V1_13_2 => &PACKET_TYPE_MAPPINGS,
+            ProtocolVersion::V1_14_4 => &v1_14_4::PACKET_TYPE_MAPPINGS,
+        }
+    }
+}
+
+impl Default for ProtocolVersion {
+    /// Returns the native protocol version.
+    fn default() -> Self {
+        ProtocolVersion::V1_13_2
+    }
+}
+
+/// Per-connection state used to translate
+/// packets to and from a protocol version.
+pub(crate) enum Translator {
+    V1_13_2,
+    V1_14_4(v1_14_4::Translator),
+}
+
+impl Translator {
+    pub fn new(version: ProtocolVersion) -> Self {
+        match version {
+            ProtocolVersion::V1_13_2 => Translator::V1_13_2,
+            ProtocolVersion::V1_14_4 => Translator::V1_14_4(Default::default()),
+        }
+    }
+
+    pub fn version(&self) -> ProtocolVersion {
+        match self {
+            Translator::V1_13_2 => ProtocolVersion::V1_13_2,
+            Translator::V1_14_4(_) => ProtocolVersion::V1_14_4,
+        }
+    }
+
+    /// Writes a packet, appending the resulting frames
+    /// (packet ID followed by packet data) to `frames`.
+    ///
+    /// A single packet may translate into several frames
+    /// when newer versions split its data across packets.
+    pub fn write_packet(&mut self, packet: &dyn Packet, frames: &mut Vec<BytesMut>) {
+        match self {
+            Translator::V1_13_2 => {
+                let mut buf = BytesMut::new();
+                buf.push_var_int(packet.ty().get_id(ProtocolVersion::V1_13_2).0 as i32);
+                packet.write_to(&mut buf);
+                frames.push(buf);
+            }
+            Translator::V1_14_4(translator) => translator.write_packet(packet, frames),
+        }
+    }
+
+    /// Reads a packet of the given type. `buf` should
+    /// be positioned after the packet ID.
+    pub fn read_packet(
+        &mut self,
+        ty: PacketType,
+        buf: &mut Cursor<&[u8]>,
+    ) -> anyhow::Result<Box<dyn Packet>> {
+        match self {
+            Translator::V1_13_2 => read_native(ty, buf),
+            Translator::V1_14_4(translator) => translator.read_packet(ty, buf),
+        }
+    }
+}
+
+/// Reads a packet in the native protocol layout.
+fn read_native(ty: PacketType, buf: &mut Cursor<&[u8]>) -> anyhow::Result<Box<dyn Packet>> {
+    let mut packet = ty.get_implementation();
+    packet.read_from(buf)?;
+    Ok(packet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PacketDirection, PacketStage};
+
+    #[test]
+    fn from_id() {
+        assert_eq!(
+            ProtocolVersion::from_id(404),
+            Some(ProtocolVersion::V1_13_2)
+        );
+        assert_eq!(
+            ProtocolVersion::from_id(498),
+            Some(ProtocolVersion::V1_14_4)
+        );
+        assert_eq!(ProtocolVersion::from_id(340), None);
+
+        for version in ProtocolVersion::values() {
+            assert_eq!(ProtocolVersion::from_id(version.id()), Some(*version));
+        }
+    }
+
+    #[test]
+    fn packet_ids_differ_per_version() {
+        assert_eq!(
+            PacketType::ChunkData.get_id(ProtocolVersion::V1_13_2),
+            PacketId(0x22, PacketDirection::Clientbound, PacketStage::Play)
+        );
+        assert_eq!(
+            PacketType::ChunkData.get_id(ProtocolVersion::V1_14_4),
+            PacketId(0x21, PacketDirection::Clientbound, PacketStage::Play)
+        );
+
+        let id = PacketId(0x03, PacketDirection::Serverbound, PacketStage::Play);
+        assert_eq!(
+            PacketType::get_from_id(id, ProtocolVersion::V1_13_2),
+            Ok(PacketType::ClientStatus)
+        );
+        assert_eq!(
+            PacketType::get_from_id(id, ProtocolVersion::V1_14_4),
+            Ok(PacketType::ChatMessageServerbound)
+        );
+    }
+
+    #[test]
+    fn packet_ids_roundtrip() {
+        for version in ProtocolVersion::values() {
+            for (id, ty) in version.packet_ids() {
+                assert_eq!(ty.get_id(*version), *id);
+            }
+        }
+    }
+}
@@ -0,0 +1,949 @@
+//! Translation between 1.13.2 and 1.14.4 (protocol 498).
+//!
+//! The block state, item, entity type and menu tables are
+//! generated by `build.rs` from the vanilla reports of both versions.
+
+use super::{read_native, ProtocolVersion};
+use crate::bytes_ext::{BytesExt, BytesMutExt, TryGetError};
+use crate::mctypes::{write_entry_to_buf, McTypeRead, McTypeWrite};
+use crate::packet::{invert_mappings, AsAny, PacketDirection, PacketId, PacketStage};
+use crate::packets::*;
+use crate::{Packet, PacketType};
+use ahash::{AHashMap, AHashSet};
+use bytes::{Buf, BytesMut};
+use feather_anvil::entity::ItemNbt;
+use feather_blocks::BlockId;
+use feather_chunk::{BitArray, Chunk, ChunkSection, GLOBAL_BITS_PER_BLOCK, SECTION_VOLUME};
+use feather_entity_metadata::{EntityMetadata, MetaEntry};
+use feather_items::ItemStack;
+use feather_misc::ParticleData;
+use feather_util::BlockPosition;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::convert::TryFrom;
+use std::io::Cursor;
+
+mod tables {
+    include!(concat!(env!("OUT_DIR"), "/v1_14_4.rs"));
+}
+
+const SERVERBOUND_PLAY: &[(u32, PacketType)] = &[
+    (0x00, PacketType::TeleportConfirm),
+    (0x01, PacketType::QueryBlockNBT),
+    (0x03, PacketType::ChatMessageServerbound),
+    (0x04, PacketType::ClientStatus),
+    (0x05, PacketType::ClientSettings),
+    (0x06, PacketType::TabCompleteServerbound),
+    (0x07, PacketType::ConfirmTransactionServerbound),
+    (0x08, PacketType::EnchantItem),
+    (0x09, PacketType::ClickWindow),
+    (0x0A, PacketType::CloseWindowServerbound),
+    (0x0B, PacketType::PluginMessageServerbound),
+    (0x0C, PacketType::EditBook),
+    (0x0D, PacketType::QueryEntityNBT),
+    (0x0E, PacketType::UseEntity),
+    (0x0F, PacketType::KeepAliveServerbound),
+    (0x11, PacketType::PlayerPosition),
+    (0x12, PacketType::PlayerPositionAndLookServerbound),
+    (0x13, PacketType::PlayerLook),
+    (0x14, PacketType::Player),
+    (0x15, PacketType::VehicleMoveServerbound),
+    (0x16, PacketType::SteerBoat),
+    (0x17, PacketType::PickItem),
+    (0x18, PacketType::CraftRecipeRequest),
+    (0x19, PacketType::PlayerAbilitiesServerbound),
+    (0x1A, PacketType::PlayerDigging),
+    (0x1B, PacketType::EntityAction),
+    (0x1C, PacketType::SteerVehicle),
+    (0x1D, PacketType::RecipeBookData),
+    (0x1E, PacketType::NameItem),
+    (0x1F, PacketType::ResourcePackStatus),
+    (0x20, PacketType::AdvancementTab),
+    (0x21, PacketType::SelectTrade),
+    (0x22, PacketType::SetBeaconEffect),
+    (0x23, PacketType::HeldItemChangeServerbound),
+    (0x24, PacketType::UpdateCommandBlock),
+    (0x25, PacketType::UpdateCommandBlockMinecart),
+    (0x26, PacketType::CreativeInventoryAction),
+    (0x28, PacketType::UpdateStructureBlock),
+    (0x29, PacketType::UpdateSign),
+    (0x2A, PacketType::AnimationServerbound),
+    (0x2B, PacketType::Spectate),
+    (0x2C, PacketType::PlayerBlockPlacement),
+    (0x2D, PacketType::UseItem),
+];
+
+const CLIENTBOUND_PLAY: &[(u32, PacketType)] = &[
+    (0x00, PacketType::SpawnObject),
+    (0x02, PacketType::SpawnGlobalEntity),
+    (0x03, PacketType::SpawnMob),
+    (0x05, PacketType::SpawnPlayer),
+    (0x06, PacketType::AnimationClientbound),
+    (0x08, PacketType::BlockBreakAnimation),
+    (0x09, PacketType::UpdateBlockEntity),
+    (0x0A, PacketType::BlockAction),
+    (0x0B, PacketType::BlockChange),
+    (0x0E, PacketType::ChatMessageClientbound),
+    (0x10, PacketType::TabCompleteClientbound),
+    (0x11, PacketType::DeclareCommands),
+    (0x12, PacketType::ConfirmTransactionClientbound),
+    (0x14, PacketType::WindowItems),
+    (0x16, PacketType::SetSlot),
+    (0x19, PacketType::NamedSoundEffect),
+    (0x1A, PacketType::DisconnectPlay),
+    (0x1B, PacketType::EntityStatus),
+    (0x1D, PacketType::UnloadChunk),
+    (0x1E, PacketType::ChangeGameState),
+    (0x20, PacketType::KeepAliveClientbound),
+    (0x21, PacketType::ChunkData),
+    (0x22, PacketType::Effect),
+    (0x23, PacketType::Particle),
+    (0x24, PacketType::UpdateLight),
+    (0x25, PacketType::JoinGame),
+    (0x28, PacketType::EntityRelativeMove),
+    (0x29, PacketType::EntityLookAndRelativeMove),
+    (0x2A, PacketType::EntityLook),
+    (0x2E, PacketType::OpenWindow),
+    (0x30, PacketType::CraftRecipeResponse),
+    (0x33, PacketType::PlayerInfo),
+    (0x35, PacketType::PlayerPositionAndLookClientbound),
+    (0x36, PacketType::UnlockRecipes),
+    (0x37, PacketType::DestroyEntities),
+    (0x39, PacketType::ResourcePackSend),
+    (0x3A, PacketType::Respawn),
+    (0x3B, PacketType::EntityHeadLook),
+    (0x3F, PacketType::HeldItemChangeClientbound),
+    (0x40, PacketType::UpdateViewPosition),
+    (0x43, PacketType::EntityMetadata),
+    (0x45, PacketType::EntityVelocity),
+    (0x46, PacketType::EntityEquipment),
+    (0x48, PacketType::UpdateHealth),
+    (0x4D, PacketType::SpawnPosition),
+    (0x4E, PacketType::TimeUpdate),
+    (0x55, PacketType::CollectItem),
+    (0x56, PacketType::EntityTeleport),
+    (0x5A, PacketType::DeclareRecipes),
+    (0x5B, PacketType::Tags),
+];
+
+/// Packet IDs for 1.14.4. The handshake, status and
+/// login stages are unchanged from 1.13.2.
+pub(crate) static PACKET_ID_MAPPINGS: Lazy<AHashMap<PacketId, PacketType>> = Lazy::new(|| {
+    let mut m: AHashMap<PacketId, PacketType> = crate::packet::PACKET_ID_MAPPINGS
+        .iter()
+        .filter(|(id, _)| id.2 != PacketStage::Play)
+        .map(|(id, ty)| (*id, *ty))
+        .collect();
+
+    for (id, ty) in SERVERBOUND_PLAY {
+        m.insert(
+            PacketId(*id, PacketDirection::Serverbound, PacketStage::Play),
+            *ty,
+        );
+    }
+    for (id, ty) in CLIENTBOUND_PLAY {
+        m.insert(
+            PacketId(*id, PacketDirection::Clientbound, PacketStage::Play),
+            *ty,
+        );
+    }
+
+    m
+});
+
+pub(crate) static PACKET_TYPE_MAPPINGS: Lazy<AHashMap<PacketType, PacketId>> =
+    Lazy::new(|| invert_mappings(&PACKET_ID_MAPPINGS));
+
+/// Metadata type ID of a pose, added in 1.14.
+const META_POSE: i32 = 18;
+const POSE_STANDING: i32 = 0;
+const POSE_SNEAKING: i32 = 5;
+
+/// Metadata flag set on sneaking entities.
+const FLAG_SNEAKING: i8 = 0x02;
+
+fn lookup(table: &[u16], id: i32) -> Option<i32> {
+    usize::try_from(id)
+        .ok()
+        .and_then(|id| table.get(id))
+        .map(|id| i32::from(*id))
+}
+
+fn block_state(id: i32) -> i32 {
+    lookup(&tables::BLOCK_STATES, id).unwrap_or(0)
+}
+
+fn block_state_of(block: BlockId) -> i32 {
+    block_state(i32::from(block.vanilla_id()))
+}
+
+fn block(id: i32) -> i32 {
+    lookup(&tables::BLOCKS, id).unwrap_or(0)
+}
+
+fn item(id: i32) -> i32 {
+    lookup(&tables::ITEMS, id).unwrap_or(0)
+}
+
+/// Translates a 1.14.4 item ID back to 1.13.2, returning
+/// `None` for items which did not exist in 1.13.2.
+fn item_reverse(id: i32) -> Option<i32> {
+    match lookup(&tables::ITEMS_REVERSE, id) {
+        _ if id == 0 => Some(0), // Air
+        Some(0) | None => None,
+        Some(id) => Some(id),
+    }
+}
+
+fn entity_type(id: i32) -> i32 {
+    lookup(&tables::ENTITY_TYPES, id).unwrap_or(0)
+}
+
+/// Returns the 1.14.4 entity type of a 1.13.2 object type,
+/// since 1.14 spawns objects using the entity type registry.
+fn object_entity_type(object: i8) -> i32 {
+    let entity = match object {
+        1 => 5,   // Boat
+        2 => 32,  // Item
+        3 => 0,   // Area effect cloud
+        10 => 39, // Minecart
+        50 => 55, // Primed TNT
+        51 => 16, // End crystal
+        60 => 2,  // Arrow
+        61 => 67, // Snowball
+        62 => 74, // Egg
+        63 => 34, // Fireball
+        64 => 65, // Small fireball
+        65 => 75, // Ender pearl
+        66 => 85, // Wither skull
+        67 => 60, // Shulker bullet
+        68 => 37, // Llama spit
+        70 => 24, // Falling block
+        71 => 33, // Item frame
+        72 => 23, // Eye of ender
+        73 => 77, // Potion
+        75 => 76, // Experience bottle
+        76 => 25, // Firework rocket
+        77 => 35, // Leash knot
+        78 => 1,  // Armor stand
+        79 => 20, // Evoker fangs
+        90 => 93, // Fishing bobber
+        91 => 68, // Spectral arrow
+        93 => 13, // Dragon fireball
+        94 => 94, // Trident
+        _ => return 0,
+    };
+    entity_type(entity)
+}
+
+/// Returns the 1.14.4 menu ID for a 1.13.2 window type.
+///
+/// Horse inventories are opened with a dedicated packet
+/// in 1.14 and are not supported.
+fn menu(window_type: &str, slots: u8) -> i32 {
+    let name = window_type.trim_start_matches("minecraft:");
+    let name = match name {
+        "chest" | "container" => format!("generic_9x{}", (slots / 9).max(1).min(6)),
+        "crafting_table" => String::from("crafting"),
+        "enchanting_table" => String::from("enchantment"),
+        "dispenser" | "dropper" => String::from("generic_3x3"),
+        "villager" => String::from("merchant"),
+        name => String::from(name),
+    };
+
+    tables::MENUS
+        .iter()
+        .find(|(menu, _)| menu.trim_start_matches("minecraft:") == name)
+        .map_or(0, |(_, id)| i32::from(*id))
+}
+
+/// Writes a block position. 1.14 moved
+/// the Y coordinate to the low bits.
+fn push_position(buf: &mut BytesMut, pos: &BlockPosition) {
+    let val = ((pos.x as u64 & 0x03FF_FFFF) << 38)
+        | ((pos.z as u64 & 0x03FF_FFFF) << 12)
+        | (pos.y as u64 & 0xFFF);
+
+    buf.push_u64(val);
+}
+
+fn try_get_position(buf: &mut Cursor<&[u8]>) -> Result<BlockPosition, TryGetError> {
+    let val = buf.try_get_i64()?;
+    let x = val >> 38;
+    let y = val << 52 >> 52;
+    let z = val << 26 >> 38;
+
+    Ok(BlockPosition::new(x as i32, y as i32, z as i32))
+}
+
+fn push_slot(buf: &mut BytesMut, slot: Option<&ItemStack>) {
+    buf.push_bool(slot.is_some());
+
+    if let Some(slot) = slot {
+        buf.push_var_int(item(slot.ty.vanilla_id() as i32));
+        buf.push_i8(slot.amount as i8);
+        let tags: ItemNbt = slot.into();
+
+        if tags != Default::default() {
+            buf.push_nbt(&tags);
+        } else {
+            buf.push_i8(0x00); // TAG_End
+        }
+    }
+}
+
+/// Writes entity metadata. 1.14 inserted the pose at index 6
+/// and, for living entities, the bed location at index 12,
+/// shifting all following entries.
+fn push_metadata(buf: &mut BytesMut, meta: &EntityMetadata, living: bool) {
+    for (index, entry) in meta.iter() {
+        let index = match index {
+            0..=5 => index,
+            6..=10 if living => index + 1,
+            _ if living => index + 2,
+            _ => index + 1,
+        };
+        buf.push_u8(index);
+        buf.push_var_int(entry.id());
+
+        match entry {
+            MetaEntry::Slot(slot) => push_slot(buf, slot.as_ref()),
+            MetaEntry::Position(pos) => push_position(buf, pos),
+            MetaEntry::OptPosition(pos) => {
+                buf.push_bool(pos.is_some());
+                if let Some(pos) = pos {
+                    push_position(buf, pos);
+                }
+            }
+            MetaEntry::OptBlockId(id) => buf.push_var_int(id.map_or(0, block_state)),
+            entry => write_entry_to_buf(entry, buf),
+        }
+    }
+
+    if let Some(MetaEntry::Byte(flags)) = meta.get(0) {
+        buf.push_u8(6);
+        buf.push_var_int(META_POSE);
+        if flags & FLAG_SNEAKING != 0 {
+            buf.push_var_int(POSE_SNEAKING);
+        } else {
+            buf.push_var_int(POSE_STANDING);
+        }
+    }
+
+    buf.push_u8(0xff); // End of metadata
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+struct Heightmaps {
+    #[serde(serialize_with = "nbt::i64_array")]
+    motion_blocking: Vec<i64>,
+}
+
+fn push_longs(buf: &mut BytesMut, data: &BitArray) {
+    let data = data.inner();
+    buf.push_var_int(data.len() as i32);
+    buf.reserve(data.len() * 8);
+    for val in data {
+        buf.push_u64(*val);
+    }
+}
+
+fn push_section(buf: &mut BytesMut, section: &ChunkSection) {
+    let mut non_air = 0;
+    for y in 0..16 {
+        for z in 0..16 {
+            for x in 0..16 {
+                if section.block_at(x, y, z) != BlockId::air() {
+                    non_air += 1;
+                }
+            }
+        }
+    }
+    buf.push_i16(non_air);
+    buf.push_u8(section.bits_per_block());
+
+    if let Some(palette) = section.palette() {
+        buf.push_var_int(palette.len() as i32);
+        for block in palette {
+            buf.push_var_int(block_state_of(*block));
+        }
+        push_longs(buf, section.data());
+    } else {
+        // The global palette differs between versions,
+        // so the data array has to be rewritten.
+        let mut data = BitArray::new(GLOBAL_BITS_PER_BLOCK, SECTION_VOLUME);
+        for index in 0..SECTION_VOLUME {
+            let state = block_state(section.data().get(index) as i32);
+            data.set(index, state as u64);
+        }
+        push_longs(buf, &data);
+    }
+}
+
+/// Writes chunk data. Light is sent separately
+/// in 1.14, and heightmaps were added.
+fn write_chunk_data(chunk: &Chunk, buf: &mut BytesMut) {
+    buf.push_i32(chunk.position().x);
+    buf.push_i32(chunk.position().z);
+    buf.push_bool(true); // Full chunk
+
+    let sections = chunk.sections();
+    let primary_mask = sections
+        .iter()
+        .enumerate()
+        .filter(|(_, section)| section.is_some())
+        .fold(0, |mask, (y, _)| mask | 1 << y);
+    buf.push_var_int(primary_mask);
+
+    let mut heightmap = BitArray::new(9, 256);
+    for z in 0..16 {
+        for x in 0..16 {
+            let height = chunk.heightmap(x, z).motion_blocking();
+            heightmap.set(z * 16 + x, u64::from(height));
+        }
+    }
+    buf.push_nbt(&Heightmaps {
+        motion_blocking: heightmap.inner().iter().map(|val| *val as i64).collect(),
+    });
+
+    let mut data = BytesMut::new();
+    for section in sections.iter().flatten() {
+        push_section(&mut data, section);
+    }
+
+    data.reserve(256 * 4);
+    for biome in chunk.biomes() {
+        data.push_i32(biome.protocol_id());
+    }
+
+    buf.push_var_int(data.len() as i32);
+    buf.extend_from_slice(&data);
+
+    buf.push_var_int(0); // Block entities are sent separately
+}
+
+fn push_tags(buf: &mut BytesMut, tags: &[(String, Vec<i32>)], translate: fn(i32) -> i32) {
+    buf.push_var_int(tags.len() as i32);
+    for (identifier, entries) in tags {
+        buf.push_string(identifier);
+        buf.push_var_int(entries.len() as i32);
+        for entry in entries {
+            buf.push_var_int(translate(*entry));
+        }
+    }
+}
+
+fn push_ingredient(buf: &mut BytesMut, ingredient: &[ItemStack]) {
+    buf.push_var_int(ingredient.len() as i32);
+    for stack in ingredient {
+        push_slot(buf, Some(stack));
+    }
+}
+
+/// Writes declared recipes. 1.14 swapped
+/// the order of the recipe ID and type.
+fn write_declare_recipes(packet: &DeclareRecipes, buf: &mut BytesMut) {
+    buf.push_var_int(packet.recipes.len() as i32);
+    for recipe in &packet.recipes {
+        match &recipe.data {
+            DeclaredRecipeData::Shapeless {
+                group,
+                ingredients,
+                result,
+            } => {
+                buf.push_string("crafting_shapeless");
+                buf.push_string(&recipe.id);
+                buf.push_string(group);
+                buf.push_var_int(ingredients.len() as i32);
+                for ingredient in ingredients {
+                    push_ingredient(buf, ingredient);
+                }
+                push_slot(buf, Some(result));
+            }
+            DeclaredRecipeData::Shaped {
+                width,
+                height,
+                group,
+                ingredients,
+                result,
+            } => {
+                buf.push_string("crafting_shaped");
+                buf.push_string(&recipe.id);
+                buf.push_var_int(*width);
+                buf.push_var_int(*height);
+                buf.push_string(group);
+                for ingredient in ingredients {
+                    push_ingredient(buf, ingredient);
+                }
+                push_slot(buf, Some(result));
+            }
+            DeclaredRecipeData::Smelting {
+                group,
+                ingredient,
+                result,
+                experience,
+                cooking_time,
+            } => {
+                buf.push_string("smelting");
+                buf.push_string(&recipe.id);
+                buf.push_string(group);
+                push_ingredient(buf, ingredient);
+                push_slot(buf, Some(result));
+                buf.push_f32(*experience);
+                buf.push_var_int(*cooking_time);
+            }
+        }
+    }
+}
+
+fn write_particle(packet: &Particle, buf: &mut BytesMut) {
+    buf.push_i32(packet.data.ordinal() as i32);
+    buf.push_bool(packet.long_distance);
+    buf.push_f32(packet.x);
+    buf.push_f32(packet.y);
+    buf.push_f32(packet.z);
+    buf.push_f32(packet.offset_x);
+    buf.push_f32(packet.offset_y);
+    buf.push_f32(packet.offset_z);
+    buf.push_f32(packet.particle_data);
+    buf.push_i32(packet.particle_count);
+    match &packet.data {
+        ParticleData::Block(block) | ParticleData::FallingDust(block) => {
+            buf.push_var_int(block_state_of(*block));
+        }
+        ParticleData::Dust {
+            red,
+            green,
+            blue,
+            scale,
+        } => {
+            buf.push_f32(*red);
+            buf.push_f32(*green);
+            buf.push_f32(*blue);
+            buf.push_f32(*scale);
+        }
+        ParticleData::Item(stack) => push_slot(buf, stack.as_ref()),
+        _ => (),
+    }
+}
+
+fn cast<P: Packet>(packet: &dyn Packet) -> &P {
+    packet
+        .as_any()
+        .downcast_ref()
+        .expect("packet type does not match implementation")
+}
+
+fn chunk_of(x: f64, z: f64) -> (i32, i32) {
+    ((x.floor() as i32) >> 4, (z.floor() as i32) >> 4)
+}
+
+/// Per-connection state for translating to 1.14.4.
+#[derive(Default)]
+pub(crate) struct Translator {
+    /// Living entities known to the client. Their
+    /// metadata layout differs from other entities.
+    living: AHashSet<i32>,
+    /// The chunk the player was last known to be in.
+    player_chunk: Option<(i32, i32)>,
+    /// The chunk last sent as the client's view center.
+    /// 1.14 clients discard chunks outside their view distance
+    /// from this chunk.
+    view_center: Option<(i32, i32)>,
+}
+
+impl Translator {
+    pub fn write_packet(&mut self, packet: &dyn Packet, frames: &mut Vec<BytesMut>) {
+        let ty = packet.ty();
+        let mut buf = BytesMut::new();
+        buf.push_var_int(ty.get_id(ProtocolVersion::V1_14_4).0 as i32);
+
+        match ty {
+            PacketType::SpawnObject => {
+                let packet = cast::<SpawnObject>(packet);
+                buf.push_var_int(packet.entity_id);
+                buf.push_uuid(&packet.object_uuid);
+                buf.push_var_int(object_entity_type(packet.ty));
+                buf.push_f64(packet.x);
+                buf.push_f64(packet.y);
+                buf.push_f64(packet.z);
+                buf.push_u8(packet.pitch);
+                buf.push_u8(packet.yaw);
+                if packet.ty == 70 {
+                    // Falling block: data is a block state
+                    buf.push_i32(block_state(packet.data));
+                } else {
+                    buf.push_i32(packet.data);
+                }
+                buf.push_i16(packet.velocity_x);
+                buf.push_i16(packet.velocity_y);
+                buf.push_i16(packet.velocity_z);
+            }
+            PacketType::SpawnMob => {
+                let packet = cast::<SpawnMob>(packet);
+                self.living.insert(packet.entity_id);
+                buf.push_var_int(packet.entity_id);
+                buf.push_uuid(&packet.entity_uuid);
+                buf.push_var_int(entity_type(packet.ty));
+                buf.push_f64(packet.x);
+                buf.push_f64(packet.y);
+                buf.push_f64(packet.z);
+                buf.push_u8(packet.yaw);
+                buf.push_u8(packet.pitch);
+                buf.push_u8(packet.head_pitch);
+                buf.push_i16(packet.velocity_x);
+                buf.push_i16(packet.velocity_y);
+                buf.push_i16(packet.velocity_z);
+                push_metadata(&mut buf, &packet.meta, true);
+            }
+            PacketType::SpawnPlayer => {
+                let packet = cast::<SpawnPlayer>(packet);
+                self.living.insert(packet.entity_id);
+                buf.push_var_int(packet.entity_id);
+                buf.push_uuid(&packet.player_uuid);
+                buf.push_f64(packet.x);
+                buf.push_f64(packet.y);
+                buf.push_f64(packet.z);
+                buf.push_u8(packet.yaw);
+                buf.push_u8(packet.pitch);
+                push_metadata(&mut buf, &packet.metadata, true);
+            }
+            PacketType::EntityMetadata => {
+                let packet = cast::<PacketEntityMetadata>(packet);
+                buf.push_var_int(packet.entity_id);
+                let living = self.living.contains(&packet.entity_id);
+                push_metadata(&mut buf, &packet.metadata, living);
+            }
+            PacketType::DestroyEntities => {
+                let packet = cast::<DestroyEntities>(packet);
+                for entity_id in &packet.entity_ids {
+                    self.living.remove(entity_id);
+                }
+                packet.write_to(&mut buf);
+            }
+            PacketType::BlockBreakAnimation => {
+                let packet = cast::<BlockBreakAnimation>(packet);
+                buf.push_var_int(packet.entity_id);
+                push_position(&mut buf, &packet.location);
+                buf.push_i8(packet.destroy_stage);
+            }
+            PacketType::UpdateBlockEntity => {
+                let packet = cast::<UpdateBlockEntity>(packet);
+                push_position(&mut buf, &packet.location);
+                buf.push_u8(packet.action);
+                buf.push_nbt(&packet.data);
+            }
+            PacketType::BlockAction => {
+                let packet = cast::<BlockAction>(packet);
+                push_position(&mut buf, &packet.location);
+                buf.push_u8(packet.action_id);
+                buf.push_u8(packet.action_param);
+                buf.push_var_int(block(packet.block_type));
+            }
+            PacketType::BlockChange => {
+                let packet = cast::<BlockChange>(packet);
+                push_position(&mut buf, &packet.location);
+                buf.push_var_int(block_state(packet.block_id));
+            }
+            PacketType::Effect => {
+                let packet = cast::<Effect>(packet);
+                buf.push_i32(packet.effect_id);
+                push_position(&mut buf, &packet.location);
+                if packet.effect_id == 2001 {
+                    // Block break: data is a block state
+                    buf.push_i32(block_state(packet.data));
+                } else {
+                    buf.push_i32(packet.data);
+                }
+                buf.push_bool(packet.disable_relative_volume);
+            }
+            PacketType::SpawnPosition => {
+                let packet = cast::<SpawnPosition>(packet);
+                push_position(&mut buf, &packet.location);
+            }
+            PacketType::JoinGame => {
+                let packet = cast::<JoinGame>(packet);
+                self.living.insert(packet.entity_id);
+                buf.push_i32(packet.entity_id);
+                buf.push_u8(packet.gamemode);
+                buf.push_i32(packet.dimension);
+                buf.push_u8(packet.max_players);
+                buf.push_string(&packet.level_type);
+                buf.push_var_int(i32::from(packet.view_distance));
+                buf.push_bool(packet.reduced_debug_info);
+            }
+            PacketType::Respawn => {
+                let packet = cast::<Respawn>(packet);
+                buf.push_i32(packet.dimension);
+                buf.push_u8(packet.gamemode);
+                buf.push_string(&packet.level_type);
+            }
+            PacketType::OpenWindow => {
+                let packet = cast::<OpenWindow>(packet);
+                buf.push_var_int(i32::from(packet.window_id));
+                buf.push_var_int(menu(&packet.window_type, packet.number_of_slots));
+                buf.push_string(&packet.window_title);
+            }
+            PacketType::WindowItems => {
+                let packet = cast::<WindowItems>(packet);
+                buf.push_u8(packet.window_id);
+                buf.push_i16(packet.slots.len() as i16);
+                for slot in &packet.slots {
+                    push_slot(&mut buf, slot.as_ref());
+                }
+            }
+            PacketType::SetSlot => {
+                let packet = cast::<SetSlot>(packet);
+                buf.push_i8(packet.window_id);
+                buf.push_i16(packet.slot);
+                push_slot(&mut buf, packet.slot_data.as_ref());
+            }
+            PacketType::EntityEquipment => {
+                let packet = cast::<EntityEquipment>(packet);
+                buf.push_var_int(packet.entity_id);
+                buf.push_var_int(packet.slot);
+                push_slot(&mut buf, packet.item.as_ref());
+            }
+            PacketType::Particle => write_particle(cast(packet), &mut buf),
+            PacketType::DeclareRecipes => write_declare_recipes(cast(packet), &mut buf),
+            PacketType::Tags => {
+                let packet = cast::<Tags>(packet);
+                push_tags(&mut buf, &packet.block_tags, block);
+                push_tags(&mut buf, &packet.item_tags, item);
+                push_tags(&mut buf, &packet.fluid_tags, |id| id);
+                buf.push_var_int(0); // Entity tags
+            }
+            PacketType::ChunkData => {
+                let packet = cast::<ChunkData>(packet);
+                self.sync_view_center(frames);
+                self.write_packet(
+                    &UpdateLight {
+                        chunk: packet.chunk.clone(),
+                    },
+                    frames,
+                );
+                write_chunk_data(&packet.chunk.read(), &mut buf);
+            }
+            PacketType::PlayerPositionAndLookClientbound => {
+                let packet = cast::<PlayerPositionAndLookClientbound>(packet);
+                // Relative teleports do not tell us where the player is
+                if packet.flags & 0b101 == 0 {
+                    self.player_chunk = Some(chunk_of(packet.x, packet.z));
+                }
+                packet.write_to(&mut buf);
+            }
+            _ => packet.write_to(&mut buf),
+        }
+
+        frames.push(buf);
+    }
+
+    /// Sends the player's chunk as the view center
+    /// if it changed since it was last sent.
+    fn sync_view_center(&mut self, frames: &mut Vec<BytesMut>) {
+        if self.player_chunk.is_none() || self.player_chunk == self.view_center {
+            return;
+        }
+
+        let (chunk_x, chunk_z) = self.player_chunk.unwrap();
+        self.view_center = self.player_chunk;
+        self.write_packet(&UpdateViewPosition { chunk_x, chunk_z }, frames);
+    }
+
+    pub fn read_packet(
+        &mut self,
+        ty: PacketType,
+        buf: &mut Cursor<&[u8]>,
+    ) -> anyhow::Result<Box<dyn Packet>> {
+        let transcoded = match ty {
+            PacketType::PlayerDigging => Some(transcode(buf, |src, dst| {
+                dst.push_var_int(src.try_get_var_int()?); // Status
+                transcode_position(src, dst)
+            })?),
+            PacketType::QueryBlockNBT => Some(transcode(buf, |src, dst| {
+                dst.push_var_int(src.try_get_var_int()?); // Transaction ID
+                transcode_position(src, dst)
+            })?),
+            PacketType::UpdateSign
+            | PacketType::UpdateCommandBlock
+            | PacketType::UpdateStructureBlock => Some(transcode(buf, transcode_position)?),
+            PacketType::PlayerBlockPlacement => Some(transcode(buf, |src, dst| {
+                let hand = src.try_get_var_int()?;
+                transcode_position(src, dst)?;
+                dst.push_var_int(src.try_get_var_int()?); // Face
+                dst.push_var_int(hand);
+                copy(src, dst, 3 * 4)?; // Cursor position
+                src.try_get_bool()?; // Inside block; not sent by 1.13 clients
+                Ok(())
+            })?),
+            PacketType::ClickWindow => Some(transcode(buf, |src, dst| {
+                copy(src, dst, 6)?; // Window ID, slot, button, action number
+                dst.push_var_int(src.try_get_var_int()?); // Mode
+                transcode_slot(src, dst)
+            })?),
+            PacketType::CreativeInventoryAction => Some(transcode(buf, |src, dst| {
+                copy(src, dst, 2)?; // Slot
+                transcode_slot(src, dst)
+            })?),
+            PacketType::EditBook => Some(transcode(buf, transcode_slot)?),
+            _ => None,
+        };
+
+        let packet = match transcoded {
+            Some(transcoded) => read_native(ty, &mut Cursor::new(&transcoded[..]))?,
+            None => read_native(ty, buf)?,
+        };
+
+        if let Some(packet) = packet.as_any().downcast_ref::<PlayerPosition>() {
+            self.player_chunk = Some(chunk_of(packet.x, packet.z));
+        } else if let Some(packet) = packet
+            .as_any()
+            .downcast_ref::<PlayerPositionAndLookServerbound>()
+        {
+            self.player_chunk = Some(chunk_of(packet.x, packet.z));
+        }
+
+        Ok(packet)
+    }
+}
+
+/// Rewrites the start of a serverbound packet into the
+/// 1.13.2 layout using `f`, then copies the remaining bytes.
+fn transcode(
+    src: &mut Cursor<&[u8]>,
+    f: impl FnOnce(&mut Cursor<&[u8]>, &mut BytesMut) -> anyhow::Result<()>,
+) -> anyhow::Result<BytesMut> {
+    let mut dst = BytesMut::new();
+    f(src, &mut dst)?;
+    dst.extend_from_slice(src.bytes());
+    Ok(dst)
+}
+
+fn copy(src: &mut Cursor<&[u8]>, dst: &mut BytesMut, len: usize) -> anyhow::Result<()> {
+    if src.remaining() < len {
+        return Err(TryGetError::NotEnoughBytes.into());
+    }
+
+    dst.extend_from_slice(&src.bytes()[..len]);
+    src.advance(len);
+    Ok(())
+}
+
+fn transcode_position(src: &mut Cursor<&[u8]>, dst: &mut BytesMut) -> anyhow::Result<()> {
+    dst.push_position(&try_get_position(src)?);
+    Ok(())
+}
+
+/// Translates the item ID of a slot. Items which did
+/// not exist in 1.13.2 are replaced with an empty slot.
+fn transcode_slot(src: &mut Cursor<&[u8]>, dst: &mut BytesMut) -> anyhow::Result<()> {
+    if !src.try_get_bool()? {
+        dst.push_bool(false);
+        return Ok(());
+    }
+
+    match item_reverse(src.try_get_var_int()?) {
+        Some(id) => {
+            dst.push_bool(true);
+            dst.push_var_int(id);
+        }
+        None => {
+            dst.push_bool(false);
+            // Skip the count and NBT
+            src.try_get_i8()?;
+            let _: Option<nbt::Blob> = src.try_get_nbt().ok();
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_layout() {
+        let mut buf = BytesMut::new();
+        push_position(&mut buf, &BlockPosition::new(1, 2, 3));
+        assert_eq!(&buf[..], &((1u64 << 38) | (3 << 12) | 2).to_be_bytes());
+
+        for pos in &[
+            BlockPosition::new(0, 0, 0),
+            BlockPosition::new(-1, 255, -1),
+            BlockPosition::new(-30_000_000, 64, 29_999_999),
+        ] {
+            let mut buf = BytesMut::new();
+            push_position(&mut buf, pos);
+            assert_eq!(try_get_position(&mut Cursor::new(&buf[..])), Ok(*pos));
+        }
+    }
+
+    #[test]
+    fn metadata_indices() {
+        let meta = EntityMetadata::entity_base().with(6, 1u8).with(11, 2u8);
+
+        let mut living = BytesMut::new();
+        push_metadata(&mut living, &meta, true);
+        let mut other = BytesMut::new();
+        push_metadata(&mut other, &meta, false);
+
+        let indices = |buf: &BytesMut| {
+            let mut cursor = Cursor::new(&buf[..]);
+            let mut indices = vec![];
+            loop {
+                let index = cursor.try_get_u8().unwrap();
+                if index == 0xff {
+                    break indices;
+                }
+                indices.push(index);
+                let ty = cursor.try_get_var_int().unwrap();
+                match ty {
+                    0 => cursor.advance(1),
+                    1 | 18 => {
+                        cursor.try_get_var_int().unwrap();
+                    }
+                    7 => cursor.advance(1),
+                    5 => {
+                        assert!(!cursor.try_get_bool().unwrap());
+                    }
+                    ty => panic!("unexpected metadata type {}", ty),
+                }
+            }
+        };
+
+        assert_eq!(indices(&living), vec![0, 1, 2, 3, 4, 5, 7, 13, 6]);
+        assert_eq!(indices(&other), vec![0, 1, 2, 3, 4, 5, 7, 12, 6]);
+    }
+
+    #[test]
+    fn player_block_placement() {
+        let mut buf = BytesMut::new();
+        buf.push_var_int(1); // Off hand
+        push_position(&mut buf, &BlockPosition::new(-5, 70, 12));
+        buf.push_var_int(1); // Top
+        buf.push_f32(0.5);
+        buf.push_f32(1.0);
+        buf.push_f32(0.25);
+        buf.push_bool(false);
+
+        let mut translator = Translator::default();
+        let packet = translator
+            .read_packet(PacketType::PlayerBlockPlacement, &mut Cursor::new(&buf[..]))
+            .unwrap();
+        let packet = cast::<PlayerBlockPlacement>(packet.as_ref());
+
+        assert_eq!(packet.location, BlockPosition::new(-5, 70, 12));
+        assert_eq!(packet.face, Face::Top);
+        assert_eq!(packet.hand, 1);
+        assert_eq!(packet.cursor_position_z, 0.25);
+    }
+
+    #[test]
+    fn menus() {
+        assert_eq!(menu("minecraft:generic_9x6", 54), 5);
+        assert_eq!(menu("minecraft:chest", 27), 2);
+        assert_eq!(menu("minecraft:crafting_table", 0), 11);
+    }
+}
@@ -0,0 +1,77 @@
+use anyhow::Context;
+use serde_json::{Map, Value};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::{env, fs::File};
+use walkdir::WalkDir;
+
+fn main() {
+    if let Err(e) = run() {
+        panic!("{:?}", e);
+    }
+
+    println!(
+        "cargo:rerun-if-changed={}",
+        concat!(env!("CARGO_MANIFEST_DIR"), "/build.rs")
+    );
+}
+
+fn run() -> anyhow::Result<()> {
+    // Load in all recipes and item tags, then dump them into
+    // ${OUT_DIR}/dump.json for inclusion in `feather-recipes`.
+    let data = format!("{}/minecraft/data/minecraft", feather_data::minecraft::PATH);
+
+    let mut dump = Map::new();
+    dump.insert(
+        "recipes".to_owned(),
+        Value::Object(load_dir(format!("{}/recipes", data))?),
+    );
+    dump.insert(
+        "item_tags".to_owned(),
+        Value::Object(load_dir(format!("{}/tags/items", data))?),
+    );
+
+    let dump_path = format!("{}/dump.json", env::var("OUT_DIR")?);
+    let mut file = File::create(&dump_path)?;
+    let vec = serde_json::to_vec(&Value::Object(dump)).unwrap();
+    file.write_all(vec.as_slice())?;
+
+    Ok(())
+}
+
+/// Loads all JSON files in `input`, keyed by their namespaced ID.
+fn load_dir(input: impl AsRef<Path>) -> anyhow::Result<Map<String, Value>> {
+    let input = input.as_ref();
+    let mut map = Map::new();
+
+    for entry in WalkDir::new(input) {
+        let entry = entry.context("entry access failed")?;
+
+        if entry.metadata()?.is_dir() {
+            continue;
+        }
+
+        // Determine path of file relative to the base directory
+        let mut relative_path = entry
+            .path()
+            .strip_prefix(input)
+            .with_context(|| format!("failed to strip prefix for `{}`", entry.path().display()))?
+            .to_str()
+            .context("path contains invalid UTF-8")?;
+        // strip .json suffix
+        relative_path = &relative_path[..relative_path.len() - 5];
+
+        // replace \\ with / for windows
+        let id = format!("minecraft:{}", relative_path.replace("\\", "/"));
+
+        let mut s = String::new();
+        let mut file = File::open(entry.path())?;
+        file.read_to_string(&mut s)?;
+
+        let value = serde_json::from_str::<Value>(&s)
+            .with_context(|| format!("failed to parse `{}`", id))?;
+        map.insert(id, value);
+    }
+
+    Ok(map)
+}
@@ -0,0 +1,573 @@
+//! Implements crafting and smelting recipes, loaded from the
+//! vanilla recipe data.
+//!
+//! Tag-based ingredients are resolved to the set of items in the
+//! tag when recipes are loaded, so matching never has to consult tags.
+
+use ahash::AHashMap;
+use feather_items::{Item, ItemStack};
+use model::{Dump, RawIngredient, RawIngredientEntry, RawRecipe, RawResult, RawTag};
+use once_cell::sync::Lazy;
+use smallvec::SmallVec;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use thiserror::Error;
+
+mod model;
+
+/// The global recipe store, initialized at runtime from
+/// the embedded recipe dump. (Generated by the build script)
+static STORE: Lazy<RecipeStore> = Lazy::new(|| {
+    static BYTES: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/dump.json"));
+
+    let dump = serde_json::from_slice::<Dump>(BYTES).expect("invalid recipe dump");
+    RecipeStore::from_dump(dump).expect("invalid recipe dump")
+});
+
+/// Returns all loaded recipes.
+pub fn recipes() -> &'static [Recipe] {
+    &STORE.recipes
+}
+
+/// Returns the recipe with the given ID, if it exists.
+/// IDs are the same as those used in MC data packs,
+/// e.g. "minecraft:crafting_table".
+pub fn recipe(id: &str) -> Option<&'static Recipe> {
+    STORE.by_id.get(id).map(|index| &STORE.recipes[*index])
+}
+
+/// Finds the crafting recipe matching the given crafting grid.
+///
+/// `grid` contains the grid's slots row by row; `width` is the
+/// width of the grid (2 for the player inventory and 3
+/// for crafting tables).
+pub fn find_crafting_recipe(grid: &[Option<ItemStack>], width: usize) -> Option<&'static Recipe> {
+    STORE.recipes.iter().find(|recipe| match &recipe.kind {
+        RecipeKind::Shaped(shaped) => shaped.matches(grid, width),
+        RecipeKind::Shapeless(shapeless) => shapeless.matches(grid),
+        RecipeKind::Smelting(_) => false,
+    })
+}
+
+/// Finds the smelting recipe for the given input item.
+pub fn find_smelting_recipe(input: Item) -> Option<&'static SmeltingRecipe> {
+    STORE
+        .recipes
+        .iter()
+        .filter_map(|recipe| match &recipe.kind {
+            RecipeKind::Smelting(smelting) => Some(smelting),
+            _ => None,
+        })
+        .find(|smelting| smelting.ingredient.matches(input))
+}
+
+/// Returns the item left in a crafting grid slot
+/// after an item of type `item` is used in a recipe.
+/// For example, the bucket of a milk bucket used for a cake.
+pub fn crafting_remainder(item: Item) -> Option<Item> {
+    match item {
+        Item::MilkBucket | Item::WaterBucket | Item::LavaBucket => Some(Item::Bucket),
+        Item::DragonBreath => Some(Item::GlassBottle),
+        _ => None,
+    }
+}
+
+/// A recipe.
+#[derive(Debug, Clone)]
+pub struct Recipe {
+    /// The namespaced ID of this recipe.
+    pub id: String,
+    /// Recipes with the same group are shown
+    /// together in the recipe book. May be empty.
+    pub group: String,
+    pub kind: RecipeKind,
+}
+
+impl Recipe {
+    /// Returns the item stack yielded by this recipe.
+    pub fn result(&self) -> ItemStack {
+        match &self.kind {
+            RecipeKind::Shaped(shaped) => shaped.result.clone(),
+            RecipeKind::Shapeless(shapeless) => shapeless.result.clone(),
+            RecipeKind::Smelting(smelting) => smelting.result.clone(),
+        }
+    }
+
+    /// Returns whether the given item is accepted
+    /// by any ingredient of this recipe.
+    pub fn uses(&self, item: Item) -> bool {
+        match &self.kind {
+            RecipeKind::Shaped(shaped) => shaped
+                .ingredients
+                .iter()
+                .flatten()
+                .any(|ingredient| ingredient.matches(item)),
+            RecipeKind::Shapeless(shapeless) => shapeless
+                .ingredients
+                .iter()
+                .any(|ingredient| ingredient.matches(item)),
+            RecipeKind::Smelting(smelting) => smelting.ingredient.matches(item),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RecipeKind {
+    Shaped(ShapedRecipe),
+    Shapeless(ShapelessRecipe),
+    Smelting(SmeltingRecipe),
+}
+
+/// A set of items, any of which is accepted
+/// for a slot in a recipe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ingredient(pub SmallVec<[Item; 1]>);
+
+impl Ingredient {
+    /// Returns whether the given item is accepted by this ingredient.
+    pub fn matches(&self, item: Item) -> bool {
+        self.0.contains(&item)
+    }
+}
+
+/// A crafting recipe whose ingredients have to be
+/// arranged in a certain pattern.
+///
+/// The pattern may be placed anywhere in the grid and
+/// may be mirrored horizontally.
+#[derive(Debug, Clone)]
+pub struct ShapedRecipe {
+    pub width: usize,
+    pub height: usize,
+    /// Ingredients of the pattern, row by row. `None`
+    /// indicates a slot which must be empty.
+    pub ingredients: Vec<Option<Ingredient>>,
+    pub result: ItemStack,
+}
+
+impl ShapedRecipe {
+    /// Returns whether the given crafting grid matches this recipe.
+    pub fn matches(&self, grid: &[Option<ItemStack>], grid_width: usize) -> bool {
+        let bounds = match Bounds::of(grid, grid_width) {
+            Some(bounds) => bounds,
+            None => return false,
+        };
+
+        if bounds.width() != self.width || bounds.height() != self.height {
+            return false;
+        }
+
+        self.matches_at(grid, grid_width, &bounds, false)
+            || self.matches_at(grid, grid_width, &bounds, true)
+    }
+
+    fn matches_at(
+        &self,
+        grid: &[Option<ItemStack>],
+        grid_width: usize,
+        bounds: &Bounds,
+        mirrored: bool,
+    ) -> bool {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let pattern_x = if mirrored { self.width - 1 - x } else { x };
+                let ingredient = &self.ingredients[y * self.width + pattern_x];
+                let slot = &grid[(bounds.min_y + y) * grid_width + bounds.min_x + x];
+
+                let matches = match (ingredient, slot) {
+                    (None, None) => true,
+                    (Some(ingredient), Some(stack)) => ingredient.matches(stack.ty),
+                    _ => false,
+                };
+                if !matches {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+}
+
+/// The smallest rectangle containing all
+/// non-empty slots in a crafting grid.
+struct Bounds {
+    min_x: usize,
+    min_y: usize,
+    max_x: usize,
+    max_y: usize,
+}
+
+impl Bounds {
+    fn of(grid: &[Option<ItemStack>], grid_width: usize) -> Option<Self> {
+        let mut bounds: Option<Bounds> = None;
+
+        for (index, _) in grid.iter().enumerate().filter(|(_, slot)| slot.is_some()) {
+            let (x, y) = (index % grid_width, index / grid_width);
+            bounds = Some(match bounds {
+                Some(b) => Bounds {
+                    min_x: b.min_x.min(x),
+                    min_y: b.min_y.min(y),
+                    max_x: b.max_x.max(x),
+                    max_y: b.max_y.max(y),
+                },
+                None => Bounds {
+                    min_x: x,
+                    min_y: y,
+                    max_x: x,
+                    max_y: y,
+                },
+            });
+        }
+
+        bounds
+    }
+
+    fn width(&self) -> usize {
+        self.max_x - self.min_x + 1
+    }
+
+    fn height(&self) -> usize {
+        self.max_y - self.min_y + 1
+    }
+}
+
+/// A crafting recipe whose ingredients may be
+/// placed anywhere in the grid.
+#[derive(Debug, Clone)]
+pub struct ShapelessRecipe {
+    pub ingredients: Vec<Ingredient>,
+    pub result: ItemStack,
+}
+
+impl ShapelessRecipe {
+    /// Returns whether the given crafting grid matches this recipe.
+    pub fn matches(&self, grid: &[Option<ItemStack>]) -> bool {
+        let items: SmallVec<[Item; 9]> = grid.iter().flatten().map(|stack| stack.ty).collect();
+        if items.len() != self.ingredients.len() {
+            return false;
+        }
+
+        let mut used = [false; 9];
+        assign_ingredients(&self.ingredients, &items, &mut used)
+    }
+}
+
+/// Attempts to assign each ingredient a distinct item,
+/// backtracking when an assignment fails. Grids hold
+/// at most nine items, so this is cheap.
+fn assign_ingredients(ingredients: &[Ingredient], items: &[Item], used: &mut [bool]) -> bool {
+    let (ingredient, rest) = match ingredients.split_first() {
+        Some(split) => split,
+        None => return true,
+    };
+
+    for (index, item) in items.iter().enumerate() {
+        if used[index] || !ingredient.matches(*item) {
+            continue;
+        }
+
+        used[index] = true;
+        if assign_ingredients(rest, items, used) {
+            return true;
+        }
+        used[index] = false;
+    }
+
+    false
+}
+
+/// A furnace recipe.
+#[derive(Debug, Clone)]
+pub struct SmeltingRecipe {
+    pub ingredient: Ingredient,
+    pub result: ItemStack,
+    /// Experience given when the result is taken out of the furnace.
+    pub experience: f32,
+    /// Number of ticks needed to smelt one item.
+    pub cooking_time: u32,
+}
+
+/// Error returned when the recipe data is invalid.
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("invalid item identifier {0}")]
+    InvalidItem(String),
+    #[error("missing tag {0}")]
+    MissingTag(String),
+    #[error("recipe pattern uses undefined key '{0}'")]
+    UndefinedKey(char),
+    #[error("recipe has too many ingredients")]
+    TooManyIngredients,
+}
+
+struct RecipeStore {
+    recipes: Vec<Recipe>,
+    by_id: AHashMap<String, usize>,
+}
+
+impl RecipeStore {
+    fn from_dump(dump: Dump) -> Result<Self, LoadError> {
+        let tags = TagResolver {
+            tags: &dump.item_tags,
+        };
+
+        let mut recipes = Vec::with_capacity(dump.recipes.len());
+        for (id, raw) in dump.recipes {
+            if let Some((group, kind)) = load_recipe_kind(&tags, raw)? {
+                recipes.push(Recipe { id, group, kind });
+            }
+        }
+
+        // Sort so that matching is deterministic.
+        recipes.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let by_id = recipes
+            .iter()
+            .enumerate()
+            .map(|(index, recipe)| (recipe.id.clone(), index))
+            .collect();
+
+        Ok(Self { recipes, by_id })
+    }
+}
+
+/// Loads a recipe, returning its group and kind. Returns `None`
+/// for special recipes, which are not described by the data.
+fn load_recipe_kind(
+    tags: &TagResolver,
+    raw: RawRecipe,
+) -> Result<Option<(String, RecipeKind)>, LoadError> {
+    Ok(Some(match raw {
+        RawRecipe::Shaped {
+            group,
+            pattern,
+            key,
+            result,
+        } => {
+            let width = pattern.iter().map(|row| row.chars().count()).max();
+            let width = width.unwrap_or(0);
+            let height = pattern.len();
+
+            let mut ingredients = Vec::with_capacity(width * height);
+            for row in &pattern {
+                let mut chars = row.chars();
+                for _ in 0..width {
+                    let ingredient = match chars.next() {
+                        Some(' ') | None => None,
+                        Some(c) => Some(load_ingredient(
+                            tags,
+                            key.get(&c).ok_or(LoadError::UndefinedKey(c))?,
+                        )?),
+                    };
+                    ingredients.push(ingredient);
+                }
+            }
+
+            let kind = RecipeKind::Shaped(ShapedRecipe {
+                width,
+                height,
+                ingredients,
+                result: load_result(&result)?,
+            });
+            (group, kind)
+        }
+        RawRecipe::Shapeless {
+            group,
+            ingredients,
+            result,
+        } => {
+            if ingredients.len() > 9 {
+                return Err(LoadError::TooManyIngredients);
+            }
+
+            let kind = RecipeKind::Shapeless(ShapelessRecipe {
+                ingredients: ingredients
+                    .iter()
+                    .map(|ingredient| load_ingredient(tags, ingredient))
+                    .collect::<Result<_, _>>()?,
+                result: load_result(&result)?,
+            });
+            (group, kind)
+        }
+        RawRecipe::Smelting {
+            group,
+            ingredient,
+            result,
+            experience,
+            cookingtime,
+        } => {
+            let kind = RecipeKind::Smelting(SmeltingRecipe {
+                ingredient: load_ingredient(tags, &ingredient)?,
+                result: ItemStack::new(item(&result)?, 1),
+                experience,
+                cooking_time: cookingtime,
+            });
+            (group, kind)
+        }
+        RawRecipe::Special => return Ok(None),
+    }))
+}
+
+fn load_ingredient(tags: &TagResolver, raw: &RawIngredient) -> Result<Ingredient, LoadError> {
+    let entries = match raw {
+        RawIngredient::Single(entry) => std::slice::from_ref(entry),
+        RawIngredient::Alternatives(entries) => entries.as_slice(),
+    };
+
+    let mut items = SmallVec::new();
+    for entry in entries {
+        match entry {
+            RawIngredientEntry::Item { item: id } => items.push(item(id)?),
+            RawIngredientEntry::Tag { tag } => tags.resolve(tag, &mut items)?,
+        }
+    }
+
+    Ok(Ingredient(items))
+}
+
+fn load_result(raw: &RawResult) -> Result<ItemStack, LoadError> {
+    Ok(ItemStack::new(item(&raw.item)?, raw.count))
+}
+
+fn item(id: &str) -> Result<Item, LoadError> {
+    Item::from_identifier(&namespaced(id)).ok_or_else(|| LoadError::InvalidItem(id.to_owned()))
+}
+
+/// Adds the default `minecraft` namespace to
+/// the given identifier if it has none.
+fn namespaced(id: &str) -> Cow<str> {
+    if id.contains(':') {
+        Cow::Borrowed(id)
+    } else {
+        Cow::Owned(format!("minecraft:{}", id))
+    }
+}
+
+struct TagResolver<'a> {
+    tags: &'a HashMap<String, RawTag>,
+}
+
+impl<'a> TagResolver<'a> {
+    /// Appends the items in the given tag to `items`,
+    /// resolving nested tags.
+    fn resolve(&self, tag: &str, items: &mut SmallVec<[Item; 1]>) -> Result<(), LoadError> {
+        let raw = self
+            .tags
+            .get(namespaced(tag).as_ref())
+            .ok_or_else(|| LoadError::MissingTag(tag.to_owned()))?;
+
+        for value in &raw.values {
+            if value.starts_with('#') {
+                self.resolve(&value[1..], items)?;
+            } else {
+                let item = item(value)?;
+                if !items.contains(&item) {
+                    items.push(item);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(items: &[Option<Item>]) -> Vec<Option<ItemStack>> {
+        items
+            .iter()
+            .map(|item| item.map(|item| ItemStack::new(item, 1)))
+            .collect()
+    }
+
+    #[test]
+    fn test_shapeless_tag() {
+        let grid = grid(&[None, None, None, Some(Item::OakLog)]);
+        let recipe = find_crafting_recipe(&grid, 2).unwrap();
+        assert_eq!(recipe.id, "minecraft:oak_planks");
+        assert_eq!(recipe.result(), ItemStack::new(Item::OakPlanks, 4));
+    }
+
+    #[test]
+    fn test_shaped_tag() {
+        let planks = Some(Item::OakPlanks);
+        let grid = grid(&[planks, planks, planks, planks]);
+        let recipe = find_crafting_recipe(&grid, 2).unwrap();
+        assert_eq!(recipe.result(), ItemStack::new(Item::CraftingTable, 1));
+
+        // Mixed planks are accepted by the `minecraft:planks` tag
+        let mut grid = grid;
+        grid[0] = Some(ItemStack::new(Item::BirchPlanks, 1));
+        let recipe = find_crafting_recipe(&grid, 2).unwrap();
+        assert_eq!(recipe.result(), ItemStack::new(Item::CraftingTable, 1));
+    }
+
+    #[test]
+    fn test_shaped_offset() {
+        // Torch in the bottom right corner of a crafting table
+        let grid = grid(&[
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(Item::Coal),
+            None,
+            None,
+            Some(Item::Stick),
+        ]);
+        let recipe = find_crafting_recipe(&grid, 3).unwrap();
+        assert_eq!(recipe.result(), ItemStack::new(Item::Torch, 4));
+    }
+
+    #[test]
+    fn test_shaped_mirrored() {
+        let planks = Some(Item::OakPlanks);
+        let stick = Some(Item::Stick);
+        let normal = grid(&[planks, planks, None, planks, stick, None, None, stick, None]);
+        let mirrored = grid(&[planks, planks, None, stick, planks, None, stick, None, None]);
+
+        for grid in &[normal, mirrored] {
+            let recipe = find_crafting_recipe(grid, 3).unwrap();
+            assert_eq!(recipe.result(), ItemStack::new(Item::WoodenAxe, 1));
+        }
+    }
+
+    #[test]
+    fn test_no_match() {
+        assert!(find_crafting_recipe(&grid(&[None; 9]), 3).is_none());
+
+        let grid = grid(&[Some(Item::Stick), None, None, Some(Item::Stick)]);
+        assert!(find_crafting_recipe(&grid, 2).is_none());
+    }
+
+    #[test]
+    fn test_smelting() {
+        let recipe = find_smelting_recipe(Item::IronOre).unwrap();
+        assert_eq!(recipe.result, ItemStack::new(Item::IronIngot, 1));
+        assert_eq!(recipe.cooking_time, 200);
+
+        assert!(find_smelting_recipe(Item::Stick).is_none());
+    }
+
+    #[test]
+    fn test_recipe_by_id() {
+        let recipe = recipe("minecraft:crafting_table").unwrap();
+        match &recipe.kind {
+            RecipeKind::Shaped(shaped) => {
+                assert_eq!((shaped.width, shaped.height), (2, 2));
+            }
+            kind => panic!("expected shaped recipe, found {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn test_uses() {
+        let recipe = recipe("minecraft:crafting_table").unwrap();
+        assert!(recipe.uses(Item::SprucePlanks));
+        assert!(!recipe.uses(Item::Stick));
+    }
+}
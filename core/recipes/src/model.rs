@@ -0,0 +1,89 @@
+//! Serde model for the vanilla recipe and tag JSON formats.
+//! Used as an intermediate representation before item identifiers
+//! and tags are resolved.
+//!
+//! See https://minecraft.gamepedia.com/Recipe#JSON_format
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// The contents of the recipe dump generated by the build script.
+#[derive(Debug, Deserialize)]
+pub struct Dump {
+    pub recipes: HashMap<String, RawRecipe>,
+    pub item_tags: HashMap<String, RawTag>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+pub enum RawRecipe {
+    #[serde(rename = "crafting_shaped", alias = "minecraft:crafting_shaped")]
+    Shaped {
+        #[serde(default)]
+        group: String,
+        pattern: Vec<String>,
+        key: HashMap<char, RawIngredient>,
+        result: RawResult,
+    },
+    #[serde(rename = "crafting_shapeless", alias = "minecraft:crafting_shapeless")]
+    Shapeless {
+        #[serde(default)]
+        group: String,
+        ingredients: Vec<RawIngredient>,
+        result: RawResult,
+    },
+    #[serde(rename = "smelting", alias = "minecraft:smelting")]
+    Smelting {
+        #[serde(default)]
+        group: String,
+        ingredient: RawIngredient,
+        /// Item identifier of the result.
+        result: String,
+        #[serde(default)]
+        experience: f32,
+        #[serde(default = "default_cooking_time")]
+        cookingtime: u32,
+    },
+    /// Recipes implemented in code by vanilla,
+    /// e.g. `crafting_special_armordye`.
+    #[serde(other)]
+    Special,
+}
+
+fn default_cooking_time() -> u32 {
+    200
+}
+
+/// An ingredient, which is either a single item/tag
+/// or a list of alternatives.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum RawIngredient {
+    Single(RawIngredientEntry),
+    Alternatives(Vec<RawIngredientEntry>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum RawIngredientEntry {
+    Item { item: String },
+    Tag { tag: String },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RawResult {
+    pub item: String,
+    #[serde(default = "default_count")]
+    pub count: u8,
+}
+
+fn default_count() -> u8 {
+    1
+}
+
+/// A tag. Values are either item identifiers or,
+/// if prefixed with `#`, other tags.
+#[derive(Debug, Deserialize)]
+pub struct RawTag {
+    pub values: Vec<String>,
+}
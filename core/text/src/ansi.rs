@@ -0,0 +1,308 @@
+//! Rendering of text to strings with ANSI escape codes,
+//! used to display chat and command feedback in a terminal,
+//! or as plain text.
+
+use crate::{Color, Text, TextComponent, TextValue};
+
+/// English translations of the keys the server sends.
+/// Keys not in this table are rendered as the key
+/// followed by its arguments.
+const TRANSLATIONS: &[(&str, &str)] = &[
+    ("chat.type.text", "<%s> %s"),
+    ("chat.type.announcement", "[%s] %s"),
+    ("chat.type.emote", "* %s %s"),
+    ("chat.type.admin", "[%s: %s]"),
+    ("multiplayer.player.joined", "%s joined the game"),
+    ("multiplayer.player.left", "%s left the game"),
+    ("commands.stop.stopping", "Stopping the server"),
+    ("commands.kick.success", "Kicked %s: %s"),
+    ("commands.ban.success", "Banned %s: %s"),
+    ("commands.pardon.success", "Unbanned %s"),
+    ("commands.op.success", "Made %s a server operator"),
+    (
+        "commands.deop.success",
+        "Made %s no longer a server operator",
+    ),
+];
+
+/// Renders `text` as a string colored and styled
+/// using ANSI escape codes.
+///
+/// Colors and styles are inherited by children as
+/// in the client. Custom colors and obfuscation
+/// have no terminal equivalent and are ignored.
+pub fn to_ansi(text: &Text) -> String {
+    let mut renderer = Renderer::default();
+    renderer.write_text(text, &Format::default());
+    renderer.finish()
+}
+
+/// Renders `text` as a string without colors or styles.
+pub fn to_plain(text: &Text) -> String {
+    let mut renderer = Renderer {
+        plain: true,
+        ..Default::default()
+    };
+    renderer.write_text(text, &Format::default());
+    renderer.finish()
+}
+
+/// The formatting in effect for a piece of text.
+#[derive(Clone, Default)]
+struct Format<'a> {
+    color: Option<&'a Color>,
+    bold: bool,
+    italic: bool,
+    underlined: bool,
+    strikethrough: bool,
+}
+
+impl<'a> Format<'a> {
+    /// Returns the formatting of `component`, which
+    /// inherits unset values from `self`.
+    fn apply(&self, component: &'a TextComponent) -> Self {
+        Self {
+            color: component.color.as_ref().or(self.color),
+            bold: component.bold.unwrap_or(self.bold),
+            italic: component.italic.unwrap_or(self.italic),
+            underlined: component.underlined.unwrap_or(self.underlined),
+            strikethrough: component.strikethrough.unwrap_or(self.strikethrough),
+        }
+    }
+
+    /// Returns the SGR parameters for this formatting,
+    /// separated by semicolons.
+    fn codes(&self) -> String {
+        let mut codes = Vec::new();
+        if self.bold {
+            codes.push(1);
+        }
+        if self.italic {
+            codes.push(3);
+        }
+        if self.underlined {
+            codes.push(4);
+        }
+        if self.strikethrough {
+            codes.push(9);
+        }
+        if let Some(code) = self.color.and_then(color_code) {
+            codes.push(code);
+        }
+
+        codes
+            .iter()
+            .map(u8::to_string)
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+}
+
+/// Returns the foreground color code closest to `color`.
+fn color_code(color: &Color) -> Option<u8> {
+    Some(match color {
+        Color::Black => 30,
+        Color::DarkRed => 31,
+        Color::DarkGreen => 32,
+        Color::Gold => 33,
+        Color::DarkBlue => 34,
+        Color::DarkPurple => 35,
+        Color::DarkAqua => 36,
+        Color::Gray => 37,
+        Color::DarkGray => 90,
+        Color::Red => 91,
+        Color::Green => 92,
+        Color::Yellow => 93,
+        Color::Blue => 94,
+        Color::LightPurple => 95,
+        Color::Aqua => 96,
+        Color::White => 97,
+        Color::Custom(_) => return None,
+    })
+}
+
+#[derive(Default)]
+struct Renderer {
+    out: String,
+    /// The SGR parameters last written to `out`.
+    current: String,
+    /// Whether to omit escape codes.
+    plain: bool,
+}
+
+impl Renderer {
+    fn write_text<'a>(&mut self, text: &'a Text, format: &Format<'a>) {
+        match text {
+            Text::String(text) => self.write_str(text, format),
+            Text::Array(texts) => {
+                // Following elements inherit the formatting of the first.
+                if let Some((first, rest)) = texts.split_first() {
+                    let first_format = match first {
+                        Text::Component(component) => format.apply(component),
+                        _ => format.clone(),
+                    };
+                    self.write_text(first, format);
+                    for text in rest {
+                        self.write_text(text, &first_format);
+                    }
+                }
+            }
+            Text::Component(component) => {
+                let format = format.apply(component);
+                self.write_value(&component.value, &format);
+                for text in component.extra.iter().flatten() {
+                    self.write_text(text, &format);
+                }
+            }
+        }
+    }
+
+    fn write_value<'a>(&mut self, value: &'a TextValue, format: &Format<'a>) {
+        match value {
+            TextValue::Text { text } => self.write_str(text, format),
+            TextValue::Translate { translate, with } => {
+                let key = String::from(translate);
+                match TRANSLATIONS.iter().find(|(k, _)| *k == key) {
+                    Some((_, pattern)) => self.write_translation(pattern, with, format),
+                    None => {
+                        self.write_str(&key, format);
+                        for arg in with {
+                            self.write_str(" ", format);
+                            self.write_text(arg, format);
+                        }
+                    }
+                }
+            }
+            TextValue::Score { value, .. } => {
+                self.write_str(value.as_deref().unwrap_or_default(), format)
+            }
+            TextValue::Selector { selector } => self.write_str(selector, format),
+            TextValue::Keybind { keybind } => self.write_str(&String::from(keybind), format),
+            TextValue::Nbt { .. } => (),
+        }
+    }
+
+    /// Writes a translation pattern, substituting `%s`
+    /// and `%N$s` placeholders with the arguments.
+    fn write_translation<'a>(&mut self, pattern: &str, with: &'a [Text], format: &Format<'a>) {
+        let mut next_arg = 0;
+        let mut rest = pattern;
+
+        while let Some(start) = rest.find('%') {
+            self.write_str(&rest[..start], format);
+            let spec = &rest[start + 1..];
+
+            let (arg, len) = if spec.starts_with('s') {
+                next_arg += 1;
+                (Some(next_arg - 1), 1)
+            } else if spec.starts_with('%') {
+                self.write_str("%", format);
+                (None, 1)
+            } else if let Some((index, len)) = positional_arg(spec) {
+                (Some(index), len)
+            } else {
+                self.write_str("%", format);
+                (None, 0)
+            };
+
+            if let Some(arg) = arg.and_then(|index| with.get(index)) {
+                self.write_text(arg, format);
+            }
+            rest = &spec[len..];
+        }
+
+        self.write_str(rest, format);
+    }
+
+    fn write_str(&mut self, text: &str, format: &Format) {
+        if text.is_empty() {
+            return;
+        }
+
+        let codes = if self.plain {
+            String::new()
+        } else {
+            format.codes()
+        };
+        if codes != self.current {
+            self.out.push_str("\x1b[0m");
+            if !codes.is_empty() {
+                self.out.push_str(&format!("\x1b[{}m", codes));
+            }
+            self.current = codes;
+        }
+        self.out.push_str(text);
+    }
+
+    fn finish(mut self) -> String {
+        if !self.current.is_empty() {
+            self.out.push_str("\x1b[0m");
+        }
+        self.out
+    }
+}
+
+/// Parses the `N$s` of a `%N$s` placeholder, returning
+/// the zero-based argument index and the length parsed.
+fn positional_arg(spec: &str) -> Option<(usize, usize)> {
+    let digits = spec.find(|c: char| !c.is_ascii_digit())?;
+    if digits == 0 || !spec[digits..].starts_with("$s") {
+        return None;
+    }
+
+    let index = spec[..digits].parse::<usize>().ok()?.checked_sub(1)?;
+    Some((index, digits + 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TextComponentBuilder, Translate};
+
+    #[test]
+    fn plain() {
+        assert_eq!(to_ansi(&Text::from("hello")), "hello");
+        assert_eq!(
+            to_ansi(&(Text::from("hello") + Text::from(" world"))),
+            "hello world"
+        );
+    }
+
+    #[test]
+    fn colors_and_styles() {
+        let text = Text::from("error").red().bold() + Text::from(" plain");
+        assert_eq!(to_ansi(&text), "\x1b[0m\x1b[1;91merror\x1b[0m plain");
+
+        let text = Text::from("parent").dark_aqua().extra(vec![
+            Text::from(" inherited"),
+            Text::from(" italic").italic(),
+        ]);
+        assert_eq!(
+            to_ansi(&text),
+            "\x1b[0m\x1b[36mparent inherited\x1b[0m\x1b[3;36m italic\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn plain_text() {
+        let text = Text::from("error").red().bold() + Translate::ChatTypeText * vec!["Steve", "hi"];
+        assert_eq!(to_plain(&text), "error<Steve> hi");
+    }
+
+    #[test]
+    fn translations() {
+        let text = Translate::ChatTypeText * vec!["Steve", "hi"];
+        assert_eq!(to_ansi(&text), "<Steve> hi");
+
+        let text = Text::translate_with("commands.unknown.key", vec!["a", "b"]);
+        assert_eq!(to_ansi(&text), "commands.unknown.key a b");
+    }
+
+    #[test]
+    fn positional_placeholders() {
+        let mut renderer = Renderer::default();
+        let with = vec![Text::from("a"), Text::from("b")];
+        renderer.write_translation("%2$s %1$s %s 100%%", &with, &Format::default());
+        assert_eq!(renderer.finish(), "b a a 100%");
+    }
+}
@@ -4,6 +4,7 @@ use std::fmt::{self, Display, Formatter};
 use std::str::FromStr;
 use uuid::Uuid;
 
+pub mod ansi;
 pub mod markdown;
 
 pub enum TextConversionError {
@@ -16,9 +16,11 @@ fn main() {
 
 fn run() -> anyhow::Result<()> {
     let path = format!("{}/minecraft", env::var("OUT_DIR")?);
+    let path_1_14 = format!("{}/minecraft-1.14", env::var("OUT_DIR")?);
     let path_1_15 = format!("{}/minecraft-1.15", env::var("OUT_DIR")?);
 
     download_version("https://launcher.mojang.com/v1/objects/3737db93722a9e39eeada7c27e7aca28b144ffa7/server.jar", &path, true).context("failed to download 1.13 data")?;
+    download_version("https://launcher.mojang.com/v1/objects/3dc3d84a581f14691199cf6831b71ed1296a9fdf/server.jar", &path_1_14, true).context("failed to download 1.14 data")?;
     download_version("https://launcher.mojang.com/v1/objects/bb2b6b1aefcd70dfd1892149ac3a215f6c636b07/server.jar", &path_1_15, false).context("failed to download 1.15 data")?;
 
     clone_minecraft_data().context("failed to clone PrismarineJS/minecraft-data")?;
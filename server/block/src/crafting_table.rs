@@ -0,0 +1,172 @@
+//! Crafting tables.
+//!
+//! Crafting tables have no block entity. Instead, each player
+//! who opens one gets a temporary entity holding the crafting
+//! grid, which is removed when the window is closed.
+
+use feather_core::blocks::BlockKind;
+use feather_core::inventory::{Area, Window};
+use feather_core::network::packets::{OpenWindow, SetSlot};
+use feather_core::text::TextRoot;
+use feather_core::util::{BlockPosition, Position};
+use feather_server_entity::drops::drop_item;
+use feather_server_types::{
+    BumpVec, Game, InteractionHandler, Inventory, InventoryUpdateEvent, Network, PlayerLeaveEvent,
+    WindowCloseEvent, WindowOpenEvent,
+};
+use fecs::{Entity, EntityBuilder, World};
+use smallvec::SmallVec;
+
+inventory::submit!(Box::new(CraftingTableInteraction) as Box<dyn InteractionHandler>);
+
+/// Component for the crafting grid entity of an opened crafting table.
+pub struct CraftingTable {
+    /// The player using the crafting table.
+    pub player: Entity,
+    /// ID of the window opened for `player`.
+    pub window_id: u8,
+}
+
+struct CraftingTableInteraction;
+
+impl InteractionHandler for CraftingTableInteraction {
+    fn handle_interaction(
+        &self,
+        game: &mut Game,
+        world: &mut World,
+        _pos: BlockPosition,
+        player: Entity,
+        window_id: u8,
+    ) {
+        let table = EntityBuilder::new()
+            .with(CraftingTable { player, window_id })
+            .with(Inventory::crafting_table())
+            .build()
+            .spawn_in(world);
+
+        let packet = OpenWindow {
+            window_id,
+            window_type: String::from("minecraft:crafting_table"),
+            window_title: TextRoot::from("Crafting").into(),
+            number_of_slots: 0,
+            entity_id: None,
+        };
+        world.get::<Network>(player).send(packet);
+
+        *world.get_mut::<Window>(player) = Window::crafting_table(player, table);
+
+        game.handle(
+            world,
+            WindowOpenEvent {
+                player,
+                opened: table,
+            },
+        );
+    }
+
+    fn block_kind(&self) -> BlockKind {
+        BlockKind::CraftingTable
+    }
+}
+
+/// Sends updated crafting grid slots to the player using the crafting table.
+#[fecs::event_handler]
+pub fn on_inventory_update_send_crafting_table_slots(
+    event: &InventoryUpdateEvent,
+    world: &mut World,
+) {
+    let table = match world.try_get::<CraftingTable>(event.entity) {
+        Some(table) => table,
+        None => return,
+    };
+
+    let inventory = world.get::<Inventory>(event.entity);
+    let window = world.get::<Window>(table.player);
+
+    for slot in &event.slots {
+        let converted = match window.convert_slot(*slot, event.entity) {
+            Some(converted) => converted,
+            None => continue, // window was closed
+        };
+
+        let packet = SetSlot {
+            window_id: table.window_id as i8,
+            slot: converted as i16,
+            slot_data: inventory.item_at(slot.area, slot.slot).unwrap(),
+        };
+        world.get::<Network>(table.player).send(packet);
+    }
+}
+
+/// When a crafting table is closed, gives the items in the
+/// crafting grid back to the player and removes the grid.
+#[fecs::event_handler]
+pub fn on_crafting_table_close_return_items(
+    event: &WindowCloseEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if world.has::<CraftingTable>(event.closed) {
+        close(game, world, event.player, event.closed);
+    }
+}
+
+/// When a player leaves while using a crafting table,
+/// gives the items in the crafting grid back to the player
+/// before their data is saved.
+#[fecs::event_handler]
+pub fn on_player_leave_close_crafting_table(
+    event: &PlayerLeaveEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    let table = world
+        .get::<Window>(event.player)
+        .wrapped_entities()
+        .iter()
+        .copied()
+        .find(|entity| world.has::<CraftingTable>(*entity));
+
+    if let Some(table) = table {
+        close(game, world, event.player, table);
+    }
+}
+
+fn close(game: &mut Game, world: &mut World, player: Entity, table: Entity) {
+    let items = {
+        let inventory = world.get::<Inventory>(table);
+        // The result slot only previews the crafting result.
+        let _ = inventory.remove_item_at(Area::CraftingOutput, 0);
+        BumpVec::from_iter_in(
+            inventory.iter_mut().filter_map(|mut guard| guard.take()),
+            game.bump(),
+        )
+    };
+
+    let mut slots = SmallVec::new();
+    let mut leftovers = BumpVec::new_in(game.bump());
+    {
+        let inventory = world.get::<Inventory>(player);
+        for item in items {
+            let (affected, remaining) = inventory.collect_item(item.clone());
+            slots.extend(affected);
+            if remaining > 0 {
+                leftovers.push(item.of_amount(remaining));
+            }
+        }
+    }
+
+    let pos = *world.get::<Position>(player);
+    for item in leftovers {
+        drop_item(game, world, item, pos);
+    }
+
+    game.handle(
+        world,
+        InventoryUpdateEvent {
+            slots,
+            entity: player,
+        },
+    );
+    game.despawn(table, world);
+}
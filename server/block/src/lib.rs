@@ -1,12 +1,17 @@
 #![forbid(unsafe_code)]
 
 pub mod chest;
+pub mod crafting_table;
 mod init;
 
 pub use chest::{
     on_chest_break_drop_contents, on_chest_break_try_disconnect, on_chest_close_decrement_viewers,
     on_chest_create_try_connect, on_chest_open_increment_viewers,
 };
+pub use crafting_table::{
+    on_crafting_table_close_return_items, on_inventory_update_send_crafting_table_slots,
+    on_player_leave_close_crafting_table,
+};
 use feather_core::{
     anvil::block_entity::BlockEntityBase,
     blocks::BlockId,
@@ -6,18 +6,21 @@ use crossbeam::channel::{Receiver, Sender};
 use std::sync::atomic::{AtomicU32, Ordering};
 
 use crate::chunk_worker;
+use crate::RegionMetrics;
 use ahash::AHashSet;
 use chunk_worker::ChunkSave;
-use feather_core::anvil::{block_entity::BlockEntityData, entity::EntityData};
+use feather_core::anvil::{
+    block_entity::BlockEntityData, entity::EntityData, region::ScheduledUpdates,
+};
 use feather_core::chunk::Chunk;
-use feather_core::util::ChunkPosition;
+use feather_core::util::{ChunkPosition, Position};
 use feather_server_types::{
     ChunkHolder, ChunkHolderReleaseEvent, ChunkLoadEvent, ChunkLoadFailEvent, ChunkUnloadEvent,
-    EntityDespawnEvent, EntitySpawnEvent, Game, HoldChunkRequest, LoadChunkRequest,
+    EntityDespawnEvent, EntitySpawnEvent, Game, HoldChunkRequest, LoadChunkRequest, Player,
     ReleaseChunkRequest, TPS,
 };
 use feather_server_util::current_time_in_millis;
-use fecs::{Entity, World};
+use fecs::{component, Entity, IntoQuery, Read, World};
 use parking_lot::RwLock;
 use rayon::prelude::*;
 use smallvec::SmallVec;
@@ -34,6 +37,8 @@ pub struct LoadingChunks(pub AHashSet<ChunkPosition>);
 pub struct ChunkWorkerHandle {
     pub sender: Sender<chunk_worker::Request>,
     pub receiver: Receiver<chunk_worker::Reply>,
+    /// Counters of the region files opened by the worker.
+    pub region_metrics: Arc<RegionMetrics>,
 }
 
 /// System for handling replies from the chunk worker thread.
@@ -48,9 +53,27 @@ pub fn handle_chunk_worker_replies(
         match reply {
             chunk_worker::Reply::LoadedChunk(pos, result) => {
                 loading_chunks.0.remove(&pos);
+
+                // The chunk may have been requested and loaded again
+                // since the worker sent it, in which case the worker
+                // keeps the tags of the loaded chunk.
+                if game.chunk_map.chunk_at(pos).is_some() {
+                    continue;
+                }
+
+                // The load may have been cancelled after the worker sent
+                // the chunk, in which case it is no longer held and the
+                // worker must drop the tags it kept for it.
+                if !game.chunk_holders.chunk_has_holders(pos) {
+                    unload_chunk(chunk_worker_handle, pos);
+                    continue;
+                }
+
                 match result {
                     Ok(loaded) => {
                         game.chunk_map.insert(loaded.chunk);
+                        let time = game.tick_count;
+                        game.scheduled_ticks.load(&loaded.scheduled_updates, time);
 
                         loaded.entities.into_iter().for_each(|builder| {
                             let entity = builder.build().spawn_in(world);
@@ -75,6 +98,29 @@ pub fn handle_chunk_worker_replies(
     }
 }
 
+/// System which sends the chunks containing players to the
+/// chunk worker, which loads the chunks closest to a player first.
+#[fecs::system]
+pub fn send_player_chunks_to_worker(
+    world: &mut World,
+    chunk_worker_handle: &ChunkWorkerHandle,
+    #[default] loading_chunks: &mut LoadingChunks,
+) {
+    // Priorities only matter while chunks are loading.
+    if loading_chunks.0.is_empty() {
+        return;
+    }
+
+    let chunks = <Read<Position>>::query()
+        .filter(component::<Player>())
+        .iter_entities(world.inner())
+        .map(|(_, pos)| pos.chunk())
+        .collect();
+    let _ = chunk_worker_handle
+        .sender
+        .send(chunk_worker::Request::SetPlayerChunks(chunks));
+}
+
 pub fn remove_chunk_holder(
     game: &mut Game,
     world: &mut World,
@@ -159,6 +205,7 @@ pub fn chunk_unload(
                     },
                 );
                 game.chunk_map.remove(unload.chunk);
+                game.scheduled_ticks.remove_chunk(unload.chunk);
                 log::trace!("Unloaded chunk at {}", unload.chunk);
             }
             chunk_unload_queue.queue.pop_front();
@@ -172,16 +219,26 @@ pub fn chunk_unload(
 }
 
 /// Event handler which handles holder release events. If
-/// a chunk has no more holders, then a chunk unload is queued.
+/// a chunk has no more holders, then a chunk unload is queued,
+/// or its load is cancelled if it is still loading.
 #[fecs::event_handler]
 pub fn on_chunk_holder_release_unload_chunk(
     event: &ChunkHolderReleaseEvent,
     game: &mut Game,
     chunk_unload_queue: &mut ChunkUnloadQueue,
+    chunk_worker_handle: &ChunkWorkerHandle,
+    #[default] loading_chunks: &mut LoadingChunks,
 ) {
     // Handle holder release events.
     // If the chunk now has zero holders, queue it for unloading.
     if !game.chunk_holders.chunk_has_holders(event.chunk) {
+        if loading_chunks.0.remove(&event.chunk) {
+            let _ = chunk_worker_handle
+                .sender
+                .send(chunk_worker::Request::CancelLoad(event.chunk));
+            return;
+        }
+
         let unload = ChunkUnload {
             chunk: event.chunk,
             time: game.tick_count + CHUNK_UNLOAD_TIME,
@@ -255,6 +312,28 @@ pub fn chunk_optimize(game: &mut Game) {
     );
 }
 
+/// The interval, in ticks, at which the
+/// region file metrics are logged.
+const REGION_METRICS_INTERVAL: u64 = TPS * 60; // 1 minute
+
+/// System which periodically logs the number of region
+/// files opened by the chunk worker, which helps to size
+/// the region cache.
+#[fecs::system]
+pub fn log_region_metrics(game: &mut Game, chunk_worker_handle: &ChunkWorkerHandle) {
+    if game.tick_count % REGION_METRICS_INTERVAL != 0 {
+        return;
+    }
+
+    let metrics = &chunk_worker_handle.region_metrics;
+    log::debug!(
+        "Region files: {} open, {} opened, {} evicted",
+        metrics.open(),
+        metrics.opened(),
+        metrics.evicted()
+    );
+}
+
 /// Adds a hold for a chunk for the given entity.
 pub fn hold_chunk(game: &mut Game, holder: &mut ChunkHolder, chunk: ChunkPosition, entity: Entity) {
     holder.holds.insert(chunk);
@@ -301,17 +380,31 @@ pub fn load_chunk(handle: &ChunkWorkerHandle, pos: ChunkPosition) {
         .unwrap();
 }
 
+/// Notifies the chunk worker that the chunk at the
+/// given position was unloaded, so that it can drop
+/// the state it keeps for the chunk.
+///
+/// Should be called after the chunk was last saved.
+pub fn unload_chunk(handle: &ChunkWorkerHandle, pos: ChunkPosition) {
+    handle
+        .sender
+        .send(chunk_worker::Request::UnloadChunk(pos))
+        .unwrap();
+}
+
 /// Asynchronously saves the chunk at the given position.
 pub fn save_chunk(
     handle: &ChunkWorkerHandle,
     chunk: Arc<RwLock<Chunk>>,
     entities: SmallVec<[EntityData; 4]>,
     block_entities: SmallVec<[BlockEntityData; 4]>,
+    scheduled_updates: ScheduledUpdates,
 ) {
     let save = ChunkSave {
         chunk,
         entities,
         block_entities,
+        scheduled_updates,
     };
     handle
         .sender
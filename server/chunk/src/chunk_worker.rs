@@ -2,26 +2,42 @@
 //! of chunks. It receives load and save requests from the server
 //! (over a channel) and executes them.
 //!
-//! If a chunk cannot be loaded, it is generated on the Rayon thread pool
-//! instead.
-use ahash::AHashMap;
+//! Chunks are loaded on the Rayon thread pool: reading from
+//! the region file, decoding the chunk and, if the chunk
+//! does not exist, generating it all run concurrently. Only a
+//! chunk's region file is locked while its data is read.
+//! Pending loads are started in order of their distance to the
+//! nearest player, and can be cancelled while they are pending.
+//!
+//! Saving is performed on the worker thread itself, so that
+//! saves are written in the order they were requested.
+//!
+//! The worker keeps the NBT tags of each loaded chunk which
+//! Feather does not model, such as structures, and writes them
+//! back when the chunk is saved, until the chunk is unloaded.
+use crate::region_cache::{RegionCache, RegionMetrics};
+use ahash::{AHashMap, AHashSet};
 use crossbeam::channel::{Receiver, Sender};
 use feather_core::anvil::entity::EntityData;
 use feather_core::anvil::region;
 use feather_core::anvil::{
     block_entity::BlockEntityData,
-    region::{RegionHandle, RegionPosition},
+    region::{ChunkTags, RegionHandle, RegionPosition, ScheduledUpdates},
 };
 use feather_core::chunk::Chunk;
 use feather_core::util::ChunkPosition;
 use feather_server_util::EntityLoader;
 use feather_server_worldgen::WorldGenerator;
 use fecs::EntityBuilder;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use smallvec::SmallVec;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant};
+
+/// Maximum time the worker waits for a request
+/// before checking for idle region files.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
 
 /// Stores a chunk and associated data (entities, block entities, tile ticks, ...)
 /// for saving to the world save.
@@ -32,6 +48,8 @@ pub struct ChunkSave {
     pub entities: SmallVec<[EntityData; 4]>,
     /// Block entities within this chunk.
     pub block_entities: SmallVec<[BlockEntityData; 4]>,
+    /// Block and liquid updates scheduled within this chunk.
+    pub scheduled_updates: ScheduledUpdates,
 }
 
 /// Stores a chunk and associated data loaded
@@ -41,6 +59,8 @@ pub struct ChunkLoad {
     /// Entities within this chunk, pre-built with their components.
     /// Includes block entities as well.
     pub entities: SmallVec<[EntityBuilder; 4]>,
+    /// Block and liquid updates scheduled within this chunk.
+    pub scheduled_updates: ScheduledUpdates,
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -53,23 +73,22 @@ pub enum Reply {
 #[allow(clippy::large_enum_variant)]
 pub enum Request {
     LoadChunk(ChunkPosition),
+    /// Cancels loading a chunk which is no longer needed.
+    /// No reply is sent for the chunk unless it is requested again.
+    CancelLoad(ChunkPosition),
+    /// Updates the chunks containing players, which are
+    /// used to prioritize the chunks closest to a player.
+    SetPlayerChunks(Vec<ChunkPosition>),
     SaveChunk(ChunkSave),
+    /// Notifies the worker that a chunk was unloaded, after
+    /// it was saved for the last time.
+    UnloadChunk(ChunkPosition),
     ShutDown,
 }
 
-/// An open region file
-struct RegionFile {
-    /// The handle for the file
-    handle: RegionHandle,
-    /// The timestamp of the last time
-    /// this region file was used. This
-    /// value is used to close
-    /// the file after it isn't used for
-    /// some period of time.
-    ///
-    /// TODO actually implement this
-    _last_used: u64,
-}
+type SharedRegion = Arc<Mutex<RegionHandle>>;
+
+type LoadResult = anyhow::Result<(ChunkLoad, ChunkTags)>;
 
 struct ChunkWorker {
     /// The directory in which the world
@@ -83,33 +102,66 @@ struct ChunkWorker {
     /// from the server thread
     receiver: Receiver<Request>,
 
-    /// A map of currently open region files
-    open_regions: AHashMap<RegionPosition, RegionFile>,
+    /// Channels used by load tasks to send
+    /// loaded chunks back to the worker
+    loaded_tx: Sender<(ChunkPosition, LoadResult)>,
+    loaded_rx: Receiver<(ChunkPosition, LoadResult)>,
+
+    /// The currently open region files
+    open_regions: RegionCache<SharedRegion>,
+
+    /// Chunks waiting for a load task to be started.
+    pending: AHashSet<ChunkPosition>,
+    /// Chunks with a running load task, mapped
+    /// to whether the load was cancelled.
+    loading: AHashMap<ChunkPosition, bool>,
+    /// Maximum number of load tasks running at once.
+    max_loading: usize,
+    /// Chunks containing players.
+    player_chunks: Vec<ChunkPosition>,
+
+    /// Unmodelled tags of the loaded chunks.
+    tags: AHashMap<ChunkPosition, ChunkTags>,
 
     /// World generator for new chunks.
     world_generator: Arc<dyn WorldGenerator>,
 
     /// State for loading entities.
-    entity_loader: EntityLoader,
+    entity_loader: Arc<EntityLoader>,
 }
 
 /// Starts a chunk worker on a new thread.
 /// The returned channels can be used
 /// to communicate with the worker.
+///
+/// At most `max_open_regions` region files are kept open,
+/// and region files unused for `region_idle_timeout` are closed.
 pub fn start(
     world_dir: &Path,
     world_gen: Arc<dyn WorldGenerator>,
-) -> (Sender<Request>, Receiver<Reply>) {
+    max_open_regions: usize,
+    region_idle_timeout: Duration,
+) -> (Sender<Request>, Receiver<Reply>, Arc<RegionMetrics>) {
     let (request_tx, request_rx) = crossbeam::channel::unbounded();
     let (reply_tx, reply_rx) = crossbeam::channel::unbounded();
+    let (loaded_tx, loaded_rx) = crossbeam::channel::unbounded();
+    let metrics = Arc::new(RegionMetrics::default());
 
     let worker = ChunkWorker {
         dir: world_dir.to_path_buf(),
         sender: reply_tx,
         receiver: request_rx,
-        open_regions: AHashMap::new(),
+        loaded_tx,
+        loaded_rx,
+        open_regions: RegionCache::new(max_open_regions, region_idle_timeout, Arc::clone(&metrics)),
+        pending: AHashSet::new(),
+        loading: AHashMap::new(),
+        // Leave threads free for the server's own parallel systems.
+        max_loading: (rayon::current_num_threads() / 2).max(1),
+        player_chunks: Vec::new(),
+        tags: AHashMap::new(),
         world_generator: world_gen,
-        entity_loader: EntityLoader::new(),
+        entity_loader: Arc::new(EntityLoader::new()),
     };
 
     log::info!("Starting chunk worker");
@@ -123,119 +175,191 @@ pub fn start(
         .spawn(move || run(worker))
         .expect("Unable to start chunk worker thread");
 
-    (request_tx, reply_rx)
+    (request_tx, reply_rx, metrics)
 }
 
 /// Runs the chunk worker on the current thread,
 /// blocking indefinitely.
 fn run(mut worker: ChunkWorker) {
-    while let Ok(request) = worker.receiver.recv() {
-        match request {
-            Request::ShutDown => break,
-            Request::SaveChunk(save) => {
-                save_chunk(&mut worker, save);
+    let receiver = worker.receiver.clone();
+    let loaded_rx = worker.loaded_rx.clone();
+
+    loop {
+        let shut_down = crossbeam::select! {
+            recv(receiver) -> request => match request {
+                Ok(Request::ShutDown) | Err(_) => true,
+                Ok(request) => {
+                    handle_request(&mut worker, request);
+                    false
+                }
+            },
+            recv(loaded_rx) -> loaded => {
+                let (pos, result) = loaded.expect("worker holds a sender");
+                finish_load(&mut worker, pos, result);
+                false
+            },
+            default(IDLE_CHECK_INTERVAL) => false,
+        };
+        if shut_down {
+            break;
+        }
+
+        start_loads(&mut worker);
+        worker.open_regions.evict_idle(Instant::now(), close_region);
+    }
+
+    worker.open_regions.close_all(close_region);
+    log::info!("Chunk worker terminating");
+}
+
+fn handle_request(worker: &mut ChunkWorker, request: Request) {
+    match request {
+        Request::LoadChunk(pos) => match worker.loading.get_mut(&pos) {
+            // Already loading, so only make sure the reply is sent.
+            Some(cancelled) => *cancelled = false,
+            None => {
+                worker.pending.insert(pos);
             }
-            Request::LoadChunk(pos) => {
-                if let Some(reply) = load_chunk(&mut worker, pos) {
-                    worker.sender.send(reply).unwrap();
+        },
+        Request::CancelLoad(pos) => {
+            if !worker.pending.remove(&pos) {
+                if let Some(cancelled) = worker.loading.get_mut(&pos) {
+                    *cancelled = true;
                 }
             }
         }
+        Request::SetPlayerChunks(chunks) => worker.player_chunks = chunks,
+        Request::SaveChunk(save) => save_chunk(worker, save),
+        Request::UnloadChunk(pos) => {
+            worker.tags.remove(&pos);
+        }
+        Request::ShutDown => unreachable!(),
     }
+}
 
-    log::info!("Chunk worker terminating");
+/// Starts load tasks for the pending chunks closest
+/// to a player, up to the maximum number of tasks.
+fn start_loads(worker: &mut ChunkWorker) {
+    let available = worker.max_loading.saturating_sub(worker.loading.len());
+    if available == 0 || worker.pending.is_empty() {
+        return;
+    }
+
+    let mut pending: Vec<_> = worker.pending.iter().copied().collect();
+    let player_chunks = &worker.player_chunks;
+    pending.sort_unstable_by_key(|pos| distance_to_nearest(*pos, player_chunks));
+
+    for pos in pending.into_iter().take(available) {
+        worker.pending.remove(&pos);
+        worker.loading.insert(pos, false);
+
+        let region = worker_region(&mut worker.open_regions, &worker.dir, pos);
+        let sender = worker.loaded_tx.clone();
+        let generator = Arc::clone(&worker.world_generator);
+        let entity_loader = Arc::clone(&worker.entity_loader);
+        rayon::spawn(move || {
+            let result = load_chunk(pos, &region, &generator, &entity_loader);
+            // The worker may have shut down in the meantime.
+            let _ = sender.send((pos, result));
+        });
+    }
 }
 
-/// Attempts to load the chunk at the specified position.
-fn load_chunk(worker: &mut ChunkWorker, pos: ChunkPosition) -> Option<Reply> {
-    let rpos = RegionPosition::from_chunk(pos);
-
-    let file = worker_region(&mut worker.open_regions, &worker.dir, rpos);
-    // Load from region file
-    load_chunk_from_handle(
-        pos,
-        &mut file.handle,
-        &Arc::from(worker.sender.clone()),
-        &worker.world_generator,
-        &worker.entity_loader,
-    )
+/// Sends a loaded chunk to the server, unless its load was cancelled.
+fn finish_load(worker: &mut ChunkWorker, pos: ChunkPosition, result: LoadResult) {
+    if worker.loading.remove(&pos) == Some(false) {
+        let tags = &mut worker.tags;
+        let result = result.map(|(load, chunk_tags)| {
+            tags.insert(pos, chunk_tags);
+            load
+        });
+        worker.sender.send(Reply::LoadedChunk(pos, result)).unwrap();
+    }
+}
+
+/// Returns the Chebyshev distance, in chunks, from `pos` to
+/// the nearest of `player_chunks`, or 0 if there are none.
+fn distance_to_nearest(pos: ChunkPosition, player_chunks: &[ChunkPosition]) -> i32 {
+    player_chunks
+        .iter()
+        .map(|player| (pos.x - player.x).abs().max((pos.z - player.z).abs()))
+        .min()
+        .unwrap_or(0)
 }
 
-fn load_chunk_from_handle(
+/// Loads the chunk at the specified position,
+/// generating it if it does not exist.
+fn load_chunk(
     pos: ChunkPosition,
-    handle: &mut RegionHandle,
-    sender: &Arc<Sender<Reply>>,
+    region: &Mutex<RegionHandle>,
     generator: &Arc<dyn WorldGenerator>,
     entity_loader: &EntityLoader,
-) -> Option<Reply> {
-    let result = handle.load_chunk(pos);
+) -> LoadResult {
+    // Only hold the lock while reading from the file.
+    let raw = region.lock().read_chunk(pos);
+
+    let data = match raw.and_then(|raw| raw.decode()) {
+        Ok(data) => data,
+        Err(region::Error::ChunkNotExist) => {
+            return Ok((generate_new_chunk(pos, generator), ChunkTags::default()))
+        }
+        Err(e) => return Err(e.into()),
+    };
 
-    match result {
-        Ok((chunk, entities, block_entities)) => {
-            let entities = entities
+    // An invalid entity is skipped rather than failing
+    // the load of the whole chunk.
+    let entities = data
+        .entities
+        .into_iter()
+        .map(|entity| entity_loader.load(entity))
+        .chain(
+            data.block_entities
                 .into_iter()
-                .filter_map(|entity| entity_loader.load(entity))
-                .chain(
-                    block_entities
-                        .into_iter()
-                        .filter_map(|block_entity| entity_loader.load_block(block_entity)),
-                )
-                .collect::<Result<SmallVec<_>, anyhow::Error>>();
-
-            Some(Reply::LoadedChunk(
-                pos,
-                match entities {
-                    Ok(entities) => Ok(ChunkLoad { chunk, entities }),
-                    Err(e) => Err(e),
-                },
-            ))
-        }
-        Err(e) => match e {
-            region::Error::ChunkNotExist => {
-                schedule_generate_new_chunk(sender, pos, generator);
+                .filter_map(|block_entity| entity_loader.load_block(block_entity)),
+        )
+        .filter_map(|result| match result {
+            Ok(builder) => Some(builder),
+            Err(e) => {
+                log::warn!("Skipping invalid entity in chunk {}: {}", pos, e);
                 None
             }
-            err => Some(Reply::LoadedChunk(pos, Err(err.into()))),
-        },
-    }
-}
+        })
+        .collect();
 
-/// Generates a new chunk asynchronously,
-/// sending the result to the provided Sender.
-fn schedule_generate_new_chunk(
-    sender: &Arc<Sender<Reply>>,
-    pos: ChunkPosition,
-    generator: &Arc<dyn WorldGenerator>,
-) {
-    let sender = sender.clone();
-    let generator = Arc::clone(generator);
-    rayon::spawn(move || {
-        sender.send(generate_new_chunk(pos, &generator)).unwrap();
-    });
+    let load = ChunkLoad {
+        chunk: data.chunk,
+        entities,
+        scheduled_updates: data.scheduled_updates,
+    };
+    Ok((load, data.tags))
 }
 
-/// Generates a new chunk synchronously,
-/// returning a Reply to send to a Sender.
-fn generate_new_chunk(pos: ChunkPosition, generator: &Arc<dyn WorldGenerator>) -> Reply {
-    Reply::LoadedChunk(
-        pos,
-        Ok(ChunkLoad {
-            chunk: generator.generate_chunk(pos),
-            entities: SmallVec::new(),
-        }),
-    )
+/// Generates a new chunk synchronously.
+fn generate_new_chunk(pos: ChunkPosition, generator: &Arc<dyn WorldGenerator>) -> ChunkLoad {
+    ChunkLoad {
+        chunk: generator.generate_chunk(pos),
+        entities: SmallVec::new(),
+        scheduled_updates: ScheduledUpdates::default(),
+    }
 }
 
 /// Saves the chunk at the specified position.
 fn save_chunk(worker: &mut ChunkWorker, save: ChunkSave) {
     let chunk = save.chunk.read();
-    let rpos = RegionPosition::from_chunk(chunk.position());
 
-    let file = worker_region(&mut worker.open_regions, &worker.dir, rpos);
+    let region = worker_region(&mut worker.open_regions, &worker.dir, chunk.position());
+    let tags = worker
+        .tags
+        .entry(chunk.position())
+        .or_insert_with(ChunkTags::default);
 
-    let result = file
-        .handle
-        .save_chunk(&*chunk, &save.entities, &save.block_entities);
+    let result = region.lock().save_chunk(
+        &*chunk,
+        &save.entities,
+        &save.block_entities,
+        &save.scheduled_updates,
+        tags,
+    );
 
     worker
         .sender
@@ -246,41 +370,89 @@ fn save_chunk(worker: &mut ChunkWorker, save: ChunkSave) {
         .unwrap();
 }
 
-/// Returns whether the given chunk's region
-/// is already loaded.
-fn is_region_loaded(
-    open_regions: &AHashMap<RegionPosition, RegionFile>,
-    rpos: RegionPosition,
-) -> bool {
-    open_regions.contains_key(&rpos)
-}
-
-fn worker_region<'a>(
-    open_regions: &'a mut AHashMap<RegionPosition, RegionFile>,
+/// Returns the handle of the region file containing a
+/// chunk, opening or creating the file if it is not open.
+fn worker_region(
+    open_regions: &mut RegionCache<SharedRegion>,
     dir: &PathBuf,
-    rpos: RegionPosition,
-) -> &'a mut RegionFile {
-    if !is_region_loaded(open_regions, rpos) {
-        // Need to load region into memory
-        let mut handle = region::load_region(&dir, rpos);
-        if handle.is_err() {
-            // Create a new region file
-            handle = region::create_region(&dir, rpos);
-        }
+    chunk: ChunkPosition,
+) -> SharedRegion {
+    let rpos = RegionPosition::from_chunk(chunk);
+    let region = open_regions.get_or_open(
+        rpos,
+        Instant::now(),
+        || {
+            // Need to load region into memory
+            let mut handle = region::load_region(&dir, rpos);
+            if handle.is_err() {
+                // Create a new region file
+                handle = region::create_region(&dir, rpos);
+            }
 
-        let handle = handle.unwrap();
+            Arc::new(Mutex::new(handle.unwrap()))
+        },
+        close_region,
+    );
+    Arc::clone(region)
+}
 
-        let last_used = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+/// Flushes a region file which is being closed.
+///
+/// Regions are only closed once no load task holds them.
+fn close_region(rpos: RegionPosition, handle: SharedRegion) {
+    log::debug!("Closing region file {:?}", rpos);
+    if let Err(e) = handle.lock().flush() {
+        log::warn!("Failed to flush region file {:?}: {}", rpos, e);
+    }
+}
 
-        let file = RegionFile {
-            handle,
-            _last_used: last_used,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::anvil::entity::{ArrowEntityData, BaseEntityData};
+    use feather_core::position;
+    use feather_server_worldgen::EmptyWorldGenerator;
+
+    #[test]
+    fn test_invalid_entity_is_skipped() {
+        let dir = std::env::temp_dir().join(format!("feather-chunk-worker-{}", std::process::id()));
+        let pos = ChunkPosition::new(3, -1);
+        let mut region = region::create_region(&dir, RegionPosition::from_chunk(pos)).unwrap();
+
+        let valid = ArrowEntityData {
+            entity: BaseEntityData::new(position!(50.0, 64.0, -10.0), Default::default()),
+            ..Default::default()
         };
+        let invalid = ArrowEntityData {
+            entity: BaseEntityData {
+                position: Default::default(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        region
+            .save_chunk(
+                &Chunk::new(pos),
+                &[EntityData::Arrow(invalid), EntityData::Arrow(valid)],
+                &[],
+                &ScheduledUpdates::default(),
+                &ChunkTags::default(),
+            )
+            .unwrap();
+
+        let generator: Arc<dyn WorldGenerator> = Arc::new(EmptyWorldGenerator {});
+        let result = load_chunk(pos, &Mutex::new(region), &generator, &EntityLoader::new());
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let (load, _) = result.unwrap();
+        assert_eq!(load.entities.len(), 1);
+    }
 
-        open_regions.insert(rpos, file);
+    #[test]
+    fn test_distance_to_nearest() {
+        let players = [ChunkPosition::new(0, 0), ChunkPosition::new(10, -10)];
+        assert_eq!(distance_to_nearest(ChunkPosition::new(2, -1), &players), 2);
+        assert_eq!(distance_to_nearest(ChunkPosition::new(9, -7), &players), 3);
+        assert_eq!(distance_to_nearest(ChunkPosition::new(5, 5), &[]), 0);
     }
-    open_regions.get_mut(&rpos).unwrap()
 }
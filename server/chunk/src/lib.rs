@@ -2,7 +2,9 @@
 
 mod chunk_manager;
 pub mod chunk_worker;
+mod region_cache;
 mod save;
 
 pub use chunk_manager::*;
+pub use region_cache::RegionMetrics;
 pub use save::*;
@@ -0,0 +1,270 @@
+//! Cache of the region files kept open by the chunk worker.
+
+use ahash::AHashMap;
+use feather_core::anvil::region::RegionPosition;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Counters of the region files opened and closed
+/// by the chunk worker.
+#[derive(Debug, Default)]
+pub struct RegionMetrics {
+    open: AtomicUsize,
+    opened: AtomicU64,
+    evicted: AtomicU64,
+}
+
+impl RegionMetrics {
+    /// Returns the number of region files currently open.
+    pub fn open(&self) -> usize {
+        self.open.load(Ordering::Relaxed)
+    }
+
+    /// Returns the total number of region files opened.
+    pub fn opened(&self) -> u64 {
+        self.opened.load(Ordering::Relaxed)
+    }
+
+    /// Returns the total number of region files closed because
+    /// they were idle or to make room for another region.
+    pub fn evicted(&self) -> u64 {
+        self.evicted.load(Ordering::Relaxed)
+    }
+}
+
+/// A region which may still be used outside the cache.
+///
+/// Regions in use are never closed: a second handle to the
+/// same file could otherwise be opened and move chunks which
+/// the first one is still reading.
+pub trait RegionRef {
+    fn in_use(&self) -> bool;
+}
+
+impl<T> RegionRef for Arc<T> {
+    fn in_use(&self) -> bool {
+        Arc::strong_count(self) > 1
+    }
+}
+
+struct CachedRegion<T> {
+    region: T,
+    /// The last time the region was used.
+    last_used: Instant,
+}
+
+/// Least-recently-used cache of open regions.
+///
+/// At most `capacity` regions are kept open, and regions which
+/// have not been used for `idle_timeout` are closed by `evict_idle`.
+/// Closed regions are passed to a callback, which should flush them.
+///
+/// Regions which are in use are not closed, so more than
+/// `capacity` regions may be open while they are.
+pub struct RegionCache<T> {
+    regions: AHashMap<RegionPosition, CachedRegion<T>>,
+    capacity: usize,
+    idle_timeout: Duration,
+    metrics: Arc<RegionMetrics>,
+}
+
+impl<T: RegionRef> RegionCache<T> {
+    pub fn new(capacity: usize, idle_timeout: Duration, metrics: Arc<RegionMetrics>) -> Self {
+        Self {
+            regions: AHashMap::new(),
+            capacity: capacity.max(1),
+            idle_timeout,
+            metrics,
+        }
+    }
+
+    /// Returns the region at `pos`, opening it with `open` if it is not
+    /// open. If the cache is full, the least recently used region is
+    /// passed to `evict` first.
+    pub fn get_or_open(
+        &mut self,
+        pos: RegionPosition,
+        now: Instant,
+        open: impl FnOnce() -> T,
+        evict: impl FnOnce(RegionPosition, T),
+    ) -> &mut T {
+        if !self.regions.contains_key(&pos) {
+            if self.regions.len() >= self.capacity {
+                let lru = self
+                    .regions
+                    .iter()
+                    .filter(|(_, cached)| !cached.region.in_use())
+                    .min_by_key(|(_, cached)| cached.last_used)
+                    .map(|(pos, _)| *pos);
+                if let Some(lru) = lru {
+                    let cached = self.remove(lru).unwrap();
+                    self.metrics.evicted.fetch_add(1, Ordering::Relaxed);
+                    evict(lru, cached.region);
+                }
+            }
+
+            let region = open();
+            self.regions.insert(
+                pos,
+                CachedRegion {
+                    region,
+                    last_used: now,
+                },
+            );
+            self.metrics.open.fetch_add(1, Ordering::Relaxed);
+            self.metrics.opened.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let cached = self.regions.get_mut(&pos).unwrap();
+        cached.last_used = now;
+        &mut cached.region
+    }
+
+    /// Closes the regions which have not been used for the idle timeout.
+    pub fn evict_idle(&mut self, now: Instant, mut evict: impl FnMut(RegionPosition, T)) {
+        let idle_timeout = self.idle_timeout;
+        let idle: Vec<_> = self
+            .regions
+            .iter()
+            .filter(|(_, cached)| {
+                now.saturating_duration_since(cached.last_used) >= idle_timeout
+                    && !cached.region.in_use()
+            })
+            .map(|(pos, _)| *pos)
+            .collect();
+
+        for pos in idle {
+            let cached = self.remove(pos).unwrap();
+            self.metrics.evicted.fetch_add(1, Ordering::Relaxed);
+            evict(pos, cached.region);
+        }
+    }
+
+    /// Closes all regions.
+    pub fn close_all(&mut self, mut close: impl FnMut(RegionPosition, T)) {
+        let positions: Vec<_> = self.regions.keys().copied().collect();
+        for pos in positions {
+            let cached = self.remove(pos).unwrap();
+            close(pos, cached.region);
+        }
+    }
+
+    fn remove(&mut self, pos: RegionPosition) -> Option<CachedRegion<T>> {
+        let cached = self.regions.remove(&pos)?;
+        self.metrics.open.fetch_sub(1, Ordering::Relaxed);
+        Some(cached)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::util::ChunkPosition;
+
+    impl RegionRef for i32 {
+        fn in_use(&self) -> bool {
+            false
+        }
+    }
+
+    fn region(x: i32) -> RegionPosition {
+        RegionPosition::from_chunk(ChunkPosition::new(x * 32, 0))
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let metrics = Arc::new(RegionMetrics::default());
+        let mut cache = RegionCache::new(2, Duration::from_secs(60), Arc::clone(&metrics));
+        let start = Instant::now();
+        let mut evicted = Vec::new();
+
+        cache.get_or_open(region(0), start, || 0, |_, _| panic!());
+        cache.get_or_open(
+            region(1),
+            start + Duration::from_secs(1),
+            || 1,
+            |_, _| panic!(),
+        );
+        // Use region 0 again, so region 1 is the least recently used.
+        cache.get_or_open(
+            region(0),
+            start + Duration::from_secs(2),
+            || panic!(),
+            |_, _| panic!(),
+        );
+
+        let value = *cache.get_or_open(
+            region(2),
+            start + Duration::from_secs(3),
+            || 2,
+            |pos, value| evicted.push((pos, value)),
+        );
+        assert_eq!(value, 2);
+        assert_eq!(evicted, vec![(region(1), 1)]);
+
+        assert_eq!(metrics.open(), 2);
+        assert_eq!(metrics.opened(), 3);
+        assert_eq!(metrics.evicted(), 1);
+    }
+
+    #[test]
+    fn evicts_idle() {
+        let metrics = Arc::new(RegionMetrics::default());
+        let mut cache = RegionCache::new(8, Duration::from_secs(60), Arc::clone(&metrics));
+        let start = Instant::now();
+        let mut evicted = Vec::new();
+
+        cache.get_or_open(region(0), start, || 0, |_, _| panic!());
+        cache.get_or_open(
+            region(1),
+            start + Duration::from_secs(30),
+            || 1,
+            |_, _| panic!(),
+        );
+
+        cache.evict_idle(start + Duration::from_secs(59), |pos, value| {
+            evicted.push((pos, value))
+        });
+        assert!(evicted.is_empty());
+
+        cache.evict_idle(start + Duration::from_secs(60), |pos, value| {
+            evicted.push((pos, value))
+        });
+        assert_eq!(evicted, vec![(region(0), 0)]);
+        assert_eq!(metrics.open(), 1);
+        assert_eq!(metrics.evicted(), 1);
+
+        let mut closed = Vec::new();
+        cache.close_all(|pos, value| closed.push((pos, value)));
+        assert_eq!(closed, vec![(region(1), 1)]);
+        assert_eq!(metrics.open(), 0);
+        assert_eq!(metrics.evicted(), 1);
+    }
+
+    #[test]
+    fn keeps_regions_in_use() {
+        let metrics = Arc::new(RegionMetrics::default());
+        let mut cache = RegionCache::new(1, Duration::from_secs(60), Arc::clone(&metrics));
+        let start = Instant::now();
+
+        let held = Arc::clone(cache.get_or_open(region(0), start, || Arc::new(0), |_, _| panic!()));
+        cache.get_or_open(
+            region(1),
+            start + Duration::from_secs(1),
+            || Arc::new(1),
+            |_, _| panic!(),
+        );
+        cache.evict_idle(start + Duration::from_secs(120), |pos, _| {
+            assert_eq!(pos, region(1))
+        });
+        assert_eq!(metrics.open(), 1);
+
+        // Once released, the region can be closed.
+        drop(held);
+        let mut evicted = Vec::new();
+        cache.evict_idle(start + Duration::from_secs(120), |pos, _| evicted.push(pos));
+        assert_eq!(evicted, vec![region(0)]);
+        assert_eq!(metrics.open(), 0);
+    }
+}
@@ -10,7 +10,7 @@ use feather_core::inventory::{Inventory, Window};
 use feather_core::util::{ChunkPosition, Gamemode, Position, Vec3d};
 use feather_server_types::{
     tasks, BlockSerializer, ChunkLoadEvent, ChunkUnloadEvent, ComponentSerializer, Game, Health,
-    HeldItem, PlayerLeaveEvent, Uuid, TICK_LENGTH, TPS,
+    HeldItem, PlayerLeaveEvent, RecipeBook, Uuid, TICK_LENGTH, TPS,
 };
 use fecs::{Entity, World};
 use std::collections::VecDeque;
@@ -49,6 +49,7 @@ pub fn on_chunk_unload_save_chunk(
     chunk_worker_handle: &ChunkWorkerHandle,
 ) {
     save_chunk_at(game, world, event.chunk, chunk_worker_handle);
+    chunk_manager::unload_chunk(chunk_worker_handle, event.chunk);
 }
 
 fn queue_for_saving(game: &mut Game, save_queue: &mut SaveQueue, chunk: ChunkPosition) {
@@ -119,12 +120,16 @@ pub fn save_chunk_at(
         .chunk_handle_at(pos)
         .expect("chunk does not exist");
 
-    if !chunk.write().check_modified() && game.chunk_entities.entities_in_chunk(pos).is_empty() {
+    if !chunk.write().check_modified()
+        && game.chunk_entities.entities_in_chunk(pos).is_empty()
+        && !game.scheduled_ticks.has_ticks_in(pos)
+    {
         return;
     }
 
     // Serialize the entities in the chunk.
     let (entities, block_entities) = serialize_entities(game, world, pos);
+    let scheduled_updates = game.scheduled_ticks.save(pos, game.tick_count);
 
     log::trace!("Queuing chunk at {} for saving", pos);
     chunk_manager::save_chunk(
@@ -132,6 +137,7 @@ pub fn save_chunk_at(
         game.chunk_map.chunk_handle_at(pos).unwrap(),
         entities.collect(),
         block_entities.collect(),
+        scheduled_updates,
     );
 }
 
@@ -204,6 +210,10 @@ pub fn save_player_data(game: &Game, world: &World, player: Entity) {
         gamemode: world.get::<Gamemode>(player).id() as i32,
         inventory,
         held_item: world.get::<HeldItem>(player).0 as i32,
+        recipe_book: world
+            .try_get::<RecipeBook>(player)
+            .map(|book| book.to_nbt())
+            .unwrap_or_default(),
     };
 
     let uuid = *world.get::<Uuid>(player);
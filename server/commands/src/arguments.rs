@@ -1,4 +1,6 @@
+use crate::graph::DeclaredArgument;
 use crate::CommandCtx;
+use feather_core::network::packets::{CommandParser, StringParserMode};
 use feather_core::position;
 use feather_core::util::{Gamemode, Position};
 use feather_definitions::Item;
@@ -10,6 +12,7 @@ use smallvec::SmallVec;
 use std::convert::Infallible;
 use std::num::ParseFloatError;
 use std::str::FromStr;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -43,6 +46,34 @@ impl ArgumentKind<CommandCtx> for EntitySelector {
     }
 }
 
+impl DeclaredArgument for EntitySelector {
+    fn parser() -> CommandParser {
+        CommandParser::Entity {
+            single: false,
+            only_players: false,
+        }
+    }
+
+    fn ask_server() -> bool {
+        true
+    }
+
+    fn suggestions(ctx: &CommandCtx, partial: &str) -> Vec<String> {
+        let players = <Read<Name>>::query()
+            .filter(component::<Player>())
+            .iter(ctx.world.inner())
+            .map(|name| name.0.to_string())
+            .collect::<Vec<_>>();
+
+        SELECTORS
+            .iter()
+            .map(|selector| selector.to_string())
+            .chain(players)
+            .filter(|suggestion| suggestion.starts_with(partial))
+            .collect()
+    }
+}
+
 impl EntitySelector {
     /// Parses the returned entities for use in reporting success messages
     /// Either the name of the entity for one entity, or how many were affected for many entities.
@@ -82,6 +113,56 @@ impl EntitySelector {
     }
 }
 
+/// Argument kind for players who may be offline, such as
+/// the targets of `/ban`. Accepts entity selectors and
+/// player names, which need not belong to online players.
+pub enum GameProfileArgument {
+    /// Entities selected by a selector or the name of an online player.
+    Entities(SmallVec<[Entity; 1]>),
+    /// The name of a player who is not online.
+    Name(String),
+}
+
+impl ArgumentKind<CommandCtx> for GameProfileArgument {
+    type ParseError = SelectorParseError;
+
+    fn satisfies<'a>(_ctx: &CommandCtx, input: &mut Input<'a>) -> bool {
+        !input.advance_until(" ").is_empty()
+    }
+
+    fn parse<'a>(ctx: &CommandCtx, input: &mut Input<'a>) -> Result<Self, Self::ParseError> {
+        let head = input.advance_until(" ");
+
+        if head.starts_with('@') {
+            return Ok(GameProfileArgument::Entities(find_selected_entities(
+                ctx, head,
+            )?));
+        }
+
+        Ok(match find_player_by_name(&ctx.world, head) {
+            Some(player) => GameProfileArgument::Entities(smallvec::smallvec![player]),
+            None => GameProfileArgument::Name(head.to_owned()),
+        })
+    }
+}
+
+impl DeclaredArgument for GameProfileArgument {
+    fn parser() -> CommandParser {
+        CommandParser::GameProfile
+    }
+
+    fn ask_server() -> bool {
+        true
+    }
+
+    fn suggestions(ctx: &CommandCtx, partial: &str) -> Vec<String> {
+        EntitySelector::suggestions(ctx, partial)
+    }
+}
+
+/// The target selector variables supported by `find_selected_entities`.
+const SELECTORS: [&str; 5] = ["@p", "@r", "@a", "@e", "@s"];
+
 fn find_selected_entities(
     ctx: &CommandCtx,
     input: &str,
@@ -131,7 +212,7 @@ fn find_selected_entities(
 
 // TODO: eliminate linear searches.
 // These search functions are incredibly naive.
-fn find_player_by_name(world: &World, name: &str) -> Option<Entity> {
+pub fn find_player_by_name(world: &World, name: &str) -> Option<Entity> {
     <Read<Name>>::query()
         .iter_entities(world.inner())
         .find(|(_, n)| n.0 == name)
@@ -282,6 +363,16 @@ impl ArgumentKind<CommandCtx> for Coordinates {
     }
 }
 
+impl DeclaredArgument for Coordinates {
+    fn parser() -> CommandParser {
+        CommandParser::Vec3
+    }
+
+    fn words() -> Option<usize> {
+        Some(3)
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum GamemodeParseError {
     #[error("invalid gamemode string {0}")]
@@ -314,6 +405,24 @@ impl ArgumentKind<CommandCtx> for ParsedGamemode {
     }
 }
 
+impl DeclaredArgument for ParsedGamemode {
+    fn parser() -> CommandParser {
+        CommandParser::String(StringParserMode::SingleWord)
+    }
+
+    fn ask_server() -> bool {
+        true
+    }
+
+    fn suggestions(_ctx: &CommandCtx, partial: &str) -> Vec<String> {
+        ["survival", "creative", "spectator", "adventure"]
+            .iter()
+            .filter(|gamemode| gamemode.starts_with(partial))
+            .map(|gamemode| gamemode.to_string())
+            .collect()
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum TextParseError {}
 
@@ -335,6 +444,16 @@ impl ArgumentKind<CommandCtx> for TextArgument {
     }
 }
 
+impl DeclaredArgument for TextArgument {
+    fn parser() -> CommandParser {
+        CommandParser::String(StringParserMode::GreedyPhrase)
+    }
+
+    fn words() -> Option<usize> {
+        None
+    }
+}
+
 impl AsRef<str> for TextArgument {
     fn as_ref(&self) -> &str {
         self.0.as_str()
@@ -367,6 +486,12 @@ impl ArgumentKind<CommandCtx> for ItemArgument {
     }
 }
 
+impl DeclaredArgument for ItemArgument {
+    fn parser() -> CommandParser {
+        CommandParser::ItemStack
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum I32ParseError {
     #[error("Invalid integer {0}")]
@@ -393,6 +518,15 @@ impl ArgumentKind<CommandCtx> for I32Argument {
     }
 }
 
+impl DeclaredArgument for I32Argument {
+    fn parser() -> CommandParser {
+        CommandParser::Integer {
+            min: None,
+            max: None,
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum PositiveI32ParseError {
     #[error("Invalid integer {0}")]
@@ -426,3 +560,144 @@ impl ArgumentKind<CommandCtx> for PositiveI32Argument {
         }
     }
 }
+
+impl DeclaredArgument for PositiveI32Argument {
+    fn parser() -> CommandParser {
+        CommandParser::Integer {
+            min: Some(0),
+            max: None,
+        }
+    }
+}
+
+/// `String` arguments are single words, parsed through `FromStr`.
+impl DeclaredArgument for String {
+    fn parser() -> CommandParser {
+        CommandParser::String(StringParserMode::SingleWord)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DurationParseError {
+    #[error("Invalid duration {0}; expected a number followed by s, m, h, d or w, such as 7d")]
+    Invalid(String),
+}
+
+/// A duration such as `30m`, `7d` or `1d12h`.
+///
+/// Supported units are seconds (`s`), minutes (`m`),
+/// hours (`h`), days (`d`) and weeks (`w`).
+#[derive(Copy, Clone, Debug)]
+pub struct DurationArgument(pub Duration);
+
+impl ArgumentKind<CommandCtx> for DurationArgument {
+    type ParseError = DurationParseError;
+
+    fn satisfies<'a>(_ctx: &CommandCtx, input: &mut Input<'a>) -> bool {
+        parse_duration(input.advance_until(" ")).is_ok()
+    }
+
+    fn parse<'a>(_ctx: &CommandCtx, input: &mut Input<'a>) -> Result<Self, Self::ParseError> {
+        parse_duration(input.advance_until(" ")).map(DurationArgument)
+    }
+}
+
+impl DeclaredArgument for DurationArgument {
+    fn parser() -> CommandParser {
+        CommandParser::String(StringParserMode::SingleWord)
+    }
+}
+
+fn parse_duration(s: &str) -> Result<Duration, DurationParseError> {
+    let invalid = || DurationParseError::Invalid(s.to_owned());
+
+    let mut secs = 0u64;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let amount = rest[..digits].parse::<u64>().map_err(|_| invalid())?;
+        let unit = match rest[digits..].chars().next() {
+            Some('s') => 1,
+            Some('m') => 60,
+            Some('h') => 60 * 60,
+            Some('d') => 24 * 60 * 60,
+            Some('w') => 7 * 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+
+        secs = amount
+            .checked_mul(unit)
+            .and_then(|amount| secs.checked_add(amount))
+            .ok_or_else(invalid)?;
+        rest = &rest[digits + 1..];
+    }
+
+    if secs == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// Formats a duration in the syntax accepted by `DurationArgument`,
+/// rounded down to the second.
+pub fn format_duration(duration: Duration) -> String {
+    const UNITS: [(char, u64); 4] = [('d', 24 * 60 * 60), ('h', 60 * 60), ('m', 60), ('s', 1)];
+
+    let mut secs = duration.as_secs();
+    let mut formatted = String::new();
+    for (unit, length) in UNITS.iter() {
+        if secs >= *length {
+            formatted.push_str(&format!("{}{}", secs / length, unit));
+            secs %= length;
+        }
+    }
+
+    if formatted.is_empty() {
+        formatted.push_str("0s");
+    }
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration() {
+        assert_eq!(parse_duration("10s").unwrap(), Duration::from_secs(10));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(30 * 60));
+        assert_eq!(
+            parse_duration("7d").unwrap(),
+            Duration::from_secs(7 * 24 * 60 * 60)
+        );
+        assert_eq!(
+            parse_duration("1d12h").unwrap(),
+            Duration::from_secs(36 * 60 * 60)
+        );
+        assert_eq!(
+            parse_duration("2w").unwrap(),
+            Duration::from_secs(14 * 24 * 60 * 60)
+        );
+
+        assert_eq!(
+            format_duration(Duration::from_secs(36 * 60 * 60 + 5)),
+            "1d12h5s"
+        );
+        assert_eq!(format_duration(Duration::from_millis(500)), "0s");
+
+        for invalid in &[
+            "",
+            "7",
+            "d",
+            "0d",
+            "7x",
+            "-1d",
+            "1.5h",
+            "99999999999999999999w",
+        ] {
+            assert!(parse_duration(invalid).is_err(), "{} parsed", invalid);
+        }
+    }
+}
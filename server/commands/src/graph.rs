@@ -0,0 +1,444 @@
+//! The command graph sent to clients in the Declare Commands
+//! packet, which clients use for argument highlighting and
+//! tab completion. The same graph is used to answer tab completion
+//! requests for arguments whose suggestions come from the server.
+
+use crate::CommandCtx;
+use feather_core::network::packets::{
+    CommandNode, CommandNodeType, CommandParser, DeclareCommands,
+};
+use feather_server_types::Permission;
+use lieutenant::CommandDispatcher;
+
+/// Index of the root node.
+const ROOT: usize = 0;
+
+/// Suggestions provider which makes the client send
+/// Tab-Complete requests to the server.
+const ASK_SERVER: &str = "minecraft:ask_server";
+
+/// An argument kind which can be described to clients.
+pub trait DeclaredArgument {
+    /// Returns the parser clients use to parse and highlight this argument.
+    fn parser() -> CommandParser;
+
+    /// Returns the number of space-separated words this argument
+    /// consumes, or `None` if it consumes the rest of the command.
+    fn words() -> Option<usize> {
+        Some(1)
+    }
+
+    /// Whether clients should ask the server for suggestions
+    /// for this argument. Should be `true` if `suggestions`
+    /// is implemented.
+    fn ask_server() -> bool {
+        false
+    }
+
+    /// Returns completions for `partial`, the partially typed argument.
+    fn suggestions(_ctx: &CommandCtx, _partial: &str) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// A command defined with `#[command]`, which implements
+/// this trait for a type named after the command's function.
+pub trait DeclaredCommand {
+    /// The usage given in the `#[command]` attribute.
+    const USAGE: &'static str;
+    /// The permission required to run the command.
+    const PERMISSION: Permission;
+
+    /// Returns the `ArgumentSpec` of each of the command's
+    /// arguments, in the order they appear in the usage.
+    fn arguments() -> Vec<ArgumentSpec>;
+
+    /// Registers the command with `dispatcher`.
+    fn register(dispatcher: &mut CommandDispatcher<CommandCtx>) -> Result<(), String>;
+}
+
+/// Description of an argument in the command graph.
+#[derive(Clone, Copy)]
+pub struct ArgumentSpec {
+    parser: CommandParser,
+    words: Option<usize>,
+    ask_server: bool,
+    suggestions: fn(&CommandCtx, &str) -> Vec<String>,
+}
+
+impl ArgumentSpec {
+    /// Returns the `ArgumentSpec` for the argument kind `T`.
+    pub fn of<T: DeclaredArgument>() -> Self {
+        Self {
+            parser: T::parser(),
+            words: T::words(),
+            ask_server: T::ask_server(),
+            suggestions: T::suggestions,
+        }
+    }
+}
+
+enum NodeKind {
+    Root,
+    Literal,
+    Argument(ArgumentSpec),
+}
+
+impl NodeKind {
+    fn is_same(&self, other: &NodeKind) -> bool {
+        match (self, other) {
+            (NodeKind::Literal, NodeKind::Literal) => true,
+            (NodeKind::Argument(a), NodeKind::Argument(b)) => a.parser == b.parser,
+            _ => false,
+        }
+    }
+}
+
+struct Node {
+    kind: NodeKind,
+    name: String,
+    executable: bool,
+    children: Vec<usize>,
+}
+
+/// The tree of all registered commands. Commands sharing a prefix
+/// share the nodes for that prefix.
+pub struct CommandGraph {
+    nodes: Vec<Node>,
+}
+
+impl Default for CommandGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandGraph {
+    /// Creates a graph with no commands.
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![Node {
+                kind: NodeKind::Root,
+                name: String::new(),
+                executable: false,
+                children: vec![],
+            }],
+        }
+    }
+
+    /// Adds a command to the graph.
+    ///
+    /// `usage` is in the format accepted by `#[command]`: literals
+    /// with `|`-separated aliases and `<argument>`s. `arguments`
+    /// describes each argument in the order they appear in `usage`.
+    ///
+    /// # Panics
+    /// Panics if the number of arguments in `usage` does not match `arguments`.
+    pub fn add(&mut self, usage: &str, arguments: &[ArgumentSpec]) {
+        let tokens: Vec<&str> = usage.split_whitespace().collect();
+        self.insert(ROOT, &tokens, arguments);
+    }
+
+    fn insert(&mut self, parent: usize, tokens: &[&str], arguments: &[ArgumentSpec]) {
+        let (token, rest) = match tokens.split_first() {
+            Some(split) => split,
+            None => {
+                assert!(arguments.is_empty(), "unused command arguments");
+                self.nodes[parent].executable = true;
+                return;
+            }
+        };
+
+        if token.starts_with('<') && token.ends_with('>') {
+            let name = &token[1..token.len() - 1];
+            let (spec, arguments) = arguments.split_first().expect("missing command argument");
+            let child = self.child(parent, name, NodeKind::Argument(*spec));
+            self.insert(child, rest, arguments);
+        } else {
+            for literal in token.split('|') {
+                let child = self.child(parent, literal, NodeKind::Literal);
+                self.insert(child, rest, arguments);
+            }
+        }
+    }
+
+    /// Returns the child of `parent` with the given name and kind,
+    /// creating it if it does not exist.
+    fn child(&mut self, parent: usize, name: &str, kind: NodeKind) -> usize {
+        let existing = self.nodes[parent].children.iter().copied().find(|child| {
+            let node = &self.nodes[*child];
+            node.name == name && node.kind.is_same(&kind)
+        });
+
+        if let Some(existing) = existing {
+            return existing;
+        }
+
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            kind,
+            name: name.to_owned(),
+            executable: false,
+            children: vec![],
+        });
+        self.nodes[parent].children.push(index);
+        index
+    }
+
+    /// Creates a Declare Commands packet describing this graph,
+    /// including only the commands whose name satisfies `permitted`.
+    pub fn to_packet(&self, permitted: impl Fn(&str) -> bool) -> DeclareCommands {
+        let nodes = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| {
+                let (node_type, parser, suggestions_type) = match &node.kind {
+                    NodeKind::Root => (CommandNodeType::Root, None, None),
+                    NodeKind::Literal => (CommandNodeType::Literal, None, None),
+                    NodeKind::Argument(spec) => (
+                        CommandNodeType::Argument,
+                        Some(spec.parser),
+                        if spec.ask_server {
+                            Some(ASK_SERVER.to_owned())
+                        } else {
+                            None
+                        },
+                    ),
+                };
+
+                CommandNode {
+                    node_type,
+                    executable: node.executable,
+                    children: node
+                        .children
+                        .iter()
+                        .filter(|child| index != ROOT || permitted(&self.nodes[**child].name))
+                        .map(|child| *child as i32)
+                        .collect(),
+                    redirect_node: None,
+                    name: node.name.clone(),
+                    parser,
+                    suggestions_type,
+                }
+            })
+            .collect();
+
+        DeclareCommands {
+            nodes,
+            root_index: ROOT as i32,
+        }
+    }
+
+    /// Returns completions for `input`, a partially typed command
+    /// without the leading slash, along with the byte offset into `input`
+    /// of the text which the completions replace.
+    ///
+    /// Only commands whose name satisfies `permitted` are completed.
+    pub fn complete(
+        &self,
+        ctx: &CommandCtx,
+        input: &str,
+        permitted: impl Fn(&str) -> bool,
+    ) -> (usize, Vec<String>) {
+        let mut suggestions = vec![];
+        self.complete_node(ROOT, ctx, input, 0, &permitted, &mut suggestions);
+
+        // Only the completions for the last (innermost)
+        // word can be sent back to the client.
+        let start = suggestions
+            .iter()
+            .map(|(start, _)| *start)
+            .max()
+            .unwrap_or(input.len());
+        let mut matches: Vec<String> = suggestions
+            .into_iter()
+            .filter(|(s, _)| *s == start)
+            .map(|(_, suggestion)| suggestion)
+            .collect();
+        matches.sort();
+        matches.dedup();
+
+        (start, matches)
+    }
+
+    fn complete_node(
+        &self,
+        node: usize,
+        ctx: &CommandCtx,
+        input: &str,
+        offset: usize,
+        permitted: &dyn Fn(&str) -> bool,
+        suggestions: &mut Vec<(usize, String)>,
+    ) {
+        let remaining = &input[offset..];
+
+        for child in &self.nodes[node].children {
+            let child_node = &self.nodes[*child];
+            if node == ROOT && !permitted(&child_node.name) {
+                continue;
+            }
+            let words = match &child_node.kind {
+                NodeKind::Root => unreachable!(),
+                NodeKind::Literal => Some(1),
+                NodeKind::Argument(spec) => spec.words,
+            };
+
+            match words.and_then(|words| word_end(remaining, words)) {
+                // This node has been typed completely; continue with its children.
+                Some(end) => {
+                    let typed = &remaining[..end];
+                    if let NodeKind::Literal = child_node.kind {
+                        if typed != child_node.name {
+                            continue;
+                        }
+                    }
+
+                    self.complete_node(
+                        *child,
+                        ctx,
+                        input,
+                        offset + end + 1,
+                        permitted,
+                        suggestions,
+                    );
+                }
+                // This node is being typed.
+                None => match &child_node.kind {
+                    NodeKind::Literal => {
+                        if child_node.name.starts_with(remaining) {
+                            suggestions.push((offset, child_node.name.clone()));
+                        }
+                    }
+                    NodeKind::Argument(spec) => {
+                        suggestions.extend(
+                            (spec.suggestions)(ctx, remaining)
+                                .into_iter()
+                                .map(|suggestion| (offset, suggestion)),
+                        );
+                    }
+                    NodeKind::Root => unreachable!(),
+                },
+            }
+        }
+    }
+}
+
+/// Returns the byte offset of the space following the first
+/// `words` words of `input`, or `None` if the words have not
+/// been typed completely.
+fn word_end(input: &str, words: usize) -> Option<usize> {
+    input
+        .match_indices(' ')
+        .nth(words.checked_sub(1)?)
+        .map(|(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::network::packets::StringParserMode;
+
+    struct Word;
+
+    impl DeclaredArgument for Word {
+        fn parser() -> CommandParser {
+            CommandParser::String(StringParserMode::SingleWord)
+        }
+    }
+
+    struct Greedy;
+
+    impl DeclaredArgument for Greedy {
+        fn parser() -> CommandParser {
+            CommandParser::String(StringParserMode::GreedyPhrase)
+        }
+
+        fn words() -> Option<usize> {
+            None
+        }
+    }
+
+    fn child_named<'a>(packet: &'a DeclareCommands, node: i32, name: &str) -> &'a CommandNode {
+        packet.nodes[node as usize]
+            .children
+            .iter()
+            .map(|child| &packet.nodes[*child as usize])
+            .find(|child| child.name == name)
+            .unwrap_or_else(|| panic!("no child named {}", name))
+    }
+
+    #[test]
+    fn test_graph_merges_prefixes() {
+        let mut graph = CommandGraph::new();
+        graph.add("kick <targets>", &[ArgumentSpec::of::<Word>()]);
+        graph.add(
+            "kick <targets> <reason>",
+            &[ArgumentSpec::of::<Word>(), ArgumentSpec::of::<Greedy>()],
+        );
+        graph.add("stop", &[]);
+
+        let packet = graph.to_packet(|_| true);
+        assert_eq!(packet.nodes.len(), 5);
+        assert_eq!(packet.root_index, 0);
+
+        let root = &packet.nodes[0];
+        assert_eq!(root.node_type, CommandNodeType::Root);
+        assert_eq!(root.children.len(), 2);
+
+        let kick = child_named(&packet, 0, "kick");
+        assert_eq!(kick.node_type, CommandNodeType::Literal);
+        assert!(!kick.executable);
+        assert_eq!(kick.children.len(), 1);
+
+        let targets = &packet.nodes[kick.children[0] as usize];
+        assert_eq!(targets.node_type, CommandNodeType::Argument);
+        assert!(targets.executable);
+        assert_eq!(
+            targets.parser,
+            Some(CommandParser::String(StringParserMode::SingleWord))
+        );
+
+        let reason = &packet.nodes[targets.children[0] as usize];
+        assert!(reason.executable);
+        assert!(reason.children.is_empty());
+
+        assert!(child_named(&packet, 0, "stop").executable);
+    }
+
+    #[test]
+    fn test_graph_aliases() {
+        let mut graph = CommandGraph::new();
+        graph.add("tell|msg <target>", &[ArgumentSpec::of::<Word>()]);
+
+        let packet = graph.to_packet(|_| true);
+        for alias in &["tell", "msg"] {
+            let node = child_named(&packet, 0, alias);
+            assert_eq!(node.children.len(), 1);
+            assert!(packet.nodes[node.children[0] as usize].executable);
+        }
+    }
+
+    #[test]
+    fn test_graph_filters_commands() {
+        let mut graph = CommandGraph::new();
+        graph.add("stop", &[]);
+        graph.add("tell|msg <target>", &[ArgumentSpec::of::<Word>()]);
+
+        let packet = graph.to_packet(|name| name != "stop");
+        let root = &packet.nodes[0];
+        assert_eq!(root.children.len(), 2);
+        assert!(root
+            .children
+            .iter()
+            .all(|child| packet.nodes[*child as usize].name != "stop"));
+    }
+
+    #[test]
+    fn test_word_end() {
+        assert_eq!(word_end("tp", 1), None);
+        assert_eq!(word_end("tp ", 1), Some(2));
+        assert_eq!(word_end("~ ~ ~ abc", 3), Some(5));
+        assert_eq!(word_end("~ ~", 3), None);
+    }
+}
@@ -1,24 +1,29 @@
 //! The implementations of various commands.
 
-use crate::arguments::Coordinates;
+use crate::arguments::{
+    find_player_by_name, format_duration, Coordinates, DurationArgument, GameProfileArgument,
+};
+use crate::permissions::*;
 use crate::{
     arguments::{EntitySelector, ItemArgument, ParsedGamemode, PositiveI32Argument, TextArgument},
-    CommandCtx,
+    CommandCtx, CommandState,
 };
+use feather_codegen::command;
 use feather_core::inventory::{Inventory, SlotIndex};
 use feather_core::text::{Text, TextComponentBuilder, TextValue};
 use feather_core::util::{Gamemode, Position};
 use feather_definitions::Item;
 use feather_server_types::{
     Ban, ChatEvent, ChatPosition, GamemodeUpdateEvent, InventoryUpdateEvent, MessageReceiver, Name,
-    Player, ShutdownChannels, Teleported, WrappedBanInfo,
+    Op, Permissions, Player, ShutdownChannels, Teleported, Whitelist, WhitelistEntry,
+    WrappedBanInfo, WrappedOpList, WrappedWhitelist, MAX_OP_LEVEL,
 };
 use feather_server_util::{name_to_uuid_offline, name_to_uuid_online};
-use fecs::{Entity, IntoQuery, Read, ResourcesProvider, World};
-use lieutenant::command;
+use fecs::{component, Entity, IntoQuery, Read, ResourcesProvider, World};
 use smallvec::SmallVec;
 use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
+use std::time::SystemTime;
 use thiserror::Error;
 use tokio::runtime::Runtime;
 use uuid::Uuid;
@@ -29,10 +34,16 @@ pub enum TpError {
     NoMatchingEntities,
     #[error("Only one entity is allowed, but the provided selector allows for more than one")]
     TooManyEntities,
+    #[error("Only entities with a position can be teleported; specify the targets")]
+    SenderHasNoPosition,
 }
 
-#[command(usage = "tp|teleport <destination>")]
+#[command(usage = "tp|teleport <destination>", permission = TELEPORT)]
 pub fn tp_1(ctx: &mut CommandCtx, destination: EntitySelector) -> anyhow::Result<Option<String>> {
+    if !ctx.world.has::<Position>(ctx.sender) {
+        return Err(TpError::SenderHasNoPosition.into());
+    }
+
     if let Some(first) = destination.entities.first() {
         if let Some(pos) = ctx.world.try_get::<Position>(*first).map(|r| *r) {
             teleport_entity_to_pos(&mut ctx.world, ctx.sender, pos);
@@ -48,8 +59,12 @@ pub fn tp_1(ctx: &mut CommandCtx, destination: EntitySelector) -> anyhow::Result
     }
 }
 
-#[command(usage = "tp|teleport <location>")]
+#[command(usage = "tp|teleport <location>", permission = TELEPORT)]
 pub fn tp_2(ctx: &mut CommandCtx, location: Coordinates) -> anyhow::Result<Option<String>> {
+    if !ctx.world.has::<Position>(ctx.sender) {
+        return Err(TpError::SenderHasNoPosition.into());
+    }
+
     teleport_entity(&mut ctx.world, ctx.sender, location);
 
     let position = ctx.world.get::<Position>(ctx.sender);
@@ -62,7 +77,7 @@ pub fn tp_2(ctx: &mut CommandCtx, location: Coordinates) -> anyhow::Result<Optio
     )))
 }
 
-#[command(usage = "tp|teleport <targets> <location>")]
+#[command(usage = "tp|teleport <targets> <location>", permission = TELEPORT)]
 pub fn tp_3(
     ctx: &mut CommandCtx,
     targets: EntitySelector,
@@ -88,7 +103,7 @@ pub fn tp_3(
     }
 }
 
-#[command(usage = "tp|teleport <targets> <destination>")]
+#[command(usage = "tp|teleport <targets> <destination>", permission = TELEPORT)]
 pub fn tp_4(
     ctx: &mut CommandCtx,
     targets: EntitySelector,
@@ -137,7 +152,7 @@ fn teleport_entity_to_pos(world: &mut World, entity: Entity, pos: Position) {
     let _ = world.add(entity, Teleported);
 }
 
-#[command(usage = "gamemode <gamemode>")]
+#[command(usage = "gamemode <gamemode>", permission = GAMEMODE)]
 pub fn gamemode_1(
     ctx: &mut CommandCtx,
     gamemode: ParsedGamemode,
@@ -149,7 +164,7 @@ pub fn gamemode_1(
     )))
 }
 
-#[command(usage = "gamemode <gamemode> <target>")]
+#[command(usage = "gamemode <gamemode> <target>", permission = GAMEMODE)]
 pub fn gamemode_2(
     ctx: &mut CommandCtx,
     gamemode: ParsedGamemode,
@@ -192,7 +207,7 @@ fn update_gamemode(ctx: &mut CommandCtx, gamemode: Gamemode, entity: Entity) {
     }
 }
 
-#[command(usage = "tell|msg|w <target> <message>")]
+#[command(usage = "tell|msg|w <target> <message>", permission = MSG)]
 pub fn whisper(
     ctx: &mut CommandCtx,
     target: EntitySelector,
@@ -251,7 +266,7 @@ pub fn whisper(
     Ok(None)
 }
 
-#[command(usage = "say <message>")]
+#[command(usage = "say <message>", permission = SAY)]
 pub fn say(ctx: &mut CommandCtx, message: TextArgument) -> anyhow::Result<Option<String>> {
     let name = ctx.world.try_get::<Name>(ctx.sender);
 
@@ -276,7 +291,7 @@ pub fn say(ctx: &mut CommandCtx, message: TextArgument) -> anyhow::Result<Option
     Ok(None)
 }
 
-#[command(usage = "me <action>")]
+#[command(usage = "me <action>", permission = ME)]
 pub fn me(ctx: &mut CommandCtx, action: TextArgument) -> anyhow::Result<Option<String>> {
     let command_output = {
         let name = ctx.world.try_get::<Name>(ctx.sender);
@@ -303,7 +318,7 @@ pub enum KickError {
     NoEntities,
 }
 
-#[command(usage = "kick <targets>")]
+#[command(usage = "kick <targets>", permission = KICK)]
 pub fn kick_1(ctx: &mut CommandCtx, targets: EntitySelector) -> anyhow::Result<Option<String>> {
     kick_players(
         ctx,
@@ -312,7 +327,7 @@ pub fn kick_1(ctx: &mut CommandCtx, targets: EntitySelector) -> anyhow::Result<O
     )
 }
 
-#[command(usage = "kick <targets> <reason>")]
+#[command(usage = "kick <targets> <reason>", permission = KICK)]
 pub fn kick_2(
     ctx: &mut CommandCtx,
     targets: EntitySelector,
@@ -338,29 +353,20 @@ fn kick_players(
             .disconnect_and_log(*entity, &mut ctx.world, &reason, "player kicked");
 
         // Send confirmation message
-        // TODO Server ops should also see the message
-        if let Some(mut sender_message_receiver) =
-            ctx.world.try_get_mut::<MessageReceiver>(ctx.sender)
-        {
-            let kick_confirm = Text::from(TextValue::translate_with(
-                "commands.kick.success",
-                vec![Text::from(name), reason.clone()],
-            ));
-            sender_message_receiver.send(kick_confirm);
-        }
+        let kick_confirm = Text::from(TextValue::translate_with(
+            "commands.kick.success",
+            vec![Text::from(name), reason.clone()],
+        ));
+        send_feedback(ctx, kick_confirm);
     }
     Ok(None)
 }
 
-#[command(usage = "stop")]
+#[command(usage = "stop", permission = STOP)]
 pub fn stop(ctx: &mut CommandCtx) -> anyhow::Result<Option<String>> {
     // Confirmation message
-    // TODO Server ops should also see the message
-    if let Some(mut sender_message_receiver) = ctx.world.try_get_mut::<MessageReceiver>(ctx.sender)
-    {
-        let text = Text::from(TextValue::translate("commands.stop.stopping"));
-        sender_message_receiver.send(text);
-    }
+    let text = Text::from(TextValue::translate("commands.stop.stopping"));
+    send_feedback(ctx, text);
 
     ctx.game
         .resources
@@ -385,7 +391,7 @@ pub enum ClearError {
     NoEntities,
 }
 
-#[command(usage = "clear")]
+#[command(usage = "clear", permission = CLEAR)]
 pub fn clear_1(ctx: &mut CommandCtx) -> anyhow::Result<Option<String>> {
     if ctx.world.try_get::<Player>(ctx.sender).is_some() {
         // Go through the player's inventory and set all the slots to no items.
@@ -410,7 +416,7 @@ pub fn clear_1(ctx: &mut CommandCtx) -> anyhow::Result<Option<String>> {
     }
 }
 
-#[command(usage = "clear <targets>")]
+#[command(usage = "clear <targets>", permission = CLEAR)]
 pub fn clear_2(ctx: &mut CommandCtx, targets: EntitySelector) -> anyhow::Result<Option<String>> {
     let mut players = true;
     for entity in &targets.entities {
@@ -440,7 +446,7 @@ pub fn clear_2(ctx: &mut CommandCtx, targets: EntitySelector) -> anyhow::Result<
     }
 }
 
-#[command(usage = "clear <targets> <item>")]
+#[command(usage = "clear <targets> <item>", permission = CLEAR)]
 pub fn clear_3(
     ctx: &mut CommandCtx,
     targets: EntitySelector,
@@ -474,7 +480,7 @@ pub fn clear_3(
     }
 }
 
-#[command(usage = "clear <targets> <item> <maxcount>")]
+#[command(usage = "clear <targets> <item> <maxcount>", permission = CLEAR)]
 pub fn clear_4(
     ctx: &mut CommandCtx,
     targets: EntitySelector,
@@ -568,7 +574,7 @@ fn clear_items(
     }
 }
 
-#[command(usage = "seed")]
+#[command(usage = "seed", permission = SEED)]
 pub fn seed(ctx: &mut CommandCtx) -> anyhow::Result<Option<String>> {
     if let Some(mut message_receiver) = ctx.world.try_get_mut::<MessageReceiver>(ctx.sender) {
         message_receiver.send(
@@ -590,40 +596,74 @@ pub enum BanError {
     NotPlayer,
     #[error("Already banned")]
     NoTargets,
+    #[error("That player does not exist")]
+    UnknownPlayer,
+    #[error("Ban duration is too long")]
+    DurationTooLong,
 }
 
-#[command(usage = "ban <targets> <reason>")]
+#[command(usage = "ban <targets> <reason>", permission = BAN)]
 pub fn ban_withreason(
     ctx: &mut CommandCtx,
-    targets: EntitySelector,
+    targets: GameProfileArgument,
     reason: TextArgument,
 ) -> anyhow::Result<Option<String>> {
-    ban_players(ctx, targets, reason.0, false)
+    ban_profiles(ctx, targets, reason.0, None)
 }
 
-#[command(usage = "ban <targets>")]
+#[command(usage = "ban <targets>", permission = BAN)]
 pub fn ban_noreason(
     ctx: &mut CommandCtx,
-    targets: EntitySelector,
+    targets: GameProfileArgument,
+) -> anyhow::Result<Option<String>> {
+    ban_profiles(ctx, targets, "Banned by an operator.".to_owned(), None)
+}
+
+#[command(usage = "tempban <targets> <duration> <reason>", permission = BAN)]
+pub fn tempban_withreason(
+    ctx: &mut CommandCtx,
+    targets: GameProfileArgument,
+    duration: DurationArgument,
+    reason: TextArgument,
 ) -> anyhow::Result<Option<String>> {
-    ban_players(ctx, targets, "Banned by an operator.".to_owned(), false)
+    let expires_after = SystemTime::now()
+        .checked_add(duration.0)
+        .ok_or(BanError::DurationTooLong)?;
+    ban_profiles(ctx, targets, reason.0, Some(expires_after))
 }
 
-#[command(usage = "ban-ip <targets> <reason>")]
+#[command(usage = "tempban <targets> <duration>", permission = BAN)]
+pub fn tempban_noreason(
+    ctx: &mut CommandCtx,
+    targets: GameProfileArgument,
+    duration: DurationArgument,
+) -> anyhow::Result<Option<String>> {
+    let expires_after = SystemTime::now()
+        .checked_add(duration.0)
+        .ok_or(BanError::DurationTooLong)?;
+    ban_profiles(
+        ctx,
+        targets,
+        "Banned by an operator.".to_owned(),
+        Some(expires_after),
+    )
+}
+
+#[command(usage = "ban-ip <targets> <reason>", permission = BAN_IP)]
 pub fn banip_withreason(
     ctx: &mut CommandCtx,
     targets: EntitySelector,
     reason: TextArgument,
 ) -> anyhow::Result<Option<String>> {
-    ban_players(ctx, targets, reason.0, true)
+    ban_player_ips(ctx, targets, reason.0)
 }
 
-#[command(usage = "ban-ip <targets>")]
+#[command(usage = "ban-ip <targets>", permission = BAN_IP)]
 pub fn banip_noreason(
     ctx: &mut CommandCtx,
     targets: EntitySelector,
 ) -> anyhow::Result<Option<String>> {
-    ban_players(ctx, targets, "Banned by an operator.".to_owned(), true)
+    ban_player_ips(ctx, targets, "Banned by an operator.".to_owned())
 }
 
 #[derive(Debug, Error)]
@@ -632,7 +672,7 @@ pub enum BanIpError {
     InvalidIp,
 }
 
-#[command(usage = "ban-ip <ip> <reason>")]
+#[command(usage = "ban-ip <ip> <reason>", permission = BAN_IP)]
 pub fn banip_withreason_ip(
     ctx: &mut CommandCtx,
     ip: String,
@@ -641,7 +681,7 @@ pub fn banip_withreason_ip(
     ban_ip(ctx, ip, reason.0)
 }
 
-#[command(usage = "ban-ip <ip>")]
+#[command(usage = "ban-ip <ip>", permission = BAN_IP)]
 pub fn banip_noreason_ip(ctx: &mut CommandCtx, ip: String) -> anyhow::Result<Option<String>> {
     ban_ip(ctx, ip, "IP Banned by an operator.".to_string())
 }
@@ -657,6 +697,7 @@ pub fn ban_ip(ctx: &mut CommandCtx, ip: String, reason: String) -> anyhow::Resul
             ip,
             Ban {
                 reason: reason.clone(),
+                name: None,
                 expires_after: None,
             },
         );
@@ -688,11 +729,75 @@ pub fn ban_ip(ctx: &mut CommandCtx, ip: String, reason: String) -> anyhow::Resul
     Ok(None)
 }
 
-pub fn ban_players(
+/// Bans players by UUID until `expires_after`, or forever
+/// if it is `None`. Players who are online are disconnected.
+fn ban_profiles(
+    ctx: &mut CommandCtx,
+    targets: GameProfileArgument,
+    reason: String,
+    expires_after: Option<SystemTime>,
+) -> anyhow::Result<Option<String>> {
+    let profiles = match targets {
+        GameProfileArgument::Entities(entities) => {
+            if entities.is_empty() {
+                return Err(BanError::NoTargets.into());
+            }
+
+            let mut profiles = Vec::with_capacity(entities.len());
+            for entity in entities {
+                if !ctx.world.has::<Player>(entity) {
+                    return Err(BanError::NotPlayer.into());
+                }
+                let uuid = *ctx.world.get::<Uuid>(entity);
+                let name = ctx.world.get::<Name>(entity).0.clone();
+                profiles.push((uuid, name, Some(entity)));
+            }
+            profiles
+        }
+        GameProfileArgument::Name(name) => {
+            let uuid = name_to_uuid(ctx, &name).ok_or(BanError::UnknownPlayer)?;
+            vec![(uuid, name, None)]
+        }
+    };
+
+    for (uuid, name, entity) in profiles {
+        {
+            let bi_lock = ctx.game.resources.get::<WrappedBanInfo>();
+            let mut ban_info = bi_lock.write().unwrap();
+
+            ban_info.uuid_bans.insert(
+                uuid.to_hyphenated_ref().to_string(),
+                Ban {
+                    reason: reason.clone(),
+                    name: Some(name.clone()),
+                    expires_after,
+                },
+            );
+        }
+
+        send_feedback(
+            ctx,
+            Text::translate_with("commands.ban.success", vec![name, reason.clone()]),
+        );
+
+        if let Some(entity) = entity {
+            ctx.game.disconnect_and_log(
+                entity,
+                &mut ctx.world,
+                &Text::from(reason.clone()),
+                "Banned by an operator.",
+            );
+        }
+    }
+
+    Ok(None)
+}
+
+/// Bans the IP addresses of players, disconnecting them.
+fn ban_player_ips(
     ctx: &mut CommandCtx,
     targets: EntitySelector,
     reason: String,
-    by_ip: bool,
 ) -> anyhow::Result<Option<String>> {
     if targets.entities.is_empty() {
         return Err(BanError::NoTargets.into());
@@ -709,27 +814,16 @@ pub fn ban_players(
             let bi_lock = ctx.game.resources.get::<WrappedBanInfo>();
             let mut ban_info = bi_lock.write().unwrap();
 
-            if by_ip {
-                let ip = ctx.world.try_get::<SocketAddr>(*entity).unwrap();
+            let ip = ctx.world.try_get::<SocketAddr>(*entity).unwrap();
 
-                ban_info.ip_bans.insert(
-                    ip.ip(),
-                    Ban {
-                        reason: reason.clone(),
-                        expires_after: None,
-                    },
-                );
-            } else {
-                let uuid = ctx.world.try_get::<Uuid>(*entity).unwrap();
-
-                ban_info.uuid_bans.insert(
-                    uuid.to_hyphenated_ref().to_string(),
-                    Ban {
-                        reason: reason.clone(),
-                        expires_after: None,
-                    },
-                );
-            }
+            ban_info.ip_bans.insert(
+                ip.ip(),
+                Ban {
+                    reason: reason.clone(),
+                    name: None,
+                    expires_after: None,
+                },
+            );
         }
 
         let name = ctx.world.try_get::<Name>(*entity).unwrap().0.clone();
@@ -754,25 +848,76 @@ pub fn ban_players(
     Ok(None)
 }
 
+#[command(usage = "banlist", permission = BANLIST)]
+pub fn banlist_all(ctx: &mut CommandCtx) -> anyhow::Result<Option<String>> {
+    banlist(ctx, true, true)
+}
+
+#[command(usage = "banlist players", permission = BANLIST)]
+pub fn banlist_players(ctx: &mut CommandCtx) -> anyhow::Result<Option<String>> {
+    banlist(ctx, true, false)
+}
+
+#[command(usage = "banlist ips", permission = BANLIST)]
+pub fn banlist_ips(ctx: &mut CommandCtx) -> anyhow::Result<Option<String>> {
+    banlist(ctx, false, true)
+}
+
+/// Lists the bans which have not expired,
+/// optionally including player and IP bans.
+fn banlist(ctx: &mut CommandCtx, players: bool, ips: bool) -> anyhow::Result<Option<String>> {
+    let lock = ctx.game.resources.get::<WrappedBanInfo>();
+    let ban_info = lock.read().unwrap();
+    let now = SystemTime::now();
+
+    let player_bans = ban_info
+        .uuid_bans
+        .iter()
+        .filter(|_| players)
+        .map(|(uuid, ban)| (ban.name.clone().unwrap_or_else(|| uuid.clone()), ban));
+    let ip_bans = ban_info
+        .ip_bans
+        .iter()
+        .filter(|_| ips)
+        .map(|(ip, ban)| (ip.to_string(), ban));
+
+    let mut entries: Vec<String> = player_bans
+        .chain(ip_bans)
+        .filter_map(|(target, ban)| match ban.expires_after {
+            None => Some(format!("{} was banned: {}", target, ban.reason)),
+            Some(expires_after) => {
+                let remaining = expires_after.duration_since(now).ok()?;
+                Some(format!(
+                    "{} was banned: {} (expires in {})",
+                    target,
+                    ban.reason,
+                    format_duration(remaining)
+                ))
+            }
+        })
+        .collect();
+
+    if entries.is_empty() {
+        return Ok(Some(String::from("There are no bans")));
+    }
+
+    entries.sort();
+    Ok(Some(format!(
+        "There are {} bans:\n{}",
+        entries.len(),
+        entries.join("\n")
+    )))
+}
+
 #[derive(Debug, Error)]
 pub enum PardonError {
     #[error("Couldn't find that players UUID, Have they changed name?")]
     NotPlayer,
 }
 
-#[command(usage = "pardon <name>")]
+#[command(usage = "pardon <name>", permission = PARDON)]
 pub fn pardon(ctx: &mut CommandCtx, name: TextArgument) -> anyhow::Result<Option<String>> {
-    // Get UUID from name
-    let online_mode = ctx.game.shared.config.server.online_mode;
-    let uuid = if online_mode {
-        Runtime::new()
-            .unwrap()
-            .block_on(name_to_uuid_online(&name.0))
-    } else {
-        Some(name_to_uuid_offline(&name.0))
-    };
-
-    let uuid = match uuid {
+    let uuid = match name_to_uuid(ctx, &name.0) {
         Some(uuid) => uuid,
         None => return Err(PardonError::NotPlayer.into()),
     };
@@ -803,7 +948,7 @@ pub enum PardonIpError {
     NotIp,
 }
 
-#[command(usage = "pardon-ip <ip>")]
+#[command(usage = "pardon-ip <ip>", permission = PARDON_IP)]
 pub fn pardonip(ctx: &mut CommandCtx, ip: String) -> anyhow::Result<Option<String>> {
     // Try to parse ip
     let addr = IpAddr::from_str(&ip).map_err(|_| PardonIpError::NotIp)?;
@@ -825,3 +970,315 @@ pub fn pardonip(ctx: &mut CommandCtx, ip: String) -> anyhow::Result<Option<Strin
 
     Ok(None)
 }
+
+#[derive(Debug, Error)]
+pub enum OpError {
+    #[error(
+        "Only players may be affected by this command, but the provided selector includes entities"
+    )]
+    NoEntities,
+    #[error("Nothing changed. The player already is an operator")]
+    AlreadyOp,
+    #[error("Nothing changed. The player is not an operator")]
+    NotOp,
+    #[error("Failed to save the operator list: {0}")]
+    Save(std::io::Error),
+}
+
+#[command(usage = "op <targets>", permission = OP)]
+pub fn op(ctx: &mut CommandCtx, targets: EntitySelector) -> anyhow::Result<Option<String>> {
+    set_op_level(ctx, &targets, MAX_OP_LEVEL)
+}
+
+#[command(usage = "deop <targets>", permission = DEOP)]
+pub fn deop(ctx: &mut CommandCtx, targets: EntitySelector) -> anyhow::Result<Option<String>> {
+    set_op_level(ctx, &targets, 0)
+}
+
+/// Sets the operator level of players, updating the op list
+/// and the players' `Permissions`. A level of 0 removes
+/// them from the op list.
+fn set_op_level(
+    ctx: &mut CommandCtx,
+    targets: &EntitySelector,
+    level: u8,
+) -> anyhow::Result<Option<String>> {
+    for entity in &targets.entities {
+        if ctx.world.try_get::<Player>(*entity).is_none() {
+            return Err(OpError::NoEntities.into());
+        }
+    }
+
+    let mut changed = false;
+    for entity in &targets.entities {
+        let uuid = *ctx.world.get::<Uuid>(*entity);
+        let name = ctx.world.get::<Name>(*entity).0.clone();
+
+        {
+            let lock = ctx.game.resources.get::<WrappedOpList>();
+            let mut ops = lock.write().unwrap();
+            if ops.get(uuid).is_some() == (level > 0) {
+                continue;
+            }
+
+            if level > 0 {
+                ops.add(Op {
+                    uuid,
+                    name: name.clone(),
+                    level,
+                    bypasses_player_limit: false,
+                });
+            } else {
+                ops.remove(uuid);
+            }
+        }
+        changed = true;
+
+        if let Some(mut permissions) = ctx.world.try_get_mut::<Permissions>(*entity) {
+            permissions.level = level;
+        }
+        ctx.game
+            .resources
+            .get::<CommandState>()
+            .send_permissions(&ctx.world, *entity);
+
+        let key = if level > 0 {
+            "commands.op.success"
+        } else {
+            "commands.deop.success"
+        };
+        send_feedback(ctx, Text::translate_with(key, vec![name]));
+    }
+
+    if changed {
+        // Saved right away, so that changes are kept
+        // even if the server does not shut down cleanly.
+        let ops = ctx
+            .game
+            .resources
+            .get::<WrappedOpList>()
+            .read()
+            .unwrap()
+            .save();
+        std::fs::write("ops.json", ops).map_err(OpError::Save)?;
+        Ok(None)
+    } else if level > 0 {
+        Err(OpError::AlreadyOp.into())
+    } else {
+        Err(OpError::NotOp.into())
+    }
+}
+
+/// Sends command feedback to the sender, and
+/// notifies all other operators (including the console).
+fn send_feedback(ctx: &mut CommandCtx, message: Text) {
+    if let Some(mut receiver) = ctx.world.try_get_mut::<MessageReceiver>(ctx.sender) {
+        receiver.send(message.clone());
+    }
+
+    let sender_name = ctx
+        .world
+        .try_get::<Name>(ctx.sender)
+        .map_or_else(|| String::from("Server"), |name| name.0.clone());
+    let notification =
+        Text::translate_with("chat.type.admin", vec![Text::from(sender_name), message])
+            .gray()
+            .italic();
+
+    let ops: Vec<Entity> = <Read<Permissions>>::query()
+        .iter_entities(ctx.world.inner())
+        .filter(|(entity, permissions)| permissions.level > 0 && *entity != ctx.sender)
+        .map(|(entity, _)| entity)
+        .collect();
+    for op in ops {
+        if let Some(mut receiver) = ctx.world.try_get_mut::<MessageReceiver>(op) {
+            receiver.send(notification.clone());
+        }
+    }
+}
+
+/// Returns the UUID of the player with the given name,
+/// looking it up from Mojang in online mode if they are not online.
+fn name_to_uuid(ctx: &CommandCtx, name: &str) -> Option<Uuid> {
+    if let Some(player) = find_player_by_name(&ctx.world, name) {
+        if let Some(uuid) = ctx.world.try_get::<Uuid>(player) {
+            return Some(*uuid);
+        }
+    }
+
+    if ctx.game.shared.config.server.online_mode {
+        Runtime::new().unwrap().block_on(name_to_uuid_online(name))
+    } else {
+        Some(name_to_uuid_offline(name))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum WhitelistError {
+    #[error("That player does not exist")]
+    UnknownPlayer,
+    #[error("Player is already whitelisted")]
+    AlreadyWhitelisted,
+    #[error("Player is not whitelisted")]
+    NotWhitelisted,
+    #[error("Whitelist is already turned on")]
+    AlreadyOn,
+    #[error("Whitelist is already turned off")]
+    AlreadyOff,
+    #[error("Failed to reload the whitelist: {0}")]
+    Reload(anyhow::Error),
+}
+
+#[command(usage = "whitelist add <name>", permission = WHITELIST)]
+pub fn whitelist_add(ctx: &mut CommandCtx, name: String) -> anyhow::Result<Option<String>> {
+    let uuid = name_to_uuid(ctx, &name).ok_or(WhitelistError::UnknownPlayer)?;
+
+    let added = ctx
+        .game
+        .resources
+        .get::<WrappedWhitelist>()
+        .write()
+        .unwrap()
+        .add(WhitelistEntry {
+            uuid,
+            name: name.clone(),
+        });
+    if !added {
+        return Err(WhitelistError::AlreadyWhitelisted.into());
+    }
+
+    send_feedback(ctx, Text::from(format!("Added {} to the whitelist", name)));
+    Ok(None)
+}
+
+#[command(usage = "whitelist remove <name>", permission = WHITELIST)]
+pub fn whitelist_remove(ctx: &mut CommandCtx, name: String) -> anyhow::Result<Option<String>> {
+    let removed = {
+        let lock = ctx.game.resources.get::<WrappedWhitelist>();
+        let mut whitelist = lock.write().unwrap();
+        let uuid = whitelist
+            .entries
+            .iter()
+            .find(|entry| entry.name.eq_ignore_ascii_case(&name))
+            .map(|entry| entry.uuid);
+        uuid.and_then(|uuid| whitelist.remove(uuid))
+    };
+
+    match removed {
+        Some(entry) => {
+            send_feedback(
+                ctx,
+                Text::from(format!("Removed {} from the whitelist", entry.name)),
+            );
+            Ok(None)
+        }
+        None => Err(WhitelistError::NotWhitelisted.into()),
+    }
+}
+
+#[command(usage = "whitelist list", permission = WHITELIST)]
+pub fn whitelist_list(ctx: &mut CommandCtx) -> anyhow::Result<Option<String>> {
+    let lock = ctx.game.resources.get::<WrappedWhitelist>();
+    let whitelist = lock.read().unwrap();
+
+    if whitelist.entries.is_empty() {
+        return Ok(Some(String::from("There are no whitelisted players")));
+    }
+
+    let names: Vec<&str> = whitelist
+        .entries
+        .iter()
+        .map(|entry| entry.name.as_str())
+        .collect();
+    Ok(Some(format!(
+        "There are {} whitelisted players: {}",
+        names.len(),
+        names.join(", ")
+    )))
+}
+
+#[command(usage = "whitelist on", permission = WHITELIST)]
+pub fn whitelist_on(ctx: &mut CommandCtx) -> anyhow::Result<Option<String>> {
+    set_whitelist_enabled(ctx, true)
+}
+
+#[command(usage = "whitelist off", permission = WHITELIST)]
+pub fn whitelist_off(ctx: &mut CommandCtx) -> anyhow::Result<Option<String>> {
+    set_whitelist_enabled(ctx, false)
+}
+
+/// Turns the whitelist on or off. This only lasts until
+/// the server restarts; the `whitelist` option in the
+/// config determines whether it is enabled on startup.
+fn set_whitelist_enabled(ctx: &mut CommandCtx, enabled: bool) -> anyhow::Result<Option<String>> {
+    {
+        let lock = ctx.game.resources.get::<WrappedWhitelist>();
+        let mut whitelist = lock.write().unwrap();
+        if whitelist.enabled == enabled {
+            return Err(if enabled {
+                WhitelistError::AlreadyOn
+            } else {
+                WhitelistError::AlreadyOff
+            }
+            .into());
+        }
+        whitelist.enabled = enabled;
+    }
+
+    let message = if enabled {
+        "Whitelist is now turned on"
+    } else {
+        "Whitelist is now turned off"
+    };
+    send_feedback(ctx, Text::from(message));
+
+    kick_unlisted_players(ctx);
+    Ok(None)
+}
+
+#[command(usage = "whitelist reload", permission = WHITELIST)]
+pub fn whitelist_reload(ctx: &mut CommandCtx) -> anyhow::Result<Option<String>> {
+    let reloaded = match std::fs::read_to_string("whitelist.json") {
+        Ok(s) => Whitelist::load(&s),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Whitelist::default()),
+        Err(e) => Err(e.into()),
+    }
+    .map_err(WhitelistError::Reload)?;
+
+    {
+        let lock = ctx.game.resources.get::<WrappedWhitelist>();
+        let mut whitelist = lock.write().unwrap();
+        whitelist.entries = reloaded.entries;
+    }
+
+    send_feedback(ctx, Text::from("Reloaded the whitelist"));
+
+    kick_unlisted_players(ctx);
+    Ok(None)
+}
+
+/// Disconnects the online players who are neither
+/// whitelisted nor operators, if the whitelist is enabled.
+fn kick_unlisted_players(ctx: &mut CommandCtx) {
+    let unlisted: Vec<Entity> = {
+        let whitelist_lock = ctx.game.resources.get::<WrappedWhitelist>();
+        let whitelist = whitelist_lock.read().unwrap();
+        let ops_lock = ctx.game.resources.get::<WrappedOpList>();
+        let ops = ops_lock.read().unwrap();
+
+        <Read<Uuid>>::query()
+            .filter(component::<Player>())
+            .iter_entities(ctx.world.inner())
+            .filter(|(_, uuid)| !whitelist.allows(**uuid) && ops.get(**uuid).is_none())
+            .map(|(entity, _)| entity)
+            .collect()
+    };
+
+    let reason = Text::from(TextValue::translate(
+        "multiplayer.disconnect.not_whitelisted",
+    ));
+    for player in unlisted {
+        ctx.game
+            .disconnect_and_log(player, &mut ctx.world, &reason, "not whitelisted");
+    }
+}
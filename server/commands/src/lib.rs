@@ -4,17 +4,40 @@
 //!
 //! Also implements vanilla commands not defined by plugins.
 
-mod arguments;
+extern crate self as feather_server_commands;
+
+pub mod arguments;
+mod graph;
 mod impls;
+pub mod permissions;
+
+pub use feather_codegen::command;
+pub use feather_server_types::Permission;
+pub use graph::{ArgumentSpec, DeclaredArgument, DeclaredCommand};
+pub use lieutenant;
 
+use arguments::TextArgument;
+use feather_core::network::packets::{DeclareCommands, EntityStatus};
+use feather_core::text::ansi::to_plain;
 use feather_core::text::{Text, TextComponentBuilder};
-use feather_server_types::{Game, MessageReceiver};
-use fecs::{Entity, World};
+use feather_server_types::{Game, MessageReceiver, Network, NetworkId, Permissions, MAX_OP_LEVEL};
+use fecs::{Entity, EntityBuilder, World};
+use graph::CommandGraph;
 use impls::*;
 use lieutenant::CommandDispatcher;
+use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 
+/// Entity status which sets a player's operator
+/// level to 0; levels 1-4 follow consecutively.
+const OP_LEVEL_0_STATUS: i8 = 24;
+
+/// Permission checked for names which are not registered
+/// commands, such as `minecraft:tp` or `TP`, so that
+/// only full operators may run whatever they resolve to.
+const UNKNOWN_COMMAND: Permission = Permission::new("*", MAX_OP_LEVEL);
+
 /// Dumb workaround for a certain lifetime issue.
 ///
 /// `CommandCtx` stores references to `Game`, and it
@@ -71,17 +94,46 @@ impl lieutenant::Context for CommandCtx {
     type Ok = Option<String>;
 }
 
+/// Registers built-in commands.
 macro_rules! commands {
-    ($dispatcher:ident : $($command:expr,)*) => {
+    ($state:ident : $($command:ident,)*) => {
         $(
-            $dispatcher.register($command).unwrap();
+            $state
+                .register::<$command>()
+                .expect("failed to register built-in command");
         )*
     }
 }
 
+/// Returns the names (including aliases) of the command with the given usage.
+fn command_names(usage: &str) -> impl Iterator<Item = &str> {
+    usage.split_whitespace().next().unwrap_or("").split('|')
+}
+
+/// Handler of a raw command, called with the text following the command name.
+pub type RawCommandHandler =
+    Box<dyn Fn(&mut CommandCtx, &str) -> anyhow::Result<Option<String>> + Send + Sync>;
+
+/// A command registered in a `CommandState`, recorded
+/// so that it can be added to another state by `merge`.
+enum Registration {
+    Declared {
+        check: fn(&CommandState) -> anyhow::Result<()>,
+        register: fn(&mut CommandState) -> anyhow::Result<()>,
+    },
+    Raw(String),
+}
+
 /// State storing all registered commands.
 pub struct CommandState {
     dispatcher: Arc<CommandDispatcher<CommandCtx>>,
+    graph: CommandGraph,
+    /// Commands registered with `register_raw`, keyed by name.
+    raw_commands: HashMap<String, RawCommandHandler>,
+    /// The permission required to run each command, keyed by name.
+    permissions: HashMap<String, Permission>,
+    /// The registered commands, in the order they were registered.
+    registrations: Vec<Registration>,
 }
 
 impl Default for CommandState {
@@ -93,10 +145,10 @@ impl Default for CommandState {
 impl CommandState {
     /// Initializes the command state.
     pub fn new() -> Self {
-        let mut dispatcher = CommandDispatcher::<CommandCtx>::new();
+        let mut state = Self::empty();
 
         commands! {
-            dispatcher:
+            state:
                 tp_1,
                 tp_2,
                 tp_3,
@@ -123,29 +175,294 @@ impl CommandState {
 
                 ban_withreason,
                 ban_noreason,
+                tempban_withreason,
+                tempban_noreason,
                 banip_withreason,
                 banip_noreason,
                 banip_withreason_ip,
                 banip_noreason_ip,
 
+                banlist_all,
+                banlist_players,
+                banlist_ips,
+
                 pardon,
                 pardonip,
+
+                op,
+                deop,
+
+                whitelist_add,
+                whitelist_remove,
+                whitelist_list,
+                whitelist_on,
+                whitelist_off,
+                whitelist_reload,
         }
 
+        state
+    }
+
+    /// Creates a command state without the built-in commands,
+    /// in which a plugin registers its commands while it is
+    /// enabled. They are then added with `merge`.
+    pub fn empty() -> Self {
         Self {
-            dispatcher: Arc::new(dispatcher),
+            dispatcher: Arc::new(CommandDispatcher::new()),
+            graph: CommandGraph::new(),
+            raw_commands: HashMap::new(),
+            permissions: HashMap::new(),
+            registrations: Vec::new(),
+        }
+    }
+
+    /// Registers a command defined with `#[command]`, such as by a plugin.
+    ///
+    /// Commands sharing a name must require the same permission, so
+    /// that a plugin cannot lower the permission of an existing command
+    /// by adding a subcommand to it. Fails if they do not.
+    pub fn register<C>(&mut self) -> anyhow::Result<()>
+    where
+        C: DeclaredCommand,
+    {
+        self.check::<C>()?;
+
+        C::register(Arc::get_mut(&mut self.dispatcher).expect("command dispatcher is shared"))
+            .map_err(|e| anyhow::anyhow!("failed to register command `{}`: {}", C::USAGE, e))?;
+
+        self.graph.add(C::USAGE, &C::arguments());
+        for name in command_names(C::USAGE) {
+            self.permissions.insert(name.to_owned(), C::PERMISSION);
+        }
+        self.registrations.push(Registration::Declared {
+            check: Self::check::<C>,
+            register: Self::register::<C>,
+        });
+
+        Ok(())
+    }
+
+    /// Checks that `C` can be registered, see `register`.
+    pub fn check<C>(&self) -> anyhow::Result<()>
+    where
+        C: DeclaredCommand,
+    {
+        for name in command_names(C::USAGE) {
+            if self.raw_commands.contains_key(name) {
+                anyhow::bail!("command `{}` already exists", name);
+            }
+            match self.permissions.get(name) {
+                Some(permission) if *permission != C::PERMISSION => anyhow::bail!(
+                    "command `{}` already exists with permission `{}`",
+                    name,
+                    permission.node
+                ),
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Registers a command whose arguments are parsed by `handler`
+    /// rather than by the dispatcher, for commands only known at
+    /// runtime. The handler is called with the (possibly empty)
+    /// text following the command name.
+    ///
+    /// Fails if a command with the same name already exists.
+    pub fn register_raw(
+        &mut self,
+        name: &str,
+        permission: Permission,
+        handler: RawCommandHandler,
+    ) -> anyhow::Result<()> {
+        self.check_raw(name)?;
+
+        self.graph.add(name, &[]);
+        self.graph.add(
+            &format!("{} <arguments>", name),
+            &[ArgumentSpec::of::<TextArgument>()],
+        );
+        self.permissions.insert(name.to_owned(), permission);
+        self.raw_commands.insert(name.to_owned(), handler);
+        self.registrations.push(Registration::Raw(name.to_owned()));
+
+        Ok(())
+    }
+
+    /// Checks that a raw command named `name` can be registered.
+    pub fn check_raw(&self, name: &str) -> anyhow::Result<()> {
+        if name.is_empty() || name.contains(char::is_whitespace) || name.contains('|') {
+            anyhow::bail!("invalid command name `{}`", name);
+        }
+        if self.permissions.contains_key(name) || self.raw_commands.contains_key(name) {
+            anyhow::bail!("command `{}` already exists", name);
+        }
+
+        Ok(())
+    }
+
+    /// Adds the commands registered in `other`, typically created
+    /// with `empty`. Either all of them are added, or none are
+    /// and an error is returned if one of them collides with
+    /// a command in this state.
+    pub fn merge(&mut self, mut other: CommandState) -> anyhow::Result<()> {
+        for registration in &other.registrations {
+            match registration {
+                Registration::Declared { check, .. } => check(self)?,
+                Registration::Raw(name) => self.check_raw(name)?,
+            }
+        }
+
+        for registration in std::mem::take(&mut other.registrations) {
+            match registration {
+                Registration::Declared { register, .. } => register(self)?,
+                Registration::Raw(name) => {
+                    let handler = other.raw_commands.remove(&name).unwrap();
+                    self.register_raw(&name, other.permissions[&name], handler)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the permission required to run the command
+    /// with the given name, or `None` if no such command exists.
+    pub fn permission(&self, name: &str) -> Option<Permission> {
+        self.permissions.get(name).copied()
+    }
+
+    /// Determines whether `sender` may run the command with the given name.
+    ///
+    /// Names which are not registered commands require `UNKNOWN_COMMAND`.
+    pub fn is_permitted(&self, world: &World, sender: Entity, name: &str) -> bool {
+        let permission = self.permission(name).unwrap_or(UNKNOWN_COMMAND);
+
+        match world.try_get::<Permissions>(sender) {
+            Some(permissions) => permissions.has(permission),
+            None => Permissions::default().has(permission),
+        }
+    }
+
+    /// Returns the Declare Commands packet for `sender`,
+    /// containing the commands they are permitted to run.
+    pub fn declare_commands(&self, world: &World, sender: Entity) -> DeclareCommands {
+        self.graph
+            .to_packet(|name| self.is_permitted(world, sender, name))
+    }
+
+    /// Sends a player their operator level and the commands
+    /// they may run. Should be called on join and whenever
+    /// the player's `Permissions` change.
+    pub fn send_permissions(&self, world: &World, player: Entity) {
+        let network = match world.try_get::<Network>(player) {
+            Some(network) => network,
+            None => return,
+        };
+
+        if let (Some(id), Some(permissions)) = (
+            world.try_get::<NetworkId>(player),
+            world.try_get::<Permissions>(player),
+        ) {
+            network.send(EntityStatus {
+                entity_id: id.0,
+                entity_status: OP_LEVEL_0_STATUS + permissions.level.min(MAX_OP_LEVEL) as i8,
+            });
+        }
+
+        network.send(self.declare_commands(world, player));
+    }
+
+    /// Returns tab completions for a partially typed command
+    /// (without the leading slash), along with the offset in
+    /// characters of the text the completions replace.
+    pub fn complete(
+        &self,
+        game: &mut Game,
+        world: &mut World,
+        sender: Entity,
+        command: &str,
+    ) -> (usize, Vec<String>) {
+        let ctx = CommandCtx {
+            game: LifetimelessMut(game),
+            world: LifetimelessMut(world),
+            sender,
+        };
+
+        let (start, matches) = self.graph.complete(&ctx, command, |name| {
+            self.is_permitted(&ctx.world, sender, name)
+        });
+
+        // Clients count offsets in characters, not bytes.
+        let start = command
+            .char_indices()
+            .take_while(|(index, _)| *index < start)
+            .count();
+        (start, matches)
+    }
+
+    /// Dispatches a command from a sender without an entity,
+    /// such as a remote console, with all permissions.
+    ///
+    /// Returns the messages sent to the sender as plain text,
+    /// one per line, or `None` if the command sent none.
+    pub fn dispatch_remote(
+        &self,
+        game: &mut Game,
+        world: &mut World,
+        command: &str,
+    ) -> Option<String> {
+        let sender = EntityBuilder::new()
+            .with(MessageReceiver::default())
+            .with(Permissions::with_level(MAX_OP_LEVEL))
+            .build()
+            .spawn_in(world);
+
+        self.dispatch(game, world, sender, command);
+
+        let output = world
+            .get_mut::<MessageReceiver>(sender)
+            .flush()
+            .map(|message| to_plain(&message))
+            .collect::<Vec<_>>();
+        world.despawn(sender);
+
+        if output.is_empty() {
+            None
+        } else {
+            Some(output.join("\n"))
         }
     }
 
     /// Dispatches a command.
+    ///
+    /// If `sender` lacks the permission for the command,
+    /// it is not run and `sender` is notified.
     pub fn dispatch(&self, game: &mut Game, world: &mut World, sender: Entity, command: &str) {
+        let name = command.split_whitespace().next().unwrap_or("");
+        if !self.is_permitted(world, sender, name) {
+            if let Some(mut receiver) = world.try_get_mut::<MessageReceiver>(sender) {
+                receiver.send(Text::from("You do not have permission to use this command.").red());
+            }
+            return;
+        }
+
         let mut ctx = CommandCtx {
             game: LifetimelessMut(game),
             world: LifetimelessMut(world),
             sender,
         };
 
-        match self.dispatcher.dispatch(&mut ctx, command) {
+        let result = match self.raw_commands.get(name) {
+            Some(handler) => {
+                let arguments = command.trim_start()[name.len()..].trim_start();
+                handler(&mut ctx, arguments).map_err(|e| vec![e])
+            }
+            None => self.dispatcher.dispatch(&mut ctx, command),
+        };
+
+        match result {
             Ok(ok) => {
                 if let Some(msg) = ok {
                     if let Some(mut receiver) = world.try_get_mut::<MessageReceiver>(sender) {
@@ -168,3 +485,101 @@ impl CommandState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::permissions::TELEPORT;
+
+    fn sender(world: &mut World, level: u8) -> Entity {
+        EntityBuilder::new()
+            .with(Permissions::with_level(level))
+            .build()
+            .spawn_in(world)
+    }
+
+    #[test]
+    fn test_permissions() {
+        let state = CommandState::new();
+        let mut world = World::new();
+        let player = sender(&mut world, 0);
+        let op = sender(&mut world, 2);
+
+        assert!(state.is_permitted(&world, player, "me"));
+        assert!(!state.is_permitted(&world, player, "tp"));
+        assert!(state.is_permitted(&world, op, "teleport"));
+        assert!(!state.is_permitted(&world, op, "stop"));
+    }
+
+    const HOME: Permission = Permission::new("myplugin.command.home", 0);
+
+    #[command(usage = "tp home", permission = HOME)]
+    fn tp_home(_ctx: &mut CommandCtx) -> anyhow::Result<Option<String>> {
+        Ok(None)
+    }
+
+    #[test]
+    fn test_subcommand_keeps_permission() {
+        let mut state = CommandState::new();
+        assert!(state.register::<tp_home>().is_err());
+        assert_eq!(state.permission("tp"), Some(TELEPORT));
+        assert_eq!(state.permission("teleport"), Some(TELEPORT));
+
+        let mut world = World::new();
+        let player = sender(&mut world, 0);
+        assert!(!state.is_permitted(&world, player, "tp"));
+    }
+
+    #[test]
+    fn test_raw_command_collision() {
+        let mut state = CommandState::new();
+        let handler: RawCommandHandler = Box::new(|_, _| Ok(None));
+        assert!(state.register_raw("tp", HOME, handler).is_err());
+        assert_eq!(state.permission("tp"), Some(TELEPORT));
+
+        let handler: RawCommandHandler = Box::new(|_, _| Ok(None));
+        state.register_raw("tp2", HOME, handler).unwrap();
+        assert_eq!(state.permission("tp2"), Some(HOME));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut state = CommandState::new();
+
+        let mut plugin = CommandState::empty();
+        let handler: RawCommandHandler = Box::new(|_, _| Ok(None));
+        plugin.register_raw("home", HOME, handler).unwrap();
+        assert_eq!(state.permission("home"), None);
+
+        state.merge(plugin).unwrap();
+        assert_eq!(state.permission("home"), Some(HOME));
+        assert_eq!(state.permission("tp"), Some(TELEPORT));
+    }
+
+    #[test]
+    fn test_merge_collision_adds_nothing() {
+        let mut state = CommandState::new();
+
+        let mut plugin = CommandState::empty();
+        let handler: RawCommandHandler = Box::new(|_, _| Ok(None));
+        plugin.register_raw("home", HOME, handler).unwrap();
+        plugin.register::<tp_home>().unwrap();
+
+        assert!(state.merge(plugin).is_err());
+        assert_eq!(state.permission("home"), None);
+        assert_eq!(state.permission("tp"), Some(TELEPORT));
+    }
+
+    #[test]
+    fn test_unknown_commands_need_max_level() {
+        let state = CommandState::new();
+        let mut world = World::new();
+        let op = sender(&mut world, 3);
+        let admin = sender(&mut world, MAX_OP_LEVEL);
+
+        for name in &["minecraft:tp", "TP", "unknown"] {
+            assert!(!state.is_permitted(&world, op, name));
+            assert!(state.is_permitted(&world, admin, name));
+        }
+    }
+}
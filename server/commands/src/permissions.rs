@@ -0,0 +1,22 @@
+//! Permission nodes of the built-in commands, granted
+//! by default at the same operator levels as in vanilla.
+
+use feather_server_types::Permission;
+
+pub const TELEPORT: Permission = Permission::new("minecraft.command.teleport", 2);
+pub const GAMEMODE: Permission = Permission::new("minecraft.command.gamemode", 2);
+pub const MSG: Permission = Permission::new("minecraft.command.msg", 0);
+pub const SAY: Permission = Permission::new("minecraft.command.say", 2);
+pub const ME: Permission = Permission::new("minecraft.command.me", 0);
+pub const KICK: Permission = Permission::new("minecraft.command.kick", 3);
+pub const STOP: Permission = Permission::new("minecraft.command.stop", 4);
+pub const CLEAR: Permission = Permission::new("minecraft.command.clear", 2);
+pub const SEED: Permission = Permission::new("minecraft.command.seed", 2);
+pub const BAN: Permission = Permission::new("minecraft.command.ban", 3);
+pub const BAN_IP: Permission = Permission::new("minecraft.command.ban-ip", 3);
+pub const BANLIST: Permission = Permission::new("minecraft.command.banlist", 3);
+pub const PARDON: Permission = Permission::new("minecraft.command.pardon", 3);
+pub const PARDON_IP: Permission = Permission::new("minecraft.command.pardon-ip", 3);
+pub const OP: Permission = Permission::new("minecraft.command.op", 3);
+pub const DEOP: Permission = Permission::new("minecraft.command.deop", 3);
+pub const WHITELIST: Permission = Permission::new("minecraft.command.whitelist", 3);
@@ -11,6 +11,7 @@ use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use tokio::fs::File;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use uuid::Uuid;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Config {
@@ -21,6 +22,12 @@ pub struct Config {
     pub log: Log,
     pub resource_pack: ResourcePack,
     pub world: World,
+    #[serde(default)]
+    pub query: Query,
+    #[serde(default)]
+    pub rcon: Rcon,
+    #[serde(default)]
+    pub plugins: Plugins,
 }
 
 impl Config {
@@ -65,6 +72,8 @@ pub struct IO {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Proxy {
     pub proxy_mode: ProxyMode,
+    #[serde(default)]
+    pub velocity_secret: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -76,6 +85,8 @@ pub struct Server {
     pub address: String,
     pub port: u16,
     pub default_gamemode: Gamemode,
+    #[serde(default)]
+    pub whitelist: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -104,6 +115,65 @@ pub struct World {
     pub seed: String,
     #[serde(with = "humantime_serde")]
     pub save_interval: Duration,
+    #[serde(default = "default_max_open_regions")]
+    pub max_open_regions: usize,
+    #[serde(default = "default_region_idle_timeout", with = "humantime_serde")]
+    pub region_idle_timeout: Duration,
+}
+
+fn default_max_open_regions() -> usize {
+    64
+}
+
+fn default_region_idle_timeout() -> Duration {
+    Duration::from_secs(5 * 60)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Query {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for Query {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 25565,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Rcon {
+    pub enabled: bool,
+    pub port: u16,
+    pub password: String,
+}
+
+impl Default for Rcon {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 25575,
+            password: String::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Plugins {
+    pub fuel: u64,
+    pub max_memory_mib: u32,
+}
+
+impl Default for Plugins {
+    fn default() -> Self {
+        Self {
+            fuel: 10_000_000,
+            max_memory_mib: 64,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -158,8 +228,147 @@ impl BanInfo {
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Ban {
-    pub expires_after: Option<SystemTime>,
     pub reason: String,
+    /// The name of the banned player when the ban was issued,
+    /// for display in `/banlist`. Not set for IP bans.
+    #[serde(default)]
+    pub name: Option<String>,
+    // TOML requires tables to follow plain values,
+    // so this must remain the last field.
+    pub expires_after: Option<SystemTime>,
+}
+
+/// The list of server operators, stored in `ops.json`
+/// in the same format as the vanilla server.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(transparent)]
+pub struct OpList {
+    pub ops: Vec<Op>,
+}
+
+impl OpList {
+    /// Loads the operators from the given string.
+    pub fn load(s: &str) -> anyhow::Result<OpList> {
+        serde_json::from_str(s).map_err(Into::into)
+    }
+
+    /// Loads the operators from the given file.
+    pub async fn load_from_file(f: &mut File) -> anyhow::Result<OpList> {
+        let mut s = String::new();
+        f.read_to_string(&mut s).await?;
+        Self::load(&s)
+    }
+
+    /// Saves the operators, writing its contents to the given string.
+    pub fn save(&self) -> String {
+        serde_json::to_string_pretty(self).expect("failed to serialize op list")
+    }
+
+    /// Saves the operators to the given file.
+    pub async fn save_to_file(&self, f: &mut File) -> anyhow::Result<()> {
+        let string = self.save();
+
+        f.write_all(string.as_bytes()).await.map_err(Into::into)
+    }
+
+    /// Returns the operator with the given UUID.
+    pub fn get(&self, uuid: Uuid) -> Option<&Op> {
+        self.ops.iter().find(|op| op.uuid == uuid)
+    }
+
+    /// Adds an operator, replacing any existing
+    /// entry with the same UUID.
+    pub fn add(&mut self, op: Op) {
+        self.remove(op.uuid);
+        self.ops.push(op);
+    }
+
+    /// Removes the operator with the given UUID, returning it.
+    pub fn remove(&mut self, uuid: Uuid) -> Option<Op> {
+        let index = self.ops.iter().position(|op| op.uuid == uuid)?;
+        Some(self.ops.remove(index))
+    }
+}
+
+/// The players allowed to join when the whitelist is enabled,
+/// stored in `whitelist.json` in the same format as the vanilla server.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(transparent)]
+pub struct Whitelist {
+    /// Whether the whitelist is enforced. Initialized
+    /// from the config; not stored in `whitelist.json`.
+    #[serde(skip)]
+    pub enabled: bool,
+    pub entries: Vec<WhitelistEntry>,
+}
+
+impl Whitelist {
+    /// Loads the whitelist from the given string.
+    pub fn load(s: &str) -> anyhow::Result<Whitelist> {
+        serde_json::from_str(s).map_err(Into::into)
+    }
+
+    /// Loads the whitelist from the given file.
+    pub async fn load_from_file(f: &mut File) -> anyhow::Result<Whitelist> {
+        let mut s = String::new();
+        f.read_to_string(&mut s).await?;
+        Self::load(&s)
+    }
+
+    /// Saves the whitelist, writing its contents to the given string.
+    pub fn save(&self) -> String {
+        serde_json::to_string_pretty(self).expect("failed to serialize whitelist")
+    }
+
+    /// Saves the whitelist to the given file.
+    pub async fn save_to_file(&self, f: &mut File) -> anyhow::Result<()> {
+        let string = self.save();
+
+        f.write_all(string.as_bytes()).await.map_err(Into::into)
+    }
+
+    /// Determines whether the player with the given UUID may join.
+    pub fn allows(&self, uuid: Uuid) -> bool {
+        !self.enabled || self.contains(uuid)
+    }
+
+    /// Determines whether the player with the given UUID is whitelisted.
+    pub fn contains(&self, uuid: Uuid) -> bool {
+        self.entries.iter().any(|entry| entry.uuid == uuid)
+    }
+
+    /// Adds a player, returning `false` if they were already whitelisted.
+    pub fn add(&mut self, entry: WhitelistEntry) -> bool {
+        if self.contains(entry.uuid) {
+            return false;
+        }
+
+        self.entries.push(entry);
+        true
+    }
+
+    /// Removes the player with the given UUID, returning their entry.
+    pub fn remove(&mut self, uuid: Uuid) -> Option<WhitelistEntry> {
+        let index = self.entries.iter().position(|entry| entry.uuid == uuid)?;
+        Some(self.entries.remove(index))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WhitelistEntry {
+    pub uuid: Uuid,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Op {
+    pub uuid: Uuid,
+    pub name: String,
+    /// The operator's permission level, from 1 to 4.
+    pub level: u8,
+    #[serde(default)]
+    pub bypasses_player_limit: bool,
 }
 
 #[cfg(test)]
@@ -181,6 +390,7 @@ mod tests {
         assert_eq!(server.view_distance, 6);
         assert_eq!(server.address, "0.0.0.0");
         assert_eq!(server.port, 25565);
+        assert_eq!(server.whitelist, false);
 
         let gameplay = &config.gameplay;
         assert_eq!(gameplay.animal_spawning, true);
@@ -200,8 +410,106 @@ mod tests {
         assert_eq!(world.generator, "default");
         assert_eq!(world.seed, "");
         assert_eq!(world.save_interval.as_millis(), 1000 * 60);
+        assert_eq!(world.max_open_regions, 64);
+        assert_eq!(world.region_idle_timeout.as_secs(), 5 * 60);
 
         let proxy = &config.proxy;
         assert_eq!(proxy.proxy_mode, ProxyMode::None);
+        assert_eq!(proxy.velocity_secret, "");
+
+        let query = &config.query;
+        assert_eq!(query.enabled, false);
+        assert_eq!(query.port, 25565);
+
+        let rcon = &config.rcon;
+        assert_eq!(rcon.enabled, false);
+        assert_eq!(rcon.port, 25575);
+        assert_eq!(rcon.password, "");
+
+        let plugins = &config.plugins;
+        assert_eq!(plugins.fuel, 10_000_000);
+        assert_eq!(plugins.max_memory_mib, 64);
+    }
+
+    #[test]
+    fn test_ban_info() {
+        let input = r#"
+            [ip_bans]
+
+            [uuid_bans.069a79f4-44e9-4726-a5be-fca90e38aaf5]
+            reason = "Griefing"
+        "#;
+
+        let mut ban_info = BanInfo::load(input).expect("invalid ban list");
+        let ban = &ban_info.uuid_bans["069a79f4-44e9-4726-a5be-fca90e38aaf5"];
+        assert_eq!(ban.reason, "Griefing");
+        assert_eq!(ban.name, None);
+        assert_eq!(ban.expires_after, None);
+
+        ban_info.uuid_bans.insert(
+            String::from("853c80ef-3c37-49fd-aa49-938b674adae6"),
+            Ban {
+                reason: String::from("Spamming"),
+                name: Some(String::from("jeb_")),
+                expires_after: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(60)),
+            },
+        );
+        assert_eq!(BanInfo::load(&ban_info.save()).unwrap(), ban_info);
+    }
+
+    #[test]
+    fn test_load_op_list() {
+        let input = r#"[
+            {
+                "uuid": "069a79f4-44e9-4726-a5be-fca90e38aaf5",
+                "name": "Notch",
+                "level": 4,
+                "bypassesPlayerLimit": false
+            }
+        ]"#;
+
+        let mut ops = OpList::load(input).expect("invalid op list");
+        let uuid = Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+        let op = ops.get(uuid).expect("missing op");
+        assert_eq!(op.name, "Notch");
+        assert_eq!(op.level, 4);
+
+        assert_eq!(OpList::load(&ops.save()).unwrap(), ops);
+
+        ops.add(Op {
+            uuid,
+            name: String::from("Notch"),
+            level: 2,
+            bypasses_player_limit: false,
+        });
+        assert_eq!(ops.ops.len(), 1);
+        assert_eq!(ops.get(uuid).unwrap().level, 2);
+
+        assert!(ops.remove(uuid).is_some());
+        assert!(ops.get(uuid).is_none());
+    }
+
+    #[test]
+    fn test_whitelist() {
+        let input = r#"[{"uuid": "069a79f4-44e9-4726-a5be-fca90e38aaf5", "name": "Notch"}]"#;
+
+        let mut whitelist = Whitelist::load(input).expect("invalid whitelist");
+        let notch = Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+        let other = Uuid::parse_str("853c80ef-3c37-49fd-aa49-938b674adae6").unwrap();
+
+        assert!(!whitelist.enabled);
+        assert!(whitelist.allows(other));
+        whitelist.enabled = true;
+        assert!(whitelist.allows(notch));
+        assert!(!whitelist.allows(other));
+
+        assert!(!whitelist.add(WhitelistEntry {
+            uuid: notch,
+            name: String::from("Notch"),
+        }));
+        assert!(whitelist.remove(notch).is_some());
+        assert!(!whitelist.allows(notch));
+
+        assert_eq!(Whitelist::load(&whitelist.save()).unwrap().entries, vec![]);
     }
 }
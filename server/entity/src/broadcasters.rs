@@ -4,6 +4,7 @@ mod inventory;
 mod item_collect;
 mod metadata;
 mod movement;
+mod status;
 
 pub use self::inventory::*;
 pub use entity_creation::*;
@@ -11,3 +12,4 @@ pub use entity_deletion::*;
 pub use item_collect::*;
 pub use metadata::*;
 pub use movement::*;
+pub use status::*;
@@ -136,7 +136,7 @@ pub fn on_damage_item(event: &ItemDamageEvent, game: &mut Game, world: &mut Worl
     let inventory = world.get_mut::<Inventory>(event.player);
 
     let mut item = match inventory.item_at_mut(event.slot.area, event.slot.slot) {
-        Ok(guard) => guard.unwrap(),
+        Ok(guard) => guard.clone().unwrap(),
         Err(_) => return,
     };
 
@@ -217,7 +217,7 @@ mod tests {
         test.world.get_mut::<HeldItem>(player1).0 = 2;
         test.world
             .get::<Inventory>(player1)
-            .set_item_at(slot.area, slot.slot, stack)
+            .set_item_at(slot.area, slot.slot, stack.clone())
             .unwrap();
 
         test.handle(
@@ -240,7 +240,7 @@ mod tests {
         test.world.get_mut::<HeldItem>(player3).0 = 2;
         test.world
             .get::<Inventory>(player3)
-            .set_item_at(slot.area, slot.slot, stack)
+            .set_item_at(slot.area, slot.slot, stack.clone())
             .unwrap();
 
         test.handle(
@@ -269,7 +269,7 @@ mod tests {
             |test, player1, player2| {
                 test.world
                     .get::<Inventory>(player1)
-                    .set_item_at(slot.area, slot.slot, stack)
+                    .set_item_at(slot.area, slot.slot, stack.clone())
                     .unwrap();
                 EntitySendEvent {
                     entity: player1,
@@ -293,6 +293,7 @@ mod tests {
             ty: Item::StoneShovel,
             amount: 1,
             damage: Some(10),
+            meta: None,
         };
         let slot = SlotIndex {
             area: Area::Main,
@@ -304,7 +305,7 @@ mod tests {
 
         test.world
             .get::<Inventory>(player1)
-            .set_item_at(slot.area, slot.slot, stack)
+            .set_item_at(slot.area, slot.slot, stack.clone())
             .unwrap();
 
         test.handle(
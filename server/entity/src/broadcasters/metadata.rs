@@ -38,7 +38,8 @@ mod tests {
         let player2 = test.player("", position!(0.0, 100.0, 0.0));
 
         let stack = ItemStack::new(Item::String, 4);
-        let item = test.entity(item::create(stack, Default::default()).with(Position::default()));
+        let item =
+            test.entity(item::create(stack.clone(), Default::default()).with(Position::default()));
 
         test.handle(
             EntitySendEvent {
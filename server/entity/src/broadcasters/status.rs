@@ -0,0 +1,85 @@
+use feather_core::network::packets::EntityStatus;
+use feather_server_types::{Game, HealthUpdateEvent, NetworkId};
+use fecs::World;
+
+/// Entity status which plays the hurt animation and sound.
+const STATUS_HURT: i8 = 2;
+/// Entity status which plays the death animation and sound.
+const STATUS_DEATH: i8 = 3;
+
+/// Broadcasts the hurt (or death) animation when an
+/// entity loses health.
+#[fecs::event_handler]
+pub fn on_health_update_broadcast_hurt(event: &HealthUpdateEvent, game: &Game, world: &mut World) {
+    if event.new >= event.old {
+        return;
+    }
+
+    let entity_status = if event.new == 0 {
+        STATUS_DEATH
+    } else {
+        STATUS_HURT
+    };
+    let packet = EntityStatus {
+        entity_id: world.get::<NetworkId>(event.entity).0,
+        entity_status,
+    };
+
+    game.broadcast_entity_update(world, packet, event.entity, None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::util::Position;
+    use feather_test_framework::Test;
+
+    #[test]
+    fn broadcast_hurt() {
+        let mut test = Test::new();
+
+        let (packet, player) = test.broadcast_routine::<EntityStatus, _, _, _>(
+            |_test, player1, _player2| HealthUpdateEvent {
+                old: 20,
+                new: 15,
+                entity: player1,
+            },
+            on_health_update_broadcast_hurt,
+            true,
+        );
+
+        assert_eq!(packet.entity_id, test.id(player));
+        assert_eq!(packet.entity_status, STATUS_HURT);
+    }
+
+    #[test]
+    fn broadcast_death() {
+        let mut test = Test::new();
+
+        let player = test.player("", Position::default());
+        let observer = test.player("", position!(10.0, 64.0, 0.0));
+
+        test.handle(
+            HealthUpdateEvent {
+                old: 15,
+                new: 0,
+                entity: player,
+            },
+            on_health_update_broadcast_hurt,
+        );
+        let packet = test.sent::<EntityStatus>(observer).unwrap();
+        assert_eq!(packet.entity_id, test.id(player));
+        assert_eq!(packet.entity_status, STATUS_DEATH);
+
+        // Healing plays no animation
+        test.handle(
+            HealthUpdateEvent {
+                old: 0,
+                new: 20,
+                entity: player,
+            },
+            on_health_update_broadcast_hurt,
+        );
+        assert!(test.sent::<EntityStatus>(observer).is_none());
+    }
+}
@@ -0,0 +1,321 @@
+//! Melee combat: weapon damage, attack cooldowns,
+//! critical hits and knockback.
+//!
+//! Players attack through Use Entity packets, which
+//! end up in `attack`. Mobs have no weapons and deal
+//! their own damage through `hit`.
+
+use crate::InventoryExt;
+use feather_core::inventory::Inventory;
+use feather_core::items::{Item, ItemStack};
+use feather_core::network::packets::{AnimationClientbound, ClientboundAnimation, EntityVelocity};
+use feather_core::util::Position;
+use feather_definitions::{Tool, ToolMaterial};
+use feather_server_types::{
+    CanTakeDamage, Dead, Game, Health, LastAttack, LastAttacker, Network, NetworkId, Player,
+    PreviousPosition, Velocity,
+};
+use feather_server_util::protocol_velocity;
+use fecs::{Entity, World};
+
+/// Knockback strength of every successful attack.
+pub const BASE_KNOCKBACK: f64 = 0.4;
+
+/// Returns whether `attacker` may attack `target`.
+///
+/// Neither entity may be dead, the target must be able
+/// to take damage, and players may only attack each other
+/// if PvP is enabled.
+pub fn can_attack(game: &Game, world: &World, attacker: Entity, target: Entity) -> bool {
+    if attacker == target || world.has::<Dead>(attacker) || !world.is_alive(target) {
+        return false;
+    }
+    if !world.has::<Health>(target)
+        || !world.has::<CanTakeDamage>(target)
+        || world.has::<Dead>(target)
+    {
+        return false;
+    }
+
+    if world.has::<Player>(attacker) && world.has::<Player>(target) {
+        game.config.gameplay.pvp
+    } else {
+        true
+    }
+}
+
+/// Makes `attacker` attack `target` with the item in its main hand.
+///
+/// The damage dealt depends on the weapon, on how far the attacker's
+/// attack cooldown has recovered, and on whether the hit is critical.
+/// The target is knocked back away from the attacker.
+///
+/// Callers should check `can_attack` first.
+pub fn attack(game: &mut Game, world: &mut World, attacker: Entity, target: Entity) {
+    let weapon = world
+        .try_get::<Inventory>(attacker)
+        .and_then(|inventory| inventory.item_in_main_hand(attacker, world));
+    let weapon = weapon.as_ref();
+
+    let strength = match world.try_get::<LastAttack>(attacker).map(|r| r.0) {
+        Some(last_attack) => attack_strength(
+            game.tick_count.saturating_sub(last_attack),
+            attack_speed(weapon),
+        ),
+        None => 1.0,
+    };
+    if world.has::<LastAttack>(attacker) {
+        world.get_mut::<LastAttack>(attacker).0 = game.tick_count;
+    } else {
+        world.add(attacker, LastAttack(game.tick_count)).unwrap();
+    }
+
+    let attacker_pos = *world.get::<Position>(attacker);
+    let falling = world
+        .try_get::<PreviousPosition>(attacker)
+        .and_then(|prev| prev.0)
+        .map_or(false, |prev| prev.y > attacker_pos.y);
+    let critical = falling && !attacker_pos.on_ground && strength > 0.9;
+
+    let mut damage = attack_damage(weapon) * (0.2 + strength * strength * 0.8);
+    if critical {
+        damage *= 1.5;
+        let packet = AnimationClientbound {
+            entity_id: world.get::<NetworkId>(target).0,
+            animation: ClientboundAnimation::CriticalEffect,
+        };
+        game.broadcast_entity_update(world, packet, target, None);
+    }
+
+    let level = enchantment_level(weapon, "minecraft:knockback");
+    let extra_knockback = if level > 0 && strength > 0.9 {
+        f64::from(level) * 0.5
+    } else {
+        0.0
+    };
+
+    hit(game, world, attacker, target, damage, extra_knockback);
+}
+
+/// Deals `damage` to `target` on behalf of `attacker`,
+/// knocking the target back away from the attacker.
+///
+/// `extra_knockback` is applied in the direction the attacker
+/// is facing, as done by the Knockback enchantment.
+pub fn hit(
+    game: &mut Game,
+    world: &mut World,
+    attacker: Entity,
+    target: Entity,
+    damage: f64,
+    extra_knockback: f64,
+) {
+    let attacker_pos = *world.get::<Position>(attacker);
+    let target_pos = *world.get::<Position>(target);
+    knockback(
+        world,
+        target,
+        attacker_pos.x - target_pos.x,
+        attacker_pos.z - target_pos.z,
+        BASE_KNOCKBACK,
+    );
+    if extra_knockback > 0.0 {
+        let yaw = f64::from(attacker_pos.yaw).to_radians();
+        knockback(world, target, yaw.sin(), -yaw.cos(), extra_knockback);
+    }
+
+    let last_attacker = LastAttacker {
+        entity: attacker,
+        tick: game.tick_count,
+    };
+    if world.has::<LastAttacker>(target) {
+        *world.get_mut::<LastAttacker>(target) = last_attacker;
+    } else {
+        world.add(target, last_attacker).unwrap();
+    }
+
+    game.damage(target, damage.round() as u32, world);
+}
+
+/// Knocks an entity back in the direction opposite
+/// to (`x`, `z`) using the vanilla formula.
+///
+/// Players are sent the new velocity directly, since
+/// their clients are in charge of their movement.
+pub fn knockback(world: &mut World, entity: Entity, x: f64, z: f64, strength: f64) {
+    let length = (x * x + z * z).sqrt();
+    if length < 1.0e-4 {
+        return;
+    }
+
+    let is_player = world.has::<Player>(entity);
+    let mut velocity = if is_player {
+        glm::vec3(0.0, 0.0, 0.0)
+    } else {
+        match world.try_get::<Velocity>(entity) {
+            Some(velocity) => velocity.0,
+            None => return,
+        }
+    };
+
+    velocity.x = velocity.x / 2.0 - x / length * strength;
+    velocity.z = velocity.z / 2.0 - z / length * strength;
+    if world.get::<Position>(entity).on_ground {
+        velocity.y = (velocity.y / 2.0 + strength).min(0.4);
+    }
+
+    if is_player {
+        let (velocity_x, velocity_y, velocity_z) = protocol_velocity(velocity);
+        let packet = EntityVelocity {
+            entity_id: world.get::<NetworkId>(entity).0,
+            velocity_x,
+            velocity_y,
+            velocity_z,
+        };
+        world.get::<Network>(entity).send(packet);
+    } else {
+        world.get_mut::<Velocity>(entity).0 = velocity;
+    }
+}
+
+/// Returns the strength of an attack, from 0 to 1,
+/// given the number of ticks since the attacker last attacked
+/// and the attack speed of its weapon.
+pub fn attack_strength(ticks_since_attack: u64, attack_speed: f64) -> f64 {
+    let cooldown = 20.0 / attack_speed;
+    ((ticks_since_attack as f64 + 0.5) / cooldown).min(1.0)
+}
+
+/// Returns the damage dealt by a fully charged
+/// attack with the given weapon, in half-hearts.
+pub fn attack_damage(weapon: Option<&ItemStack>) -> f64 {
+    let base = match weapon {
+        Some(weapon) => item_attack_damage(weapon.ty),
+        None => 1.0,
+    };
+
+    let sharpness = enchantment_level(weapon, "minecraft:sharpness");
+    if sharpness > 0 {
+        base + 0.5 * f64::from(sharpness) + 0.5
+    } else {
+        base
+    }
+}
+
+/// Returns the number of fully charged attacks per
+/// second which can be performed with the given weapon.
+pub fn attack_speed(weapon: Option<&ItemStack>) -> f64 {
+    match weapon {
+        Some(weapon) => item_attack_speed(weapon.ty),
+        None => 4.0,
+    }
+}
+
+fn item_attack_damage(item: Item) -> f64 {
+    if item == Item::Trident {
+        return 9.0;
+    }
+
+    let material = match item.tool_material() {
+        Some(material) => material,
+        None => return 1.0,
+    };
+    match (item.tool(), material) {
+        (Some(Tool::Sword), ToolMaterial::Wooden) | (Some(Tool::Sword), ToolMaterial::Golden) => {
+            4.0
+        }
+        (Some(Tool::Sword), ToolMaterial::Stone) => 5.0,
+        (Some(Tool::Sword), ToolMaterial::Iron) => 6.0,
+        (Some(Tool::Sword), ToolMaterial::Diamond) => 7.0,
+        (Some(Tool::Axe), ToolMaterial::Wooden) | (Some(Tool::Axe), ToolMaterial::Golden) => 7.0,
+        (Some(Tool::Axe), _) => 9.0,
+        (Some(Tool::Pickaxe), ToolMaterial::Wooden)
+        | (Some(Tool::Pickaxe), ToolMaterial::Golden) => 2.0,
+        (Some(Tool::Pickaxe), ToolMaterial::Stone) => 3.0,
+        (Some(Tool::Pickaxe), ToolMaterial::Iron) => 4.0,
+        (Some(Tool::Pickaxe), ToolMaterial::Diamond) => 5.0,
+        (Some(Tool::Shovel), ToolMaterial::Wooden) | (Some(Tool::Shovel), ToolMaterial::Golden) => {
+            2.5
+        }
+        (Some(Tool::Shovel), ToolMaterial::Stone) => 3.5,
+        (Some(Tool::Shovel), ToolMaterial::Iron) => 4.5,
+        (Some(Tool::Shovel), ToolMaterial::Diamond) => 5.5,
+        _ => 1.0,
+    }
+}
+
+fn item_attack_speed(item: Item) -> f64 {
+    if item == Item::Trident {
+        return 1.1;
+    }
+
+    match (item.tool(), item.tool_material()) {
+        (Some(Tool::Sword), _) => 1.6,
+        (Some(Tool::Axe), Some(ToolMaterial::Wooden))
+        | (Some(Tool::Axe), Some(ToolMaterial::Stone)) => 0.8,
+        (Some(Tool::Axe), Some(ToolMaterial::Iron)) => 0.9,
+        (Some(Tool::Axe), _) => 1.0,
+        (Some(Tool::Pickaxe), _) => 1.2,
+        (Some(Tool::Shovel), _) => 1.0,
+        (Some(Tool::Hoe), Some(ToolMaterial::Wooden))
+        | (Some(Tool::Hoe), Some(ToolMaterial::Golden)) => 1.0,
+        (Some(Tool::Hoe), Some(ToolMaterial::Stone)) => 2.0,
+        (Some(Tool::Hoe), Some(ToolMaterial::Iron)) => 3.0,
+        (Some(Tool::Hoe), _) => 4.0,
+        _ => 4.0,
+    }
+}
+
+fn enchantment_level(item: Option<&ItemStack>, id: &str) -> i16 {
+    item.and_then(|item| item.meta.as_ref())
+        .and_then(|meta| meta.enchantments.iter().find(|ench| ench.id == id))
+        .map_or(0, |ench| ench.level.max(0))
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+    use feather_core::items::Enchantment;
+
+    #[test]
+    fn weapon_damage() {
+        assert_eq!(attack_damage(None), 1.0);
+        assert_eq!(
+            attack_damage(Some(&ItemStack::new(Item::DiamondSword, 1))),
+            7.0
+        );
+        assert_eq!(attack_damage(Some(&ItemStack::new(Item::StoneAxe, 1))), 9.0);
+        assert_eq!(attack_damage(Some(&ItemStack::new(Item::Dirt, 1))), 1.0);
+
+        let mut sword = ItemStack::new(Item::IronSword, 1);
+        sword
+            .meta_mut()
+            .enchantments
+            .push(Enchantment::new("minecraft:sharpness", 3));
+        assert_eq!(attack_damage(Some(&sword)), 8.0);
+    }
+
+    #[test]
+    fn weapon_speed() {
+        assert_eq!(attack_speed(None), 4.0);
+        assert_eq!(
+            attack_speed(Some(&ItemStack::new(Item::GoldenSword, 1))),
+            1.6
+        );
+        assert_eq!(attack_speed(Some(&ItemStack::new(Item::IronAxe, 1))), 0.9);
+        assert_eq!(
+            attack_speed(Some(&ItemStack::new(Item::DiamondHoe, 1))),
+            4.0
+        );
+    }
+
+    #[test]
+    fn cooldown() {
+        // A sword recovers in 12.5 ticks
+        assert!(attack_strength(0, 1.6) < 0.1);
+        assert!((attack_strength(6, 1.6) - 0.52).abs() < 1.0e-6);
+        assert_eq!(attack_strength(12, 1.6), 1.0);
+        assert_eq!(attack_strength(1000, 1.6), 1.0);
+    }
+}
@@ -30,7 +30,7 @@ pub fn on_block_break_drop_loot(event: &BlockUpdateEvent, game: &mut Game, world
 
             // If the block was not broken with the correct tool, don't drop loot.
             if event.old.kind().best_tool_required() {
-                let tool_used = item.map(|item| item.ty.tool()).flatten();
+                let tool_used = item.as_ref().map(|item| item.ty.tool()).flatten();
 
                 let best_tool = event.old.kind().best_tool();
 
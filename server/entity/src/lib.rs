@@ -7,6 +7,7 @@
 extern crate feather_core;
 
 mod broadcasters;
+pub mod combat;
 pub mod drops;
 mod fall_damage;
 mod inventory;
@@ -19,7 +20,9 @@ pub use broadcasters::*;
 pub use drops::on_block_break_drop_loot;
 pub use fall_damage::update_blocks_fallen;
 pub use mob::*;
-pub use object::falling_block::{on_entity_land_remove_falling_block, spawn_falling_blocks};
+pub use object::falling_block::{
+    on_entity_land_remove_falling_block, on_scheduled_tick_spawn_falling_block,
+};
 pub use object::item::{item_collect, on_item_drop_spawn_item_entity};
 pub use object::*;
 
@@ -1,18 +1,26 @@
 //! Components and functionality shared across all mobs.
 
+pub mod ai;
 mod boss;
 mod defensive;
 mod hostile;
 mod neutral;
 mod passive;
+pub mod spawning;
 
+use ai::{AttackTarget, Navigation};
 pub use boss::*;
 pub use defensive::*;
+use feather_core::anvil::entity::{BaseEntityData, EntityData, EntityDataKind, MobData};
+use feather_core::biomes::SpawnCategory;
 use feather_core::entitymeta::EntityMetadata;
 use feather_core::network::packets::SpawnMob;
 use feather_core::network::Packet;
-use feather_core::util::Position;
-use feather_server_types::{NetworkId, SpawnPacketCreator, Uuid, Velocity};
+use feather_core::util::{Position, Vec3d};
+use feather_server_types::{
+    CanTakeDamage, ComponentSerializer, EntityLoaderRegistration, Game, Health, MaxHealth,
+    NetworkId, Physics, PhysicsBuilder, SpawnPacketCreator, Uuid, Velocity,
+};
 use feather_server_util::{degrees_to_stops, protocol_velocity};
 use fecs::{EntityBuilder, EntityRef};
 pub use hostile::*;
@@ -88,10 +96,320 @@ pub enum MobKind {
     Phantom = 90,
 }
 
+impl MobKind {
+    /// Returns the maximum health of this kind of mob, in half-hearts.
+    ///
+    /// Mobs whose health varies (e.g. by size for slimes or
+    /// randomly for horses) use the vanilla default.
+    pub fn max_health(self) -> u32 {
+        use MobKind::*;
+        match self {
+            Cod | Pufferfish | Rabbit | Salmon | TropicalFish => 3,
+            Chicken | SnowGolem => 4,
+            Bat | Parrot => 6,
+            Endermite | Sheep | Silverfish | Wolf => 8,
+            Cow | Dolphin | Ghast | MushroomCow | Ocelot | Pig | Squid => 10,
+            CaveSpider => 12,
+            Vex => 14,
+            Donkey | Horse | Llama | Mule | SkeletonHorse | ZombieHorse => 15,
+            MagmaCube | Slime | Spider => 16,
+            Blaze | Creeper | Drowned | Husk | PigZombie | Skeleton | Stray | Villager
+            | WitherSkeleton | Zombie | ZombieVillager | Phantom => 20,
+            EvocationIllager | VindicationIllager => 24,
+            Witch => 26,
+            Guardian | PolarBear | Shulker | Turtle => 30,
+            IllusionIllager => 32,
+            Enderman => 40,
+            ElderGuardian => 80,
+            Giant | IronGolem => 100,
+            EnderDragon => 200,
+            Wither => 300,
+        }
+    }
+
+    /// Returns the width and height of this kind of mob's
+    /// bounding box, in blocks.
+    pub fn size(self) -> (f64, f64) {
+        use MobKind::*;
+        match self {
+            Bat | Parrot => (0.5, 0.9),
+            Blaze => (0.6, 1.8),
+            CaveSpider => (0.7, 0.5),
+            Chicken => (0.4, 0.7),
+            Cod => (0.5, 0.3),
+            Cow | MushroomCow => (0.9, 1.4),
+            Creeper => (0.6, 1.7),
+            Donkey => (1.3965, 1.5),
+            Dolphin => (0.9, 0.6),
+            Drowned | EvocationIllager | Husk | IllusionIllager | PigZombie | Villager
+            | VindicationIllager | Witch | Zombie | ZombieVillager => (0.6, 1.95),
+            ElderGuardian => (1.9975, 1.9975),
+            EnderDragon => (16.0, 8.0),
+            Enderman => (0.6, 2.9),
+            Endermite | Silverfish => (0.4, 0.3),
+            Ghast => (4.0, 4.0),
+            Giant => (3.6, 12.0),
+            Guardian => (0.85, 0.85),
+            Horse | Mule | SkeletonHorse | ZombieHorse => (1.3965, 1.6),
+            IronGolem => (1.4, 2.7),
+            Llama => (0.9, 1.87),
+            MagmaCube | Slime => (2.04, 2.04),
+            Ocelot => (0.6, 0.7),
+            Phantom => (0.9, 0.5),
+            Pig => (0.9, 0.9),
+            PolarBear => (1.3, 1.4),
+            Pufferfish => (0.7, 0.7),
+            Rabbit => (0.4, 0.5),
+            Salmon => (0.7, 0.4),
+            Sheep => (0.9, 1.3),
+            Shulker => (1.0, 1.0),
+            Skeleton | Stray => (0.6, 1.99),
+            SnowGolem => (0.7, 1.9),
+            Spider => (1.4, 0.9),
+            Squid => (0.8, 0.8),
+            TropicalFish => (0.5, 0.4),
+            Turtle => (1.2, 0.4),
+            Vex => (0.4, 0.8),
+            Wither => (0.9, 3.5),
+            WitherSkeleton => (0.7, 2.4),
+            Wolf => (0.6, 0.85),
+        }
+    }
+
+    /// Returns the category this kind of mob spawns naturally in,
+    /// which determines its mob cap and whether it despawns.
+    ///
+    /// Bosses, golems, villagers, guardians and shulkers
+    /// have no category, so they never despawn.
+    pub fn spawn_category(self) -> Option<SpawnCategory> {
+        use MobKind::*;
+        match self {
+            Blaze | CaveSpider | Creeper | Drowned | Enderman | Endermite | EvocationIllager
+            | Ghast | Giant | Guardian | Husk | IllusionIllager | MagmaCube | Phantom
+            | PigZombie | Silverfish | Skeleton | Slime | Spider | Stray | Vex
+            | VindicationIllager | Witch | WitherSkeleton | Zombie | ZombieVillager => {
+                Some(SpawnCategory::Monster)
+            }
+            Chicken | Cow | Donkey | Horse | Llama | Mule | MushroomCow | Ocelot | Parrot | Pig
+            | PolarBear | Rabbit | Sheep | SkeletonHorse | Turtle | Wolf | ZombieHorse => {
+                Some(SpawnCategory::Creature)
+            }
+            Bat => Some(SpawnCategory::Ambient),
+            Cod | Dolphin | Pufferfish | Salmon | Squid | TropicalFish => {
+                Some(SpawnCategory::WaterCreature)
+            }
+            ElderGuardian | EnderDragon | IronGolem | Shulker | SnowGolem | Villager | Wither => {
+                None
+            }
+        }
+    }
+
+    /// Returns the kind of entity this kind of mob is saved as.
+    pub fn data_kind(self) -> EntityDataKind {
+        use MobKind::*;
+        match self {
+            Bat => EntityDataKind::Bat,
+            Blaze => EntityDataKind::Blaze,
+            CaveSpider => EntityDataKind::CaveSpider,
+            Chicken => EntityDataKind::Chicken,
+            Cod => EntityDataKind::Cod,
+            Cow => EntityDataKind::Cow,
+            Creeper => EntityDataKind::Creeper,
+            Donkey => EntityDataKind::Donkey,
+            Dolphin => EntityDataKind::Dolphin,
+            Drowned => EntityDataKind::Drowned,
+            ElderGuardian => EntityDataKind::ElderGuardian,
+            EnderDragon => EntityDataKind::EnderDragon,
+            Enderman => EntityDataKind::Enderman,
+            Endermite => EntityDataKind::Endermite,
+            EvocationIllager => EntityDataKind::Evoker,
+            Ghast => EntityDataKind::Ghast,
+            Giant => EntityDataKind::Giant,
+            Guardian => EntityDataKind::Guardian,
+            Horse => EntityDataKind::Horse,
+            Husk => EntityDataKind::Husk,
+            IllusionIllager => EntityDataKind::Illusioner,
+            Llama => EntityDataKind::Llama,
+            MagmaCube => EntityDataKind::MagmaCube,
+            Mule => EntityDataKind::Mule,
+            MushroomCow => EntityDataKind::Mooshroom,
+            Ocelot => EntityDataKind::Ocelot,
+            Parrot => EntityDataKind::Parrot,
+            Pig => EntityDataKind::Pig,
+            Pufferfish => EntityDataKind::Pufferfish,
+            PigZombie => EntityDataKind::ZombiePigman,
+            PolarBear => EntityDataKind::PolarBear,
+            Rabbit => EntityDataKind::Rabbit,
+            Salmon => EntityDataKind::Salmon,
+            Sheep => EntityDataKind::Sheep,
+            Shulker => EntityDataKind::Shulker,
+            Silverfish => EntityDataKind::Silverfish,
+            Skeleton => EntityDataKind::Skeleton,
+            SkeletonHorse => EntityDataKind::SkeletonHorse,
+            Slime => EntityDataKind::Slime,
+            SnowGolem => EntityDataKind::SnowGolem,
+            Spider => EntityDataKind::Spider,
+            Squid => EntityDataKind::Squid,
+            Stray => EntityDataKind::Stray,
+            TropicalFish => EntityDataKind::TropicalFish,
+            Turtle => EntityDataKind::Turtle,
+            Vex => EntityDataKind::Vex,
+            Villager => EntityDataKind::Villager,
+            IronGolem => EntityDataKind::IronGolem,
+            VindicationIllager => EntityDataKind::Vindicator,
+            Witch => EntityDataKind::Witch,
+            Wither => EntityDataKind::Wither,
+            WitherSkeleton => EntityDataKind::WitherSkeleton,
+            Wolf => EntityDataKind::Wolf,
+            Zombie => EntityDataKind::Zombie,
+            ZombieHorse => EntityDataKind::ZombieHorse,
+            ZombieVillager => EntityDataKind::ZombieVillager,
+            Phantom => EntityDataKind::Phantom,
+        }
+    }
+
+    /// Returns whether this kind of mob is affected by gravity.
+    /// Flying and swimming mobs are not.
+    pub fn has_gravity(self) -> bool {
+        use MobKind::*;
+        !matches!(
+            self,
+            Bat | Blaze
+                | Cod
+                | Dolphin
+                | ElderGuardian
+                | EnderDragon
+                | Ghast
+                | Guardian
+                | Phantom
+                | Pufferfish
+                | Salmon
+                | Shulker
+                | Squid
+                | TropicalFish
+                | Vex
+                | Wither
+        )
+    }
+}
+
+/// Component storing the kind of entity a mob is saved as,
+/// along with the tags loaded with it which the server does
+/// not interpret, which are saved back unchanged.
+pub struct MobNbt {
+    kind: EntityDataKind,
+    data: MobData,
+    /// Whether the mob never despawns, saved as `PersistenceRequired`.
+    /// Only mobs created by natural spawning despawn.
+    persistent: bool,
+}
+
+impl MobNbt {
+    /// Returns whether the mob should never despawn: it is
+    /// persistent or has a custom name, e.g. from a name tag.
+    pub fn is_persistent(&self) -> bool {
+        self.persistent || self.data.has_custom_name()
+    }
+}
+
 /// Returns the base components for a mob with the given
 /// kind.
+///
+/// This includes the mob's health, allowing it to be attacked,
+/// the physics and navigation used by its AI, its
+/// `SpawnCategory`, if any, and what is needed to save it.
 pub fn base(kind: MobKind) -> EntityBuilder {
-    super::base().with(spawn_packet_creator(kind))
+    let builder = super::base()
+        .with(spawn_packet_creator(kind))
+        .with(MobNbt {
+            kind: kind.data_kind(),
+            data: MobData::default(),
+            persistent: true,
+        })
+        .with(ComponentSerializer(&serialize))
+        .with(Health(kind.max_health()))
+        .with(MaxHealth(kind.max_health()))
+        .with(CanTakeDamage)
+        .with(physics(kind))
+        .with(Navigation::default())
+        .with(AttackTarget::default());
+
+    match kind.spawn_category() {
+        Some(category) => builder.with(category),
+        None => builder,
+    }
+}
+
+/// Registers `load` for the mobs which `spawning::create` can create.
+macro_rules! mob_loaders {
+    ($($kind:ident,)*) => {
+        $(
+            inventory::submit! {
+                EntityLoaderRegistration::new(EntityDataKind::$kind, &load)
+            }
+        )*
+    };
+}
+
+mob_loaders! {
+    Bat, Blaze, CaveSpider, Chicken, Cod, Cow, Creeper, Dolphin, Donkey, Drowned,
+    ElderGuardian, EnderDragon, Enderman, Endermite, Evoker, Ghast, Guardian, Horse, Husk,
+    IronGolem, Llama, MagmaCube, Mooshroom, Mule, Ocelot, Parrot, Phantom, Pig, PolarBear,
+    Pufferfish, Rabbit, Salmon, Sheep, Shulker, Silverfish, Skeleton, SkeletonHorse, Slime,
+    SnowGolem, Spider, Squid, Stray, TropicalFish, Turtle, Vex, Villager, Vindicator, Witch,
+    Wither, WitherSkeleton, Wolf, Zombie, ZombiePigman, ZombieVillager,
+}
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    let kind = EntityDataKind::from(&data);
+    let mob = data
+        .into_mob()
+        .expect("attempted to use mob::load to load a non-mob");
+    let pos = mob.base.read_position()?;
+    let vel = mob.base.read_velocity()?;
+    // Health is stored as a float, but partial health is not supported.
+    let health = mob.health.max(0.0).ceil() as u32;
+    let persistent = mob.persistence_required();
+
+    let builder = kind
+        .id()
+        .and_then(spawning::create)
+        .ok_or_else(|| anyhow::anyhow!("cannot create mob of kind {:?}", kind))?;
+    Ok(builder
+        .with(pos)
+        .with(Velocity(glm::vec3(vel.x, vel.y, vel.z)))
+        .with(Health(health))
+        .with(MobNbt {
+            kind,
+            data: mob,
+            persistent,
+        }))
+}
+
+fn serialize(_game: &Game, accessor: &EntityRef) -> EntityData {
+    let nbt = accessor.get::<MobNbt>();
+    let vel = accessor.get::<Velocity>().0;
+
+    let mut data = nbt.data.clone();
+    data.base = BaseEntityData::new(*accessor.get::<Position>(), Vec3d::new(vel.x, vel.y, vel.z));
+    data.health = accessor.get::<Health>().0 as f32;
+    data.set_persistence_required(nbt.persistent);
+    EntityData::from_mob(nbt.kind, data).expect("mob saved as a non-mob")
+}
+
+/// Returns the `Physics` component for a mob with the given kind.
+fn physics(kind: MobKind) -> Physics {
+    // The physics engine cannot handle bounding
+    // boxes larger than two blocks.
+    let (width, height) = kind.size();
+    let (width, height) = (width.min(2.0), height.min(2.0));
+
+    let builder = PhysicsBuilder::for_living().bbox(width, height, width);
+    if kind.has_gravity() {
+        builder.build()
+    } else {
+        builder.gravity(0.0).build()
+    }
 }
 
 /// Returns a `SpawnPacketCreator` for a mob with the given kind.
@@ -132,3 +450,42 @@ pub fn spawn_packet_creator(kind: MobKind) -> SpawnPacketCreator {
 
     SpawnPacketCreator(Box::leak(f))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_test_framework::Test;
+
+    #[test]
+    fn load_and_save() {
+        let mut test = Test::new();
+        let pos = position!(1.0, 64.0, 2.0, 90.0, 0.0);
+        let data = EntityData::Sheep(MobData::new(
+            BaseEntityData::new(pos, Vec3d::broadcast(0.0)),
+            4.5,
+        ));
+
+        let sheep = test.entity(load(data).unwrap());
+        assert!(test.world.has::<crate::sheep::Sheep>(sheep));
+        assert_eq!(test.world.get::<Health>(sheep).0, 5);
+
+        let accessor = test.world.entity(sheep).unwrap();
+        match serialize(&test.game, &accessor) {
+            EntityData::Sheep(data) => {
+                assert_eq!(data.base.read_position(), Ok(pos));
+                assert!((data.health - 5.0).abs() < std::f32::EPSILON);
+            }
+            other => panic!("expected sheep, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn saved_as_data_kind() {
+        let mut test = Test::new();
+        let zombie = test.entity(crate::zombie::create().with(position!(0.0, 64.0, 0.0)));
+
+        let accessor = test.world.entity(zombie).unwrap();
+        let data = serialize(&test.game, &accessor);
+        assert_eq!(EntityDataKind::from(&data), EntityDataKind::Zombie);
+    }
+}
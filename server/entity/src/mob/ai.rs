@@ -0,0 +1,640 @@
+//! Goal-based mob AI.
+//!
+//! Each mob has a `GoalSelector` holding its goals, such as
+//! wandering around or attacking a player, each with a priority.
+//! Every tick, the selector stops goals which no longer apply and
+//! starts goals which can run, letting goals with a higher priority
+//! (lower number) take over parts of the mob controlled by
+//! lower-priority goals.
+//!
+//! Goals move mobs through the `Navigation` component, which finds
+//! a path to the target and walks the mob along it by driving its
+//! `Velocity`. Physics and the movement broadcasters take care of
+//! the rest. Navigation only works on the ground, so flying and
+//! swimming mobs merely look around, and fish have no goals at all.
+
+mod flee;
+mod follow_owner;
+mod look;
+mod melee;
+mod panic;
+mod target;
+mod wander;
+
+pub use flee::Flee;
+pub use follow_owner::{FollowOwner, Owner};
+pub use look::LookAtPlayer;
+pub use melee::MeleeAttack;
+pub use panic::Panic;
+pub use target::{HurtByTarget, NearestAttackableTarget};
+pub use wander::Wander;
+
+use feather_core::util::{BlockPosition, Position};
+use feather_server_physics::pathfinding::{Path, PathOptions, PathStatus, Pathfinder};
+use feather_server_types::{AABBExt, CanTakeDamage, Dead, Game, Physics, Player, Velocity};
+use feather_server_util::nearby_entities;
+use fecs::{component, Entity, IntoQuery, Read, World, Write};
+use std::mem;
+use std::ops::BitOr;
+
+/// Squared horizontal distance at which a mob
+/// has reached its navigation target.
+const ARRIVAL_DISTANCE_SQUARED: f64 = 0.25;
+/// Number of ticks after which navigation gives up
+/// on reaching its target.
+const MAX_NAVIGATION_TICKS: u32 = 200;
+/// Vertical velocity of a mob jumping onto a block.
+const JUMP_VELOCITY: f64 = 0.42;
+/// Squared horizontal distance at which a mob
+/// has reached a node of its path.
+const NODE_REACHED_DISTANCE_SQUARED: f64 = 0.16;
+/// Maximum number of pathfinding nodes expanded
+/// each tick, shared between all mobs.
+const PATHFINDING_BUDGET: usize = 4096;
+
+/// The parts of a mob controlled by a goal. Goals which
+/// control the same part of a mob cannot run at the same time.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Controls(u8);
+
+impl Controls {
+    /// The goal moves the mob through its `Navigation`.
+    pub const MOVE: Controls = Controls(1);
+    /// The goal turns the mob's head.
+    pub const LOOK: Controls = Controls(1 << 1);
+    /// The goal selects the mob's `AttackTarget`.
+    pub const TARGET: Controls = Controls(1 << 2);
+
+    /// Returns whether `self` and `other` have a part in common.
+    pub fn intersects(self, other: Controls) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for Controls {
+    type Output = Controls;
+
+    fn bitor(self, other: Controls) -> Controls {
+        Controls(self.0 | other.0)
+    }
+}
+
+/// State passed to goals: the mob and the world it lives in.
+pub struct AiContext<'a> {
+    pub game: &'a mut Game,
+    pub world: &'a mut World,
+    /// The mob running the goal.
+    pub entity: Entity,
+}
+
+impl<'a> AiContext<'a> {
+    /// Returns the mob's position.
+    pub fn position(&self) -> Position {
+        *self.world.get::<Position>(self.entity)
+    }
+
+    /// Makes the mob walk towards `target` at `speed` blocks per tick.
+    ///
+    /// A new path is searched for whenever the target
+    /// moves to another block.
+    pub fn navigate_to(&mut self, target: Position, speed: f64) {
+        let mut navigation = self.world.get_mut::<Navigation>(self.entity);
+        navigation.target = Some(target);
+        navigation.speed = speed;
+        navigation.ticks = 0;
+    }
+
+    /// Returns whether the mob is walking towards a target.
+    pub fn is_navigating(&self) -> bool {
+        self.world.get::<Navigation>(self.entity).target.is_some()
+    }
+
+    /// Stops the mob's navigation.
+    pub fn stop_navigation(&mut self) {
+        self.world.get_mut::<Navigation>(self.entity).stop();
+    }
+
+    /// Turns the mob's head towards `target`.
+    pub fn look_at(&mut self, target: Position) {
+        let mut pos = self.world.get_mut::<Position>(self.entity);
+        let (dx, dy, dz) = (target.x - pos.x, target.y - pos.y, target.z - pos.z);
+        pos.yaw = yaw_towards(dx, dz);
+        pos.pitch = -dy.atan2((dx * dx + dz * dz).sqrt()).to_degrees() as f32;
+    }
+
+    /// Returns the closest entity within `range` blocks
+    /// of the mob matching `filter`.
+    pub fn nearest_entity(
+        &self,
+        range: f64,
+        filter: impl Fn(&World, Entity) -> bool,
+    ) -> Option<Entity> {
+        let pos = self.position();
+        nearby_entities(self.world, self.game, pos, glm::vec3(range, range, range))
+            .into_iter()
+            .filter(|entity| *entity != self.entity && filter(self.world, *entity))
+            .map(|entity| {
+                let distance = self.world.get::<Position>(entity).distance_squared_to(pos);
+                (entity, distance)
+            })
+            .filter(|(_, distance)| *distance <= range * range)
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .map(|(entity, _)| entity)
+    }
+
+    /// Returns the squared distance from the mob to `entity`.
+    pub fn distance_squared_to(&self, entity: Entity) -> f64 {
+        self.world
+            .get::<Position>(entity)
+            .distance_squared_to(self.position())
+    }
+}
+
+/// A behavior of a mob, such as wandering around
+/// or attacking a player.
+pub trait Goal: Send + Sync + 'static {
+    /// Returns the parts of the mob this goal controls.
+    fn controls(&self) -> Controls;
+
+    /// Returns whether the goal should start running.
+    fn can_start(&mut self, ctx: &mut AiContext) -> bool;
+
+    /// Returns whether the goal should keep running.
+    fn should_continue(&mut self, ctx: &mut AiContext) -> bool {
+        self.can_start(ctx)
+    }
+
+    /// Called when the goal starts running.
+    fn start(&mut self, _ctx: &mut AiContext) {}
+
+    /// Called each tick while the goal is running.
+    fn tick(&mut self, _ctx: &mut AiContext) {}
+
+    /// Called when the goal stops running, either because it
+    /// should no longer continue or because it was interrupted
+    /// by a goal with a higher priority.
+    fn stop(&mut self, _ctx: &mut AiContext) {}
+}
+
+struct PrioritizedGoal {
+    priority: u8,
+    goal: Box<dyn Goal>,
+    running: bool,
+}
+
+/// Component holding the goals of a mob.
+///
+/// Goals are added at creation with `with`, where lower
+/// priorities take precedence.
+#[derive(Default)]
+pub struct GoalSelector {
+    goals: Vec<PrioritizedGoal>,
+}
+
+impl GoalSelector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a goal with the given priority.
+    pub fn with(mut self, priority: u8, goal: impl Goal) -> Self {
+        let index = self
+            .goals
+            .iter()
+            .position(|other| other.priority > priority)
+            .unwrap_or_else(|| self.goals.len());
+        self.goals.insert(
+            index,
+            PrioritizedGoal {
+                priority,
+                goal: Box::new(goal),
+                running: false,
+            },
+        );
+        self
+    }
+
+    /// Stops goals which should no longer run, starts
+    /// goals which can run and ticks running goals.
+    pub fn tick(&mut self, ctx: &mut AiContext) {
+        for goal in self.goals.iter_mut().filter(|goal| goal.running) {
+            if !goal.goal.should_continue(ctx) {
+                goal.goal.stop(ctx);
+                goal.running = false;
+            }
+        }
+
+        for index in 0..self.goals.len() {
+            if self.goals[index].running || !self.can_take_over(index) {
+                continue;
+            }
+            if !self.goals[index].goal.can_start(ctx) {
+                continue;
+            }
+
+            let controls = self.goals[index].goal.controls();
+            for other in self.goals.iter_mut().filter(|other| other.running) {
+                if other.goal.controls().intersects(controls) {
+                    other.goal.stop(ctx);
+                    other.running = false;
+                }
+            }
+
+            let goal = &mut self.goals[index];
+            goal.goal.start(ctx);
+            goal.running = true;
+        }
+
+        for goal in self.goals.iter_mut().filter(|goal| goal.running) {
+            goal.goal.tick(ctx);
+        }
+    }
+
+    /// Returns whether the goal at `index` has a higher
+    /// priority than all running goals it conflicts with.
+    fn can_take_over(&self, index: usize) -> bool {
+        let goal = &self.goals[index];
+        let controls = goal.goal.controls();
+        self.goals
+            .iter()
+            .filter(|other| other.running)
+            .filter(|other| other.goal.controls().intersects(controls))
+            .all(|other| other.priority > goal.priority)
+    }
+}
+
+/// The entity a mob is attacking, selected by
+/// goals which control `Controls::TARGET`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct AttackTarget(pub Option<Entity>);
+
+/// Component which walks a mob towards a target position.
+///
+/// Paths are searched for by `find_paths`. Until a path is
+/// found, or if the mob cannot find one from where it is, the
+/// mob walks in a straight line, jumping onto blocks in its way.
+#[derive(Clone, Debug, Default)]
+pub struct Navigation {
+    /// The position the mob is walking to.
+    pub target: Option<Position>,
+    /// The speed of the mob, in blocks per tick.
+    pub speed: f64,
+    /// Number of ticks since navigation started.
+    pub ticks: u32,
+    /// The path the mob is following.
+    pub path: Option<Path>,
+    /// Index of the next node of `path` to walk to.
+    pub next_node: usize,
+    /// The block `path` leads to, or which is being searched for.
+    path_goal: Option<BlockPosition>,
+    search: Option<Pathfinder>,
+}
+
+impl Navigation {
+    /// Stops navigation, discarding the current path.
+    pub fn stop(&mut self) {
+        self.target = None;
+        self.path = None;
+        self.path_goal = None;
+        self.search = None;
+    }
+}
+
+/// System which ticks the goals of all mobs.
+#[fecs::system]
+pub fn tick_mob_ai(game: &mut Game, world: &mut World) {
+    let entities: Vec<Entity> = <Read<GoalSelector>>::query()
+        .filter(!component::<Dead>())
+        .iter_entities(world.inner())
+        .map(|(entity, _)| entity)
+        .collect();
+
+    for entity in entities {
+        // A mob may be killed by another's goals.
+        if !world.is_alive(entity) {
+            continue;
+        }
+
+        // Goals need mutable access to the world, so
+        // the selector is taken out while it runs.
+        let mut selector = mem::take(&mut *world.get_mut::<GoalSelector>(entity));
+        selector.tick(&mut AiContext {
+            game: &mut *game,
+            world: &mut *world,
+            entity,
+        });
+
+        if let Some(mut slot) = world.try_get_mut::<GoalSelector>(entity) {
+            *slot = selector;
+        }
+    }
+}
+
+/// System which searches for paths to mobs' navigation targets.
+///
+/// Searches expand at most `PATHFINDING_BUDGET` nodes per tick in
+/// total. Searches which run out of budget resume on the next tick.
+#[fecs::system]
+pub fn find_paths(game: &mut Game, world: &mut World) {
+    let mut budget = PATHFINDING_BUDGET;
+    for (mut navigation, pos, physics) in
+        <(Write<Navigation>, Read<Position>, Read<Physics>)>::query().iter_mut(world.inner_mut())
+    {
+        if budget == 0 {
+            break;
+        }
+
+        let goal = match navigation.target {
+            Some(target) => target.block(),
+            None => continue,
+        };
+        if navigation.path_goal != Some(goal) {
+            navigation.path_goal = Some(goal);
+            navigation.search = Some(Pathfinder::new(
+                pos.block(),
+                goal,
+                PathOptions::for_physics(&physics),
+            ));
+        }
+
+        let status = match &mut navigation.search {
+            Some(search) => search.step(&game.chunk_map, &mut budget),
+            None => continue,
+        };
+        match status {
+            PathStatus::Searching => (),
+            PathStatus::Found(path) => {
+                navigation.path = Some(path);
+                navigation.next_node = 0;
+                navigation.search = None;
+            }
+            PathStatus::NotFound => {
+                navigation.path = None;
+                navigation.search = None;
+            }
+        }
+    }
+}
+
+/// System which walks mobs along their paths.
+#[fecs::system]
+pub fn tick_navigation(game: &mut Game, world: &mut World) {
+    <(
+        Write<Position>,
+        Write<Velocity>,
+        Write<Navigation>,
+        Read<Physics>,
+    )>::query()
+    .par_for_each_mut(
+        world.inner_mut(),
+        |(mut pos, mut velocity, mut navigation, physics)| {
+            let target = match navigation.target {
+                Some(target) => target,
+                None => return,
+            };
+
+            let (dx, dz) = (target.x - pos.x, target.z - pos.z);
+            if dx * dx + dz * dz < ARRIVAL_DISTANCE_SQUARED
+                || navigation.ticks >= MAX_NAVIGATION_TICKS
+            {
+                navigation.stop();
+                return;
+            }
+            navigation.ticks += 1;
+
+            // Once the path has been walked, the
+            // mob heads straight for the target.
+            let mut next = target;
+            if let Some(path) = &navigation.path {
+                let mut index = navigation.next_node;
+                while let Some(node) = path.node_position(index) {
+                    let (dx, dz) = (node.x - pos.x, node.z - pos.z);
+                    if dx * dx + dz * dz < NODE_REACHED_DISTANCE_SQUARED
+                        && (node.y - pos.y).abs() < 1.0
+                    {
+                        index += 1;
+                    } else {
+                        next = node;
+                        break;
+                    }
+                }
+                navigation.next_node = index;
+            }
+
+            let (dx, dz) = (next.x - pos.x, next.z - pos.z);
+            let distance_squared = dx * dx + dz * dz;
+            if distance_squared < 1.0e-6 {
+                return;
+            }
+
+            let distance = distance_squared.sqrt();
+            let (dir_x, dir_z) = (dx / distance, dz / distance);
+            velocity.0.x = dir_x * navigation.speed;
+            velocity.0.z = dir_z * navigation.speed;
+            pos.yaw = yaw_towards(dx, dz);
+
+            let reach = physics.bbox.size().x / 2.0 + 0.3;
+            let climbing = next.y - pos.y > 0.5;
+            if pos.on_ground && (climbing || is_blocked(game, *pos, dir_x * reach, dir_z * reach)) {
+                velocity.0.y = JUMP_VELOCITY;
+            }
+        },
+    );
+}
+
+/// Returns whether a mob at `pos` walking by (`dx`, `dz`)
+/// runs into a block which it can jump onto.
+fn is_blocked(game: &Game, pos: Position, dx: f64, dz: f64) -> bool {
+    let is_solid = |y: f64| {
+        game.block_at(position!(pos.x + dx, pos.y + y, pos.z + dz).block())
+            .map_or(false, |block| block.is_solid())
+    };
+    is_solid(0.0) && !is_solid(1.0) && !is_solid(2.0)
+}
+
+/// Returns the yaw, in degrees, of an entity facing along (`dx`, `dz`).
+fn yaw_towards(dx: f64, dz: f64) -> f32 {
+    (-dx).atan2(dz).to_degrees() as f32
+}
+
+/// Returns whether `entity` is a player who mobs may target,
+/// i.e. one who is alive and not in creative mode.
+pub fn is_targetable_player(world: &World, entity: Entity) -> bool {
+    world.has::<Player>(entity) && world.has::<CanTakeDamage>(entity) && !world.has::<Dead>(entity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::blocks::BlockId;
+    use feather_test_framework::Test;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Goal which counts its ticks and runs while `active` is set.
+    struct Counter {
+        controls: Controls,
+        active: bool,
+        ticks: Arc<AtomicUsize>,
+    }
+
+    impl Counter {
+        fn new(controls: Controls, active: bool) -> (Self, Arc<AtomicUsize>) {
+            let ticks = Arc::new(AtomicUsize::new(0));
+            let goal = Counter {
+                controls,
+                active,
+                ticks: Arc::clone(&ticks),
+            };
+            (goal, ticks)
+        }
+    }
+
+    impl Goal for Counter {
+        fn controls(&self) -> Controls {
+            self.controls
+        }
+
+        fn can_start(&mut self, _ctx: &mut AiContext) -> bool {
+            self.active
+        }
+
+        fn tick(&mut self, _ctx: &mut AiContext) {
+            self.ticks.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Returns the indices of the goals which are running.
+    fn running(selector: &GoalSelector) -> Vec<usize> {
+        selector
+            .goals
+            .iter()
+            .enumerate()
+            .filter(|(_, goal)| goal.running)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    fn tick(test: &mut Test, selector: &mut GoalSelector, entity: Entity) {
+        selector.tick(&mut AiContext {
+            game: &mut test.game,
+            world: &mut test.world,
+            entity,
+        });
+    }
+
+    #[test]
+    fn priorities() {
+        let mut test = Test::new();
+        let entity = test.entity(crate::zombie::create().with(Position::default()));
+
+        let (wander, wander_ticks) = Counter::new(Controls::MOVE, true);
+        let (look, look_ticks) = Counter::new(Controls::LOOK, true);
+        let (attack, attack_ticks) = Counter::new(Controls::MOVE | Controls::LOOK, true);
+        let mut selector = GoalSelector::new()
+            .with(7, wander)
+            .with(8, look)
+            .with(2, attack);
+
+        // The attack goal excludes the others
+        tick(&mut test, &mut selector, entity);
+        assert_eq!(running(&selector), vec![0]);
+        assert_eq!(attack_ticks.load(Ordering::SeqCst), 1);
+        assert_eq!(wander_ticks.load(Ordering::SeqCst), 0);
+        assert_eq!(look_ticks.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn compatible_goals_run_together() {
+        let mut test = Test::new();
+        let entity = test.entity(crate::zombie::create().with(Position::default()));
+
+        let (wander, wander_ticks) = Counter::new(Controls::MOVE, true);
+        let (look, look_ticks) = Counter::new(Controls::LOOK, true);
+        let mut selector = GoalSelector::new().with(7, wander).with(8, look);
+
+        tick(&mut test, &mut selector, entity);
+        tick(&mut test, &mut selector, entity);
+        assert_eq!(wander_ticks.load(Ordering::SeqCst), 2);
+        assert_eq!(look_ticks.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn higher_priority_interrupts() {
+        let mut test = Test::new();
+        let entity = test.entity(crate::zombie::create().with(Position::default()));
+
+        let (wander, wander_ticks) = Counter::new(Controls::MOVE, true);
+        let mut selector = GoalSelector::new().with(7, wander);
+        tick(&mut test, &mut selector, entity);
+        assert_eq!(wander_ticks.load(Ordering::SeqCst), 1);
+
+        let (panic, panic_ticks) = Counter::new(Controls::MOVE, true);
+        selector = selector.with(1, panic);
+        tick(&mut test, &mut selector, entity);
+        assert_eq!(running(&selector), vec![0]);
+        assert_eq!(panic_ticks.load(Ordering::SeqCst), 1);
+        assert_eq!(wander_ticks.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn navigation() {
+        let mut test = Test::new();
+        let entity = test.entity(crate::zombie::create().with(position!(0.0, 64.0, 0.0)));
+
+        test.world.get_mut::<Navigation>(entity).target = Some(position!(10.0, 64.0, 0.0));
+        test.world.get_mut::<Navigation>(entity).speed = 0.1;
+        test.run(tick_navigation);
+
+        let velocity = test.world.get::<Velocity>(entity).0;
+        assert!((velocity.x - 0.1).abs() < 1.0e-6);
+        assert!(velocity.z.abs() < 1.0e-6);
+        assert!((test.world.get::<Position>(entity).yaw + 90.0).abs() < 1.0e-3);
+
+        // Arriving stops navigation
+        test.position(entity, position!(10.0, 64.0, 0.2));
+        test.run(tick_navigation);
+        assert!(test.world.get::<Navigation>(entity).target.is_none());
+    }
+
+    #[test]
+    fn navigation_follows_path() {
+        let mut test = Test::new();
+        for x in -5..16 {
+            for z in -5..6 {
+                let pos = BlockPosition::new(x, 63, z);
+                test.game.chunk_map.set_block_at(pos, BlockId::stone());
+            }
+        }
+        // A wall with a gap at z = 3
+        for z in -5..6 {
+            for y in 64..66 {
+                if z != 3 {
+                    let pos = BlockPosition::new(5, y, z);
+                    test.game.chunk_map.set_block_at(pos, BlockId::stone());
+                }
+            }
+        }
+
+        let entity = test.entity(crate::zombie::create().with(position!(0.5, 64.0, 0.5)));
+        test.world.get_mut::<Navigation>(entity).target = Some(position!(10.5, 64.0, 0.5));
+        test.world.get_mut::<Navigation>(entity).speed = 0.1;
+        test.run(find_paths);
+
+        let first_node = {
+            let navigation = test.world.get::<Navigation>(entity);
+            let path = navigation.path.as_ref().unwrap();
+            assert!(path.reaches_goal);
+            assert!(path.nodes.contains(&BlockPosition::new(5, 64, 3)));
+            path.node_position(0).unwrap()
+        };
+
+        // The mob walks towards the first node of its path
+        test.run(tick_navigation);
+        let velocity = test.world.get::<Velocity>(entity).0;
+        let (dx, dz) = (first_node.x - 0.5, first_node.z - 0.5);
+        let length = (dx * dx + dz * dz).sqrt();
+        assert!((velocity.x - dx / length * 0.1).abs() < 1.0e-6);
+        assert!((velocity.z - dz / length * 0.1).abs() < 1.0e-6);
+    }
+}
@@ -0,0 +1,61 @@
+use super::{AiContext, Controls, Goal};
+use feather_core::util::Position;
+use fecs::{Entity, World};
+
+/// Goal which makes a mob run away from nearby
+/// entities of a certain kind, e.g. villagers from zombies.
+pub struct Flee {
+    range: f64,
+    speed: f64,
+    filter: fn(&World, Entity) -> bool,
+    target: Option<Position>,
+}
+
+impl Flee {
+    /// Creates a goal running at `speed` blocks per tick
+    /// from entities within `range` blocks matching `filter`.
+    pub fn new(range: f64, speed: f64, filter: fn(&World, Entity) -> bool) -> Self {
+        Self {
+            range,
+            speed,
+            filter,
+            target: None,
+        }
+    }
+}
+
+impl Goal for Flee {
+    fn controls(&self) -> Controls {
+        Controls::MOVE
+    }
+
+    fn can_start(&mut self, ctx: &mut AiContext) -> bool {
+        let threat = match ctx.nearest_entity(self.range, self.filter) {
+            Some(threat) => *ctx.world.get::<Position>(threat),
+            None => return false,
+        };
+
+        // Run directly away from the threat
+        let mut target = ctx.position();
+        let (dx, dz) = (target.x - threat.x, target.z - threat.z);
+        let distance = (dx * dx + dz * dz).sqrt().max(1.0e-4);
+        target.x += dx / distance * self.range;
+        target.z += dz / distance * self.range;
+        self.target = Some(target);
+        true
+    }
+
+    fn should_continue(&mut self, ctx: &mut AiContext) -> bool {
+        ctx.is_navigating()
+    }
+
+    fn start(&mut self, ctx: &mut AiContext) {
+        if let Some(target) = self.target.take() {
+            ctx.navigate_to(target, self.speed);
+        }
+    }
+
+    fn stop(&mut self, ctx: &mut AiContext) {
+        ctx.stop_navigation();
+    }
+}
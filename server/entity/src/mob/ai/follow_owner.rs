@@ -0,0 +1,94 @@
+use super::{AiContext, Controls, Goal};
+use feather_core::util::Position;
+use feather_server_types::{Player, Uuid};
+use fecs::{component, Entity, IntoQuery, Read};
+
+/// Distance from its owner at which a pet
+/// teleports instead of walking.
+const TELEPORT_DISTANCE: f64 = 12.0;
+
+/// The player who owns a tamed mob.
+#[derive(Copy, Clone, Debug)]
+pub struct Owner(pub Uuid);
+
+/// Goal which makes a tamed mob follow its `Owner`.
+pub struct FollowOwner {
+    speed: f64,
+    /// Distance from the owner at which the mob starts following.
+    start_distance: f64,
+    /// Distance from the owner at which the mob stops following.
+    stop_distance: f64,
+    owner: Option<Entity>,
+}
+
+impl FollowOwner {
+    /// Creates a goal following the owner at `speed` blocks per tick
+    /// when they are between `stop_distance` and `start_distance` blocks away.
+    pub fn new(speed: f64, start_distance: f64, stop_distance: f64) -> Self {
+        Self {
+            speed,
+            start_distance,
+            stop_distance,
+            owner: None,
+        }
+    }
+
+    /// Returns the owner of the mob if they are online.
+    fn find_owner(ctx: &AiContext) -> Option<Entity> {
+        let owner = ctx.world.try_get::<Owner>(ctx.entity)?.0;
+        <Read<Uuid>>::query()
+            .filter(component::<Player>())
+            .iter_entities(ctx.world.inner())
+            .find(|(_, uuid)| **uuid == owner)
+            .map(|(entity, _)| entity)
+    }
+}
+
+impl Goal for FollowOwner {
+    fn controls(&self) -> Controls {
+        Controls::MOVE | Controls::LOOK
+    }
+
+    fn can_start(&mut self, ctx: &mut AiContext) -> bool {
+        self.owner = Self::find_owner(ctx);
+        match self.owner {
+            Some(owner) => ctx.distance_squared_to(owner) > self.start_distance.powi(2),
+            None => false,
+        }
+    }
+
+    fn should_continue(&mut self, ctx: &mut AiContext) -> bool {
+        match self.owner {
+            Some(owner) => {
+                ctx.world.is_alive(owner)
+                    && ctx.distance_squared_to(owner) > self.stop_distance.powi(2)
+            }
+            None => false,
+        }
+    }
+
+    fn tick(&mut self, ctx: &mut AiContext) {
+        let owner = match self.owner {
+            Some(owner) => owner,
+            None => return,
+        };
+        let owner_pos = *ctx.world.get::<Position>(owner);
+        ctx.look_at(owner_pos);
+
+        if ctx.distance_squared_to(owner) > TELEPORT_DISTANCE.powi(2) {
+            let mut pos = ctx.world.get_mut::<Position>(ctx.entity);
+            pos.x = owner_pos.x;
+            pos.y = owner_pos.y;
+            pos.z = owner_pos.z;
+            drop(pos);
+            ctx.stop_navigation();
+        } else {
+            ctx.navigate_to(owner_pos, self.speed);
+        }
+    }
+
+    fn stop(&mut self, ctx: &mut AiContext) {
+        self.owner = None;
+        ctx.stop_navigation();
+    }
+}
@@ -0,0 +1,73 @@
+use super::{AiContext, Controls, Goal};
+use feather_core::util::Position;
+use feather_server_types::{Dead, Player};
+use fecs::Entity;
+use rand::Rng;
+
+/// Height of a player's eyes above their feet.
+const PLAYER_EYE_HEIGHT: f64 = 1.62;
+
+/// Goal which makes a mob look at nearby players every now and then.
+pub struct LookAtPlayer {
+    range: f64,
+    target: Option<Entity>,
+    ticks_left: u32,
+}
+
+impl LookAtPlayer {
+    /// Creates a goal looking at players within `range` blocks.
+    pub fn new(range: f64) -> Self {
+        Self {
+            range,
+            target: None,
+            ticks_left: 0,
+        }
+    }
+}
+
+impl Goal for LookAtPlayer {
+    fn controls(&self) -> Controls {
+        // Looking turns the whole mob, so it
+        // can't be done while walking.
+        Controls::MOVE | Controls::LOOK
+    }
+
+    fn can_start(&mut self, ctx: &mut AiContext) -> bool {
+        if ctx.game.rng().gen_range(0, 50) != 0 {
+            return false;
+        }
+
+        self.target = ctx.nearest_entity(self.range, |world, entity| {
+            world.has::<Player>(entity) && !world.has::<Dead>(entity)
+        });
+        self.target.is_some()
+    }
+
+    fn should_continue(&mut self, ctx: &mut AiContext) -> bool {
+        match self.target {
+            Some(target) => {
+                self.ticks_left > 0
+                    && ctx.world.is_alive(target)
+                    && ctx.distance_squared_to(target) <= self.range * self.range
+            }
+            None => false,
+        }
+    }
+
+    fn start(&mut self, ctx: &mut AiContext) {
+        self.ticks_left = 40 + ctx.game.rng().gen_range(0, 40);
+    }
+
+    fn tick(&mut self, ctx: &mut AiContext) {
+        if let Some(target) = self.target {
+            let mut pos: Position = *ctx.world.get::<Position>(target);
+            pos.y += PLAYER_EYE_HEIGHT;
+            ctx.look_at(pos);
+        }
+        self.ticks_left = self.ticks_left.saturating_sub(1);
+    }
+
+    fn stop(&mut self, _ctx: &mut AiContext) {
+        self.target = None;
+    }
+}
@@ -0,0 +1,77 @@
+use super::{AiContext, AttackTarget, Controls, Goal};
+use crate::combat;
+use feather_core::network::packets::{AnimationClientbound, ClientboundAnimation};
+use feather_core::util::Position;
+use feather_server_types::NetworkId;
+use fecs::Entity;
+
+/// Number of ticks between two attacks of a mob.
+const ATTACK_INTERVAL: u64 = 20;
+/// Squared distance from which a mob can hit its target.
+const ATTACK_REACH_SQUARED: f64 = 4.0;
+
+/// Goal which makes a mob chase and hit its `AttackTarget`.
+pub struct MeleeAttack {
+    speed: f64,
+    damage: f64,
+    last_attack: u64,
+}
+
+impl MeleeAttack {
+    /// Creates a goal chasing the target at `speed` blocks
+    /// per tick and dealing `damage` half-hearts per hit.
+    pub fn new(speed: f64, damage: f64) -> Self {
+        Self {
+            speed,
+            damage,
+            last_attack: 0,
+        }
+    }
+
+    fn target(ctx: &AiContext) -> Option<Entity> {
+        ctx.world
+            .get::<AttackTarget>(ctx.entity)
+            .0
+            .filter(|target| combat::can_attack(ctx.game, ctx.world, ctx.entity, *target))
+    }
+}
+
+impl Goal for MeleeAttack {
+    fn controls(&self) -> Controls {
+        Controls::MOVE | Controls::LOOK
+    }
+
+    fn can_start(&mut self, ctx: &mut AiContext) -> bool {
+        Self::target(ctx).is_some()
+    }
+
+    fn tick(&mut self, ctx: &mut AiContext) {
+        let target = match Self::target(ctx) {
+            Some(target) => target,
+            None => return,
+        };
+
+        let target_pos = *ctx.world.get::<Position>(target);
+        ctx.look_at(target_pos);
+        ctx.navigate_to(target_pos, self.speed);
+
+        if ctx.distance_squared_to(target) > ATTACK_REACH_SQUARED
+            || ctx.game.tick_count < self.last_attack + ATTACK_INTERVAL
+        {
+            return;
+        }
+        self.last_attack = ctx.game.tick_count;
+
+        let packet = AnimationClientbound {
+            entity_id: ctx.world.get::<NetworkId>(ctx.entity).0,
+            animation: ClientboundAnimation::SwingMainArm,
+        };
+        ctx.game
+            .broadcast_entity_update(ctx.world, packet, ctx.entity, None);
+        combat::hit(ctx.game, ctx.world, ctx.entity, target, self.damage, 0.0);
+    }
+
+    fn stop(&mut self, ctx: &mut AiContext) {
+        ctx.stop_navigation();
+    }
+}
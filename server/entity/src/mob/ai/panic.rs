@@ -0,0 +1,65 @@
+use super::{AiContext, Controls, Goal};
+use feather_core::util::Position;
+use feather_server_types::LastAttacker;
+use rand::Rng;
+
+/// Horizontal distance, in blocks, within which
+/// panicking mobs choose where to run.
+const PANIC_RANGE: f64 = 5.0;
+
+/// Goal which makes a mob run around quickly after being attacked.
+pub struct Panic {
+    speed: f64,
+    /// The tick of the last attack this goal reacted to.
+    handled_attack: Option<u64>,
+    target: Option<Position>,
+}
+
+impl Panic {
+    /// Creates a goal running at `speed` blocks per tick.
+    pub fn new(speed: f64) -> Self {
+        Self {
+            speed,
+            handled_attack: None,
+            target: None,
+        }
+    }
+}
+
+impl Goal for Panic {
+    fn controls(&self) -> Controls {
+        Controls::MOVE
+    }
+
+    fn can_start(&mut self, ctx: &mut AiContext) -> bool {
+        let tick = match ctx.world.try_get::<LastAttacker>(ctx.entity) {
+            Some(attacker) => attacker.tick,
+            None => return false,
+        };
+        if self.handled_attack == Some(tick) {
+            return false;
+        }
+        self.handled_attack = Some(tick);
+
+        let mut rng = ctx.game.rng();
+        let mut target = ctx.position();
+        target.x += rng.gen_range(-PANIC_RANGE, PANIC_RANGE);
+        target.z += rng.gen_range(-PANIC_RANGE, PANIC_RANGE);
+        self.target = Some(target);
+        true
+    }
+
+    fn should_continue(&mut self, ctx: &mut AiContext) -> bool {
+        ctx.is_navigating()
+    }
+
+    fn start(&mut self, ctx: &mut AiContext) {
+        if let Some(target) = self.target.take() {
+            ctx.navigate_to(target, self.speed);
+        }
+    }
+
+    fn stop(&mut self, ctx: &mut AiContext) {
+        ctx.stop_navigation();
+    }
+}
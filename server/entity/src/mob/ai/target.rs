@@ -0,0 +1,117 @@
+use super::{AiContext, AttackTarget, Controls, Goal};
+use crate::combat;
+use feather_server_types::LastAttacker;
+use fecs::{Entity, World};
+use rand::Rng;
+
+/// Goal which makes a mob target the closest entity
+/// matching a filter, e.g. zombies targeting players.
+pub struct NearestAttackableTarget {
+    range: f64,
+    filter: fn(&World, Entity) -> bool,
+    target: Option<Entity>,
+}
+
+impl NearestAttackableTarget {
+    /// Creates a goal targeting entities within
+    /// `range` blocks which match `filter`.
+    pub fn new(range: f64, filter: fn(&World, Entity) -> bool) -> Self {
+        Self {
+            range,
+            filter,
+            target: None,
+        }
+    }
+}
+
+impl Goal for NearestAttackableTarget {
+    fn controls(&self) -> Controls {
+        Controls::TARGET
+    }
+
+    fn can_start(&mut self, ctx: &mut AiContext) -> bool {
+        if ctx.game.rng().gen_range(0, 10) != 0 {
+            return false;
+        }
+
+        self.target = ctx.nearest_entity(self.range, self.filter);
+        self.target.is_some()
+    }
+
+    fn should_continue(&mut self, ctx: &mut AiContext) -> bool {
+        is_valid_target(ctx, self.target, self.range)
+    }
+
+    fn start(&mut self, ctx: &mut AiContext) {
+        ctx.world.get_mut::<AttackTarget>(ctx.entity).0 = self.target;
+    }
+
+    fn stop(&mut self, ctx: &mut AiContext) {
+        ctx.world.get_mut::<AttackTarget>(ctx.entity).0 = None;
+        self.target = None;
+    }
+}
+
+/// Goal which makes a mob target the last entity which attacked it.
+pub struct HurtByTarget {
+    range: f64,
+    /// The tick of the last attack this goal reacted to.
+    handled_attack: Option<u64>,
+    target: Option<Entity>,
+}
+
+impl HurtByTarget {
+    /// Creates a goal targeting attackers until
+    /// they are more than `range` blocks away.
+    pub fn new(range: f64) -> Self {
+        Self {
+            range,
+            handled_attack: None,
+            target: None,
+        }
+    }
+}
+
+impl Goal for HurtByTarget {
+    fn controls(&self) -> Controls {
+        Controls::TARGET
+    }
+
+    fn can_start(&mut self, ctx: &mut AiContext) -> bool {
+        let attacker = match ctx.world.try_get::<LastAttacker>(ctx.entity) {
+            Some(attacker) => *attacker,
+            None => return false,
+        };
+        if self.handled_attack == Some(attacker.tick) {
+            return false;
+        }
+        self.handled_attack = Some(attacker.tick);
+
+        self.target = Some(attacker.entity);
+        is_valid_target(ctx, self.target, self.range)
+    }
+
+    fn should_continue(&mut self, ctx: &mut AiContext) -> bool {
+        is_valid_target(ctx, self.target, self.range)
+    }
+
+    fn start(&mut self, ctx: &mut AiContext) {
+        ctx.world.get_mut::<AttackTarget>(ctx.entity).0 = self.target;
+    }
+
+    fn stop(&mut self, ctx: &mut AiContext) {
+        ctx.world.get_mut::<AttackTarget>(ctx.entity).0 = None;
+        self.target = None;
+    }
+}
+
+/// Returns whether the mob can keep attacking `target`.
+fn is_valid_target(ctx: &AiContext, target: Option<Entity>, range: f64) -> bool {
+    match target {
+        Some(target) => {
+            combat::can_attack(ctx.game, ctx.world, ctx.entity, target)
+                && ctx.distance_squared_to(target) <= range * range
+        }
+        None => false,
+    }
+}
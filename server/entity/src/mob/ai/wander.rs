@@ -0,0 +1,61 @@
+use super::{AiContext, Controls, Goal};
+use feather_core::util::Position;
+use rand::Rng;
+
+/// Horizontal distance, in blocks, within which
+/// wandering mobs choose where to walk.
+const WANDER_RANGE: f64 = 10.0;
+
+/// Goal which makes a mob walk to random nearby
+/// positions every now and then.
+pub struct Wander {
+    speed: f64,
+    /// The mob starts wandering with a chance
+    /// of one in `chance` each tick.
+    chance: u32,
+    target: Option<Position>,
+}
+
+impl Wander {
+    /// Creates a goal wandering at `speed` blocks per tick.
+    pub fn new(speed: f64) -> Self {
+        Self {
+            speed,
+            chance: 120,
+            target: None,
+        }
+    }
+}
+
+impl Goal for Wander {
+    fn controls(&self) -> Controls {
+        Controls::MOVE
+    }
+
+    fn can_start(&mut self, ctx: &mut AiContext) -> bool {
+        let mut rng = ctx.game.rng();
+        if rng.gen_range(0, self.chance) != 0 {
+            return false;
+        }
+
+        let mut target = ctx.position();
+        target.x += rng.gen_range(-WANDER_RANGE, WANDER_RANGE);
+        target.z += rng.gen_range(-WANDER_RANGE, WANDER_RANGE);
+        self.target = Some(target);
+        true
+    }
+
+    fn should_continue(&mut self, ctx: &mut AiContext) -> bool {
+        ctx.is_navigating()
+    }
+
+    fn start(&mut self, ctx: &mut AiContext) {
+        if let Some(target) = self.target.take() {
+            ctx.navigate_to(target, self.speed);
+        }
+    }
+
+    fn stop(&mut self, ctx: &mut AiContext) {
+        ctx.stop_navigation();
+    }
+}
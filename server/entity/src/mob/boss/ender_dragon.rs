@@ -1,8 +1,11 @@
+use crate::mob::ai::{GoalSelector, LookAtPlayer};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct EnderDragon;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::EnderDragon).with(EnderDragon)
+    mob::base(MobKind::EnderDragon)
+        .with(EnderDragon)
+        .with(GoalSelector::new().with(8, LookAtPlayer::new(16.0)))
 }
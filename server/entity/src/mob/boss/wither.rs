@@ -1,8 +1,11 @@
+use crate::mob::ai::{GoalSelector, LookAtPlayer};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct Wither;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Wither).with(Wither)
+    mob::base(MobKind::Wither)
+        .with(Wither)
+        .with(GoalSelector::new().with(8, LookAtPlayer::new(16.0)))
 }
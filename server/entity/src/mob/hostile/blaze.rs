@@ -1,8 +1,11 @@
+use crate::mob::ai::{GoalSelector, LookAtPlayer};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct Blaze;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Blaze).with(Blaze)
+    mob::base(MobKind::Blaze)
+        .with(Blaze)
+        .with(GoalSelector::new().with(8, LookAtPlayer::new(16.0)))
 }
@@ -1,8 +1,21 @@
+use crate::cat::Cat;
+use crate::mob::ai::{Flee, GoalSelector, LookAtPlayer, Wander};
+use crate::ocelot::Ocelot;
 use crate::{mob, MobKind};
-use fecs::EntityBuilder;
+use fecs::{Entity, EntityBuilder, World};
 
 pub struct Creeper;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Creeper).with(Creeper)
+    mob::base(MobKind::Creeper).with(Creeper).with(
+        GoalSelector::new()
+            .with(3, Flee::new(6.0, 0.15, is_ocelot))
+            .with(5, Wander::new(0.1))
+            .with(6, LookAtPlayer::new(8.0)),
+    )
+}
+
+/// Creepers flee from ocelots and cats.
+fn is_ocelot(world: &World, entity: Entity) -> bool {
+    world.has::<Ocelot>(entity) || world.has::<Cat>(entity)
 }
@@ -1,8 +1,11 @@
+use crate::mob::ai::{GoalSelector, LookAtPlayer};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct ElderGuardian;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::ElderGuardian).with(ElderGuardian)
+    mob::base(MobKind::ElderGuardian)
+        .with(ElderGuardian)
+        .with(GoalSelector::new().with(8, LookAtPlayer::new(8.0)))
 }
@@ -1,8 +1,13 @@
+use crate::mob::ai::{GoalSelector, LookAtPlayer, Wander};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct Evoker;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::EvocationIllager).with(Evoker)
+    mob::base(MobKind::EvocationIllager).with(Evoker).with(
+        GoalSelector::new()
+            .with(5, Wander::new(0.1))
+            .with(6, LookAtPlayer::new(8.0)),
+    )
 }
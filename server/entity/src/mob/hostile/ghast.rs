@@ -1,8 +1,11 @@
+use crate::mob::ai::{GoalSelector, LookAtPlayer};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct Ghast;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Ghast).with(Ghast)
+    mob::base(MobKind::Ghast)
+        .with(Ghast)
+        .with(GoalSelector::new().with(8, LookAtPlayer::new(16.0)))
 }
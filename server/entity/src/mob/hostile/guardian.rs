@@ -1,8 +1,11 @@
+use crate::mob::ai::{GoalSelector, LookAtPlayer};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct Guardian;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Guardian).with(Guardian)
+    mob::base(MobKind::Guardian)
+        .with(Guardian)
+        .with(GoalSelector::new().with(8, LookAtPlayer::new(8.0)))
 }
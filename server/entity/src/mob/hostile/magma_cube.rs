@@ -1,8 +1,11 @@
+use crate::mob::ai::{GoalSelector, LookAtPlayer};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct MagmaCube;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::MagmaCube).with(MagmaCube)
+    mob::base(MobKind::MagmaCube)
+        .with(MagmaCube)
+        .with(GoalSelector::new().with(8, LookAtPlayer::new(8.0)))
 }
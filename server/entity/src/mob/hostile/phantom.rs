@@ -1,8 +1,11 @@
+use crate::mob::ai::{GoalSelector, LookAtPlayer};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct Phantom;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Phantom).with(Phantom)
+    mob::base(MobKind::Phantom)
+        .with(Phantom)
+        .with(GoalSelector::new().with(8, LookAtPlayer::new(8.0)))
 }
@@ -1,8 +1,11 @@
+use crate::mob::ai::{GoalSelector, LookAtPlayer};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct Shulker;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Shulker).with(Shulker)
+    mob::base(MobKind::Shulker)
+        .with(Shulker)
+        .with(GoalSelector::new().with(8, LookAtPlayer::new(8.0)))
 }
@@ -1,8 +1,20 @@
+use crate::mob::ai::{Flee, GoalSelector, LookAtPlayer, Wander};
+use crate::wolf::Wolf;
 use crate::{mob, MobKind};
-use fecs::EntityBuilder;
+use fecs::{Entity, EntityBuilder, World};
 
 pub struct Skeleton;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Skeleton).with(Skeleton)
+    mob::base(MobKind::Skeleton).with(Skeleton).with(
+        GoalSelector::new()
+            .with(3, Flee::new(6.0, 0.15, is_wolf))
+            .with(5, Wander::new(0.1))
+            .with(6, LookAtPlayer::new(8.0)),
+    )
+}
+
+/// Skeletons flee from wolves.
+fn is_wolf(world: &World, entity: Entity) -> bool {
+    world.has::<Wolf>(entity)
 }
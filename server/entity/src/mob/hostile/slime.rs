@@ -1,8 +1,11 @@
+use crate::mob::ai::{GoalSelector, LookAtPlayer};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct Slime;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Slime).with(Slime)
+    mob::base(MobKind::Slime)
+        .with(Slime)
+        .with(GoalSelector::new().with(8, LookAtPlayer::new(8.0)))
 }
@@ -1,8 +1,11 @@
+use crate::mob::ai::{GoalSelector, LookAtPlayer};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct Vex;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Vex).with(Vex)
+    mob::base(MobKind::Vex)
+        .with(Vex)
+        .with(GoalSelector::new().with(8, LookAtPlayer::new(8.0)))
 }
@@ -1,8 +1,13 @@
+use crate::mob::ai::{GoalSelector, LookAtPlayer, Wander};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct Witch;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Witch).with(Witch)
+    mob::base(MobKind::Witch).with(Witch).with(
+        GoalSelector::new()
+            .with(5, Wander::new(0.1))
+            .with(6, LookAtPlayer::new(8.0)),
+    )
 }
@@ -1,8 +1,21 @@
+use crate::mob::ai::{
+    self, GoalSelector, HurtByTarget, LookAtPlayer, MeleeAttack, NearestAttackableTarget, Wander,
+};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct Zombie;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Zombie).with(Zombie)
+    mob::base(MobKind::Zombie).with(Zombie).with(
+        GoalSelector::new()
+            .with(2, MeleeAttack::new(0.15, 3.0))
+            .with(7, Wander::new(0.1))
+            .with(8, LookAtPlayer::new(8.0))
+            .with(1, HurtByTarget::new(32.0))
+            .with(
+                2,
+                NearestAttackableTarget::new(16.0, ai::is_targetable_player),
+            ),
+    )
 }
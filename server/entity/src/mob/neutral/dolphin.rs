@@ -1,8 +1,11 @@
+use crate::mob::ai::{GoalSelector, LookAtPlayer};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct Dolphin;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Dolphin).with(Dolphin)
+    mob::base(MobKind::Dolphin)
+        .with(Dolphin)
+        .with(GoalSelector::new().with(8, LookAtPlayer::new(8.0)))
 }
@@ -1,8 +1,16 @@
+use crate::mob::ai::{FollowOwner, GoalSelector, HurtByTarget, LookAtPlayer, MeleeAttack, Wander};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct Wolf;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Wolf).with(Wolf)
+    mob::base(MobKind::Wolf).with(Wolf).with(
+        GoalSelector::new()
+            .with(4, MeleeAttack::new(0.18, 4.0))
+            .with(6, FollowOwner::new(0.15, 10.0, 2.0))
+            .with(7, Wander::new(0.1))
+            .with(8, LookAtPlayer::new(8.0))
+            .with(1, HurtByTarget::new(32.0)),
+    )
 }
@@ -1,8 +1,15 @@
+use crate::mob::ai::{GoalSelector, HurtByTarget, LookAtPlayer, MeleeAttack, Wander};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct ZombiePigman;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::PigZombie).with(ZombiePigman)
+    mob::base(MobKind::PigZombie).with(ZombiePigman).with(
+        GoalSelector::new()
+            .with(4, MeleeAttack::new(0.15, 5.0))
+            .with(7, Wander::new(0.1))
+            .with(8, LookAtPlayer::new(8.0))
+            .with(1, HurtByTarget::new(32.0)),
+    )
 }
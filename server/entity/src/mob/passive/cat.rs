@@ -1,8 +1,15 @@
+use crate::mob::ai::{FollowOwner, GoalSelector, LookAtPlayer, Panic, Wander};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct Cat;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Ocelot).with(Cat)
+    mob::base(MobKind::Ocelot).with(Cat).with(
+        GoalSelector::new()
+            .with(1, Panic::new(0.2))
+            .with(5, FollowOwner::new(0.15, 10.0, 2.0))
+            .with(6, Wander::new(0.1))
+            .with(7, LookAtPlayer::new(6.0)),
+    )
 }
@@ -1,8 +1,14 @@
+use crate::mob::ai::{GoalSelector, LookAtPlayer, Panic, Wander};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct Mooshroom;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::MushroomCow).with(Mooshroom)
+    mob::base(MobKind::MushroomCow).with(Mooshroom).with(
+        GoalSelector::new()
+            .with(1, Panic::new(0.2))
+            .with(6, Wander::new(0.1))
+            .with(7, LookAtPlayer::new(6.0)),
+    )
 }
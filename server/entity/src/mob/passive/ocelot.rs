@@ -1,8 +1,14 @@
+use crate::mob::ai::{GoalSelector, LookAtPlayer, Panic, Wander};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct Ocelot;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Ocelot).with(Ocelot)
+    mob::base(MobKind::Ocelot).with(Ocelot).with(
+        GoalSelector::new()
+            .with(1, Panic::new(0.2))
+            .with(6, Wander::new(0.1))
+            .with(7, LookAtPlayer::new(6.0)),
+    )
 }
@@ -1,8 +1,15 @@
+use crate::mob::ai::{self, Flee, GoalSelector, LookAtPlayer, Panic, Wander};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct Rabbit;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Rabbit).with(Rabbit)
+    mob::base(MobKind::Rabbit).with(Rabbit).with(
+        GoalSelector::new()
+            .with(1, Panic::new(0.2))
+            .with(3, Flee::new(8.0, 0.15, ai::is_targetable_player))
+            .with(6, Wander::new(0.1))
+            .with(7, LookAtPlayer::new(6.0)),
+    )
 }
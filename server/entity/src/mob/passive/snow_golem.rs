@@ -1,8 +1,13 @@
+use crate::mob::ai::{GoalSelector, LookAtPlayer, Wander};
 use crate::{mob, MobKind};
 use fecs::EntityBuilder;
 
 pub struct SnowGolem;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::SnowGolem).with(SnowGolem)
+    mob::base(MobKind::SnowGolem).with(SnowGolem).with(
+        GoalSelector::new()
+            .with(6, Wander::new(0.1))
+            .with(7, LookAtPlayer::new(6.0)),
+    )
 }
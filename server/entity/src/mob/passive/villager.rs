@@ -1,8 +1,29 @@
+use crate::drowned::Drowned;
+use crate::husk::Husk;
+use crate::mob::ai::{Flee, GoalSelector, LookAtPlayer, Panic, Wander};
+use crate::vindicator::Vindicator;
+use crate::zombie::Zombie;
+use crate::zombie_villager::ZombieVillager;
 use crate::{mob, MobKind};
-use fecs::EntityBuilder;
+use fecs::{Entity, EntityBuilder, World};
 
 pub struct Villager;
 
 pub fn create() -> EntityBuilder {
-    mob::base(MobKind::Villager).with(Villager)
+    mob::base(MobKind::Villager).with(Villager).with(
+        GoalSelector::new()
+            .with(1, Panic::new(0.2))
+            .with(3, Flee::new(8.0, 0.15, is_zombie))
+            .with(6, Wander::new(0.1))
+            .with(7, LookAtPlayer::new(6.0)),
+    )
+}
+
+/// Villagers flee from zombies.
+fn is_zombie(world: &World, entity: Entity) -> bool {
+    world.has::<Zombie>(entity)
+        || world.has::<Husk>(entity)
+        || world.has::<Drowned>(entity)
+        || world.has::<ZombieVillager>(entity)
+        || world.has::<Vindicator>(entity)
 }
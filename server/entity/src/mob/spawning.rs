@@ -0,0 +1,533 @@
+//! Natural spawning and despawning of mobs.
+//!
+//! Each tick, for each category of mobs below its cap, groups
+//! of mobs are spawned at random positions in the chunks around
+//! players, picked from the spawn lists of the biome they spawn in.
+//! Monsters need darkness, animals need grass and light, bats need
+//! caves and water creatures need water.
+//!
+//! Mobs other than animals despawn once no player is nearby,
+//! unless they are persistent: only naturally spawned mobs
+//! without a custom name despawn.
+
+use super::MobNbt;
+use feather_core::biomes::{SpawnCategory, SpawnEntry};
+use feather_core::blocks::{BlockId, BlockKind, SimplifiedBlockKind};
+use feather_core::chunk_map::chunk_relative_pos;
+use feather_core::util::{BlockPosition, ChunkPosition, Position};
+use feather_server_types::{Dead, EntitySpawnEvent, Game, Player};
+use fecs::{component, EntityBuilder, IntoQuery, Read, World};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+
+/// Radius, in chunks, of the square of chunks
+/// around each player in which mobs spawn.
+const CHUNK_RADIUS: i32 = 8;
+/// Number of chunks around a single player, for which
+/// mob caps are defined.
+const CHUNKS_PER_PLAYER: usize = 289;
+/// Animals only spawn once every this many ticks.
+const ANIMAL_SPAWN_INTERVAL: u64 = 400;
+/// Number of groups of mobs spawned in each chunk per tick.
+const GROUPS_PER_CHUNK: usize = 3;
+/// Maximum distance along each axis between
+/// consecutive mobs of a group.
+const GROUP_SPREAD: i32 = 6;
+/// Mobs do not spawn closer than this to
+/// a player or to the world spawn point.
+const MIN_SPAWN_DISTANCE: f64 = 24.0;
+/// Mobs further than this from every player despawn.
+const DESPAWN_DISTANCE: f64 = 128.0;
+/// Mobs further than this from every player
+/// have a small chance of despawning each tick.
+const RANDOM_DESPAWN_DISTANCE: f64 = 32.0;
+/// One in this many mobs eligible for
+/// random despawning despawns each tick.
+const RANDOM_DESPAWN_CHANCE: u32 = 800;
+const SEA_LEVEL: i32 = 63;
+
+/// System which naturally spawns mobs around players.
+#[fecs::system]
+pub fn spawn_mobs(game: &mut Game, world: &mut World) {
+    if !game.game_rules.do_mob_spawning {
+        return;
+    }
+
+    let players = player_positions(world);
+    if players.is_empty() {
+        return;
+    }
+
+    let (chunk_count, mut chunks) = chunks_around(game, &players);
+    chunks.shuffle(&mut *game.rng());
+
+    let mut counts: HashMap<SpawnCategory, usize> = HashMap::new();
+    for category in <Read<SpawnCategory>>::query().iter(world.inner()) {
+        *counts.entry(*category).or_default() += 1;
+    }
+
+    for &category in &SpawnCategory::values() {
+        if !should_spawn(game, category) {
+            continue;
+        }
+
+        let cap = category.mob_cap() * chunk_count / CHUNKS_PER_PLAYER;
+        let mut count = counts.get(&category).copied().unwrap_or_default();
+        for chunk in &chunks {
+            if count >= cap {
+                break;
+            }
+            count += spawn_in_chunk(game, world, *chunk, category, &players);
+        }
+    }
+}
+
+/// System which despawns mobs far away from players.
+///
+/// Animals and persistent mobs never despawn.
+#[fecs::system]
+pub fn despawn_mobs(game: &mut Game, world: &mut World) {
+    let players = player_positions(world);
+    if players.is_empty() {
+        return;
+    }
+
+    let mut despawned = vec![];
+    for (entity, (pos, category, nbt)) in
+        <(Read<Position>, Read<SpawnCategory>, Read<MobNbt>)>::query().iter_entities(world.inner())
+    {
+        if category.is_peaceful() || nbt.is_persistent() {
+            continue;
+        }
+
+        let distance_squared = players
+            .iter()
+            .map(|player| player.distance_squared_to(*pos))
+            .fold(f64::INFINITY, f64::min);
+        if distance_squared > DESPAWN_DISTANCE * DESPAWN_DISTANCE
+            || (distance_squared > RANDOM_DESPAWN_DISTANCE * RANDOM_DESPAWN_DISTANCE
+                && game.rng().gen_range(0, RANDOM_DESPAWN_CHANCE) == 0)
+        {
+            despawned.push(entity);
+        }
+    }
+
+    for entity in despawned {
+        game.despawn(entity, world);
+    }
+}
+
+fn player_positions(world: &World) -> Vec<Position> {
+    <(Read<Position>, Read<Player>)>::query()
+        .filter(!component::<Dead>())
+        .iter(world.inner())
+        .map(|(pos, _)| *pos)
+        .collect()
+}
+
+/// Returns whether mobs of the given category may spawn this tick.
+fn should_spawn(game: &Game, category: SpawnCategory) -> bool {
+    let gameplay = &game.config.gameplay;
+    match category {
+        SpawnCategory::Monster => gameplay.monster_spawning,
+        SpawnCategory::Creature => {
+            gameplay.animal_spawning && game.tick_count % ANIMAL_SPAWN_INTERVAL == 0
+        }
+        SpawnCategory::Ambient | SpawnCategory::WaterCreature => gameplay.animal_spawning,
+    }
+}
+
+/// Returns the number of loaded chunks around players, which
+/// determines mob caps, along with the chunks mobs may spawn in:
+/// those not on the edge of the area around players.
+fn chunks_around(game: &Game, players: &[Position]) -> (usize, Vec<ChunkPosition>) {
+    let mut chunks = HashSet::new();
+    let mut spawnable = HashSet::new();
+    for player in players {
+        let center = player.chunk();
+        for x in -CHUNK_RADIUS..=CHUNK_RADIUS {
+            for z in -CHUNK_RADIUS..=CHUNK_RADIUS {
+                let chunk = ChunkPosition::new(center.x + x, center.z + z);
+                if game.chunk_map.chunk_at(chunk).is_none() {
+                    continue;
+                }
+
+                chunks.insert(chunk);
+                if x.abs() < CHUNK_RADIUS && z.abs() < CHUNK_RADIUS {
+                    spawnable.insert(chunk);
+                }
+            }
+        }
+    }
+
+    (chunks.len(), spawnable.into_iter().collect())
+}
+
+/// Attempts to spawn groups of mobs of the given category
+/// in a chunk. Returns the number of mobs spawned.
+fn spawn_in_chunk(
+    game: &mut Game,
+    world: &mut World,
+    chunk: ChunkPosition,
+    category: SpawnCategory,
+    players: &[Position],
+) -> usize {
+    let start = match random_position_in_chunk(game, chunk) {
+        Some(start) => start,
+        None => return 0,
+    };
+    if game.block_at(start).map_or(true, |block| block.is_solid()) {
+        return 0;
+    }
+
+    (0..GROUPS_PER_CHUNK)
+        .map(|_| spawn_group(game, world, start, category, players))
+        .sum()
+}
+
+fn random_position_in_chunk(game: &Game, chunk: ChunkPosition) -> Option<BlockPosition> {
+    // Mobs spawn below the top of the highest non-empty section.
+    let top = {
+        let chunk = game.chunk_map.chunk_at(chunk)?;
+        let highest_section = chunk.sections().iter().rposition(Option::is_some)?;
+        highest_section as i32 * 16 + 16
+    };
+
+    let mut rng = game.rng();
+    Some(BlockPosition::new(
+        chunk.x * 16 + rng.gen_range(0, 16),
+        rng.gen_range(0, top),
+        chunk.z * 16 + rng.gen_range(0, 16),
+    ))
+}
+
+/// Spawns a group of mobs starting at `start`. The kind of mob
+/// and the group size are picked from the biome's spawn list.
+fn spawn_group(
+    game: &mut Game,
+    world: &mut World,
+    start: BlockPosition,
+    category: SpawnCategory,
+    players: &[Position],
+) -> usize {
+    let entry = match pick_entry(game, start, category) {
+        Some(entry) => entry,
+        None => return 0,
+    };
+    let group_size = game.rng().gen_range(entry.min_group, entry.max_group + 1);
+
+    let mut pos = start;
+    let mut spawned = 0;
+    for _ in 0..group_size {
+        pos = {
+            let mut rng = game.rng();
+            BlockPosition::new(
+                pos.x + rng.gen_range(0, GROUP_SPREAD) - rng.gen_range(0, GROUP_SPREAD),
+                pos.y,
+                pos.z + rng.gen_range(0, GROUP_SPREAD) - rng.gen_range(0, GROUP_SPREAD),
+            )
+        };
+
+        let position = position!(
+            f64::from(pos.x) + 0.5,
+            f64::from(pos.y),
+            f64::from(pos.z) + 0.5,
+            0.0,
+            game.rng().gen_range(0.0, 360.0),
+        );
+        if !is_far_from_players(game, position, players) || !can_spawn_at(game, category, pos) {
+            continue;
+        }
+
+        let builder = match create(entry.mob) {
+            Some(builder) => builder,
+            None => {
+                log::warn!("Cannot spawn unknown mob {}", entry.mob);
+                return spawned;
+            }
+        };
+        let entity = builder.with(position).build().spawn_in(world);
+        world.get_mut::<MobNbt>(entity).persistent = false;
+        game.handle(world, EntitySpawnEvent { entity });
+        spawned += 1;
+    }
+
+    spawned
+}
+
+/// Picks a random entry from the spawn list of the biome at `pos`.
+fn pick_entry(game: &Game, pos: BlockPosition, category: SpawnCategory) -> Option<SpawnEntry> {
+    let biome = {
+        let chunk = game.chunk_map.chunk_at(pos.chunk())?;
+        let (x, _, z) = chunk_relative_pos(pos);
+        chunk.biome_at(x, z)
+    };
+
+    biome
+        .spawn_entries(category)
+        .choose_weighted(&mut *game.rng(), |entry| entry.weight)
+        .ok()
+        .copied()
+}
+
+fn is_far_from_players(game: &Game, pos: Position, players: &[Position]) -> bool {
+    let min_distance_squared = MIN_SPAWN_DISTANCE * MIN_SPAWN_DISTANCE;
+    let spawn_point = position!(
+        f64::from(game.level.spawn_x),
+        f64::from(game.level.spawn_y),
+        f64::from(game.level.spawn_z),
+    );
+
+    spawn_point.distance_squared_to(pos) >= min_distance_squared
+        && players
+            .iter()
+            .all(|player| player.distance_squared_to(pos) >= min_distance_squared)
+}
+
+/// Returns whether a mob of the given category can
+/// spawn with its feet in the block at `pos`.
+fn can_spawn_at(game: &Game, category: SpawnCategory, pos: BlockPosition) -> bool {
+    let (below, block, above) = match (
+        game.block_at(pos.down()),
+        game.block_at(pos),
+        game.block_at(pos.up()),
+    ) {
+        (Some(below), Some(block), Some(above)) => (below, block, above),
+        _ => return false,
+    };
+
+    if category == SpawnCategory::WaterCreature {
+        return block.simplified_kind() == SimplifiedBlockKind::Water
+            && !above.is_solid()
+            && pos.y > 45
+            && pos.y < SEA_LEVEL;
+    }
+
+    let clear = |block: BlockId| !block.is_solid() && !block.is_fluid();
+    if !below.is_solid() || below.kind() == BlockKind::Bedrock || !clear(block) || !clear(above) {
+        return false;
+    }
+
+    let (sky_light, block_light) = match light_at(game, pos) {
+        Some(light) => light,
+        None => return false,
+    };
+    let mut rng = game.rng();
+    match category {
+        SpawnCategory::Monster => {
+            if sky_light > rng.gen_range(0, 32) {
+                return false;
+            }
+            let light = block_light.max(sky_light.saturating_sub(game.time.sky_darkness()));
+            light <= rng.gen_range(0, 8)
+        }
+        SpawnCategory::Creature => {
+            below.kind() == BlockKind::GrassBlock && sky_light.max(block_light) > 8
+        }
+        SpawnCategory::Ambient => {
+            pos.y < SEA_LEVEL && sky_light.max(block_light) <= rng.gen_range(0, 4)
+        }
+        SpawnCategory::WaterCreature => unreachable!(),
+    }
+}
+
+/// Returns the sky light and block light at `pos`.
+fn light_at(game: &Game, pos: BlockPosition) -> Option<(u8, u8)> {
+    let chunk = game.chunk_map.chunk_at(pos.chunk())?;
+    let (x, y, z) = chunk_relative_pos(pos);
+    Some((chunk.sky_light_at(x, y, z), chunk.block_light_at(x, y, z)))
+}
+
+/// Creates a mob from its identifier, e.g. `minecraft:zombie`,
+/// as used in spawn lists and saved in the world.
+pub fn create(mob: &str) -> Option<EntityBuilder> {
+    use crate::*;
+
+    let builder = match mob {
+        "minecraft:bat" => bat::create(),
+        "minecraft:blaze" => blaze::create(),
+        "minecraft:cave_spider" => cave_spider::create(),
+        "minecraft:chicken" => chicken::create(),
+        "minecraft:cod" => cod::create(),
+        "minecraft:cow" => cow::create(),
+        "minecraft:creeper" => creeper::create(),
+        "minecraft:dolphin" => dolphin::create(),
+        "minecraft:donkey" => donkey::create(),
+        "minecraft:drowned" => drowned::create(),
+        "minecraft:elder_guardian" => elder_guardian::create(),
+        "minecraft:ender_dragon" => ender_dragon::create(),
+        "minecraft:enderman" => enderman::create(),
+        "minecraft:endermite" => endermite::create(),
+        "minecraft:evoker" => evoker::create(),
+        "minecraft:ghast" => ghast::create(),
+        "minecraft:guardian" => guardian::create(),
+        "minecraft:horse" => horse::create(),
+        "minecraft:husk" => husk::create(),
+        "minecraft:iron_golem" => iron_golem::create(),
+        "minecraft:llama" => llama::create(),
+        "minecraft:magma_cube" => magma_cube::create(),
+        "minecraft:mooshroom" => mooshroom::create(),
+        "minecraft:mule" => mule::create(),
+        "minecraft:ocelot" => ocelot::create(),
+        "minecraft:parrot" => parrot::create(),
+        "minecraft:phantom" => phantom::create(),
+        "minecraft:pig" => pig::create(),
+        "minecraft:polar_bear" => polar_bear::create(),
+        "minecraft:pufferfish" => pufferfish::create(),
+        "minecraft:rabbit" => rabbit::create(),
+        "minecraft:salmon" => salmon::create(),
+        "minecraft:sheep" => sheep::create(),
+        "minecraft:shulker" => shulker::create(),
+        "minecraft:silverfish" => silverfish::create(),
+        "minecraft:skeleton" => skeleton::create(),
+        "minecraft:skeleton_horse" => skeleton_horse::create(),
+        "minecraft:slime" => slime::create(),
+        "minecraft:snow_golem" => snow_golem::create(),
+        "minecraft:spider" => spider::create(),
+        "minecraft:squid" => squid::create(),
+        "minecraft:stray" => stray::create(),
+        "minecraft:tropical_fish" => tropical_fish::create(),
+        "minecraft:turtle" => turtle::create(),
+        "minecraft:vex" => vex::create(),
+        "minecraft:villager" => villager::create(),
+        "minecraft:vindicator" => vindicator::create(),
+        "minecraft:witch" => witch::create(),
+        "minecraft:wither" => wither::create(),
+        "minecraft:wither_skeleton" => wither_skeleton::create(),
+        "minecraft:wolf" => wolf::create(),
+        "minecraft:zombie" => zombie::create(),
+        "minecraft:zombie_pigman" => zombie_pigman::create(),
+        "minecraft:zombie_villager" => zombie_villager::create(),
+        _ => return None,
+    };
+    Some(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::anvil::entity::{BaseEntityData, EntityData, EntityDataKind, MobData};
+    use feather_core::biomes::Biome;
+    use feather_core::util::Vec3d;
+    use feather_test_framework::Test;
+    use fecs::Entity;
+
+    /// Adds a mob as if it was naturally spawned.
+    fn natural(test: &mut Test, builder: EntityBuilder) -> Entity {
+        let entity = test.entity(builder);
+        test.world.get_mut::<MobNbt>(entity).persistent = false;
+        entity
+    }
+
+    #[test]
+    fn despawn_far_monsters() {
+        let mut test = Test::new();
+        test.player("player", position!(0.0, 64.0, 0.0));
+        let near = natural(
+            &mut test,
+            crate::zombie::create().with(position!(10.0, 64.0, 0.0)),
+        );
+        let far = natural(
+            &mut test,
+            crate::zombie::create().with(position!(200.0, 64.0, 0.0)),
+        );
+        let cow = natural(
+            &mut test,
+            crate::cow::create().with(position!(200.0, 64.0, 0.0)),
+        );
+
+        test.run(despawn_mobs);
+
+        assert!(test.world.is_alive(near));
+        assert!(!test.world.is_alive(far));
+        assert!(test.world.is_alive(cow));
+    }
+
+    #[test]
+    fn persistent_mobs_do_not_despawn() {
+        let mut test = Test::new();
+        test.player("player", position!(0.0, 64.0, 0.0));
+        let pos = position!(200.0, 64.0, 0.0);
+
+        // Not created by natural spawning
+        let created = test.entity(crate::zombie::create().with(pos));
+
+        let load = |data: MobData| {
+            super::super::load(EntityData::from_mob(EntityDataKind::Zombie, data).unwrap()).unwrap()
+        };
+        let mut data = MobData::new(BaseEntityData::new(pos, Vec3d::new(0.0, 0.0, 0.0)), 20.0);
+        data.set_persistence_required(true);
+        let required = test.entity(load(data.clone()));
+
+        data.set_persistence_required(false);
+        data.other
+            .insert(String::from("CustomName"), "{\"text\":\"Bob\"}".into());
+        let named = test.entity(load(data.clone()));
+
+        data.other.remove("CustomName");
+        let despawnable = test.entity(load(data));
+
+        test.run(despawn_mobs);
+
+        assert!(test.world.is_alive(created));
+        assert!(test.world.is_alive(required));
+        assert!(test.world.is_alive(named));
+        assert!(!test.world.is_alive(despawnable));
+    }
+
+    #[test]
+    fn creatures_spawn_on_grass() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(5, 64, 5);
+        {
+            let mut chunk = test.game.chunk_map.chunk_at_mut(pos.chunk()).unwrap();
+            chunk.set_block_at(5, 63, 5, BlockId::stone());
+            chunk.set_sky_light_at(5, 64, 5, 15);
+        }
+        assert!(!can_spawn_at(&test.game, SpawnCategory::Creature, pos));
+
+        test.game
+            .chunk_map
+            .set_block_at(pos.down(), BlockId::grass_block());
+        assert!(can_spawn_at(&test.game, SpawnCategory::Creature, pos));
+
+        // No room for the mob
+        test.game.chunk_map.set_block_at(pos.up(), BlockId::stone());
+        assert!(!can_spawn_at(&test.game, SpawnCategory::Creature, pos));
+    }
+
+    #[test]
+    fn monsters_need_darkness() {
+        let mut test = Test::new();
+        let pos = BlockPosition::new(5, 64, 5);
+        test.game
+            .chunk_map
+            .set_block_at(pos.down(), BlockId::stone());
+        test.game
+            .chunk_map
+            .chunk_at_mut(pos.chunk())
+            .unwrap()
+            .set_block_light_at(5, 64, 5, 15);
+
+        for _ in 0..100 {
+            assert!(!can_spawn_at(&test.game, SpawnCategory::Monster, pos));
+        }
+    }
+
+    #[test]
+    fn create_spawn_list_mobs() {
+        for biome in &[
+            Biome::Plains,
+            Biome::Desert,
+            Biome::WarmOcean,
+            Biome::Nether,
+        ] {
+            for category in &SpawnCategory::values() {
+                for entry in biome.spawn_entries(*category) {
+                    assert!(create(entry.mob).is_some(), "{}", entry.mob);
+                }
+            }
+        }
+    }
+}
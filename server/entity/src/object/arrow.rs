@@ -1,16 +1,27 @@
-use feather_core::anvil::entity::{ArrowEntityData, BaseEntityData, EntityData};
+use feather_core::anvil::entity::{ArrowEntityData, BaseEntityData, EntityData, EntityDataKind};
 use feather_core::network::packets::SpawnObject;
 use feather_core::network::Packet;
 use feather_core::util::{Position, Vec3d};
 use feather_server_types::{
-    ComponentSerializer, Game, NetworkId, PhysicsBuilder, SpawnPacketCreator, Uuid, Velocity,
+    ComponentSerializer, EntityLoaderRegistration, Game, NetworkId, PhysicsBuilder,
+    SpawnPacketCreator, Uuid, Velocity,
 };
 use feather_server_util::{degrees_to_stops, protocol_velocity};
 use fecs::{EntityBuilder, EntityRef};
 
+/// Component storing the tags of an arrow which
+/// are kept when it is saved, such as `crit`.
+#[derive(Clone, Debug, Default)]
+pub struct ArrowNbt(ArrowEntityData);
+
+inventory::submit! {
+    EntityLoaderRegistration::new(EntityDataKind::Arrow, &load)
+}
+
 pub fn create() -> EntityBuilder {
     crate::base()
         .with(SpawnPacketCreator(&create_spawn_packet))
+        .with(ArrowNbt::default())
         .with(ComponentSerializer(&serialize))
         .with(
             PhysicsBuilder::new()
@@ -50,8 +61,22 @@ fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
 fn serialize(_game: &Game, accessor: &EntityRef) -> EntityData {
     let vel = accessor.get::<Velocity>().0;
 
-    EntityData::Arrow(ArrowEntityData {
-        entity: BaseEntityData::new(*accessor.get::<Position>(), Vec3d::new(vel.x, vel.y, vel.z)),
-        critical: 0, // TODO
-    })
+    let mut data = accessor.get::<ArrowNbt>().0.clone();
+    data.entity = BaseEntityData::new(*accessor.get::<Position>(), Vec3d::new(vel.x, vel.y, vel.z));
+    EntityData::Arrow(data)
+}
+
+fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    match data {
+        EntityData::Arrow(data) => {
+            let pos = data.entity.read_position()?;
+            let vel = data.entity.read_velocity()?;
+
+            Ok(create()
+                .with(pos)
+                .with(Velocity(glm::vec3(vel.x, vel.y, vel.z)))
+                .with(ArrowNbt(data)))
+        }
+        _ => panic!("attempted to use arrow::load to load a non-arrow"),
+    }
 }
@@ -9,14 +9,11 @@ use feather_core::network::packets::{Effect, SpawnObject};
 use feather_core::network::Packet;
 use feather_core::util::{BlockPosition, Position};
 use feather_server_types::{
-    BlockUpdateCause, BumpVec, EntityLandEvent, EntitySpawnEvent, Game, NetworkId, PhysicsBuilder,
-    SpawnPacketCreator, Uuid, Velocity,
+    BlockUpdateCause, EntityLandEvent, EntitySpawnEvent, Game, NetworkId, PhysicsBuilder,
+    ScheduledTickEvent, SpawnPacketCreator, TickList, Uuid, Velocity,
 };
-use feather_server_util::{
-    degrees_to_stops, protocol_velocity, BlockNotifyBlock, BlockNotifyFallingBlock,
-    BlockNotifyPosition,
-};
-use fecs::{component, EntityBuilder, EntityRef, IntoQuery, Read, World};
+use feather_server_util::{degrees_to_stops, protocol_velocity};
+use fecs::{EntityBuilder, EntityRef, World};
 
 /// Marker component indicating an entity is a falling block.
 #[derive(Copy, Clone, Debug)]
@@ -26,52 +23,30 @@ pub struct FallingBlock;
 #[derive(Copy, Clone, Debug)]
 pub struct FallingBlockType(pub BlockId);
 
-/// System to create a falling block when a block notify
-/// entity is spawned with `BlockNotifyFallingBlock`.
-#[fecs::system]
-pub fn spawn_falling_blocks(game: &mut Game, world: &mut World) {
-    let mut actions = BumpVec::new_in(game.bump());
-
-    actions.extend(
-        <(Read<BlockNotifyBlock>, Read<BlockNotifyPosition>)>::query()
-            .filter(component::<BlockNotifyFallingBlock>())
-            .iter_entities(world.inner())
-            .map(|(entity, (block, position))| {
-                let builder = if game.block_at(position.0 - BlockPosition::new(0, 1, 0))
-                    == Some(BlockId::air())
-                {
-                    Some(
-                        create(block.0, position.0)
-                            .with(position.0.position() + position!(0.0, -0.5, 0.0)),
-                    )
-                } else {
-                    None
-                };
-
-                (entity, builder, position.0)
-            }),
-    );
-
-    for (entity_to_delete, entity_builder, block_to_clear) in actions {
-        world.despawn(entity_to_delete);
-
-        if let Some(entity_builder) = entity_builder {
-            let created_entity = entity_builder.build().spawn_in(world);
-            game.handle(
-                world,
-                EntitySpawnEvent {
-                    entity: created_entity,
-                },
-            );
-
-            game.set_block_at(
-                world,
-                block_to_clear,
-                BlockId::air(),
-                BlockUpdateCause::Unknown,
-            );
-        }
+/// When the scheduled tick of a falling block is executed,
+/// replaces the block with a falling block entity if
+/// the block below it is air.
+#[fecs::event_handler]
+pub fn on_scheduled_tick_spawn_falling_block(
+    event: &ScheduledTickEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if event.list != TickList::Block || !event.block.can_fall() {
+        return;
+    }
+
+    if game.block_at(event.pos - BlockPosition::new(0, 1, 0)) != Some(BlockId::air()) {
+        return;
     }
+
+    let entity = create(event.block, event.pos)
+        .with(event.pos.position() + position!(0.0, -0.5, 0.0))
+        .build()
+        .spawn_in(world);
+    game.handle(world, EntitySpawnEvent { entity });
+
+    game.set_block_at(world, event.pos, BlockId::air(), BlockUpdateCause::Unknown);
 }
 
 /// When a falling block lands on the ground, deletes
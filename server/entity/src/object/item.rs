@@ -31,6 +31,20 @@ pub struct CollectableAt(u64);
 #[derive(Debug)]
 struct IsRemoved(AtomicBool);
 
+/// Component storing the tags of an item entity which
+/// are kept when it is saved, such as `Age` and `Owner`.
+#[derive(Clone, Debug)]
+pub struct ItemEntityNbt(ItemEntityData);
+
+impl Default for ItemEntityNbt {
+    fn default() -> Self {
+        Self(ItemEntityData {
+            health: 5,
+            ..Default::default()
+        })
+    }
+}
+
 inventory::submit! {
     EntityLoaderRegistration::new(EntityDataKind::Item, &load)
 }
@@ -70,7 +84,7 @@ pub fn on_item_drop_spawn_item_entity(event: &ItemDropEvent, game: &mut Game, wo
 
     drop(rng);
 
-    let entity = create(event.stack, game.tick_count + TPS)
+    let entity = create(event.stack.clone(), game.tick_count + TPS)
         .with(pos)
         .with(Velocity(velocity))
         .build()
@@ -127,7 +141,7 @@ pub fn item_collect(game: &mut Game, world: &mut World) {
                         // we now have unique access to this item and its components.
                         let mut stack = world.get_mut_unchecked::<ItemStack>(item);
 
-                        let (slots, stack_remaining) = inventory.collect_item(*stack);
+                        let (slots, stack_remaining) = inventory.collect_item(stack.clone());
 
                         let initial_remaining = stack.amount;
 
@@ -144,7 +158,7 @@ pub fn item_collect(game: &mut Game, world: &mut World) {
                             stack.amount = stack_remaining;
                             world
                                 .get_mut_unchecked::<EntityMetadata>(item)
-                                .set(META_INDEX_ITEM_SLOT, Some(*stack));
+                                .set(META_INDEX_ITEM_SLOT, Some(stack.clone()));
                         }
 
                         item_collect_events.lock().push(ItemCollectEvent {
@@ -177,13 +191,14 @@ pub fn item_collect(game: &mut Game, world: &mut World) {
 /// Returns an entity builder to create an item entity
 /// with the given stack and collectable tick.
 pub fn create(stack: ItemStack, collectable_at: u64) -> EntityBuilder {
-    let meta = EntityMetadata::entity_base().with(META_INDEX_ITEM_SLOT, Some(stack));
+    let meta = EntityMetadata::entity_base().with(META_INDEX_ITEM_SLOT, Some(stack.clone()));
     let collectable_at = CollectableAt(collectable_at);
 
     crate::base()
         .with(stack)
         .with(IsRemoved(AtomicBool::new(false)))
         .with(collectable_at)
+        .with(ItemEntityNbt::default())
         .with(SpawnPacketCreator(&create_spawn_packet))
         .with(ComponentSerializer(&serialize))
         .with(meta)
@@ -224,24 +239,23 @@ fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
 fn serialize(game: &Game, accessor: &EntityRef) -> EntityData {
     let vel = accessor.get::<Velocity>().0;
     let item = accessor.get::<ItemStack>();
-    let nbt = ItemNbt::from(*item);
+    let nbt = ItemNbt::from(&*item);
     let nbt = if nbt == Default::default() {
         None
     } else {
         Some(nbt)
     };
-    EntityData::Item(ItemEntityData {
-        entity: BaseEntityData::new(*accessor.get::<Position>(), Vec3d::new(vel.x, vel.y, vel.z)),
-        age: 0, // todo
-        pickup_delay: (accessor.get::<CollectableAt>().0 as i64 - game.tick_count as i64).max(0)
-            as i16,
-        item: ItemData {
-            count: item.amount as i8,
-            item: item.ty.identifier().to_owned(),
-            nbt,
-        },
-        health: 5, // todo
-    })
+
+    let mut data = accessor.get::<ItemEntityNbt>().0.clone();
+    data.entity = BaseEntityData::new(*accessor.get::<Position>(), Vec3d::new(vel.x, vel.y, vel.z));
+    data.pickup_delay =
+        (accessor.get::<CollectableAt>().0 as i64 - game.tick_count as i64).max(0) as i16;
+    data.item = ItemData {
+        count: item.amount as i8,
+        item: item.ty.identifier().to_owned(),
+        nbt,
+    };
+    EntityData::Item(data)
 }
 
 fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
@@ -252,12 +266,13 @@ fn load(data: EntityData) -> anyhow::Result<EntityBuilder> {
 
             Item::from_identifier(&data.item.item)
                 .ok_or_else(|| anyhow::anyhow!("invalid item {}", data.item.item))?;
-            let stack = data.item.into();
+            let stack = data.item.clone().into();
             let collectable_at = data.pickup_delay;
 
             Ok(create(stack, collectable_at as u64)
                 .with(pos)
-                .with(Velocity(glm::vec3(vel.x, vel.y, vel.z))))
+                .with(Velocity(glm::vec3(vel.x, vel.y, vel.z)))
+                .with(ItemEntityNbt(data)))
         }
         _ => panic!("attempted to use item::load to load a non-item"),
     }
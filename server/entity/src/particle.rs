@@ -29,6 +29,6 @@ fn create_spawn_packet(accessor: &EntityRef) -> Box<dyn Packet> {
         offset_z: 0.0,
         particle_data: 0.0, // TODO: what is this?
         particle_count: count as i32,
-        data: *particle,
+        data: particle.clone(),
     })
 }
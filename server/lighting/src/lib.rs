@@ -38,6 +38,30 @@
 //! If we are recalculating light for an entire chunk, e.g. when a chunk is generated,
 //! we first zero out light, then find all light sources in the chunk and perform
 //! algorithm #1 on them as if they had just been placed.
+//!
+//! # Algorithms: sky light
+//! Sky light has a value of 15 for every block above the highest opaque
+//! block in its column, as given by the chunk's `light_blocking` heightmap.
+//! From there, it spreads like block light, except that a value of 15 travels
+//! straight down without decreasing. Only changes in opacity affect sky light:
+//!
+//! * Loading of a chunk. For each column, we set blocks at or above the
+//! heightmap value to 15 and all others to 0. Wherever the lit part of a column
+//! borders on the dark part of an adjacent column (in this chunk or in an adjacent
+//! loaded chunk), we spread light using flood fill, lighting overhangs and caves.
+//!
+//! * Creation of an opaque block. We set the block's light to 0, then perform flood
+//! fill and set to 0 all blocks whose light could have come from it, including the
+//! column below it if it was lit directly by the sky. Finally, we spread
+//! light back into the darkened area from the blocks bordering it.
+//!
+//! * Removal of an opaque block. If the block above has a value of 15, the new
+//! block can see the sky and gets 15 as well; otherwise, it gets the highest value
+//! of an adjacent block minus 1. We then spread the new value using flood fill
+//! as in algorithm #5.
+//!
+//! Empty chunk sections are never created to store sky light; they are lit
+//! according to the heightmap, which matches how clients treat them.
 
 extern crate nalgebra_glm as glm;
 
@@ -59,6 +83,8 @@ use std::collections::VecDeque;
 use std::marker::PhantomData;
 use std::sync::Arc;
 
+mod sky;
+
 #[fecs::event_handler]
 pub fn on_block_update_notify_lighting_worker(
     event: &BlockUpdateEvent,
@@ -224,6 +250,10 @@ fn load_chunk(worker: &mut Worker, pos: ChunkPosition, handle: Arc<RwLock<Chunk>
         .0
         .insert(pos, lights_in_chunk(&*handle.read()).collect());
     worker.chunk_map.0.insert(pos, handle);
+
+    if let Some(mut ctx) = Context::new(&worker.chunk_map, pos) {
+        sky::initialize_chunk(&mut ctx, pos);
+    }
 }
 
 fn lights_in_chunk<'a>(chunk: &'a Chunk) -> impl Iterator<Item = BlockPosition> + 'a {
@@ -299,6 +329,23 @@ impl<'a> Context<'a> {
         }
     }
 
+    fn sky_light_at(&mut self, pos: BlockPosition) -> u8 {
+        match self.chunk_at_mut(pos.chunk()) {
+            Some(chunk) => {
+                let (x, y, z) = chunk_relative_pos(pos);
+                sky::sky_light_in_chunk(chunk, x, y, z)
+            }
+            None => 0,
+        }
+    }
+
+    fn set_sky_light_at(&mut self, pos: BlockPosition, value: u8) {
+        if let Some(chunk) = self.chunk_at_mut(pos.chunk()) {
+            let (x, y, z) = chunk_relative_pos(pos);
+            sky::set_sky_light_in_chunk(chunk, x, y, z, value);
+        }
+    }
+
     fn block_at(&mut self, pos: BlockPosition) -> BlockId {
         match self.chunk_at_mut(pos.chunk()) {
             Some(chunk) => {
@@ -325,6 +372,13 @@ fn handle_block_update(worker: &mut Worker, pos: BlockPosition, old: BlockId, ne
         None => return, // Unloaded chunk
     };
 
+    // Sky light only depends on opacity.
+    if !old.is_opaque() && new.is_opaque() {
+        sky::opaque_creation(&mut ctx, pos);
+    } else if old.is_opaque() && !new.is_opaque() {
+        sky::opaque_removal(&mut ctx, pos);
+    }
+
     // Determine which algorithm to use.
     if old.light_emission() < new.light_emission() {
         ctx.set_block_light_at(pos, new.light_emission());
@@ -608,6 +662,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sky_light_overhang() {
+        let chunk_map = chunk_map();
+        let pos = ChunkPosition::new(0, 0);
+        let mut ctx = Context::new(&chunk_map, pos).unwrap();
+
+        ctx.set_block_at(BlockPosition::new(8, 64, 8), BlockId::stone());
+        ctx.set_block_at(BlockPosition::new(8, 70, 8), BlockId::stone());
+
+        sky::initialize_chunk(&mut ctx, pos);
+
+        assert_eq!(ctx.sky_light_at(BlockPosition::new(8, 71, 8)), 15);
+        assert_eq!(ctx.sky_light_at(BlockPosition::new(8, 70, 8)), 0);
+        assert_eq!(ctx.sky_light_at(BlockPosition::new(8, 69, 8)), 14);
+        assert_eq!(ctx.sky_light_at(BlockPosition::new(8, 65, 8)), 14);
+        assert_eq!(ctx.sky_light_at(BlockPosition::new(7, 65, 8)), 15);
+        assert_eq!(ctx.sky_light_at(BlockPosition::new(8, 63, 8)), 0);
+    }
+
+    #[test]
+    fn test_sky_light_block_updates() {
+        let chunk_map = chunk_map();
+        let pos = ChunkPosition::new(0, 0);
+        let mut ctx = Context::new(&chunk_map, pos).unwrap();
+
+        ctx.set_block_at(BlockPosition::new(8, 64, 8), BlockId::stone());
+        sky::initialize_chunk(&mut ctx, pos);
+        assert_eq!(ctx.sky_light_at(BlockPosition::new(8, 65, 8)), 15);
+
+        let roof = BlockPosition::new(8, 70, 8);
+        ctx.set_block_at(roof, BlockId::stone());
+        sky::opaque_creation(&mut ctx, roof);
+
+        assert_eq!(ctx.sky_light_at(roof), 0);
+        assert_eq!(ctx.sky_light_at(BlockPosition::new(8, 69, 8)), 14);
+        assert_eq!(ctx.sky_light_at(BlockPosition::new(8, 65, 8)), 14);
+
+        ctx.set_block_at(roof, BlockId::air());
+        sky::opaque_removal(&mut ctx, roof);
+
+        assert_eq!(ctx.sky_light_at(roof), 15);
+        assert_eq!(ctx.sky_light_at(BlockPosition::new(8, 65, 8)), 15);
+    }
+
     fn chunk_map() -> ChunkMap {
         let mut chunk_map = ChunkMap::new();
 
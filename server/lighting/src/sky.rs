@@ -0,0 +1,210 @@
+//! Sky light calculation. See the crate-level docs for
+//! a description of the algorithms implemented here.
+
+use crate::{adjacent_blocks, Context};
+use feather_core::chunk::{Chunk, CHUNK_HEIGHT, CHUNK_WIDTH};
+use feather_core::chunk_map::chunk_relative_pos;
+use feather_core::util::{BlockPosition, ChunkPosition};
+use std::collections::VecDeque;
+
+/// The sky light value of blocks which can see the sky directly.
+pub const MAX_SKY_LIGHT: u8 = 15;
+
+/// Returns the sky light at the given chunk-relative position.
+///
+/// Empty sections are never created just to store light. Their
+/// sky light is instead derived from the `light_blocking` heightmap,
+/// which is what clients do for sections missing from the chunk data.
+pub fn sky_light_in_chunk(chunk: &Chunk, x: usize, y: usize, z: usize) -> u8 {
+    if chunk.section(y / 16).is_some() {
+        chunk.sky_light_at(x, y, z)
+    } else if y >= usize::from(chunk.heightmap(x, z).light_blocking()) {
+        MAX_SKY_LIGHT
+    } else {
+        0
+    }
+}
+
+/// Sets the sky light at the given chunk-relative position.
+/// Does nothing if the section containing the position is empty.
+pub fn set_sky_light_in_chunk(chunk: &mut Chunk, x: usize, y: usize, z: usize, value: u8) {
+    if chunk.section(y / 16).is_some() {
+        chunk.set_sky_light_at(x, y, z, value);
+    }
+}
+
+/// Algorithm #5, as described in the module-level docs.
+///
+/// Initializes sky light for a newly loaded chunk and spreads it
+/// to and from the adjacent loaded chunks.
+pub fn initialize_chunk(context: &mut Context, pos: ChunkPosition) {
+    let chunk = match context.chunk_at_mut(pos) {
+        Some(chunk) => chunk,
+        None => return,
+    };
+
+    // Columns: everything above the highest opaque block
+    // can see the sky; everything below starts out dark.
+    for x in 0..CHUNK_WIDTH {
+        for z in 0..CHUNK_WIDTH {
+            let height = usize::from(chunk.heightmap(x, z).light_blocking());
+            for y in 0..CHUNK_HEIGHT {
+                let value = if y >= height { MAX_SKY_LIGHT } else { 0 };
+                set_sky_light_in_chunk(chunk, x, y, z, value);
+            }
+        }
+    }
+
+    // Wherever two horizontally adjacent columns have different
+    // heights, the lit part of the taller column borders on dark
+    // blocks, so light has to spread from there under overhangs
+    // and into caves.
+    let mut queue = VecDeque::new();
+    let base_x = pos.x * CHUNK_WIDTH as i32;
+    let base_z = pos.z * CHUNK_WIDTH as i32;
+    for x in 0..CHUNK_WIDTH as i32 {
+        for z in 0..CHUNK_WIDTH as i32 {
+            let column = BlockPosition::new(base_x + x, 0, base_z + z);
+            let height = match column_height(context, column) {
+                Some(height) => height,
+                None => continue,
+            };
+
+            let neighbors = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+            for (dx, dz) in neighbors.iter() {
+                let neighbor = BlockPosition::new(column.x + dx, 0, column.z + dz);
+                let neighbor_height = match column_height(context, neighbor) {
+                    Some(height) => height,
+                    None => continue, // Unloaded chunk
+                };
+
+                for y in height..neighbor_height {
+                    queue.push_back(BlockPosition::new(column.x, y, column.z));
+                }
+                for y in neighbor_height..height {
+                    queue.push_back(BlockPosition::new(neighbor.x, y, neighbor.z));
+                }
+            }
+        }
+    }
+
+    propagate(context, queue);
+}
+
+/// Algorithm #6, as described in the module-level docs.
+pub fn opaque_creation(context: &mut Context, position: BlockPosition) {
+    let old_light = context.sky_light_at(position);
+    context.set_sky_light_at(position, 0);
+
+    let relight = darken(context, position, old_light);
+    propagate(context, relight);
+}
+
+/// Algorithm #7, as described in the module-level docs.
+pub fn opaque_removal(context: &mut Context, position: BlockPosition) {
+    let above = position.up();
+    let value = if above.y < CHUNK_HEIGHT as i32 {
+        if context.sky_light_at(above) == MAX_SKY_LIGHT {
+            MAX_SKY_LIGHT
+        } else {
+            adjacent_blocks(position)
+                .into_iter()
+                .map(|pos| context.sky_light_at(pos))
+                .max()
+                .unwrap_or(0)
+                .saturating_sub(1)
+        }
+    } else {
+        MAX_SKY_LIGHT
+    };
+
+    context.set_sky_light_at(position, value);
+
+    let mut queue = VecDeque::new();
+    queue.push_back(position);
+    propagate(context, queue);
+}
+
+/// Returns the `light_blocking` height of the column containing
+/// `column`, or `None` if its chunk is not loaded.
+fn column_height(context: &mut Context, column: BlockPosition) -> Option<usize> {
+    let chunk = context.chunk_at_mut(column.chunk())?;
+    let (x, _, z) = chunk_relative_pos(column);
+    Some(usize::from(chunk.heightmap(x, z).light_blocking()))
+}
+
+/// Spreads sky light outwards from each block in `queue`
+/// using flood fill.
+///
+/// Light decreases by one for each block travelled, except
+/// that full sky light travels straight down without loss.
+fn propagate(context: &mut Context, mut queue: VecDeque<BlockPosition>) {
+    while let Some(pos) = queue.pop_front() {
+        let light = context.sky_light_at(pos);
+        if light <= 1 {
+            continue;
+        }
+
+        for neighbor in adjacent_blocks(pos) {
+            if context.block_at(neighbor).is_opaque() {
+                continue;
+            }
+
+            let value = if light == MAX_SKY_LIGHT && neighbor == pos.down() {
+                MAX_SKY_LIGHT
+            } else {
+                light - 1
+            };
+
+            if context.sky_light_at(neighbor) >= value {
+                continue;
+            }
+
+            context.set_sky_light_at(neighbor, value);
+
+            // Don't continue through blocks whose light
+            // can't be stored (empty sections, unloaded chunks).
+            if context.sky_light_at(neighbor) == value {
+                queue.push_back(neighbor);
+            }
+        }
+    }
+}
+
+/// Sets to 0 all blocks whose sky light may have come from `start`,
+/// which previously had a light value of `old_light`.
+///
+/// Returns the blocks bordering the darkened area which are lit
+/// by other sources; these should be passed to `propagate` to
+/// refill the darkened area.
+fn darken(context: &mut Context, start: BlockPosition, old_light: u8) -> VecDeque<BlockPosition> {
+    let mut relight = VecDeque::new();
+    let mut queue = VecDeque::new();
+    queue.push_back((start, old_light));
+
+    while let Some((pos, light)) = queue.pop_front() {
+        for neighbor in adjacent_blocks(pos) {
+            let neighbor_light = context.sky_light_at(neighbor);
+            if neighbor_light == 0 {
+                continue;
+            }
+
+            let lit_from_above =
+                light == MAX_SKY_LIGHT && neighbor_light == MAX_SKY_LIGHT && neighbor == pos.down();
+
+            if neighbor_light < light || lit_from_above {
+                context.set_sky_light_at(neighbor, 0);
+                if context.sky_light_at(neighbor) == 0 {
+                    queue.push_back((neighbor, neighbor_light));
+                } else {
+                    // Light derived from the heightmap; still lit.
+                    relight.push_back(neighbor);
+                }
+            } else {
+                relight.push_back(neighbor);
+            }
+        }
+    }
+
+    relight
+}
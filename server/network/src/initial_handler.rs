@@ -14,26 +14,30 @@
 //! speeding up the login process and making the latency calculation in
 //! the server list ping as low as possible.
 
-use std::net::SocketAddr;
+use std::io::{Cursor, Read};
+use std::net::{IpAddr, SocketAddr};
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Arc, RwLock};
 use std::time::SystemTime;
 
+use hmac::{Hmac, Mac, NewMac};
 use rand::rngs::OsRng;
 use rsa::{PaddingScheme, PublicKey, RSAPrivateKey};
 use rsa_der as der;
+use sha2::Sha256;
 
 use thiserror::Error;
 
-use feather_core::network::{cast_packet, Packet, PacketStage, PacketType};
-use feather_core::text::{Text, TextRoot};
+use feather_core::network::{cast_packet, Packet, PacketStage, PacketType, ProtocolVersion};
+use feather_core::text::{Text, TextRoot, TextValue};
 
 use crate::{PROTOCOL_VERSION, SERVER_VERSION};
 use feather_core::network::packets::{
-    DisconnectLogin, EncryptionRequest, EncryptionResponse, Handshake, HandshakeState, LoginStart,
-    LoginSuccess, Ping, Pong, Request, Response, SetCompression,
+    DisconnectLogin, EncryptionRequest, EncryptionResponse, Handshake, HandshakeState,
+    LoginPluginRequest, LoginPluginResponse, LoginStart, LoginSuccess, Ping, Pong, Request,
+    Response, SetCompression,
 };
-use feather_server_types::{BanInfo, Config, ProxyMode};
+use feather_server_types::{BanInfo, Config, OpList, ProxyMode, Whitelist};
 use feather_server_util::name_to_uuid_offline;
 use mojang_api::ProfileProperty;
 use once_cell::sync::Lazy;
@@ -50,6 +54,13 @@ const RSA_KEY_BITS: usize = 1024;
 /// The number of bytes in the shared secret
 const SHARED_SECRET_LEN: usize = 128 / 8;
 
+/// The login plugin channel used by Velocity's modern forwarding.
+const VELOCITY_CHANNEL: &str = "velocity:player_info";
+/// The version of Velocity's forwarding format which we support.
+const VELOCITY_FORWARDING_VERSION: i32 = 1;
+/// The number of bytes in the HMAC-SHA256 signature of Velocity's forwarded data.
+const VELOCITY_SIGNATURE_LEN: usize = 32;
+
 pub static RSA_KEY: Lazy<RSAPrivateKey> = Lazy::new(|| {
     let mut rng = OsRng;
     RSAPrivateKey::new(&mut rng, RSA_KEY_BITS).unwrap()
@@ -72,6 +83,8 @@ pub struct JoinResult {
     pub username: Option<String>,
     pub uuid: Uuid,
     pub props: Vec<mojang_api::ProfileProperty>,
+    /// The player's real IP address, if forwarded by a proxy.
+    pub forwarded_ip: Option<IpAddr>,
 }
 
 impl JoinResult {
@@ -95,6 +108,7 @@ impl Default for JoinResult {
             username: None,
             uuid: Uuid::new_v4(),
             props: vec![],
+            forwarded_ip: None,
         }
     }
 }
@@ -127,10 +141,19 @@ pub struct InitialHandler {
     /// The verify token generated for this exchange.
     verify_token: VerifyToken,
 
+    /// The message ID of the login plugin request
+    /// sent to Velocity, if in Velocity proxy mode.
+    velocity_message_id: i32,
+
     /// The server's configuration.
     config: Arc<Config>,
     /// Server bans
     ban_info: Arc<RwLock<BanInfo>>,
+    /// Server whitelist
+    whitelist: Arc<RwLock<Whitelist>>,
+    /// Server operators, who may join even if
+    /// they are not whitelisted
+    ops: Arc<RwLock<OpList>>,
     /// The server's player count.
     player_count: Arc<AtomicU32>,
     /// The server's icon, if any was loaded.
@@ -144,6 +167,10 @@ pub struct InitialHandler {
     /// the player should join.
     info: Option<JoinResult>,
 
+    /// The protocol version sent by the client in the handshake,
+    /// if it is supported.
+    protocol_version: Option<ProtocolVersion>,
+
     /// The stage of this initial handler.
     stage: Stage,
 }
@@ -152,6 +179,8 @@ impl InitialHandler {
     pub fn new(
         config: Arc<Config>,
         ban_info: Arc<RwLock<BanInfo>>,
+        whitelist: Arc<RwLock<Whitelist>>,
+        ops: Arc<RwLock<OpList>>,
         player_count: Arc<AtomicU32>,
         server_icon: Arc<Option<String>>,
         client_ip: SocketAddr,
@@ -164,8 +193,12 @@ impl InitialHandler {
 
             verify_token: rand::random(),
 
+            velocity_message_id: rand::random(),
+
             config,
             ban_info,
+            whitelist,
+            ops,
             player_count,
             server_icon,
 
@@ -173,6 +206,8 @@ impl InitialHandler {
 
             info: None,
 
+            protocol_version: None,
+
             stage: Stage::AwaitHandshake,
         }
     }
@@ -220,6 +255,9 @@ async fn _handle_packet(ih: &mut InitialHandler, packet: Box<dyn Packet>) -> Res
         PacketType::EncryptionResponse => {
             handle_encryption_response(ih, &cast_packet::<EncryptionResponse>(packet)).await?
         }
+        PacketType::LoginPluginResponse => {
+            handle_login_plugin_response(ih, &cast_packet::<LoginPluginResponse>(packet))?
+        }
         ty => return Err(Error::InvalidPacket(ty, ih.stage)),
     }
 
@@ -229,6 +267,8 @@ async fn _handle_packet(ih: &mut InitialHandler, packet: Box<dyn Packet>) -> Res
 fn handle_handshake(ih: &mut InitialHandler, packet: &Handshake) -> Result<(), Error> {
     check_stage(ih, Stage::AwaitHandshake, packet.ty())?;
 
+    ih.protocol_version = ProtocolVersion::from_id(packet.protocol_version);
+
     ih.stage = match packet.next_state {
         HandshakeState::Status => {
             ih.action_queue.push(Action::SetStage(PacketStage::Status));
@@ -237,9 +277,9 @@ fn handle_handshake(ih: &mut InitialHandler, packet: &Handshake) -> Result<(), E
         HandshakeState::Login => {
             // While status requests can use differing
             // protocol versions, a client
-            // needs to have a matching protocol version
+            // needs to have a supported protocol version
             // to log in.
-            if packet.protocol_version != PROTOCOL_VERSION {
+            if ih.protocol_version.is_none() {
                 return Err(Error::InvalidProtocol(packet.protocol_version));
             }
 
@@ -251,6 +291,7 @@ fn handle_handshake(ih: &mut InitialHandler, packet: &Handshake) -> Result<(), E
                     username: None,
                     uuid: bungeecord_data.uuid,
                     props: bungeecord_data.properties,
+                    forwarded_ip: None,
                 });
             }
 
@@ -313,15 +354,141 @@ impl BungeeCordData {
     }
 }
 
+/// Verifies and extracts the player information sent by a Velocity proxy
+/// in response to a login plugin request on the `velocity:player_info` channel.
+/// This is used to enable Velocity's modern forwarding.
+///
+/// The data is prefixed with an HMAC-SHA256 signature of the rest of the data,
+/// keyed with the secret shared between Velocity and the server. The rest of
+/// the data has the following format:
+///
+/// | Field          | Type                                               |
+/// |----------------|----------------------------------------------------|
+/// | Version        | VarInt; always 1                                   |
+/// | Client address | String                                             |
+/// | UUID           | UUID                                               |
+/// | Username       | String                                             |
+/// | Properties     | VarInt count, then for each property: name (String),
+/// value (String), has signature (Boolean), signature (optional String)  |
+fn extract_velocity_data(data: &[u8], secret: &[u8]) -> Result<VelocityData, Error> {
+    if data.len() < VELOCITY_SIGNATURE_LEN {
+        return Err(Error::VelocitySpecMismatch("missing signature".to_string()));
+    }
+    let (signature, data) = data.split_at(VELOCITY_SIGNATURE_LEN);
+
+    let mut mac = Hmac::<Sha256>::new_varkey(secret).map_err(|_| Error::VelocityBadSignature)?;
+    mac.update(data);
+    mac.verify(signature)
+        .map_err(|_| Error::VelocityBadSignature)?;
+
+    let mut cursor = Cursor::new(data);
+
+    let version = read_var_int(&mut cursor)?;
+    if version != VELOCITY_FORWARDING_VERSION {
+        return Err(Error::VelocitySpecMismatch(format!(
+            "unsupported forwarding version {}",
+            version
+        )));
+    }
+
+    let client = read_string(&mut cursor)?
+        .parse()
+        .map_err(|e: std::net::AddrParseError| Error::VelocitySpecMismatch(e.to_string()))?;
+
+    let mut uuid = [0u8; 16];
+    read_exact(&mut cursor, &mut uuid)?;
+    let uuid = Uuid::from_bytes(uuid);
+
+    let username = read_string(&mut cursor)?;
+
+    let num_properties = read_var_int(&mut cursor)?;
+    let mut properties = vec![];
+    for _ in 0..num_properties {
+        let name = read_string(&mut cursor)?;
+        let value = read_string(&mut cursor)?;
+
+        let mut has_signature = [0u8];
+        read_exact(&mut cursor, &mut has_signature)?;
+        let signature = if has_signature[0] != 0 {
+            read_string(&mut cursor)?
+        } else {
+            String::new()
+        };
+
+        properties.push(ProfileProperty {
+            name,
+            value,
+            signature,
+        });
+    }
+
+    Ok(VelocityData {
+        client,
+        uuid,
+        username,
+        properties,
+    })
+}
+
+fn read_exact(cursor: &mut Cursor<&[u8]>, buf: &mut [u8]) -> Result<(), Error> {
+    cursor
+        .read_exact(buf)
+        .map_err(|_| Error::VelocitySpecMismatch("unexpected end of data".to_string()))
+}
+
+fn read_var_int(cursor: &mut Cursor<&[u8]>) -> Result<i32, Error> {
+    let mut result = 0;
+    for i in 0..5 {
+        let mut byte = [0u8];
+        read_exact(cursor, &mut byte)?;
+
+        result |= i32::from(byte[0] & 0b0111_1111) << (7 * i);
+        if byte[0] & 0b1000_0000 == 0 {
+            return Ok(result);
+        }
+    }
+
+    Err(Error::VelocitySpecMismatch("VarInt too long".to_string()))
+}
+
+fn read_string(cursor: &mut Cursor<&[u8]>) -> Result<String, Error> {
+    let len = read_var_int(cursor)?;
+    if len < 0 || len > 32767 {
+        return Err(Error::VelocitySpecMismatch(format!(
+            "invalid string length {}",
+            len
+        )));
+    }
+
+    let mut buf = vec![0u8; len as usize];
+    read_exact(cursor, &mut buf)?;
+
+    String::from_utf8(buf).map_err(|e| Error::VelocitySpecMismatch(e.to_string()))
+}
+
+#[derive(Debug, PartialEq)]
+struct VelocityData {
+    client: IpAddr,
+    uuid: Uuid,
+    username: String,
+    properties: Vec<ProfileProperty>,
+}
+
 fn handle_request(ih: &mut InitialHandler, packet: &Request) -> Result<(), Error> {
     check_stage(ih, Stage::AwaitRequest, packet.ty())?;
     let server_icon = (*ih.server_icon).clone().unwrap_or_default();
 
+    // Report the client's own protocol version if we support
+    // it, so that the client does not show the server as outdated.
+    let protocol = ih
+        .protocol_version
+        .map_or(PROTOCOL_VERSION, ProtocolVersion::id);
+
     // Send response packet
     let mut json = serde_json::json!({
         "version": {
             "name": SERVER_VERSION,
-            "protocol": PROTOCOL_VERSION,
+            "protocol": protocol,
         },
         "players": {
             "max": ih.config.server.max_players,
@@ -371,35 +538,24 @@ fn handle_login_start(ih: &mut InitialHandler, packet: &LoginStart) -> Result<()
         return Ok(());
     }
 
-    let (reason, remove_ban) = {
-        let ban_info = ih.ban_info.read().unwrap();
-        let ip_ban = ban_info.ip_bans.get(&ih.client_ip.ip());
+    // Velocity authenticates the player itself and forwards
+    // the player's information (including their real IP address)
+    // in response to a login plugin request.
+    if ih.config.proxy.proxy_mode == ProxyMode::Velocity {
+        let request = LoginPluginRequest {
+            message_id: ih.velocity_message_id,
+            channel: VELOCITY_CHANNEL.to_string(),
+            data: vec![],
+        };
+        send_packet(ih, request);
 
-        if let Some(ban) = ip_ban {
-            // Expire the ban if it's over.
-            if let Some(expires) = ban.expires_after {
-                if expires < SystemTime::now() {
-                    (None, true)
-                } else {
-                    (Some(Text::from(ban.reason.clone())), false)
-                }
-            } else {
-                (Some(Text::from(ban.reason.clone())), false)
-            }
-        } else {
-            (None, false)
-        }
-    };
+        ih.stage = Stage::AwaitLoginPluginResponse;
+        return Ok(());
+    }
 
-    if let Some(reason) = reason {
+    if let Some(reason) = check_ip_ban(ih) {
         disconnect_login(ih, reason);
         return Ok(());
-    } else if remove_ban {
-        ih.ban_info
-            .write()
-            .unwrap()
-            .ip_bans
-            .remove(&ih.client_ip.ip());
     }
 
     // If in online mode, encryption needs to be enabled,
@@ -504,6 +660,7 @@ async fn handle_encryption_response(
                 username: Some(auth.name),
                 uuid: auth.id,
                 props: auth.properties,
+                forwarded_ip: None,
             };
             ih.info = Some(info);
         }
@@ -515,6 +672,46 @@ async fn handle_encryption_response(
     Ok(())
 }
 
+fn handle_login_plugin_response(
+    ih: &mut InitialHandler,
+    packet: &LoginPluginResponse,
+) -> Result<(), Error> {
+    check_stage(ih, Stage::AwaitLoginPluginResponse, packet.ty())?;
+
+    if packet.message_id != ih.velocity_message_id {
+        return Err(Error::VelocitySpecMismatch(format!(
+            "unexpected login plugin message ID {}",
+            packet.message_id
+        )));
+    }
+
+    // The client doesn't understand the channel, so
+    // it didn't come through Velocity.
+    if !packet.successful {
+        return Err(Error::NotVelocity);
+    }
+
+    let velocity_data =
+        extract_velocity_data(&packet.data, ih.config.proxy.velocity_secret.as_bytes())?;
+
+    ih.client_ip = SocketAddr::new(velocity_data.client, ih.client_ip.port());
+    if let Some(reason) = check_ip_ban(ih) {
+        disconnect_login(ih, reason);
+        return Ok(());
+    }
+
+    ih.info = Some(JoinResult {
+        username: Some(velocity_data.username),
+        uuid: velocity_data.uuid,
+        props: velocity_data.properties,
+        forwarded_ip: Some(velocity_data.client),
+    });
+
+    finish(ih);
+
+    Ok(())
+}
+
 fn decrypt_using_rsa(data: &[u8], key: &RSAPrivateKey) -> Result<Vec<u8>, Error> {
     let buf = key
         .decrypt(PaddingScheme::PKCS1v15, data)
@@ -523,6 +720,40 @@ fn decrypt_using_rsa(data: &[u8], key: &RSAPrivateKey) -> Result<Vec<u8>, Error>
     Ok(buf)
 }
 
+/// Checks whether the client's IP address is banned,
+/// returning the ban reason if so. Expired bans are removed.
+fn check_ip_ban(ih: &mut InitialHandler) -> Option<Text> {
+    let (reason, remove_ban) = {
+        let ban_info = ih.ban_info.read().unwrap();
+        let ip_ban = ban_info.ip_bans.get(&ih.client_ip.ip());
+
+        if let Some(ban) = ip_ban {
+            // Expire the ban if it's over.
+            if let Some(expires) = ban.expires_after {
+                if expires < SystemTime::now() {
+                    (None, true)
+                } else {
+                    (Some(Text::from(ban.reason.clone())), false)
+                }
+            } else {
+                (Some(Text::from(ban.reason.clone())), false)
+            }
+        } else {
+            (None, false)
+        }
+    };
+
+    if remove_ban {
+        ih.ban_info
+            .write()
+            .unwrap()
+            .ip_bans
+            .remove(&ih.client_ip.ip());
+    }
+
+    reason
+}
+
 /// Terminates the login process, sending Set Compression (if necessary)
 /// and Login Success.
 ///
@@ -572,6 +803,16 @@ fn finish(ih: &mut InitialHandler) {
         ih.ban_info.write().unwrap().uuid_bans.remove(&uuid_str);
     }
 
+    if !ih.whitelist.read().unwrap().allows(info.uuid)
+        && ih.ops.read().unwrap().get(info.uuid).is_none()
+    {
+        let reason = Text::from(TextValue::translate(
+            "multiplayer.disconnect.not_whitelisted",
+        ));
+        disconnect_login(ih, reason);
+        return;
+    }
+
     // Send Login Success
     let login_success = LoginSuccess {
         uuid: uuid_str,
@@ -638,6 +879,12 @@ enum Error {
     AuthenticationFailed(mojang_api::Error),
     #[error("received BungeeCord data does not match the specification: {0}")]
     BungeeSpecMismatch(String),
+    #[error("received Velocity data does not match the specification: {0}")]
+    VelocitySpecMismatch(String),
+    #[error("Unable to verify player details")]
+    VelocityBadSignature,
+    #[error("This server requires you to connect with Velocity.")]
+    NotVelocity,
     #[error("option that should not be None was None")]
     /// An Error type than can be used as the error type of using the Try operator on Option
     /// types. In rust-core, this is an unstable feature (issue #42327)
@@ -652,6 +899,7 @@ enum Stage {
     AwaitPing,
     AwaitLoginStart,
     AwaitEncryptionResponse,
+    AwaitLoginPluginResponse,
     Finished,
 }
 
@@ -659,13 +907,15 @@ enum Stage {
 mod tests {
     use feather_core::network::cast_packet;
     use feather_core::network::packets::{
-        Handshake, HandshakeState, LoginSuccess, Ping, Pong, Request, Response, SetCompression,
+        Handshake, HandshakeState, LoginPluginRequest, LoginPluginResponse, LoginStart,
+        LoginSuccess, Ping, Pong, Request, Response, SetCompression,
     };
     use feather_core::network::PacketType;
 
     use crate::PROTOCOL_VERSION;
 
     use super::*;
+    use feather_server_types::Op;
     use mojang_api::ProfileProperty;
 
     #[test]
@@ -806,6 +1056,140 @@ mod tests {
         }
     }
 
+    #[test]
+    fn extract_velocity_data_normal() {
+        let data = velocity_data(b"secret");
+
+        assert_eq!(
+            extract_velocity_data(&data, b"secret").unwrap(),
+            VelocityData {
+                client: "192.168.1.67".parse().unwrap(),
+                uuid: Uuid::parse_str("905c7e4fb96b45139645d123225575e2").unwrap(),
+                username: "test".to_string(),
+                properties: vec![ProfileProperty {
+                    name: "textures".to_string(),
+                    value: "textures_value".to_string(),
+                    signature: "textures_signature".to_string(),
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn extract_velocity_data_bad_signature() {
+        let data = velocity_data(b"secret");
+
+        assert_eq!(
+            extract_velocity_data(&data, b"wrong secret").err().unwrap(),
+            Error::VelocityBadSignature
+        );
+    }
+
+    #[test]
+    fn extract_velocity_data_too_short() {
+        assert_eq!(
+            extract_velocity_data(&[0; 16], b"secret").err().unwrap(),
+            Error::VelocitySpecMismatch("missing signature".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_velocity_login_sequence() {
+        let mut config = Config::default();
+        config.proxy.proxy_mode = ProxyMode::Velocity;
+        config.proxy.velocity_secret = "secret".to_string();
+        let mut ih = ih_with_config(config);
+
+        let handshake = Handshake {
+            protocol_version: PROTOCOL_VERSION,
+            server_address: String::default(),
+            server_port: 25565,
+            next_state: HandshakeState::Login,
+        };
+        ih.handle_packet(Box::new(handshake)).await;
+        ih.actions_to_execute();
+
+        let login_start = LoginStart {
+            username: String::from("test"),
+        };
+        ih.handle_packet(Box::new(login_start)).await;
+
+        let mut actions = ih.actions_to_execute();
+        assert_eq!(actions.len(), 1);
+        let message_id = match actions.remove(0) {
+            Action::SendPacket(request) => {
+                assert_eq!(request.ty(), PacketType::LoginPluginRequest);
+
+                let request = cast_packet::<LoginPluginRequest>(request);
+                assert_eq!(request.channel, VELOCITY_CHANNEL);
+                request.message_id
+            }
+            _ => panic!(),
+        };
+
+        let response = LoginPluginResponse {
+            message_id,
+            successful: true,
+            data: velocity_data(b"secret"),
+        };
+        ih.handle_packet(Box::new(response)).await;
+
+        let actions = ih.actions_to_execute();
+        assert_eq!(actions.len(), 5);
+        match actions.last().unwrap() {
+            Action::JoinGame(info) => {
+                assert_eq!(info.username.as_deref(), Some("test"));
+                assert_eq!(
+                    info.uuid,
+                    Uuid::parse_str("905c7e4fb96b45139645d123225575e2").unwrap()
+                );
+                assert_eq!(info.forwarded_ip, Some("192.168.1.67".parse().unwrap()));
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_login_supported_protocols() {
+        for version in ProtocolVersion::values() {
+            let mut ih = ih();
+
+            let handshake = Handshake {
+                protocol_version: version.id(),
+                server_address: String::default(),
+                server_port: 25565,
+                next_state: HandshakeState::Login,
+            };
+            ih.handle_packet(Box::new(handshake)).await;
+
+            let actions = ih.actions_to_execute();
+            assert_eq!(actions.len(), 1);
+            match actions.first().unwrap() {
+                Action::SetStage(stage) => assert_eq!(*stage, PacketStage::Login),
+                _ => panic!(),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_login_unsupported_protocol() {
+        let mut ih = ih();
+
+        let handshake = Handshake {
+            protocol_version: 340, // 1.12.2
+            server_address: String::default(),
+            server_port: 25565,
+            next_state: HandshakeState::Login,
+        };
+        ih.handle_packet(Box::new(handshake)).await;
+
+        let actions = ih.actions_to_execute();
+        match actions.first().unwrap() {
+            Action::SendPacket(packet) => assert_eq!(packet.ty(), PacketType::DisconnectLogin),
+            _ => panic!(),
+        }
+    }
+
     #[test]
     fn test_initial_handler_new() {
         let mut ih = ih();
@@ -952,10 +1336,109 @@ mod tests {
         }
     }
 
+    /// Creates signed player information as sent by Velocity.
+    fn velocity_data(secret: &[u8]) -> Vec<u8> {
+        fn push_string(data: &mut Vec<u8>, s: &str) {
+            data.push(s.len() as u8);
+            data.extend_from_slice(s.as_bytes());
+        }
+
+        let mut data = vec![VELOCITY_FORWARDING_VERSION as u8];
+        push_string(&mut data, "192.168.1.67");
+        data.extend_from_slice(
+            Uuid::parse_str("905c7e4fb96b45139645d123225575e2")
+                .unwrap()
+                .as_bytes(),
+        );
+        push_string(&mut data, "test");
+        data.push(1); // Number of properties
+        push_string(&mut data, "textures");
+        push_string(&mut data, "textures_value");
+        data.push(1); // Has signature
+        push_string(&mut data, "textures_signature");
+
+        let mut mac = Hmac::<Sha256>::new_varkey(secret).unwrap();
+        mac.update(&data);
+
+        let mut signed = mac.finalize().into_bytes().to_vec();
+        signed.extend_from_slice(&data);
+        signed
+    }
+
+    #[tokio::test]
+    async fn test_login_not_whitelisted() {
+        let mut config = Config::default();
+        config.server.online_mode = false;
+        let mut ih = ih_with_config(config);
+        ih.whitelist.write().unwrap().enabled = true;
+
+        let handshake = Handshake {
+            protocol_version: PROTOCOL_VERSION,
+            server_address: String::default(),
+            server_port: 25565,
+            next_state: HandshakeState::Login,
+        };
+        ih.handle_packet(Box::new(handshake)).await;
+        ih.actions_to_execute();
+
+        let login_start = LoginStart {
+            username: String::from("test"),
+        };
+        ih.handle_packet(Box::new(login_start)).await;
+
+        // Set Compression and Enable Compression precede the disconnect.
+        let mut actions = ih.actions_to_execute().into_iter().skip(2);
+        match actions.next().unwrap() {
+            Action::SendPacket(packet) => assert_eq!(packet.ty(), PacketType::DisconnectLogin),
+            _ => panic!(),
+        }
+        match actions.next().unwrap() {
+            Action::Disconnect => (),
+            _ => panic!(),
+        }
+        assert!(actions.next().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_login_op_bypasses_whitelist() {
+        let mut config = Config::default();
+        config.server.online_mode = false;
+        let mut ih = ih_with_config(config);
+        ih.whitelist.write().unwrap().enabled = true;
+        ih.ops.write().unwrap().add(Op {
+            uuid: name_to_uuid_offline("test"),
+            name: String::from("test"),
+            level: 1,
+            bypasses_player_limit: false,
+        });
+
+        let handshake = Handshake {
+            protocol_version: PROTOCOL_VERSION,
+            server_address: String::default(),
+            server_port: 25565,
+            next_state: HandshakeState::Login,
+        };
+        ih.handle_packet(Box::new(handshake)).await;
+        ih.actions_to_execute();
+
+        let login_start = LoginStart {
+            username: String::from("test"),
+        };
+        ih.handle_packet(Box::new(login_start)).await;
+
+        let mut actions = ih.actions_to_execute().into_iter().skip(2);
+        match actions.next().unwrap() {
+            Action::SendPacket(packet) => assert_eq!(packet.ty(), PacketType::LoginSuccess),
+            _ => panic!(),
+        }
+    }
+
     fn ih() -> InitialHandler {
         InitialHandler::new(
             Arc::new(Config::default()),
             Arc::new(RwLock::new(BanInfo::default())),
+            Arc::new(RwLock::new(Whitelist::default())),
+            Arc::new(RwLock::new(OpList::default())),
             Arc::new(AtomicU32::new(0)),
             Arc::new(Some(String::from("test"))),
             "127.0.0.1:8080".parse().unwrap(),
@@ -966,6 +1449,8 @@ mod tests {
         InitialHandler::new(
             Arc::new(Config::default()),
             Arc::new(RwLock::new(BanInfo::default())),
+            Arc::new(RwLock::new(Whitelist::default())),
+            Arc::new(RwLock::new(OpList::default())),
             Arc::new(AtomicU32::new(count)),
             Arc::new(Some(String::from("test"))),
             "127.0.0.1:8080".parse().unwrap(),
@@ -976,6 +1461,8 @@ mod tests {
         InitialHandler::new(
             Arc::new(config),
             Arc::new(RwLock::new(BanInfo::default())),
+            Arc::new(RwLock::new(Whitelist::default())),
+            Arc::new(RwLock::new(OpList::default())),
             Arc::new(AtomicU32::new(0)),
             Arc::new(Some(String::from("test"))),
             "127.0.0.1:8080".parse().unwrap(),
@@ -4,8 +4,10 @@
 //! and Tokio. Contains a listener task which accepts new connections
 //! and a worker task for each client which reads and writes packets.
 
+/// The native protocol version, 1.13.2. Other supported
+/// versions are translated by the codec.
 pub const PROTOCOL_VERSION: u32 = 404;
-pub const SERVER_VERSION: &str = "Feather 1.13.2";
+pub const SERVER_VERSION: &str = "Feather 1.13.2-1.14.4";
 
 #[macro_use]
 extern crate feather_core;
@@ -15,6 +17,7 @@ use feather_core::anvil::player::PlayerData;
 use feather_core::util::Position;
 use feather_server_types::{
     Config, PacketBuffers, ServerToWorkerMessage, Uuid, WorkerToServerMessage, WrappedBanInfo,
+    WrappedOpList, WrappedWhitelist,
 };
 use fecs::Entity;
 use once_cell::sync::Lazy;
@@ -22,12 +25,16 @@ use parking_lot::Mutex;
 use std::net::SocketAddr;
 use std::sync::atomic::AtomicU32;
 use std::sync::Arc;
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, UdpSocket};
 
 mod initial_handler;
 mod listener;
+mod query;
+mod rcon;
 mod worker;
 
+pub use query::QueryInfo;
+
 #[derive(Debug)]
 #[allow(clippy::large_enum_variant)]
 pub enum ListenerToServerMessage {
@@ -85,6 +92,8 @@ impl NetworkIoManager {
         listener: TcpListener,
         config: Arc<Config>,
         ban_info: WrappedBanInfo,
+        whitelist: WrappedWhitelist,
+        ops: WrappedOpList,
         player_count: Arc<AtomicU32>,
         server_icon: Arc<Option<String>>,
         packet_buffers: Arc<PacketBuffers>,
@@ -96,7 +105,7 @@ impl NetworkIoManager {
             listener,
             listener_tx.clone(),
             listener_rx,
-            (config, ban_info),
+            (config, ban_info, whitelist, ops),
             player_count,
             server_icon,
             packet_buffers,
@@ -117,6 +126,18 @@ impl NetworkIoManager {
     }
 }
 
+/// Starts the GameSpy4 query listener, answering
+/// requests received on `socket`.
+pub fn start_query(socket: UdpSocket, info: QueryInfo) {
+    tokio::spawn(query::run_query(socket, info));
+}
+
+/// Starts the RCON listener, accepting
+/// connections on `listener`.
+pub fn start_rcon(listener: TcpListener, config: Arc<Config>) {
+    tokio::spawn(rcon::run_rcon(listener, config));
+}
+
 /// Initializes certain static variables.
 pub fn init() {
     Lazy::force(&initial_handler::RSA_KEY);
@@ -126,7 +147,7 @@ async fn run_listener(
     listener: TcpListener,
     tx: flume::Sender<ListenerToServerMessage>,
     rx: flume::Receiver<ServerToListenerMessage>,
-    config_bans: (Arc<Config>, WrappedBanInfo),
+    config_bans: (Arc<Config>, WrappedBanInfo, WrappedWhitelist, WrappedOpList),
     player_count: Arc<AtomicU32>,
     server_icon: Arc<Option<String>>,
     packet_buffers: Arc<PacketBuffers>,
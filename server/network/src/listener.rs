@@ -5,7 +5,9 @@
 
 use crate::worker::run_worker;
 use crate::{ListenerToServerMessage, ServerToListenerMessage};
-use feather_server_types::{Config, PacketBuffers, WrappedBanInfo};
+use feather_server_types::{
+    Config, PacketBuffers, WrappedBanInfo, WrappedOpList, WrappedWhitelist,
+};
 
 use std::sync::atomic::AtomicU32;
 use std::sync::Arc;
@@ -17,7 +19,7 @@ pub async fn run_listener(
     mut listener: TcpListener,
     tx: flume::Sender<ListenerToServerMessage>,
     rx: flume::Receiver<ServerToListenerMessage>,
-    config_bans: (Arc<Config>, WrappedBanInfo),
+    config_bans: (Arc<Config>, WrappedBanInfo, WrappedWhitelist, WrappedOpList),
     player_count: Arc<AtomicU32>,
     server_icon: Arc<Option<String>>,
     packet_buffers: Arc<PacketBuffers>,
@@ -42,6 +44,8 @@ pub async fn run_listener(
             Arc::clone(&rx),
             Arc::clone(&config_bans.0),
             Arc::clone(&config_bans.1),
+            Arc::clone(&config_bans.2),
+            Arc::clone(&config_bans.3),
             Arc::clone(&player_count),
             Arc::clone(&server_icon),
             Arc::clone(&packet_buffers),
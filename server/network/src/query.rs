@@ -0,0 +1,315 @@
+//! Listener for the GameSpy4 query protocol, which server lists
+//! and monitoring tools use to fetch the MOTD, player list and
+//! other information over UDP.
+//!
+//! See https://wiki.vg/Query for the protocol.
+
+use crate::{PROTOCOL_VERSION, SERVER_VERSION};
+use feather_core::network::ProtocolVersion;
+use feather_server_types::Config;
+use rand::Rng;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+
+/// Bytes prefixing each request.
+const MAGIC: [u8; 2] = [0xFE, 0xFD];
+
+const HANDSHAKE: u8 = 9;
+const STAT: u8 = 0;
+
+/// Duration after which a challenge token is no longer accepted.
+const TOKEN_LIFETIME: Duration = Duration::from_secs(30);
+
+/// Padding preceding the key-value section of a full stat response.
+const KEY_VALUES_PADDING: &[u8] = b"splitnum\0\x80\0";
+/// Padding preceding the player section of a full stat response.
+const PLAYERS_PADDING: &[u8] = b"\x01player_\0\0";
+
+/// Information about the server reported in responses.
+pub struct QueryInfo {
+    pub config: Arc<Config>,
+    pub player_count: Arc<AtomicU32>,
+    pub player_names: Arc<RwLock<Vec<String>>>,
+    /// The names and versions of the enabled plugins.
+    pub plugins: Arc<RwLock<Vec<(String, String)>>>,
+}
+
+/// Runs the query listener, answering requests received on `socket`.
+pub async fn run_query(mut socket: UdpSocket, info: QueryInfo) {
+    let mut tokens = ChallengeTokens::default();
+    let mut buf = [0; 1460];
+
+    loop {
+        let (len, addr) = match socket.recv_from(&mut buf).await {
+            Ok(res) => res,
+            Err(e) => {
+                log::debug!("Failed to receive query request: {}", e);
+                continue;
+            }
+        };
+
+        if let Some(response) = handle_request(&buf[..len], addr, &mut tokens, &info) {
+            if let Err(e) = socket.send_to(&response, &addr).await {
+                log::debug!("Failed to send query response to {}: {}", addr, e);
+            }
+        }
+    }
+}
+
+/// Challenge tokens issued to clients in handshakes,
+/// which must be included in subsequent stat requests.
+#[derive(Default)]
+struct ChallengeTokens {
+    tokens: HashMap<SocketAddr, (i32, Instant)>,
+}
+
+impl ChallengeTokens {
+    /// Issues a new token to `addr`, replacing any previous one.
+    fn issue(&mut self, addr: SocketAddr) -> i32 {
+        self.tokens
+            .retain(|_, (_, issued)| issued.elapsed() < TOKEN_LIFETIME);
+
+        let token = rand::thread_rng().gen_range(0, i32::max_value());
+        self.tokens.insert(addr, (token, Instant::now()));
+        token
+    }
+
+    /// Determines whether `token` was issued to `addr`
+    /// and has not yet expired.
+    fn verify(&self, addr: SocketAddr, token: i32) -> bool {
+        match self.tokens.get(&addr) {
+            Some((issued_token, issued)) => {
+                *issued_token == token && issued.elapsed() < TOKEN_LIFETIME
+            }
+            None => false,
+        }
+    }
+}
+
+/// Returns the response to a request, or `None` if the
+/// request is malformed or has an invalid challenge token.
+fn handle_request(
+    request: &[u8],
+    addr: SocketAddr,
+    tokens: &mut ChallengeTokens,
+    info: &QueryInfo,
+) -> Option<Vec<u8>> {
+    if request.len() < 7 || request[..2] != MAGIC {
+        return None;
+    }
+
+    let kind = request[2];
+    let session_id = &request[3..7];
+    let payload = &request[7..];
+
+    let mut response = vec![kind];
+    response.extend_from_slice(session_id);
+
+    match kind {
+        HANDSHAKE => {
+            let token = tokens.issue(addr);
+            write_str(&mut response, &token.to_string());
+        }
+        STAT => {
+            let token = i32::from_be_bytes(payload.get(..4)?.try_into().unwrap());
+            if !tokens.verify(addr, token) {
+                return None;
+            }
+
+            // Full stat requests are padded to 8 bytes.
+            if payload.len() >= 8 {
+                write_full_stat(&mut response, info);
+            } else {
+                write_basic_stat(&mut response, info);
+            }
+        }
+        _ => return None,
+    }
+
+    Some(response)
+}
+
+fn write_basic_stat(response: &mut Vec<u8>, info: &QueryInfo) {
+    let server = &info.config.server;
+
+    write_str(response, &server.motd);
+    write_str(response, "SMP");
+    write_str(response, &info.config.world.name);
+    write_str(
+        response,
+        &info.player_count.load(Ordering::Acquire).to_string(),
+    );
+    write_str(response, &server.max_players.to_string());
+    response.extend_from_slice(&server.port.to_le_bytes());
+    write_str(response, &server.address);
+}
+
+fn write_full_stat(response: &mut Vec<u8>, info: &QueryInfo) {
+    let server = &info.config.server;
+
+    response.extend_from_slice(KEY_VALUES_PADDING);
+    let key_values = [
+        ("hostname", server.motd.clone()),
+        ("gametype", String::from("SMP")),
+        ("game_id", String::from("MINECRAFT")),
+        ("version", String::from(native_version().name())),
+        ("plugins", plugins(info)),
+        ("map", info.config.world.name.clone()),
+        (
+            "numplayers",
+            info.player_count.load(Ordering::Acquire).to_string(),
+        ),
+        ("maxplayers", server.max_players.to_string()),
+        ("hostport", server.port.to_string()),
+        ("hostip", server.address.clone()),
+    ];
+    for (key, value) in key_values.iter() {
+        write_str(response, key);
+        write_str(response, value);
+    }
+    write_str(response, "");
+
+    response.extend_from_slice(PLAYERS_PADDING);
+    for name in info.player_names.read().unwrap().iter() {
+        write_str(response, name);
+    }
+    write_str(response, "");
+}
+
+/// Returns the version of the native protocol, which
+/// server lists show as the version of the server.
+fn native_version() -> ProtocolVersion {
+    ProtocolVersion::from_id(PROTOCOL_VERSION).expect("native protocol version is not supported")
+}
+
+/// Returns the server version followed by the enabled plugins,
+/// in the vanilla format: `Feather <version>: name1 version1; name2 version2`.
+fn plugins(info: &QueryInfo) -> String {
+    let plugins = info.plugins.read().unwrap();
+    if plugins.is_empty() {
+        return String::from(SERVER_VERSION);
+    }
+
+    let plugins: Vec<String> = plugins
+        .iter()
+        .map(|(name, version)| format!("{} {}", name, version))
+        .collect();
+    format!("{}: {}", SERVER_VERSION, plugins.join("; "))
+}
+
+/// Writes a null-terminated string.
+fn write_str(response: &mut Vec<u8>, s: &str) {
+    response.extend_from_slice(s.as_bytes());
+    response.push(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SESSION_ID: [u8; 4] = [0, 0, 0, 1];
+
+    fn query_info() -> QueryInfo {
+        QueryInfo {
+            config: Arc::new(Config::default()),
+            player_count: Arc::new(AtomicU32::new(2)),
+            player_names: Arc::new(RwLock::new(vec![
+                String::from("Notch"),
+                String::from("jeb_"),
+            ])),
+            plugins: Arc::new(RwLock::new(vec![
+                (String::from("wasm"), String::from("0.6.0")),
+                (String::from("greeter"), String::from("1.0.0")),
+            ])),
+        }
+    }
+
+    fn request(kind: u8, payload: &[u8]) -> Vec<u8> {
+        let mut request = MAGIC.to_vec();
+        request.push(kind);
+        request.extend_from_slice(&SESSION_ID);
+        request.extend_from_slice(payload);
+        request
+    }
+
+    /// Performs a handshake, returning the challenge token.
+    fn handshake(addr: SocketAddr, tokens: &mut ChallengeTokens, info: &QueryInfo) -> i32 {
+        let response = handle_request(&request(HANDSHAKE, &[]), addr, tokens, info).unwrap();
+        assert_eq!(response[0], HANDSHAKE);
+        assert_eq!(response[1..5], SESSION_ID);
+        assert_eq!(*response.last().unwrap(), 0);
+
+        let token = std::str::from_utf8(&response[5..response.len() - 1]).unwrap();
+        token.parse().unwrap()
+    }
+
+    #[test]
+    fn test_basic_stat() {
+        let addr = "127.0.0.1:1234".parse().unwrap();
+        let mut tokens = ChallengeTokens::default();
+        let info = query_info();
+
+        let token = handshake(addr, &mut tokens, &info);
+        let response = handle_request(
+            &request(STAT, &token.to_be_bytes()),
+            addr,
+            &mut tokens,
+            &info,
+        )
+        .unwrap();
+
+        let mut expected = vec![STAT];
+        expected.extend_from_slice(&SESSION_ID);
+        expected.extend_from_slice(b"A Feather server\0SMP\0world\x002\x0016\0");
+        expected.extend_from_slice(&25565u16.to_le_bytes());
+        expected.extend_from_slice(b"0.0.0.0\0");
+        assert_eq!(response, expected);
+    }
+
+    #[test]
+    fn test_full_stat() {
+        let addr = "127.0.0.1:1234".parse().unwrap();
+        let mut tokens = ChallengeTokens::default();
+        let info = query_info();
+
+        let token = handshake(addr, &mut tokens, &info);
+        let mut payload = token.to_be_bytes().to_vec();
+        payload.extend_from_slice(&[0; 4]);
+        let response = handle_request(&request(STAT, &payload), addr, &mut tokens, &info).unwrap();
+
+        assert_eq!(
+            response[5..5 + KEY_VALUES_PADDING.len()],
+            *KEY_VALUES_PADDING
+        );
+        let body = std::str::from_utf8(&response[5 + KEY_VALUES_PADDING.len()..]).unwrap();
+        assert!(body.starts_with("hostname\0A Feather server\0gametype\0SMP\0"));
+        assert!(body.contains("version\01.13.2\0"));
+        assert!(body.contains(&format!(
+            "plugins\0{}: wasm 0.6.0; greeter 1.0.0\0",
+            SERVER_VERSION
+        )));
+        assert!(body.contains("map\0world\0numplayers\x002\0maxplayers\x0016\0"));
+        assert!(body.ends_with("\0\0\x01player_\0\0Notch\0jeb_\0\0"));
+    }
+
+    #[test]
+    fn test_invalid_token() {
+        let addr = "127.0.0.1:1234".parse().unwrap();
+        let other_addr = "127.0.0.2:1234".parse().unwrap();
+        let mut tokens = ChallengeTokens::default();
+        let info = query_info();
+
+        assert!(handle_request(&request(STAT, &[0; 4]), addr, &mut tokens, &info).is_none());
+
+        let token = handshake(addr, &mut tokens, &info);
+        let stat = request(STAT, &token.to_be_bytes());
+        assert!(handle_request(&stat, other_addr, &mut tokens, &info).is_none());
+        assert!(handle_request(&stat[..9], addr, &mut tokens, &info).is_none());
+        assert!(handle_request(&stat, addr, &mut tokens, &info).is_some());
+    }
+}
@@ -0,0 +1,238 @@
+//! Remote console using the Source RCON protocol, which
+//! allows administrators to run commands remotely.
+//!
+//! See https://wiki.vg/RCON for the protocol.
+
+use anyhow::bail;
+use feather_server_commands::CommandState;
+use feather_server_types::{tasks, Config};
+use fecs::ResourcesProvider;
+use std::convert::TryInto;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Packet type of responses to commands.
+const RESPONSE_VALUE: i32 = 0;
+/// Packet type of command requests.
+const EXEC_COMMAND: i32 = 2;
+/// Packet type of login responses.
+const AUTH_RESPONSE: i32 = 2;
+/// Packet type of login requests.
+const AUTH: i32 = 3;
+
+/// Request ID sent in login responses if the password is wrong.
+const AUTH_FAILURE_ID: i32 = -1;
+
+/// Maximum length of the remainder of a request after its length field.
+const MAX_REQUEST_LEN: usize = 1460;
+/// Maximum length of a response body. Longer
+/// responses are split into multiple packets.
+const MAX_RESPONSE_BODY_LEN: usize = 4096;
+
+/// Runs the RCON listener, accepting connections on `listener`.
+pub async fn run_rcon(mut listener: TcpListener, config: Arc<Config>) {
+    loop {
+        let (stream, ip) = match listener.accept().await {
+            Ok(res) => res,
+            Err(e) => {
+                log::info!("Failed to accept RCON connection: {}", e);
+                continue;
+            }
+        };
+
+        log::info!("RCON connection received from {}", ip);
+
+        let config = Arc::clone(&config);
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, ip, &config).await {
+                log::debug!("RCON connection from {} closed: {}", ip, e);
+            }
+        });
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Packet {
+    id: i32,
+    kind: i32,
+    body: String,
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    ip: SocketAddr,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let mut authenticated = false;
+
+    loop {
+        let request = read_packet(&mut stream).await?;
+
+        let responses = match request.kind {
+            AUTH => {
+                authenticated = request.body == config.rcon.password;
+                if !authenticated {
+                    log::info!("RCON client {} used an incorrect password", ip);
+                }
+
+                let id = if authenticated {
+                    request.id
+                } else {
+                    AUTH_FAILURE_ID
+                };
+                vec![Packet {
+                    id,
+                    kind: AUTH_RESPONSE,
+                    body: String::new(),
+                }]
+            }
+            EXEC_COMMAND if authenticated => {
+                let output = execute(request.body).await.unwrap_or_default();
+                split_response(request.id, &output)
+            }
+            EXEC_COMMAND => vec![Packet {
+                id: AUTH_FAILURE_ID,
+                kind: AUTH_RESPONSE,
+                body: String::new(),
+            }],
+            // Clients may send an empty response value after a command
+            // and wait for it to be mirrored to find the end of a
+            // multi-packet response.
+            RESPONSE_VALUE => vec![Packet {
+                id: request.id,
+                kind: RESPONSE_VALUE,
+                body: String::new(),
+            }],
+            kind => bail!("unknown packet type {}", kind),
+        };
+
+        for response in &responses {
+            stream.write_all(&encode_packet(response)).await?;
+        }
+    }
+}
+
+/// Executes a command on the server thread, returning its output.
+async fn execute(command: String) -> Option<String> {
+    let command = command.trim().trim_start_matches('/').to_owned();
+    log::info!("RCON executed command `{}`", command);
+
+    let output = tasks().sync(move |game, world| {
+        let resources = Arc::clone(&game.resources);
+        let commands = resources.get::<CommandState>();
+        commands.dispatch_remote(game, world, &command)
+    });
+
+    match output.await {
+        Ok(output) => output,
+        Err(_) => None,
+    }
+}
+
+/// Splits command output into response packets with
+/// bodies no longer than `MAX_RESPONSE_BODY_LEN`.
+fn split_response(id: i32, output: &str) -> Vec<Packet> {
+    let mut packets = Vec::new();
+    let mut rest = output;
+
+    loop {
+        let mut len = rest.len().min(MAX_RESPONSE_BODY_LEN);
+        while !rest.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        packets.push(Packet {
+            id,
+            kind: RESPONSE_VALUE,
+            body: rest[..len].to_owned(),
+        });
+        rest = &rest[len..];
+
+        if rest.is_empty() {
+            return packets;
+        }
+    }
+}
+
+async fn read_packet(stream: &mut TcpStream) -> anyhow::Result<Packet> {
+    let len = stream.read_i32_le().await?;
+    if len < 10 || len as usize > MAX_REQUEST_LEN {
+        bail!("invalid packet length {}", len);
+    }
+
+    let mut buf = vec![0; len as usize];
+    stream.read_exact(&mut buf).await?;
+    decode_packet(&buf)
+}
+
+/// Decodes a packet, excluding its length field.
+fn decode_packet(buf: &[u8]) -> anyhow::Result<Packet> {
+    // The body is followed by a null terminator and a null padding byte.
+    if buf.len() < 10 || !buf.ends_with(&[0, 0]) {
+        bail!("packet body is not null-terminated");
+    }
+
+    let id = i32::from_le_bytes(buf[0..4].try_into().unwrap());
+    let kind = i32::from_le_bytes(buf[4..8].try_into().unwrap());
+    let body = String::from_utf8(buf[8..buf.len() - 2].to_vec())?;
+
+    Ok(Packet { id, kind, body })
+}
+
+fn encode_packet(packet: &Packet) -> Vec<u8> {
+    let len = 4 + 4 + packet.body.len() + 2;
+
+    let mut buf = Vec::with_capacity(4 + len);
+    buf.extend_from_slice(&(len as i32).to_le_bytes());
+    buf.extend_from_slice(&packet.id.to_le_bytes());
+    buf.extend_from_slice(&packet.kind.to_le_bytes());
+    buf.extend_from_slice(packet.body.as_bytes());
+    buf.extend_from_slice(&[0, 0]);
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        let packet = Packet {
+            id: 7,
+            kind: EXEC_COMMAND,
+            body: String::from("seed"),
+        };
+
+        let encoded = encode_packet(&packet);
+        assert_eq!(
+            encoded,
+            [14, 0, 0, 0, 7, 0, 0, 0, 2, 0, 0, 0, b's', b'e', b'e', b'd', 0, 0]
+        );
+        assert_eq!(decode_packet(&encoded[4..]).unwrap(), packet);
+
+        assert!(decode_packet(&[7, 0, 0, 0, 2, 0, 0, 0, b's', 0]).is_err());
+    }
+
+    #[test]
+    fn test_split_response() {
+        let packets = split_response(1, "");
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].body, "");
+
+        let output = "é".repeat(MAX_RESPONSE_BODY_LEN);
+        let packets = split_response(1, &output);
+        assert_eq!(packets.len(), 2);
+        assert!(packets
+            .iter()
+            .all(|packet| packet.id == 1 && packet.body.len() <= MAX_RESPONSE_BODY_LEN));
+        assert_eq!(
+            packets
+                .iter()
+                .map(|packet| packet.body.as_str())
+                .collect::<String>(),
+            output
+        );
+    }
+}
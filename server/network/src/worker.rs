@@ -13,7 +13,8 @@ use feather_core::anvil::player::PlayerData;
 use feather_core::network::{MinecraftCodec, Packet, PacketDirection};
 use feather_core::util::{Position, Vec3d};
 use feather_server_types::{
-    BanInfo, Config, PacketBuffers, ServerToWorkerMessage, Uuid, WorkerToServerMessage,
+    BanInfo, Config, OpList, PacketBuffers, ServerToWorkerMessage, Uuid, Whitelist,
+    WorkerToServerMessage,
 };
 use fecs::Entity;
 use futures::future::Either;
@@ -63,6 +64,8 @@ pub async fn run_worker(
     listener_rx: Arc<Mutex<flume::Receiver<ServerToListenerMessage>>>,
     config: Arc<Config>,
     ban_info: Arc<RwLock<BanInfo>>,
+    whitelist: Arc<RwLock<Whitelist>>,
+    ops: Arc<RwLock<OpList>>,
     player_count: Arc<AtomicU32>,
     server_icon: Arc<Option<String>>,
     packet_buffers: Arc<PacketBuffers>,
@@ -73,6 +76,8 @@ pub async fn run_worker(
     let initial_handler = Some(InitialHandler::new(
         Arc::clone(&config),
         Arc::clone(&ban_info),
+        Arc::clone(&whitelist),
+        Arc::clone(&ops),
         Arc::clone(&player_count),
         Arc::clone(&server_icon),
         ip,
@@ -200,8 +205,12 @@ async fn handle_ih_actions(worker: &mut Worker) -> anyhow::Result<()> {
             Action::JoinGame(info) => {
                 let data = load_player_data(&worker.config, info.uuid).await?;
                 let position = data.animal.base.read_position()?;
+                let ip = info
+                    .forwarded_ip
+                    .map(|ip| SocketAddr::new(ip, worker.ip.port()))
+                    .unwrap_or(worker.ip);
                 let info = NewClientInfo {
-                    ip: worker.ip,
+                    ip,
                     username: info.username.unwrap_or_else(|| String::from("undefined")),
                     profile: info.props,
                     uuid: info.uuid,
@@ -246,6 +255,7 @@ async fn load_player_data(config: &Config, uuid: Uuid) -> Result<PlayerData, any
                 gamemode: config.server.default_gamemode.id() as i32,
                 inventory: vec![],
                 held_item: 0,
+                recipe_book: Default::default(),
             };
 
             feather_core::anvil::player::save_player_data(
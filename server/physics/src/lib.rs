@@ -5,6 +5,7 @@ extern crate nalgebra_glm as glm;
 mod block_bboxes;
 mod entity;
 mod math;
+pub mod pathfinding;
 
 pub use entity::entity_physics;
 pub use math::*;
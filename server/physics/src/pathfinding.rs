@@ -0,0 +1,652 @@
+//! A* pathfinding through the blocks of the world.
+//!
+//! Paths are made of block positions at which an entity's feet
+//! rest. Entities walk between adjacent blocks, jump up one block,
+//! fall a limited number of blocks and swim through water. Fences,
+//! walls, closed doors and harmful blocks such as lava are avoided.
+//!
+//! Searches are incremental: `Pathfinder::step` expands at most
+//! as many nodes as it is given in its budget, so that the
+//! searches of many entities can share a fixed amount of work
+//! each tick and resume on the next one.
+
+use feather_core::blocks::{BlockId, SimplifiedBlockKind};
+use feather_core::chunk_map::ChunkMap;
+use feather_core::position;
+use feather_core::util::{BlockPosition, Position};
+use feather_server_types::{AABBExt, Physics};
+use smallvec::SmallVec;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Cost of walking to an adjacent block.
+const WALK_COST: u32 = 10;
+/// Cost of walking to a diagonally adjacent block.
+const DIAGONAL_COST: u32 = 14;
+/// Additional cost of jumping up a block.
+const JUMP_COST: u32 = 10;
+/// Additional cost of each block fallen.
+const FALL_COST: u32 = 10;
+/// Additional cost of moving through water.
+const WATER_COST: u32 = 20;
+
+/// Options describing the entity a path is searched for.
+#[derive(Copy, Clone, Debug)]
+pub struct PathOptions {
+    /// Width of the entity's bounding box, in blocks.
+    pub width: f64,
+    /// Height of the entity's bounding box, in blocks.
+    pub height: f64,
+    /// Maximum number of blocks the entity may fall.
+    pub max_fall: u32,
+    /// Whether the entity can walk through closed wooden doors.
+    pub can_open_doors: bool,
+    /// Whether the entity can swim through water.
+    pub can_swim: bool,
+    /// Maximum number of nodes to expand before settling
+    /// for a path to the closest node found.
+    pub max_nodes: usize,
+}
+
+impl PathOptions {
+    /// Returns the default options for an entity with the given physics.
+    pub fn for_physics(physics: &Physics) -> Self {
+        let size = physics.bbox.size();
+        Self {
+            width: size.x,
+            height: size.y,
+            max_fall: 3,
+            can_open_doors: false,
+            can_swim: true,
+            max_nodes: 1024,
+        }
+    }
+
+    /// Returns the number of blocks the entity
+    /// occupies along the X and Z axes.
+    fn footprint(&self) -> i32 {
+        (self.width.ceil() as i32).max(1)
+    }
+
+    /// Returns the number of blocks the entity
+    /// occupies along the Y axis.
+    fn body_height(&self) -> i32 {
+        (self.height.ceil() as i32).max(1)
+    }
+}
+
+/// A path found by a `Pathfinder`.
+#[derive(Clone, Debug)]
+pub struct Path {
+    /// The blocks to walk through, excluding the start.
+    /// Each node is the block at the entity's feet, at the
+    /// north-west corner of its footprint.
+    pub nodes: Vec<BlockPosition>,
+    /// Whether the path reaches the goal. If not, it leads
+    /// to the reachable block closest to the goal.
+    pub reaches_goal: bool,
+    footprint: i32,
+}
+
+impl Path {
+    /// Returns the position an entity walks to in order to
+    /// reach the node at `index`: the center of its footprint.
+    pub fn node_position(&self, index: usize) -> Option<Position> {
+        let node = self.nodes.get(index)?;
+        let offset = f64::from(self.footprint) / 2.0;
+        Some(position!(
+            f64::from(node.x) + offset,
+            f64::from(node.y),
+            f64::from(node.z) + offset,
+        ))
+    }
+}
+
+/// The state of a path search.
+#[derive(Clone, Debug)]
+pub enum PathStatus {
+    /// The search needs more steps.
+    Searching,
+    /// A path was found.
+    Found(Path),
+    /// The start position has no neighbors to walk to.
+    NotFound,
+}
+
+/// How the contents of a block affect movement.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Cell {
+    /// Blocks which can be walked through, such as air.
+    Open,
+    /// Water, which can be swum through.
+    Water,
+    /// Blocks which can be stood on.
+    Solid,
+    /// Blocks which can be neither walked through nor stood
+    /// on, such as fences, closed doors and unloaded blocks.
+    Blocked,
+    /// Blocks which hurt entities walking into or onto them.
+    Harmful,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Node {
+    cost: u32,
+    parent: Option<BlockPosition>,
+    closed: bool,
+}
+
+/// Entry of the open set, ordered so that the
+/// `BinaryHeap` pops the lowest estimate first.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct OpenNode {
+    estimate: u32,
+    pos: BlockPosition,
+}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then_with(|| self.pos.cmp(&other.pos))
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// An in-progress A* search for a path between two blocks.
+#[derive(Clone, Debug)]
+pub struct Pathfinder {
+    start: BlockPosition,
+    goal: BlockPosition,
+    options: PathOptions,
+    open: BinaryHeap<OpenNode>,
+    nodes: HashMap<BlockPosition, Node>,
+    expanded: usize,
+    /// The expanded node closest to the goal.
+    closest: BlockPosition,
+    closest_distance: u32,
+}
+
+impl Pathfinder {
+    /// Creates a search for a path from `start` to `goal`,
+    /// which are the blocks at the entity's feet.
+    pub fn new(start: BlockPosition, goal: BlockPosition, options: PathOptions) -> Self {
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            start,
+            Node {
+                cost: 0,
+                parent: None,
+                closed: false,
+            },
+        );
+        let mut open = BinaryHeap::new();
+        open.push(OpenNode {
+            estimate: heuristic(start, goal),
+            pos: start,
+        });
+
+        Self {
+            start,
+            goal,
+            options,
+            open,
+            nodes,
+            expanded: 0,
+            closest: start,
+            closest_distance: heuristic(start, goal),
+        }
+    }
+
+    /// Continues the search, expanding at most `budget` nodes.
+    /// `budget` is decreased by the number of nodes expanded.
+    pub fn step(&mut self, blocks: &ChunkMap, budget: &mut usize) -> PathStatus {
+        while *budget > 0 {
+            let current = match self.open.pop() {
+                Some(node) => node.pos,
+                None => return self.finish(),
+            };
+            let cost = {
+                let node = self.nodes.get_mut(&current).unwrap();
+                // The heap may hold outdated entries
+                // for nodes found again at a lower cost.
+                if node.closed {
+                    continue;
+                }
+                node.closed = true;
+                node.cost
+            };
+            *budget -= 1;
+            self.expanded += 1;
+
+            if current == self.goal {
+                return PathStatus::Found(self.path_to(current, true));
+            }
+            let distance = heuristic(current, self.goal);
+            if distance < self.closest_distance {
+                self.closest = current;
+                self.closest_distance = distance;
+            }
+            if self.expanded >= self.options.max_nodes {
+                return self.finish();
+            }
+
+            for (neighbor, step_cost) in self.neighbors(blocks, current) {
+                let cost = cost + step_cost;
+                let node = self.nodes.entry(neighbor).or_insert(Node {
+                    cost: u32::max_value(),
+                    parent: None,
+                    closed: false,
+                });
+                if node.closed || cost >= node.cost {
+                    continue;
+                }
+                node.cost = cost;
+                node.parent = Some(current);
+                self.open.push(OpenNode {
+                    estimate: cost + heuristic(neighbor, self.goal),
+                    pos: neighbor,
+                });
+            }
+        }
+
+        PathStatus::Searching
+    }
+
+    /// Ends a search which did not reach the goal.
+    fn finish(&self) -> PathStatus {
+        if self.closest == self.start {
+            PathStatus::NotFound
+        } else {
+            PathStatus::Found(self.path_to(self.closest, false))
+        }
+    }
+
+    fn path_to(&self, end: BlockPosition, reaches_goal: bool) -> Path {
+        let mut nodes = vec![];
+        let mut current = end;
+        while current != self.start {
+            nodes.push(current);
+            current = self.nodes[&current].parent.unwrap();
+        }
+        nodes.reverse();
+
+        Path {
+            nodes,
+            reaches_goal,
+            footprint: self.options.footprint(),
+        }
+    }
+
+    /// Returns the blocks reachable from `pos` in
+    /// one move, along with the cost of each move.
+    fn neighbors(
+        &self,
+        blocks: &ChunkMap,
+        pos: BlockPosition,
+    ) -> SmallVec<[(BlockPosition, u32); 8]> {
+        let mut neighbors = SmallVec::new();
+        let offset =
+            |dx: i32, dy: i32, dz: i32| BlockPosition::new(pos.x + dx, pos.y + dy, pos.z + dz);
+
+        for &(dx, dz) in &[(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let next = offset(dx, 0, dz);
+            if let Some(cost) = self.stand_cost(blocks, next) {
+                neighbors.push((next, WALK_COST + cost));
+                continue;
+            }
+
+            let above = offset(dx, 1, dz);
+            if self.is_clear(blocks, pos.up()) {
+                if let Some(cost) = self.stand_cost(blocks, above) {
+                    neighbors.push((above, WALK_COST + JUMP_COST + cost));
+                    continue;
+                }
+            }
+
+            if self.is_clear(blocks, next) {
+                for fall in 1..=self.options.max_fall as i32 {
+                    let below = offset(dx, -fall, dz);
+                    if let Some(cost) = self.stand_cost(blocks, below) {
+                        let fall_cost = FALL_COST * fall as u32;
+                        neighbors.push((below, WALK_COST + fall_cost + cost));
+                        break;
+                    }
+                    if !self.is_clear(blocks, below) {
+                        break;
+                    }
+                }
+            }
+        }
+
+        // Diagonal moves must not cut corners.
+        for &(dx, dz) in &[(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+            let next = offset(dx, 0, dz);
+            if self.stand_cost(blocks, offset(dx, 0, 0)).is_none()
+                || self.stand_cost(blocks, offset(0, 0, dz)).is_none()
+            {
+                continue;
+            }
+            if let Some(cost) = self.stand_cost(blocks, next) {
+                neighbors.push((next, DIAGONAL_COST + cost));
+            }
+        }
+
+        // Swimming up and down
+        if self.cell(blocks, pos) == Cell::Water {
+            for &dy in &[1, -1] {
+                let next = offset(0, dy, 0);
+                if let Some(cost) = self.stand_cost(blocks, next) {
+                    neighbors.push((next, WALK_COST + cost));
+                }
+            }
+        }
+
+        neighbors
+    }
+
+    /// Returns the additional cost of the entity standing
+    /// at `pos`, or `None` if it cannot stand there.
+    fn stand_cost(&self, blocks: &ChunkMap, pos: BlockPosition) -> Option<u32> {
+        if !self.is_clear(blocks, pos) {
+            return None;
+        }
+        if self
+            .footprint(pos)
+            .any(|pos| self.cell(blocks, pos) == Cell::Water)
+        {
+            return Some(WATER_COST);
+        }
+
+        let mut supported = false;
+        for below in self.footprint(pos.down()) {
+            match self.cell(blocks, below) {
+                Cell::Solid => supported = true,
+                Cell::Harmful => return None,
+                _ => (),
+            }
+        }
+
+        if supported {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether the entity's body fits at `pos`.
+    fn is_clear(&self, blocks: &ChunkMap, pos: BlockPosition) -> bool {
+        (0..self.options.body_height()).all(|dy| {
+            self.footprint(BlockPosition::new(pos.x, pos.y + dy, pos.z))
+                .all(|pos| match self.cell(blocks, pos) {
+                    Cell::Open => true,
+                    Cell::Water => self.options.can_swim,
+                    _ => false,
+                })
+        })
+    }
+
+    /// Returns the blocks at the level of `pos`
+    /// covered by the entity's bounding box.
+    fn footprint(&self, pos: BlockPosition) -> impl Iterator<Item = BlockPosition> {
+        let size = self.options.footprint();
+        (0..size).flat_map(move |dx| {
+            (0..size).map(move |dz| BlockPosition::new(pos.x + dx, pos.y, pos.z + dz))
+        })
+    }
+
+    fn cell(&self, blocks: &ChunkMap, pos: BlockPosition) -> Cell {
+        match blocks.block_at(pos) {
+            Some(block) => classify(block, &self.options),
+            None => Cell::Blocked,
+        }
+    }
+}
+
+fn classify(block: BlockId, options: &PathOptions) -> Cell {
+    let open = block.open() == Some(true);
+    match block.simplified_kind() {
+        SimplifiedBlockKind::Lava
+        | SimplifiedBlockKind::Fire
+        | SimplifiedBlockKind::Cactus
+        | SimplifiedBlockKind::MagmaBlock => Cell::Harmful,
+        SimplifiedBlockKind::Water => Cell::Water,
+        SimplifiedBlockKind::WoodenDoor if open || options.can_open_doors => Cell::Open,
+        SimplifiedBlockKind::IronDoor | SimplifiedBlockKind::FenceGate if open => Cell::Open,
+        SimplifiedBlockKind::WoodenDoor
+        | SimplifiedBlockKind::IronDoor
+        | SimplifiedBlockKind::FenceGate
+        | SimplifiedBlockKind::Fence
+        | SimplifiedBlockKind::CobblestoneWall => Cell::Blocked,
+        _ if block.is_solid() => Cell::Solid,
+        _ => Cell::Open,
+    }
+}
+
+/// Estimates the cost of moving between two blocks.
+fn heuristic(from: BlockPosition, to: BlockPosition) -> u32 {
+    let (dx, dy, dz) = (
+        f64::from(from.x - to.x),
+        f64::from(from.y - to.y),
+        f64::from(from.z - to.z),
+    );
+    ((dx * dx + dy * dy + dz * dz).sqrt() * f64::from(WALK_COST)) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feather_core::blocks::HalfUpperLower;
+    use feather_core::chunk::Chunk;
+    use feather_core::util::ChunkPosition;
+
+    /// Returns a world with a stone floor at y = 63.
+    fn world() -> ChunkMap {
+        let mut map = ChunkMap::new();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                map.insert(Chunk::new(ChunkPosition::new(x, z)));
+            }
+        }
+        for x in -16..32 {
+            for z in -16..32 {
+                map.set_block_at(BlockPosition::new(x, 63, z), BlockId::stone());
+            }
+        }
+        map
+    }
+
+    fn options() -> PathOptions {
+        PathOptions {
+            width: 0.6,
+            height: 1.95,
+            max_fall: 3,
+            can_open_doors: false,
+            can_swim: true,
+            max_nodes: 1024,
+        }
+    }
+
+    fn find(map: &ChunkMap, goal: BlockPosition, options: PathOptions) -> PathStatus {
+        let mut pathfinder = Pathfinder::new(BlockPosition::new(0, 64, 0), goal, options);
+        pathfinder.step(map, &mut usize::max_value())
+    }
+
+    fn path(map: &ChunkMap, goal: BlockPosition) -> Path {
+        match find(map, goal, options()) {
+            PathStatus::Found(path) => path,
+            status => panic!("no path found: {:?}", status),
+        }
+    }
+
+    /// Builds a wall along x = 5 of the given height.
+    fn wall(map: &ChunkMap, height: i32, block: BlockId) {
+        for z in -16..32 {
+            for y in 64..64 + height {
+                map.set_block_at(BlockPosition::new(5, y, z), block);
+            }
+        }
+    }
+
+    #[test]
+    fn straight_line() {
+        let map = world();
+        let path = path(&map, BlockPosition::new(8, 64, 0));
+        assert!(path.reaches_goal);
+        assert_eq!(path.nodes.len(), 8);
+        assert!(path.nodes.iter().all(|node| node.y == 64));
+        assert_eq!(path.node_position(7), Some(position!(8.5, 64.0, 0.5)));
+    }
+
+    #[test]
+    fn jump() {
+        let map = world();
+        wall(&map, 1, BlockId::stone());
+        let path = path(&map, BlockPosition::new(8, 64, 0));
+        assert!(path.reaches_goal);
+        assert!(path.nodes.contains(&BlockPosition::new(5, 65, 0)));
+    }
+
+    #[test]
+    fn walls_too_high_are_unreachable() {
+        let map = world();
+        wall(&map, 2, BlockId::stone());
+        let path = path(&map, BlockPosition::new(8, 64, 0));
+        assert!(!path.reaches_goal);
+        assert_eq!(path.nodes.last(), Some(&BlockPosition::new(4, 64, 0)));
+    }
+
+    #[test]
+    fn fences_cannot_be_jumped() {
+        let map = world();
+        wall(&map, 1, BlockId::oak_fence());
+        let path = path(&map, BlockPosition::new(8, 64, 0));
+        assert!(!path.reaches_goal);
+    }
+
+    #[test]
+    fn fall_height() {
+        let map = world();
+        // A hole of depth 3, which can be fallen into
+        // but not climbed out of.
+        for y in 61..=63 {
+            map.set_block_at(BlockPosition::new(3, y, 0), BlockId::air());
+        }
+        let path = path(&map, BlockPosition::new(3, 61, 0));
+        assert!(path.reaches_goal);
+        assert_eq!(path.nodes.len(), 3);
+
+        // Too deep
+        map.set_block_at(BlockPosition::new(3, 60, 0), BlockId::air());
+        let status = find(&map, BlockPosition::new(3, 60, 0), options());
+        match status {
+            PathStatus::Found(path) => assert!(!path.reaches_goal),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn doors() {
+        let map = world();
+        wall(&map, 3, BlockId::stone());
+        map.set_block_at(BlockPosition::new(5, 64, 0), BlockId::oak_door());
+        map.set_block_at(
+            BlockPosition::new(5, 65, 0),
+            BlockId::oak_door().with_half_upper_lower(HalfUpperLower::Upper),
+        );
+
+        let goal = BlockPosition::new(8, 64, 0);
+        assert!(!path(&map, goal).reaches_goal);
+
+        let options = PathOptions {
+            can_open_doors: true,
+            ..options()
+        };
+        match find(&map, goal, options) {
+            PathStatus::Found(path) => assert!(path.reaches_goal),
+            status => panic!("no path found: {:?}", status),
+        }
+    }
+
+    #[test]
+    fn avoids_lava() {
+        let map = world();
+        for z in -16..32 {
+            map.set_block_at(BlockPosition::new(5, 63, z), BlockId::lava());
+        }
+        let path = path(&map, BlockPosition::new(8, 64, 0));
+        assert!(!path.reaches_goal);
+    }
+
+    #[test]
+    fn swims() {
+        let map = world();
+        for z in -16..32 {
+            for x in 3..=6 {
+                map.set_block_at(BlockPosition::new(x, 63, z), BlockId::water());
+            }
+        }
+        let path = path(&map, BlockPosition::new(8, 64, 0));
+        assert!(path.reaches_goal);
+        assert!(path.nodes.contains(&BlockPosition::new(4, 63, 0)));
+
+        let options = PathOptions {
+            can_swim: false,
+            ..options()
+        };
+        match find(&map, BlockPosition::new(8, 64, 0), options) {
+            PathStatus::Found(path) => assert!(!path.reaches_goal),
+            status => panic!("unexpected status: {:?}", status),
+        }
+    }
+
+    #[test]
+    fn wide_entities() {
+        let map = world();
+        wall(&map, 2, BlockId::stone());
+        // A one-block gap in the wall
+        map.set_block_at(BlockPosition::new(5, 64, 0), BlockId::air());
+        map.set_block_at(BlockPosition::new(5, 65, 0), BlockId::air());
+
+        let goal = BlockPosition::new(8, 64, 0);
+        assert!(path(&map, goal).reaches_goal);
+
+        let options = PathOptions {
+            width: 1.4,
+            height: 0.9,
+            ..options()
+        };
+        match find(&map, goal, options) {
+            PathStatus::Found(path) => assert!(!path.reaches_goal),
+            status => panic!("unexpected status: {:?}", status),
+        }
+    }
+
+    #[test]
+    fn budget() {
+        let map = world();
+        let mut pathfinder = Pathfinder::new(
+            BlockPosition::new(0, 64, 0),
+            BlockPosition::new(20, 64, 20),
+            options(),
+        );
+        let mut budget = 5;
+        assert!(matches!(
+            pathfinder.step(&map, &mut budget),
+            PathStatus::Searching
+        ));
+        assert_eq!(budget, 0);
+
+        let mut budget = usize::max_value();
+        match pathfinder.step(&map, &mut budget) {
+            PathStatus::Found(path) => assert!(path.reaches_goal),
+            status => panic!("no path found: {:?}", status),
+        }
+    }
+}
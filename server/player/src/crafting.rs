@@ -0,0 +1,521 @@
+//! Crafting: keeps crafting result slots up to date with
+//! the crafting grid, consumes ingredients when a result is taken,
+//! fills crafting grids from the recipe book, and returns the items
+//! in a player's own crafting grid when they close their inventory.
+//!
+//! This works for any inventory with `CraftingInput` and
+//! `CraftingOutput` areas, i.e. both the player's 2x2 grid
+//! and the 3x3 grid of crafting tables.
+
+use ahash::AHashMap;
+use entity::drops::drop_item;
+use feather_core::inventory::{slot, Area, Inventory, SlotIndex, Window};
+use feather_core::items::{Item, ItemStack};
+use feather_core::recipes::{self, Ingredient, Recipe, RecipeKind};
+use feather_core::util::Position;
+use feather_server_types::{Game, InventoryUpdateEvent, PlayerLeaveEvent, WindowCloseEvent};
+use fecs::{Entity, World};
+use smallvec::{smallvec, SmallVec};
+
+/// Network index of the first crafting grid slot
+/// in both player inventory and crafting table windows.
+const GRID_NETWORK_INDEX: usize = 1;
+
+/// Areas of the player inventory from which
+/// the recipe book takes ingredients.
+const INGREDIENT_AREAS: [Area; 2] = [Area::Hotbar, Area::Main];
+
+/// When a crafting grid changes, updates its result slot.
+#[fecs::event_handler]
+pub fn on_inventory_update_update_crafting_result(
+    event: &InventoryUpdateEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if event
+        .slots
+        .iter()
+        .any(|slot| slot.area == Area::CraftingInput)
+    {
+        update_crafting_result(game, world, event.entity);
+    }
+}
+
+fn update_crafting_result(game: &mut Game, world: &mut World, entity: Entity) {
+    let changed = {
+        let inventory = match world.try_get::<Inventory>(entity) {
+            Some(inventory) => inventory,
+            None => return,
+        };
+        let grid = match inventory.items_in_area(Area::CraftingInput) {
+            Ok(grid) => grid,
+            Err(_) => return,
+        };
+        let width = match grid.len() {
+            4 => 2,
+            9 => 3,
+            _ => return,
+        };
+
+        let result = recipes::find_crafting_recipe(&grid, width).map(|recipe| recipe.result());
+
+        let mut output = match inventory.item_at_mut(Area::CraftingOutput, 0) {
+            Ok(output) => output,
+            Err(_) => return,
+        };
+        if *output == result {
+            false
+        } else {
+            *output = result;
+            true
+        }
+    };
+
+    if changed {
+        game.handle(
+            world,
+            InventoryUpdateEvent {
+                slots: smallvec![slot(Area::CraftingOutput, 0)],
+                entity,
+            },
+        );
+    }
+}
+
+/// When a player closes their inventory, moves the items
+/// in their 2x2 crafting grid back to the rest of their
+/// inventory, dropping those which do not fit.
+#[fecs::event_handler]
+pub fn on_window_close_return_crafting_grid(
+    event: &WindowCloseEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    if event.closed == event.player {
+        return_crafting_grid(game, world, event.player);
+    }
+}
+
+/// When a player leaves, moves the items in their 2x2
+/// crafting grid back before their data is saved.
+#[fecs::event_handler]
+pub fn on_player_leave_return_crafting_grid(
+    event: &PlayerLeaveEvent,
+    game: &mut Game,
+    world: &mut World,
+) {
+    return_crafting_grid(game, world, event.player);
+}
+
+fn return_crafting_grid(game: &mut Game, world: &mut World, player: Entity) {
+    let (slots, leftovers) = match world.try_get::<Inventory>(player) {
+        Some(inventory) => empty_crafting_grid(&inventory),
+        None => return,
+    };
+    if slots.is_empty() {
+        return;
+    }
+
+    let pos = *world.get::<Position>(player);
+    for item in leftovers {
+        drop_item(game, world, item, pos);
+    }
+
+    game.handle(
+        world,
+        InventoryUpdateEvent {
+            slots,
+            entity: player,
+        },
+    );
+}
+
+/// Moves the items in the crafting grid of a player inventory
+/// to its other slots. Returns the affected slots and
+/// the items which did not fit.
+fn empty_crafting_grid(inventory: &Inventory) -> (SmallVec<[SlotIndex; 2]>, Vec<ItemStack>) {
+    let mut slots = SmallVec::new();
+    let mut leftovers = Vec::new();
+
+    let grid = match inventory.items_in_area(Area::CraftingInput) {
+        Ok(grid) => grid,
+        Err(_) => return (slots, leftovers),
+    };
+    for (index, stack) in grid.into_iter().enumerate() {
+        let stack = match stack {
+            Some(stack) => stack,
+            None => continue,
+        };
+
+        inventory
+            .remove_item_at(Area::CraftingInput, index)
+            .unwrap();
+        slots.push(slot(Area::CraftingInput, index));
+
+        let (affected, remaining) = inventory.collect_item(stack.clone());
+        slots.extend(affected);
+        if remaining > 0 {
+            leftovers.push(stack.of_amount(remaining));
+        }
+    }
+
+    (slots, leftovers)
+}
+
+/// Called when a player takes the result out of the
+/// crafting grid of `crafter`. Clears the result slot
+/// and consumes one of each ingredient, which in turn
+/// updates the result for the remaining ingredients.
+pub fn take_crafting_result(game: &mut Game, world: &mut World, crafter: Entity) {
+    let mut slots: SmallVec<[SlotIndex; 2]> = smallvec![slot(Area::CraftingOutput, 0)];
+    {
+        let inventory = world.get::<Inventory>(crafter);
+        inventory
+            .remove_item_at(Area::CraftingOutput, 0)
+            .expect("crafter has no crafting output");
+
+        let grid = inventory
+            .items_in_area(Area::CraftingInput)
+            .expect("crafter has no crafting grid");
+        for (index, stack) in grid.into_iter().enumerate() {
+            let stack = match stack {
+                Some(stack) => stack,
+                None => continue,
+            };
+
+            let remaining = if stack.amount > 1 {
+                Some(stack.of_amount(stack.amount - 1))
+            } else {
+                recipes::crafting_remainder(stack.ty).map(|item| ItemStack::new(item, 1))
+            };
+
+            *inventory.item_at_mut(Area::CraftingInput, index).unwrap() = remaining;
+            slots.push(slot(Area::CraftingInput, index));
+        }
+    }
+
+    game.handle(
+        world,
+        InventoryUpdateEvent {
+            slots,
+            entity: crafter,
+        },
+    );
+}
+
+/// Moves the ingredients for `recipe` from a player's inventory into
+/// the crafting grid of their open window, as requested by the recipe book.
+/// Items already in the grid are moved back to the player's inventory first.
+///
+/// Places one set of ingredients, or as many as possible if `make_all`
+/// is set. Returns `false` if no ingredients could be placed, in
+/// which case the client should be told to show the recipe's ghost.
+pub fn fill_crafting_grid(
+    game: &mut Game,
+    world: &mut World,
+    player: Entity,
+    recipe: &Recipe,
+    make_all: bool,
+) -> bool {
+    let crafter = {
+        let window = world.get::<Window>(player);
+        match window.convert_network(GRID_NETWORK_INDEX) {
+            Some(index) if index.area == Area::CraftingInput => {
+                window.corresponding_entity(GRID_NETWORK_INDEX).unwrap()
+            }
+            _ => return false,
+        }
+    };
+
+    let mut player_slots: SmallVec<[SlotIndex; 2]> = SmallVec::new();
+    let mut grid_slots: SmallVec<[SlotIndex; 2]> = SmallVec::new();
+
+    let filled = {
+        let inventory = world.get::<Inventory>(player);
+        let grid = world.get::<Inventory>(crafter);
+
+        let grid_width = match grid.items_in_area(Area::CraftingInput).map(|g| g.len()) {
+            Ok(4) => 2,
+            Ok(9) => 3,
+            _ => return false,
+        };
+
+        match recipe_layout(recipe, grid_width) {
+            Some(layout) => {
+                clear_grid(&inventory, &grid, &mut player_slots, &mut grid_slots)
+                    && place_ingredients(
+                        &inventory,
+                        &grid,
+                        &layout,
+                        make_all,
+                        &mut player_slots,
+                        &mut grid_slots,
+                    )
+            }
+            None => false,
+        }
+    };
+
+    if crafter == player {
+        player_slots.extend(grid_slots);
+    } else if !grid_slots.is_empty() {
+        game.handle(
+            world,
+            InventoryUpdateEvent {
+                slots: grid_slots,
+                entity: crafter,
+            },
+        );
+    }
+    if !player_slots.is_empty() {
+        game.handle(
+            world,
+            InventoryUpdateEvent {
+                slots: player_slots,
+                entity: player,
+            },
+        );
+    }
+
+    filled
+}
+
+/// Returns the grid index for each ingredient of `recipe`
+/// when placed in a grid of the given width, or `None`
+/// if the recipe does not fit.
+fn recipe_layout(recipe: &Recipe, grid_width: usize) -> Option<Vec<(usize, &Ingredient)>> {
+    match &recipe.kind {
+        RecipeKind::Shaped(shaped) => {
+            if shaped.width > grid_width || shaped.height > grid_width {
+                return None;
+            }
+
+            Some(
+                shaped
+                    .ingredients
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, ingredient)| {
+                        let (x, y) = (index % shaped.width, index / shaped.width);
+                        ingredient
+                            .as_ref()
+                            .map(|ingredient| (y * grid_width + x, ingredient))
+                    })
+                    .collect(),
+            )
+        }
+        RecipeKind::Shapeless(shapeless) => {
+            if shapeless.ingredients.len() > grid_width * grid_width {
+                return None;
+            }
+
+            Some(shapeless.ingredients.iter().enumerate().collect())
+        }
+        RecipeKind::Smelting(_) => None,
+    }
+}
+
+/// Moves all items in the crafting grid to the player's inventory.
+/// Returns `false` if the inventory could not hold them.
+fn clear_grid(
+    inventory: &Inventory,
+    grid: &Inventory,
+    player_slots: &mut SmallVec<[SlotIndex; 2]>,
+    grid_slots: &mut SmallVec<[SlotIndex; 2]>,
+) -> bool {
+    let items = grid.items_in_area(Area::CraftingInput).unwrap();
+    for (index, stack) in items.into_iter().enumerate() {
+        let stack = match stack {
+            Some(stack) => stack,
+            None => continue,
+        };
+
+        let (affected, remaining) = inventory.collect_item(stack.clone());
+        player_slots.extend(affected);
+        grid_slots.push(slot(Area::CraftingInput, index));
+
+        if remaining > 0 {
+            grid.set_item_at(Area::CraftingInput, index, stack.of_amount(remaining))
+                .unwrap();
+            return false;
+        }
+        grid.remove_item_at(Area::CraftingInput, index).unwrap();
+    }
+
+    true
+}
+
+/// Places sets of ingredients into the (empty) crafting grid.
+/// Returns `false` if the player does not have the ingredients
+/// for a single set.
+fn place_ingredients(
+    inventory: &Inventory,
+    grid: &Inventory,
+    layout: &[(usize, &Ingredient)],
+    make_all: bool,
+    player_slots: &mut SmallVec<[SlotIndex; 2]>,
+    grid_slots: &mut SmallVec<[SlotIndex; 2]>,
+) -> bool {
+    let mut available: AHashMap<Item, u32> = AHashMap::new();
+    for area in &INGREDIENT_AREAS {
+        for stack in inventory
+            .items_in_area(*area)
+            .unwrap()
+            .into_iter()
+            .flatten()
+        {
+            *available.entry(stack.ty).or_default() += stack.amount as u32;
+        }
+    }
+
+    // Each grid slot keeps the item type chosen for the
+    // first set, so that the slot holds a single stack.
+    let mut chosen: Vec<Option<Item>> = vec![None; layout.len()];
+    let mut sets = 0;
+    let max_sets = if make_all { u32::max_value() } else { 1 };
+
+    'sets: while sets < max_sets {
+        let mut remaining = available.clone();
+        let mut next_chosen = chosen.clone();
+
+        for ((_, ingredient), chosen) in layout.iter().zip(next_chosen.iter_mut()) {
+            let item = match chosen {
+                Some(item) => *item,
+                None => match ingredient
+                    .0
+                    .iter()
+                    .copied()
+                    .filter(|item| remaining.get(item).copied().unwrap_or(0) > 0)
+                    .max_by_key(|item| remaining[item])
+                {
+                    Some(item) => item,
+                    None => break 'sets,
+                },
+            };
+
+            let count = remaining.entry(item).or_default();
+            if *count == 0 || sets + 1 > item.stack_size() {
+                break 'sets;
+            }
+            *count -= 1;
+            *chosen = Some(item);
+        }
+
+        available = remaining;
+        chosen = next_chosen;
+        sets += 1;
+    }
+
+    if sets == 0 {
+        return false;
+    }
+
+    for ((index, _), item) in layout.iter().zip(chosen) {
+        let item = item.unwrap();
+        take_items(inventory, item, sets, player_slots);
+        grid.set_item_at(
+            Area::CraftingInput,
+            *index,
+            ItemStack::new(item, sets as u8),
+        )
+        .unwrap();
+        grid_slots.push(slot(Area::CraftingInput, *index));
+    }
+
+    true
+}
+
+/// Removes `amount` items of type `item` from the player's inventory.
+fn take_items(
+    inventory: &Inventory,
+    item: Item,
+    mut amount: u32,
+    player_slots: &mut SmallVec<[SlotIndex; 2]>,
+) {
+    for area in &INGREDIENT_AREAS {
+        for (index, stack) in inventory
+            .items_in_area(*area)
+            .unwrap()
+            .into_iter()
+            .enumerate()
+        {
+            if amount == 0 {
+                return;
+            }
+
+            let stack = match stack {
+                Some(stack) if stack.ty == item => stack,
+                _ => continue,
+            };
+
+            let taken = amount.min(stack.amount as u32);
+            amount -= taken;
+            *inventory.item_at_mut(*area, index).unwrap() = if taken == stack.amount as u32 {
+                None
+            } else {
+                Some(stack.of_amount(stack.amount - taken as u8))
+            };
+            player_slots.push(slot(*area, index));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_crafting_grid_returns_items() {
+        let inventory = Inventory::player();
+        inventory
+            .set_item_at(Area::CraftingInput, 0, ItemStack::new(Item::Stone, 3))
+            .unwrap();
+        inventory
+            .set_item_at(Area::CraftingInput, 3, ItemStack::new(Item::Dirt, 1))
+            .unwrap();
+
+        let (slots, leftovers) = empty_crafting_grid(&inventory);
+        assert!(leftovers.is_empty());
+        assert!(slots.contains(&slot(Area::CraftingInput, 0)));
+        assert!(slots.contains(&slot(Area::CraftingInput, 3)));
+        assert!(inventory
+            .items_in_area(Area::CraftingInput)
+            .unwrap()
+            .iter()
+            .all(Option::is_none));
+
+        let returned: Vec<_> = [Area::Hotbar, Area::Main]
+            .iter()
+            .flat_map(|area| inventory.items_in_area(*area).unwrap())
+            .flatten()
+            .collect();
+        assert_eq!(
+            returned,
+            vec![
+                ItemStack::new(Item::Stone, 3),
+                ItemStack::new(Item::Dirt, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_crafting_grid_full_inventory() {
+        let inventory = Inventory::player();
+        for area in &[Area::Hotbar, Area::Main] {
+            let size = inventory.items_in_area(*area).unwrap().len();
+            for index in 0..size {
+                inventory
+                    .set_item_at(*area, index, ItemStack::new(Item::Diamond, 64))
+                    .unwrap();
+            }
+        }
+        inventory
+            .set_item_at(Area::CraftingInput, 1, ItemStack::new(Item::Stone, 3))
+            .unwrap();
+
+        let (slots, leftovers) = empty_crafting_grid(&inventory);
+        assert_eq!(slots.as_slice(), &[slot(Area::CraftingInput, 1)]);
+        assert_eq!(leftovers, vec![ItemStack::new(Item::Stone, 3)]);
+        assert_eq!(inventory.item_at(Area::CraftingInput, 1).unwrap(), None);
+    }
+}
@@ -5,9 +5,10 @@ use feather_core::network::packets::{
     HeldItemChangeClientbound, JoinGame, PlayerPositionAndLookClientbound, SpawnPosition, Tags,
 };
 use feather_core::util::{BlockPosition, Difficulty, Dimension, Gamemode, Position};
+use feather_server_commands::CommandState;
 use feather_server_network::{ListenerToServerMessage, NetworkIoManager, ServerToListenerMessage};
 use feather_server_types::{
-    BumpVec, ChunkSendEvent, Game, HeldItem, Network, NetworkId, PlayerJoinEvent,
+    BumpVec, ChunkSendEvent, Game, HeldItem, Network, NetworkId, PlayerJoinEvent, RecipeBook,
     WorkerToServerMessage,
 };
 use fecs::{IntoQuery, Read, World};
@@ -108,7 +109,12 @@ pub fn on_chunk_send_join_player(event: &ChunkSendEvent, game: &Game, world: &mu
 }
 
 #[fecs::event_handler]
-pub fn on_player_join_send_join_packets(event: &PlayerJoinEvent, game: &Game, world: &mut World) {
+pub fn on_player_join_send_join_packets(
+    event: &PlayerJoinEvent,
+    game: &Game,
+    world: &mut World,
+    #[default] commands: &CommandState,
+) {
     let network = world.get::<Network>(event.player);
     let id = world.get::<NetworkId>(event.player);
     let gamemode = *world.get::<Gamemode>(event.player);
@@ -123,6 +129,7 @@ pub fn on_player_join_send_join_packets(event: &PlayerJoinEvent, game: &Game, wo
         max_players: game.config.server.max_players as u8,
         level_type: game.level.generator_name.clone(),
         reduced_debug_info: false,
+        view_distance: game.config.server.view_distance,
     };
     network.send(join_packet);
 
@@ -131,7 +138,7 @@ pub fn on_player_join_send_join_packets(event: &PlayerJoinEvent, game: &Game, wo
     };
     network.send(held_item_packet);
 
-    // TODO declare recipes
+    network.send(crate::declare_recipes().clone());
 
     let tags_packet = Tags {
         block_tags: vec![],
@@ -161,7 +168,9 @@ pub fn on_player_join_send_join_packets(event: &PlayerJoinEvent, game: &Game, wo
     };
     network.send(tags_packet);
 
-    // TODO declare commands
+    commands.send_permissions(world, event.player);
 
-    // TODO unlock recipes
+    network.send(crate::unlock_recipes_init(
+        &world.get::<RecipeBook>(event.player),
+    ));
 }
@@ -4,9 +4,11 @@ extern crate nalgebra_glm as glm;
 
 mod broadcasters;
 mod chat;
+mod crafting;
 mod death;
 mod join;
 mod packet_handlers;
+mod recipe_book;
 mod view;
 
 use feather_core::inventory::{Area, Inventory, SlotIndex, Window};
@@ -19,17 +21,20 @@ use feather_server_types::{
     BlocksFallen, CanBreak, CanInstaBreak, CanRespawn, CanTakeDamage, ChunkHolder,
     CreationPacketCreator, EntitySpawnEvent, Game, GamemodeUpdateEvent, Health, HealthUpdateEvent,
     HeldItem, InventoryUpdateEvent, LastKnownPositions, MaxHealth, MessageReceiver, Name, Network,
-    NetworkId, OpenWindowCount, Player, PlayerJoinEvent, PlayerPreJoinEvent, PreviousPosition,
-    PreviousVelocity, ProfileProperties, SpawnPacketCreator, Uuid, Velocity,
+    NetworkId, OpenWindowCount, Permissions, Player, PlayerJoinEvent, PlayerPreJoinEvent,
+    PreviousPosition, PreviousVelocity, ProfileProperties, RecipeBook, SpawnPacketCreator, Uuid,
+    Velocity, WrappedOpList,
 };
 use feather_server_util::degrees_to_stops;
-use fecs::{Entity, EntityRef, World};
+use fecs::{Entity, EntityRef, ResourcesProvider, World};
 
 pub use broadcasters::*;
 pub use chat::*;
+pub use crafting::*;
 pub use death::*;
 pub use join::*;
 pub use packet_handlers::*;
+pub use recipe_book::*;
 use std::sync::atomic::Ordering;
 pub use view::*;
 
@@ -109,8 +114,21 @@ pub fn create(game: &mut Game, world: &mut World, info: NewClientInfo) -> Entity
     world
         .add(entity, HeldItem(info.data.held_item as usize))
         .unwrap();
+    world
+        .add(entity, RecipeBook::from(info.data.recipe_book))
+        .unwrap();
 
     world.add(entity, MessageReceiver::default()).unwrap();
+    let op_level = game
+        .resources
+        .get::<WrappedOpList>()
+        .read()
+        .unwrap()
+        .get(info.uuid)
+        .map_or(0, |op| op.level);
+    world
+        .add(entity, Permissions::with_level(op_level))
+        .unwrap();
 
     world.add(entity, Player).unwrap();
 
@@ -122,6 +140,8 @@ pub fn create(game: &mut Game, world: &mut World, info: NewClientInfo) -> Entity
     world.add(entity, BlocksFallen::default()).unwrap();
 
     game.player_count.fetch_add(1, Ordering::SeqCst);
+    let name = world.get::<Name>(entity).0.clone();
+    game.player_names.write().unwrap().push(name);
     game.handle(world, EntitySpawnEvent { entity });
     game.handle(world, PlayerPreJoinEvent { player: entity });
     game.handle(world, PlayerJoinEvent { player: entity });
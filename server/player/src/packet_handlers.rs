@@ -7,17 +7,24 @@ mod digging;
 mod inventory;
 mod movement;
 mod placement;
+mod recipe_book;
+mod use_entity;
 mod use_item;
 mod window;
 
 pub use self::inventory::*;
 pub use animation::handle_animation;
-pub use chat::handle_chat;
+pub use chat::{handle_chat, handle_tab_complete};
 pub use client_status::handle_client_status;
 pub use digging::*;
+use feather_core::network::packets::BlockChange;
+use feather_core::util::BlockPosition;
+use feather_server_types::{Game, Network};
 use fecs::{Entity, World};
 pub use movement::handle_movement_packets;
 pub use placement::handle_player_block_placement;
+pub use recipe_book::{handle_craft_recipe_request, handle_recipe_book_data};
+pub use use_entity::handle_use_entity;
 pub use use_item::handle_player_use_item;
 pub use window::handle_close_window;
 
@@ -40,3 +47,14 @@ where
         })
     }
 }
+
+/// Sends the current block at `pos` to a player, undoing
+/// the change predicted by their client for a cancelled action.
+fn resend_block(game: &Game, world: &World, player: Entity, pos: BlockPosition) {
+    if let (Some(block), Some(network)) = (game.block_at(pos), world.try_get::<Network>(player)) {
+        network.send(BlockChange {
+            location: pos,
+            block_id: block.vanilla_id() as i32,
+        });
+    }
+}
@@ -1,8 +1,12 @@
 use crate::IteratorExt;
-use feather_core::network::packets::ChatMessageServerbound;
+use feather_core::network::packets::{
+    ChatMessageServerbound, TabCompleteClientbound, TabCompleteMatch, TabCompleteServerbound,
+};
 use feather_core::text::{TextRoot, Translate};
 use feather_server_commands::CommandState;
-use feather_server_types::{ChatEvent, ChatPosition, Game, Name, PacketBuffers};
+use feather_server_types::{
+    ChatEvent, ChatPosition, ChatPreEvent, Game, Name, Network, PacketBuffers, PluginEvents,
+};
 use fecs::World;
 use std::sync::Arc;
 
@@ -13,6 +17,7 @@ pub fn handle_chat(
     world: &mut World,
     packet_buffers: &Arc<PacketBuffers>,
     #[default] commands: &CommandState,
+    #[default] plugin_events: &PluginEvents,
 ) {
     packet_buffers
         .received::<ChatMessageServerbound>()
@@ -25,14 +30,21 @@ pub fn handle_chat(
                 );
                 commands.dispatch(game, world, player, &packet.message[1..]);
             } else {
+                let mut event = ChatPreEvent {
+                    player,
+                    message: packet.message.clone(),
+                    cancelled: false,
+                };
+                if !plugin_events.trigger_pre(&mut event, game, world) {
+                    return;
+                }
+
                 let player_name = world.get::<Name>(player);
                 let message: String = TextRoot::from(
-                    Translate::ChatTypeText
-                        * vec![player_name.0.to_string(), packet.message.to_string()],
+                    Translate::ChatTypeText * vec![player_name.0.to_string(), event.message],
                 )
                 .into();
 
-                log::info!("<{}> {}", player_name.0, packet.message);
                 drop(player_name);
 
                 game.handle(
@@ -45,3 +57,40 @@ pub fn handle_chat(
             }
         });
 }
+
+/// Handles tab completion requests for commands.
+#[fecs::system]
+pub fn handle_tab_complete(
+    game: &mut Game,
+    world: &mut World,
+    packet_buffers: &Arc<PacketBuffers>,
+    #[default] commands: &CommandState,
+) {
+    packet_buffers
+        .received::<TabCompleteServerbound>()
+        .for_each_valid(world, |world, (player, packet)| {
+            if !packet.text.starts_with('/') {
+                return;
+            }
+
+            let (start, matches) = commands.complete(game, world, player, &packet.text[1..]);
+
+            // Offsets in the response are in characters
+            // and include the slash.
+            let start = start + 1;
+            let response = TabCompleteClientbound {
+                transaction_id: packet.transaction_id,
+                start: start as i32,
+                length: (packet.text.chars().count() - start) as i32,
+                matches: matches
+                    .into_iter()
+                    .map(|text| TabCompleteMatch {
+                        text,
+                        tooltip: None,
+                    })
+                    .collect(),
+            };
+
+            world.get::<Network>(player).send(response);
+        });
+}
@@ -4,6 +4,7 @@
 //! for actions mostly unrelated to digging including eating, shooting bows,
 //! swapping items out to the offhand, and dropping items.
 
+use super::resend_block;
 use crate::{ItemTimedUse, IteratorExt};
 use entity::InventoryExt;
 use feather_core::blocks::{BlockId, HalfUpperLower, Part, SimplifiedBlockKind};
@@ -13,9 +14,9 @@ use feather_core::network::packets::{PlayerDigging, PlayerDiggingStatus};
 use feather_core::util::{BlockPosition, Gamemode, Position};
 use feather_definitions::Tool;
 use feather_server_types::{
-    BlockUpdateCause, CanBreak, CanInstaBreak, EntitySpawnEvent, Game, HeldItem,
-    InventoryUpdateEvent, ItemDamageEvent, ItemDropEvent, PacketBuffers, Velocity,
-    PLAYER_EYE_HEIGHT, TPS,
+    BlockBreakPreEvent, BlockUpdateCause, CanBreak, CanInstaBreak, EntitySpawnEvent, Game,
+    HeldItem, InventoryUpdateEvent, ItemDamageEvent, ItemDropEvent, ItemDropPreEvent,
+    PacketBuffers, PluginEvents, Velocity, PLAYER_EYE_HEIGHT, TPS,
 };
 use feather_server_util::{charge_from_ticks_held, compute_projectile_velocity};
 use fecs::{Entity, IntoQuery, Read, World, Write};
@@ -45,6 +46,7 @@ pub fn handle_player_digging(
     game: &mut Game,
     world: &mut World,
     packet_buffers: &Arc<PacketBuffers>,
+    #[default] plugin_events: &PluginEvents,
 ) {
     use PlayerDiggingStatus::*;
 
@@ -52,15 +54,23 @@ pub fn handle_player_digging(
         .received::<PlayerDigging>()
         .for_each_valid(world, |world, (player, packet)| match packet.status {
             StartedDigging | FinishedDigging | CancelledDigging => {
-                handle_digging(game, world, player, packet)
+                handle_digging(game, world, plugin_events, player, packet)
+            }
+            DropItem | DropItemStack => {
+                handle_drop_item_stack(game, world, plugin_events, player, packet)
             }
-            DropItem | DropItemStack => handle_drop_item_stack(game, world, player, packet),
             ConsumeItem => handle_consume_item(game, world, player, packet),
             status => log::warn!("Unhandled Player Digging status {:?}", status),
         });
 }
 
-fn handle_digging(game: &mut Game, world: &mut World, player: Entity, packet: PlayerDigging) {
+fn handle_digging(
+    game: &mut Game,
+    world: &mut World,
+    plugin_events: &PluginEvents,
+    player: Entity,
+    packet: PlayerDigging,
+) {
     if !world.has::<CanBreak>(player) {
         log::trace!(
             "Player cannot break blocks but sent player digging status {:?}",
@@ -70,10 +80,12 @@ fn handle_digging(game: &mut Game, world: &mut World, player: Entity, packet: Pl
     }
 
     match packet.status {
-        PlayerDiggingStatus::StartedDigging => handle_started_digging(game, world, player, packet),
+        PlayerDiggingStatus::StartedDigging => {
+            handle_started_digging(game, world, plugin_events, player, packet)
+        }
         PlayerDiggingStatus::CancelledDigging => handle_cancelled_digging(game, world, player),
         PlayerDiggingStatus::FinishedDigging => {
-            handle_finished_digging(game, world, player, packet)
+            handle_finished_digging(game, world, plugin_events, player, packet)
         }
         _ => unreachable!(),
     }
@@ -102,6 +114,7 @@ pub struct FinishDiggingEvent {
 fn handle_started_digging(
     game: &mut Game,
     world: &mut World,
+    plugin_events: &PluginEvents,
     player: Entity,
     packet: PlayerDigging,
 ) {
@@ -129,7 +142,7 @@ fn handle_started_digging(
             .hardness()
             < 0.01
     {
-        dig(game, world, player, packet.location);
+        dig(game, world, plugin_events, player, packet.location);
     } else {
         // Insert new `Digging`.
         let block = game.block_at(packet.location).unwrap_or_default();
@@ -170,10 +183,14 @@ pub fn advance_dig_progress(game: &mut Game, world: &mut World) {
             let item_in_main_hand: Slot = inventory
                 .item_at(Area::Hotbar, held_item.0)
                 .expect("held item out of bounds");
-            let held_tool = item_in_main_hand.map(|item| item.ty.tool()).flatten();
+            let held_tool = item_in_main_hand
+                .as_ref()
+                .map(|item| item.ty.tool())
+                .flatten();
 
             let multiplier = if best_tool == held_tool && best_tool.is_some() {
                 let dig_multiplier = item_in_main_hand
+                    .as_ref()
                     .unwrap()
                     .ty
                     .tool_material()
@@ -211,6 +228,7 @@ fn handle_cancelled_digging(game: &mut Game, world: &mut World, player: Entity)
 fn handle_finished_digging(
     game: &mut Game,
     world: &mut World,
+    plugin_events: &PluginEvents,
     player: Entity,
     packet: PlayerDigging,
 ) {
@@ -240,13 +258,19 @@ fn handle_finished_digging(
     }
 
     // Attempt to break the block
-    dig(game, world, player, digging.pos);
+    dig(game, world, plugin_events, player, digging.pos);
 
     // Finished
     game.handle(world, FinishDiggingEvent { player, digging });
 }
 
-fn dig(game: &mut Game, world: &mut World, player: Entity, pos: BlockPosition) {
+fn dig(
+    game: &mut Game,
+    world: &mut World,
+    plugin_events: &PluginEvents,
+    player: Entity,
+    pos: BlockPosition,
+) {
     let block = match game.block_at(pos) {
         Some(block) => block,
         None => {
@@ -263,10 +287,8 @@ fn dig(game: &mut Game, world: &mut World, player: Entity, pos: BlockPosition) {
         }
     };
 
-    damage_tool(player, block, game, world);
-
     // Handle multi-block destruction (i.e. doors and beds)
-    if let Some(other_pos) = match block.simplified_kind() {
+    let other_pos = match block.simplified_kind() {
         SimplifiedBlockKind::Bed => {
             let direction = block.facing_cardinal().unwrap();
             Some(match block.part().unwrap() {
@@ -281,7 +303,25 @@ fn dig(game: &mut Game, world: &mut World, player: Entity, pos: BlockPosition) {
             })
         }
         _ => None,
-    } {
+    };
+
+    let mut event = BlockBreakPreEvent {
+        player,
+        pos,
+        block,
+        cancelled: false,
+    };
+    if !plugin_events.trigger_pre(&mut event, game, world) {
+        resend_block(game, world, player, pos);
+        if let Some(other_pos) = other_pos {
+            resend_block(game, world, player, other_pos);
+        }
+        return;
+    }
+
+    damage_tool(player, block, game, world);
+
+    if let Some(other_pos) = other_pos {
         if game.block_at(other_pos).unwrap().kind() == block.kind() {
             game.set_block_at(
                 world,
@@ -333,6 +373,7 @@ fn damage_tool(player: Entity, block: BlockId, game: &mut Game, world: &mut Worl
 fn handle_drop_item_stack(
     game: &mut Game,
     world: &mut World,
+    plugin_events: &PluginEvents,
     player: Entity,
     packet: PlayerDigging,
 ) {
@@ -342,9 +383,13 @@ fn handle_drop_item_stack(
     );
 
     let held_item = world.get::<HeldItem>(player).0;
-    let inventory = world.get::<Inventory>(player);
+    let idx = SlotIndex {
+        area: Area::Hotbar,
+        slot: held_item,
+    };
 
     let stack = {
+        let inventory = world.get::<Inventory>(player);
         if let Some(item) = inventory.item_at(Area::Hotbar, held_item).unwrap() {
             item
         } else {
@@ -353,6 +398,26 @@ fn handle_drop_item_stack(
         }
     };
 
+    let mut event = ItemDropPreEvent {
+        player,
+        slot: idx,
+        stack: stack.clone(),
+        cancelled: false,
+    };
+    if !plugin_events.trigger_pre(&mut event, game, world) {
+        // Resend the slot, as the client has already removed the item.
+        game.handle(
+            world,
+            InventoryUpdateEvent {
+                slots: smallvec![idx],
+                entity: player,
+            },
+        );
+        return;
+    }
+
+    let inventory = world.get::<Inventory>(player);
+
     let amnt = match packet.status {
         PlayerDiggingStatus::DropItem => {
             if stack.amount == 0 {
@@ -377,10 +442,6 @@ fn handle_drop_item_stack(
 
     drop(inventory);
 
-    let idx = SlotIndex {
-        area: Area::Hotbar,
-        slot: held_item,
-    };
     let inv_update = InventoryUpdateEvent {
         slots: smallvec![idx],
         entity: player,
@@ -428,7 +489,7 @@ fn handle_shoot_bow(game: &mut Game, world: &mut World, player: Entity) {
             }
 
             // Consume arrow
-            let (arrow_slot, arrow_stack) = arrow_to_consume.unwrap();
+            let (arrow_slot, arrow_stack) = arrow_to_consume.clone().unwrap();
             let mut arrow_stack: ItemStack = arrow_stack;
             arrow_stack.amount -= 1;
 
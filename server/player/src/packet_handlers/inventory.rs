@@ -48,7 +48,7 @@ pub fn handle_creative_inventory_action(
                         // Cause item to be dropped
                         let event = ItemDropEvent {
                             slot: None,
-                            stack: *stack,
+                            stack: stack.clone(),
                             player,
                         };
                         game.handle(world, event);
@@ -328,7 +328,7 @@ fn handle_click_window(
 
 /// Stores an item currently picked by
 /// a player's cursor.
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 struct PickedItem(ItemStack);
 
 fn handle_single_click(
@@ -338,7 +338,11 @@ fn handle_single_click(
     packet: ClickWindow,
     button: MouseButton,
 ) -> anyhow::Result<()> {
-    if let Some(picked) = world.try_get::<PickedItem>(player).map(|i| *i) {
+    if is_crafting_output(world, player, packet.slot as usize) {
+        return handle_crafting_output_click(game, world, player, packet);
+    }
+
+    if let Some(picked) = world.try_get::<PickedItem>(player).map(|i| i.clone()) {
         // Put down the item on the clicked slot. Based on the mouse button:
         // * left => whole stack
         // * right => single item
@@ -351,13 +355,11 @@ fn handle_single_click(
         let accessor = window.accessor(world)?;
         let current_item = accessor.item_at(packet.slot as usize)?;
 
-        if let Some(current_item) = current_item.and_then(|item| {
-            if item.eq_ignore_amount(picked.0) {
-                None
-            } else {
-                Some(item)
-            }
-        }) {
+        if let Some(current_item) = current_item
+            .as_ref()
+            .filter(|item| !item.eq_ignore_amount(&picked.0))
+            .cloned()
+        {
             // Different items - swap
             accessor.set_item_at(packet.slot as usize, picked.0)?;
             drop(accessor);
@@ -370,7 +372,7 @@ fn handle_single_click(
                 MouseButton::Right => 1,
             };
 
-            let current_count = current_item.map(|stack| stack.amount).unwrap_or(0);
+            let current_count = current_item.as_ref().map(|stack| stack.amount).unwrap_or(0);
             let new_count = (count + current_count).min(picked.0.ty.stack_size() as u8);
 
             accessor.set_item_at(packet.slot as usize, picked.0.of_amount(new_count))?;
@@ -390,7 +392,7 @@ fn handle_single_click(
 
         // Pick up the item in the slot
         let picked_up = accessor.item_at(packet.slot as usize)?;
-        let mut count = picked_up.map(|item| item.amount).unwrap_or(0);
+        let mut count = picked_up.as_ref().map(|item| item.amount).unwrap_or(0);
         if button == MouseButton::Right {
             count = (count + 1) / 2;
         }
@@ -423,6 +425,55 @@ fn handle_single_click(
     Ok(())
 }
 
+fn is_crafting_output(world: &World, player: Entity, slot: usize) -> bool {
+    world
+        .get::<Window>(player)
+        .convert_network(slot)
+        .map(|index| index.area == Area::CraftingOutput)
+        .unwrap_or(false)
+}
+
+/// Handles a click on a crafting result slot. The whole
+/// result is taken at once, and only if it fits onto
+/// the currently picked item.
+fn handle_crafting_output_click(
+    game: &mut Game,
+    world: &mut World,
+    player: Entity,
+    packet: ClickWindow,
+) -> anyhow::Result<()> {
+    let (result, crafter) = {
+        let window = world.get::<Window>(player);
+        let accessor = window.accessor(world)?;
+        (
+            accessor.item_at(packet.slot as usize)?,
+            window.corresponding_entity(packet.slot as usize).unwrap(),
+        )
+    };
+
+    let result = match result {
+        Some(result) => result,
+        None => return Ok(()),
+    };
+
+    let picked = world.try_get::<PickedItem>(player).map(|i| i.clone());
+    match picked {
+        Some(picked) => {
+            if !picked.0.eq_ignore_amount(&result)
+                || picked.0.amount + result.amount > result.ty.stack_size() as u8
+            {
+                return Ok(());
+            }
+            world.get_mut::<PickedItem>(player).0.amount += result.amount;
+        }
+        None => world.add(player, PickedItem(result)).unwrap(),
+    }
+
+    crate::crafting::take_crafting_result(game, world, crafter);
+
+    Ok(())
+}
+
 fn handle_double_click(
     game: &mut Game,
     world: &mut World,
@@ -441,7 +492,7 @@ fn handle_double_click(
 
         // Get information about the currently picked item (if nothing is picked, return)
         let picked = match world.try_get_mut::<PickedItem>(player) {
-            Some(picked) => picked.0,
+            Some(picked) => picked.0.clone(),
             None => return Ok(()),
         };
         stack_size = picked.ty.stack_size() as u8;
@@ -452,9 +503,14 @@ fn handle_double_click(
 
         // Iterate through all inventory slots, picking up items of the same type
         for (index, slot) in inventory.enumerate() {
+            // Crafting results can only be taken by clicking on them.
+            if index.area == Area::CraftingOutput {
+                continue;
+            }
+
             if let Some(slot) = slot {
                 // Remove items from the inventory until the player's PickedItem has reached its max stack size
-                if picked.eq_ignore_amount(slot) && slot.amount != stack_size {
+                if picked.eq_ignore_amount(&slot) && slot.amount != stack_size {
                     if let Some(mut item_stack) =
                         inventory.remove_item_at(index.area, index.slot)?
                     {
@@ -521,6 +577,10 @@ fn handle_number_key(
     let slot: usize = packet.slot as usize;
     let hotbar_slot_index = (key - 1) as usize;
 
+    if is_crafting_output(world, player, slot) {
+        return Ok(());
+    }
+
     let window = world.get::<Window>(player);
     let accessor = window.accessor(world)?;
 
@@ -2,39 +2,93 @@ use feather_core::network::packets::{
     PlayerLook, PlayerPosition, PlayerPositionAndLookServerbound,
 };
 use feather_core::util::Position;
-use feather_server_types::{Network, PacketBuffers};
-use fecs::{component, IntoQuery, World, Write};
+use feather_server_types::{
+    Game, Network, PacketBuffers, PlayerMovePreEvent, PluginEvents, Teleported,
+};
+use fecs::{component, Entity, IntoQuery, Read, World, Write};
 use std::sync::Arc;
 
 /// System to handle player movement updates.
 #[fecs::system]
-pub fn handle_movement_packets(world: &mut World, packet_buffers: &Arc<PacketBuffers>) {
-    <Write<Position>>::query()
+pub fn handle_movement_packets(
+    game: &mut Game,
+    world: &mut World,
+    packet_buffers: &Arc<PacketBuffers>,
+    #[default] plugin_events: &PluginEvents,
+) {
+    if !plugin_events.has_pre_handlers::<PlayerMovePreEvent>() {
+        <Write<Position>>::query()
+            .filter(component::<Network>())
+            .par_entities_for_each_mut(world.inner_mut(), |(player, mut position)| {
+                *position = apply_packets(packet_buffers, player, *position);
+            });
+        return;
+    }
+
+    // Movement is handled sequentially, as plugins
+    // need access to the world to handle pre-events.
+    let players: Vec<_> = <Read<Position>>::query()
         .filter(component::<Network>())
-        .par_entities_for_each_mut(world.inner_mut(), |(player, mut position)| {
-            let mut position: &mut Position = &mut *position;
-            for position_and_look in
-                packet_buffers.received_for::<PlayerPositionAndLookServerbound>(player)
-            {
-                position.x = position_and_look.x;
-                position.y = position_and_look.feet_y;
-                position.z = position_and_look.z;
-                position.pitch = position_and_look.pitch;
-                position.yaw = position_and_look.yaw;
-                position.on_ground = position_and_look.on_ground;
-            }
-
-            for position_update in packet_buffers.received_for::<PlayerPosition>(player) {
-                position.x = position_update.x;
-                position.y = position_update.feet_y;
-                position.z = position_update.z;
-                position.on_ground = position_update.on_ground;
-            }
-
-            for look in packet_buffers.received_for::<PlayerLook>(player) {
-                position.pitch = look.pitch;
-                position.yaw = look.yaw;
-                position.on_ground = look.on_ground;
-            }
-        });
+        .iter_entities(world.inner())
+        .map(|(player, position)| (player, *position))
+        .collect();
+
+    for (player, from) in players {
+        let requested = apply_packets(packet_buffers, player, from);
+        if requested == from {
+            continue;
+        }
+
+        let mut event = PlayerMovePreEvent {
+            player,
+            from,
+            to: requested,
+            cancelled: false,
+        };
+        let to = if plugin_events.trigger_pre(&mut event, game, world) {
+            event.to
+        } else {
+            from
+        };
+
+        if let Some(mut position) = world.try_get_mut::<Position>(player) {
+            *position = to;
+        }
+        if to != requested {
+            let _ = world.add(player, Teleported);
+        }
+    }
+}
+
+/// Returns the position of a player after applying
+/// the movement packets received from them.
+fn apply_packets(
+    packet_buffers: &PacketBuffers,
+    player: Entity,
+    mut position: Position,
+) -> Position {
+    for position_and_look in packet_buffers.received_for::<PlayerPositionAndLookServerbound>(player)
+    {
+        position.x = position_and_look.x;
+        position.y = position_and_look.feet_y;
+        position.z = position_and_look.z;
+        position.pitch = position_and_look.pitch;
+        position.yaw = position_and_look.yaw;
+        position.on_ground = position_and_look.on_ground;
+    }
+
+    for position_update in packet_buffers.received_for::<PlayerPosition>(player) {
+        position.x = position_update.x;
+        position.y = position_update.feet_y;
+        position.z = position_update.z;
+        position.on_ground = position_update.on_ground;
+    }
+
+    for look in packet_buffers.received_for::<PlayerLook>(player) {
+        position.pitch = look.pitch;
+        position.yaw = look.yaw;
+        position.on_ground = look.on_ground;
+    }
+
+    position
 }
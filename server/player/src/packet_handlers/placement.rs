@@ -1,5 +1,6 @@
 //! Handling of player block placement packets.
 
+use super::resend_block;
 use crate::IteratorExt;
 use entity::InventoryExt;
 use feather_core::blocks::categories::PlacementType;
@@ -12,8 +13,8 @@ use feather_core::item_block::ItemToBlock;
 use feather_core::network::packets::PlayerBlockPlacement;
 use feather_core::util::{BlockPosition, Gamemode, Position, Vec3d};
 use feather_server_types::{
-    BlockUpdateCause, Game, HeldItem, InteractionHandler, InventoryUpdateEvent, OpenWindowCount,
-    PacketBuffers,
+    BlockPlacePreEvent, BlockUpdateCause, Game, HeldItem, InteractionHandler, InventoryUpdateEvent,
+    OpenWindowCount, PacketBuffers, PluginEvents,
 };
 use feather_server_util::is_block_supported_at;
 use fecs::{Entity, World};
@@ -48,6 +49,7 @@ pub fn handle_player_block_placement(
     game: &mut Game,
     world: &mut World,
     packet_buffers: &Arc<PacketBuffers>,
+    #[default] plugin_events: &PluginEvents,
 ) {
     packet_buffers
         .received::<PlayerBlockPlacement>()
@@ -85,7 +87,7 @@ pub fn handle_player_block_placement(
                 );
             } else {
                 // Try to place a block
-                handle_block_placement(game, world, player, target_block, packet);
+                handle_block_placement(game, world, plugin_events, player, target_block, packet);
             }
         });
 }
@@ -93,6 +95,7 @@ pub fn handle_player_block_placement(
 pub fn handle_block_placement(
     game: &mut Game,
     world: &mut World,
+    plugin_events: &PluginEvents,
     player: Entity,
     target_block: BlockId,
     packet: PlayerBlockPlacement,
@@ -113,70 +116,98 @@ pub fn handle_block_placement(
         None => return, // Item is not a block
     };
 
-    if !handle_slab_placement(game, world, block, packet.location, packet.face) {
-        // TODO: waterlogged blocks, more
-        let pos = if target_block.is_replaceable() {
-            packet.location
-        } else {
-            packet.location + packet.face.placement_offset()
-        };
-
-        let current_block = match game.block_at(pos) {
-            Some(block) => block,
-            None => return,
-        };
+    let (pos, block, cause) = match slab_placement(game, block, packet.location, packet.face) {
+        // Completing a double slab does not break the existing slab.
+        Some((pos, slab)) => (pos, slab, BlockUpdateCause::Unknown),
+        None => {
+            // TODO: waterlogged blocks, more
+            let pos = if target_block.is_replaceable() {
+                packet.location
+            } else {
+                packet.location + packet.face.placement_offset()
+            };
 
-        if !current_block.is_replaceable() {
-            return;
-        }
+            let current_block = match game.block_at(pos) {
+                Some(block) => block,
+                None => return,
+            };
 
-        // Deny replacing grass with grass for example
-        if current_block.is_replaceable()
-            && !current_block.is_air()
-            && !current_block.is_fluid()
-            && block.is_replaceable()
-        {
-            return;
-        }
+            if !current_block.is_replaceable() {
+                return;
+            }
 
-        let block = update_block_state_for_placement(
-            game,
-            block,
-            pos,
-            *world.get::<Position>(player),
-            &packet,
-        );
+            // Deny replacing grass with grass for example
+            if current_block.is_replaceable()
+                && !current_block.is_air()
+                && !current_block.is_fluid()
+                && block.is_replaceable()
+            {
+                return;
+            }
 
-        // Abort if block that needs support wouldn't have the needed support blocks
-        if !is_block_supported_at(block, game, pos) {
-            return;
-        }
+            let block = update_block_state_for_placement(
+                game,
+                block,
+                pos,
+                *world.get::<Position>(player),
+                &packet,
+            );
 
-        // handle multi-block placements (i.e. doors and beds)
-        if let Some((other_pos, other_block)) = match block.simplified_kind() {
-            SimplifiedBlockKind::Bed => {
-                let mut head = block;
-                head.set_part(Part::Head);
-                Some((pos + block.facing_cardinal().unwrap().offset(), head))
-            }
-            SimplifiedBlockKind::IronDoor | SimplifiedBlockKind::WoodenDoor => {
-                let mut upper = block;
-                upper.set_half_upper_lower(HalfUpperLower::Upper);
-                Some((pos.up(), upper))
+            // Abort if block that needs support wouldn't have the needed support blocks
+            if !is_block_supported_at(block, game, pos) {
+                return;
             }
-            _ => None,
-        } {
-            game.set_block_at(
-                world,
-                other_pos,
-                other_block,
-                BlockUpdateCause::Entity(player),
-            );
+
+            (pos, block, BlockUpdateCause::Entity(player))
         }
+    };
 
-        game.set_block_at(world, pos, block, BlockUpdateCause::Entity(player));
+    let mut event = BlockPlacePreEvent {
+        player,
+        pos,
+        block,
+        cancelled: false,
+    };
+    if !plugin_events.trigger_pre(&mut event, game, world) {
+        // Undo the placement predicted by the client
+        // and resend the item it removed from the stack.
+        resend_block(game, world, player, pos);
+        let held_item = world.get::<HeldItem>(player).0;
+        game.handle(
+            world,
+            InventoryUpdateEvent {
+                slots: smallvec![slot(Area::Hotbar, held_item)],
+                entity: player,
+            },
+        );
+        return;
+    }
+    let block = event.block;
+
+    // handle multi-block placements (i.e. doors and beds)
+    if let Some((other_pos, other_block)) = match block.simplified_kind() {
+        SimplifiedBlockKind::Bed => {
+            let mut head = block;
+            head.set_part(Part::Head);
+            Some((pos + block.facing_cardinal().unwrap().offset(), head))
+        }
+        SimplifiedBlockKind::IronDoor | SimplifiedBlockKind::WoodenDoor => {
+            let mut upper = block;
+            upper.set_half_upper_lower(HalfUpperLower::Upper);
+            Some((pos.up(), upper))
+        }
+        _ => None,
+    } {
+        game.set_block_at(
+            world,
+            other_pos,
+            other_block,
+            BlockUpdateCause::Entity(player),
+        );
     }
 
+    game.set_block_at(world, pos, block, cause);
+
     // Update player's inventory if in survival
     let event = {
         if gamemode != Gamemode::Creative {
@@ -212,16 +243,16 @@ pub fn handle_block_placement(
     }
 }
 
-// returns true if placement handling should stop
-fn handle_slab_placement(
-    game: &mut Game,
-    world: &mut World,
+/// Returns the position and the double slab to place if placing
+/// `block_to_place` completes a slab into a double slab.
+fn slab_placement(
+    game: &Game,
     block_to_place: BlockId,
     mut target_block_pos: BlockPosition,
     placement_face: PacketFace,
-) -> bool {
+) -> Option<(BlockPosition, BlockId)> {
     if block_to_place.simplified_kind() != SimplifiedBlockKind::Slab {
-        return false;
+        return None;
     }
 
     let mut target_block = game.block_at(target_block_pos).unwrap();
@@ -233,35 +264,27 @@ fn handle_slab_placement(
         if (target_block_slab_kind == SlabKind::Bottom && placement_face == PacketFace::Bottom)
             || (target_block_slab_kind == SlabKind::Top && placement_face == PacketFace::Top)
         {
-            return false;
+            return None;
         }
     } else {
         target_block_pos = target_block_pos + placement_face.placement_offset();
         if let Some(block) = game.block_at(target_block_pos) {
             target_block = block;
         } else {
-            return false;
+            return None;
         }
 
         if target_block.simplified_kind() != SimplifiedBlockKind::Slab {
-            return false;
+            return None;
         }
     }
 
     if target_block.kind() != block_to_place.kind() {
-        return false;
+        return None;
     }
 
     target_block.set_slab_kind(SlabKind::Double);
-
-    game.set_block_at(
-        world,
-        target_block_pos,
-        target_block,
-        BlockUpdateCause::Unknown,
-    );
-
-    true
+    Some((target_block_pos, target_block))
 }
 
 fn update_block_state_for_placement(
@@ -0,0 +1,70 @@
+//! Handling of recipe book packets: Recipe Book Data
+//! and Craft Recipe Request.
+
+use crate::IteratorExt;
+use feather_core::network::packets::{
+    CraftRecipeRequest, CraftRecipeResponse, RecipeBookData, RecipeBookDataType,
+};
+use feather_core::recipes;
+use feather_server_types::{Game, Network, PacketBuffers, RecipeBook};
+use fecs::World;
+use std::sync::Arc;
+
+/// System for handling Recipe Book Data packets, which
+/// are sent when a player views a recipe or changes
+/// the state of the recipe book GUI.
+#[fecs::system]
+pub fn handle_recipe_book_data(world: &mut World, packet_buffers: &Arc<PacketBuffers>) {
+    packet_buffers
+        .received::<RecipeBookData>()
+        .for_each_valid(world, |world, (player, packet)| {
+            let mut book = world.get_mut::<RecipeBook>(player);
+            match packet.data {
+                RecipeBookDataType::DisplayedRecipe(recipe_id) => {
+                    book.to_be_displayed.remove(&recipe_id);
+                }
+                RecipeBookDataType::RecipeBookStates {
+                    crafting_book_open,
+                    crafting_filter_active,
+                    smelting_book_open,
+                    smelting_filter_active,
+                } => {
+                    book.crafting_book_open = crafting_book_open;
+                    book.crafting_filter_active = crafting_filter_active;
+                    book.smelting_book_open = smelting_book_open;
+                    book.smelting_filter_active = smelting_filter_active;
+                }
+            }
+        });
+}
+
+/// System for handling Craft Recipe Request packets, which
+/// are sent when a player clicks a recipe in the recipe book.
+#[fecs::system]
+pub fn handle_craft_recipe_request(
+    game: &mut Game,
+    world: &mut World,
+    packet_buffers: &Arc<PacketBuffers>,
+) {
+    packet_buffers
+        .received::<CraftRecipeRequest>()
+        .for_each_valid(world, |world, (player, packet)| {
+            let recipe = match recipes::recipe(&packet.recipe) {
+                Some(recipe) => recipe,
+                None => return,
+            };
+            if !world.get::<RecipeBook>(player).known.contains(&recipe.id) {
+                return;
+            }
+
+            if !crate::fill_crafting_grid(game, world, player, recipe, packet.make_all) {
+                // Have the client show the recipe's ingredients
+                // as a "ghost" in the crafting grid.
+                let response = CraftRecipeResponse {
+                    window_id: packet.window_id,
+                    recipe: packet.recipe,
+                };
+                world.get::<Network>(player).send(response);
+            }
+        });
+}
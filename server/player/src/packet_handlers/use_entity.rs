@@ -0,0 +1,57 @@
+//! Handling of Use Entity packets, which are sent when
+//! a player attacks or interacts with an entity.
+
+use crate::IteratorExt;
+use entity::combat;
+use feather_core::network::packets::{UseEntity, UseEntityType};
+use feather_core::util::Position;
+use feather_server_types::{Game, NetworkId, PacketBuffers};
+use feather_server_util::nearby_entities;
+use fecs::{Entity, World};
+use std::sync::Arc;
+
+/// Maximum squared distance from which a player may attack an entity.
+const MAX_ATTACK_RADIUS_SQUARED: f64 = 36.0;
+
+/// System for handling Use Entity packets.
+///
+/// Only attacks are handled; interactions are ignored for now.
+#[fecs::system]
+pub fn handle_use_entity(game: &mut Game, world: &mut World, packet_buffers: &Arc<PacketBuffers>) {
+    packet_buffers
+        .received::<UseEntity>()
+        .for_each_valid(world, |world, (player, packet)| {
+            if let UseEntityType::Attack = packet.ty {
+                handle_attack(game, world, player, packet.target);
+            }
+        });
+}
+
+fn handle_attack(game: &mut Game, world: &mut World, player: Entity, target_id: i32) {
+    let pos = *world.get::<Position>(player);
+    let radius = MAX_ATTACK_RADIUS_SQUARED.sqrt();
+
+    let target = nearby_entities(world, game, pos, glm::vec3(radius, radius, radius))
+        .into_iter()
+        .find(|entity| {
+            world
+                .try_get::<NetworkId>(*entity)
+                .map_or(false, |id| id.0 == target_id)
+        });
+    let target = match target {
+        Some(target) => target,
+        None => {
+            log::trace!("player {:?} attacked an unknown or distant entity", player);
+            return;
+        }
+    };
+
+    if world.get::<Position>(target).distance_squared_to(pos) > MAX_ATTACK_RADIUS_SQUARED {
+        log::trace!("player {:?} tried to attack too far", player);
+        return;
+    }
+
+    if combat::can_attack(game, world, player, target) {
+        combat::attack(game, world, player, target);
+    }
+}
@@ -20,7 +20,11 @@ pub fn handle_close_window(
 
             let windows_closed: SmallVec<[Entity; 2]> = {
                 let mut window = world.get_mut::<Window>(player);
-                let windows_closed = window.wrapped_entities().into();
+                let mut windows_closed: SmallVec<_> = window.wrapped_entities().into();
+                // The player closed their own inventory.
+                if windows_closed.is_empty() {
+                    windows_closed.push(player);
+                }
                 *window = Window::player(player);
                 windows_closed
             };
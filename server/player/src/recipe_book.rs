@@ -0,0 +1,203 @@
+//! The recipe book: declaring recipes to clients
+//! and unlocking recipes for players.
+//!
+//! Vanilla unlocks most recipes through advancements
+//! which trigger when a player obtains one of the recipe's
+//! ingredients. We don't implement advancements yet, so
+//! that condition is checked directly on inventory updates.
+
+use ahash::AHashMap;
+use feather_core::inventory::{Area, Inventory};
+use feather_core::items::ItemStack;
+use feather_core::network::packets::{
+    DeclareRecipes, DeclaredIngredient, DeclaredRecipe, DeclaredRecipeData, UnlockRecipes,
+    UnlockRecipesAction,
+};
+use feather_core::recipes::{self, Ingredient, Recipe, RecipeKind};
+use feather_definitions::Item;
+use feather_server_types::{InventoryUpdateEvent, Network, RecipeBook};
+use fecs::{Entity, World};
+use once_cell::sync::Lazy;
+
+/// The Declare Recipes packet sent to every player on join.
+static DECLARE_RECIPES: Lazy<DeclareRecipes> = Lazy::new(|| DeclareRecipes {
+    recipes: recipes::recipes().iter().map(declare_recipe).collect(),
+});
+
+/// The recipes using each item as an ingredient.
+static RECIPES_BY_INGREDIENT: Lazy<AHashMap<Item, Vec<&'static Recipe>>> = Lazy::new(|| {
+    let mut index: AHashMap<Item, Vec<&'static Recipe>> = AHashMap::new();
+    for recipe in recipes::recipes() {
+        let mut items: Vec<Item> = ingredients(recipe)
+            .flat_map(|ingredient| ingredient.0.iter().copied())
+            .collect();
+        items.sort();
+        items.dedup();
+
+        for item in items {
+            index.entry(item).or_default().push(recipe);
+        }
+    }
+    index
+});
+
+/// Returns the recipes which use `item` as an ingredient.
+fn recipes_using(item: Item) -> &'static [&'static Recipe] {
+    RECIPES_BY_INGREDIENT
+        .get(&item)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn ingredients(recipe: &Recipe) -> Box<dyn Iterator<Item = &Ingredient> + '_> {
+    match &recipe.kind {
+        RecipeKind::Shaped(shaped) => Box::new(shaped.ingredients.iter().flatten()),
+        RecipeKind::Shapeless(shapeless) => Box::new(shapeless.ingredients.iter()),
+        RecipeKind::Smelting(smelting) => Box::new(std::iter::once(&smelting.ingredient)),
+    }
+}
+
+/// Returns the Declare Recipes packet sent to players on join.
+pub fn declare_recipes() -> &'static DeclareRecipes {
+    &DECLARE_RECIPES
+}
+
+fn declare_recipe(recipe: &Recipe) -> DeclaredRecipe {
+    let data = match &recipe.kind {
+        RecipeKind::Shaped(shaped) => DeclaredRecipeData::Shaped {
+            width: shaped.width as i32,
+            height: shaped.height as i32,
+            group: recipe.group.clone(),
+            ingredients: shaped
+                .ingredients
+                .iter()
+                .map(|ingredient| {
+                    ingredient
+                        .as_ref()
+                        .map(declare_ingredient)
+                        .unwrap_or_default()
+                })
+                .collect(),
+            result: shaped.result.clone(),
+        },
+        RecipeKind::Shapeless(shapeless) => DeclaredRecipeData::Shapeless {
+            group: recipe.group.clone(),
+            ingredients: shapeless
+                .ingredients
+                .iter()
+                .map(declare_ingredient)
+                .collect(),
+            result: shapeless.result.clone(),
+        },
+        RecipeKind::Smelting(smelting) => DeclaredRecipeData::Smelting {
+            group: recipe.group.clone(),
+            ingredient: declare_ingredient(&smelting.ingredient),
+            result: smelting.result.clone(),
+            experience: smelting.experience,
+            cooking_time: smelting.cooking_time as i32,
+        },
+    };
+
+    DeclaredRecipe {
+        id: recipe.id.clone(),
+        data,
+    }
+}
+
+fn declare_ingredient(ingredient: &Ingredient) -> DeclaredIngredient {
+    ingredient
+        .0
+        .iter()
+        .map(|item| ItemStack::new(*item, 1))
+        .collect()
+}
+
+/// Creates the Unlock Recipes packet which initializes
+/// a player's recipe book on join.
+pub fn unlock_recipes_init(book: &RecipeBook) -> UnlockRecipes {
+    let mut recipe_ids: Vec<String> = book.known.iter().cloned().collect();
+    recipe_ids.sort();
+    let mut displayed_recipe_ids: Vec<String> = book.to_be_displayed.iter().cloned().collect();
+    displayed_recipe_ids.sort();
+
+    UnlockRecipes {
+        action: UnlockRecipesAction::Init,
+        crafting_book_open: book.crafting_book_open,
+        crafting_filter_active: book.crafting_filter_active,
+        smelting_book_open: book.smelting_book_open,
+        smelting_filter_active: book.smelting_filter_active,
+        recipe_ids,
+        displayed_recipe_ids,
+    }
+}
+
+/// Unlocks the given recipes for a player, notifying
+/// their client. Recipes which are already known are ignored.
+pub fn unlock_recipes<'a>(
+    world: &mut World,
+    player: Entity,
+    recipes: impl IntoIterator<Item = &'a Recipe>,
+) {
+    let mut book = world.get_mut::<RecipeBook>(player);
+
+    let mut recipe_ids = vec![];
+    for recipe in recipes {
+        if book.known.insert(recipe.id.clone()) {
+            book.to_be_displayed.insert(recipe.id.clone());
+            recipe_ids.push(recipe.id.clone());
+        }
+    }
+
+    if recipe_ids.is_empty() {
+        return;
+    }
+
+    let packet = UnlockRecipes {
+        action: UnlockRecipesAction::Add,
+        crafting_book_open: book.crafting_book_open,
+        crafting_filter_active: book.crafting_filter_active,
+        smelting_book_open: book.smelting_book_open,
+        smelting_filter_active: book.smelting_filter_active,
+        recipe_ids,
+        displayed_recipe_ids: vec![],
+    };
+    drop(book);
+    world.get::<Network>(player).send(packet);
+}
+
+/// When a player obtains an item, unlocks the
+/// recipes which use that item.
+#[fecs::event_handler]
+pub fn on_inventory_update_unlock_recipes(event: &InventoryUpdateEvent, world: &mut World) {
+    if !world.has::<RecipeBook>(event.entity) {
+        return;
+    }
+
+    let items: Vec<_> = {
+        let inventory = world.get::<Inventory>(event.entity);
+        event
+            .slots
+            .iter()
+            // Crafting results only preview the result
+            .filter(|slot| slot.area != Area::CraftingOutput)
+            .filter_map(|slot| inventory.item_at(slot.area, slot.slot).ok().flatten())
+            .map(|stack| stack.ty)
+            .collect()
+    };
+
+    if items.is_empty() {
+        return;
+    }
+
+    let unlocked: Vec<&'static Recipe> = {
+        let book = world.get::<RecipeBook>(event.entity);
+        items
+            .iter()
+            .flat_map(|item| recipes_using(*item))
+            .copied()
+            .filter(|recipe| !book.known.contains(&recipe.id))
+            .collect()
+    };
+
+    unlock_recipes(world, event.entity, unlocked);
+}
@@ -286,8 +286,6 @@ fn send_chunk_to_player(
             },
         );
     } else {
-        let contains = chunks_to_send.0.contains_key(&chunk_pos);
-
         let vec = match chunks_to_send.0.get_mut(&chunk_pos) {
             Some(vec) => vec,
             None => {
@@ -297,10 +295,10 @@ fn send_chunk_to_player(
         };
         vec.push(player);
 
-        if !contains {
-            // Queue chunk for loading if it isn't already.
-            game.handle(world, LoadChunkRequest { chunk: chunk_pos });
-        }
+        // Queue chunk for loading if it isn't already. The chunk may
+        // already have pending players even if it is not loading,
+        // since loads are cancelled once a chunk has no holders.
+        game.handle(world, LoadChunkRequest { chunk: chunk_pos });
     }
 }
 
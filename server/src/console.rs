@@ -0,0 +1,102 @@
+//! The server console, which executes commands
+//! read from standard input and prints chat and
+//! command feedback to the terminal.
+
+use feather_core::text::ansi::to_ansi;
+use feather_core::text::Text;
+use feather_server_commands::CommandState;
+use feather_server_types::{
+    tasks, ChatEvent, ChatPosition, Console, MessageReceiver, Permissions, MAX_OP_LEVEL,
+};
+use fecs::{component, Entity, EntityBuilder, IntoQuery, World, Write};
+use std::io::{self, BufRead};
+use std::sync::Arc;
+use std::thread;
+
+/// Spawns the console entity and starts reading
+/// commands from standard input.
+///
+/// Must be called after the task manager is initialized.
+pub fn init(world: &mut World) {
+    let console = EntityBuilder::new()
+        .with(Console)
+        .with(MessageReceiver::default())
+        .with(Permissions::with_level(MAX_OP_LEVEL))
+        .build()
+        .spawn_in(world);
+
+    start_reading(console);
+}
+
+/// Starts the thread reading commands from standard input.
+/// Each command is dispatched on the ticking thread with
+/// `console` as its sender.
+///
+/// A plain thread is used rather than Tokio's `stdin`, which
+/// would block the runtime from shutting down while waiting for input.
+fn start_reading(console: Entity) {
+    thread::Builder::new()
+        .name(String::from("console"))
+        .spawn(move || {
+            let stdin = io::stdin();
+            for line in stdin.lock().lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => {
+                        log::warn!("Failed to read from the console: {}", e);
+                        return;
+                    }
+                };
+
+                let command = line.trim().trim_start_matches('/');
+                if command.is_empty() {
+                    continue;
+                }
+
+                log::info!("Console executed command `{}`", command);
+                let command = command.to_owned();
+                tasks().sync(move |game, world| {
+                    let resources = Arc::clone(&game.resources);
+                    let commands = resources.get::<CommandState>();
+                    commands.dispatch(game, world, console, &command);
+                });
+            }
+        })
+        .expect("failed to spawn console thread");
+}
+
+/// System which prints messages sent to the console.
+#[fecs::system]
+pub fn flush_console_message_receiver(world: &mut World) {
+    for mut receiver in <Write<MessageReceiver>>::query()
+        .filter(component::<Console>())
+        .iter_mut(world.inner_mut())
+    {
+        for message in receiver.flush() {
+            println!("{}", to_ansi(&message));
+        }
+    }
+}
+
+/// Forwards chat messages to the console.
+#[fecs::event_handler]
+pub fn on_chat_send_to_console(event: &ChatEvent, world: &mut World) {
+    if let ChatPosition::GameInfo = event.position {
+        return;
+    }
+
+    let message: Text = match serde_json::from_str(&event.message) {
+        Ok(message) => message,
+        Err(e) => {
+            log::warn!("Received malformed chat message `{}`: {}", event.message, e);
+            return;
+        }
+    };
+
+    for mut receiver in <Write<MessageReceiver>>::query()
+        .filter(component::<Console>())
+        .iter_mut(world.inner_mut())
+    {
+        receiver.send(message.clone());
+    }
+}
@@ -1,4 +1,5 @@
 //! Defines the event handlers.
+use crate::console::*;
 use feather_server_block::*;
 use feather_server_chunk::*;
 use feather_server_entity::*;
@@ -19,7 +20,7 @@ macro_rules! event_handlers {
 }
 
 pub fn build_event_handlers() -> EventHandlers {
-    event_handlers! {
+    let handlers = event_handlers! {
         on_block_update_notify_adjacent,
         on_block_break_broadcast_effect,
         on_block_update_broadcast,
@@ -51,6 +52,8 @@ pub fn build_event_handlers() -> EventHandlers {
         on_player_join_send_weather,
         on_player_join_broadcast_join_message,
 
+        on_player_leave_close_crafting_table,
+        on_player_leave_return_crafting_grid,
         on_player_leave_save_data,
 
         on_chunk_load_notify_lighting_worker,
@@ -68,7 +71,10 @@ pub fn build_event_handlers() -> EventHandlers {
 
         on_damage_item,
 
+        on_inventory_update_update_crafting_result,
+        on_inventory_update_unlock_recipes,
         on_inventory_update_send_set_slot,
+        on_inventory_update_send_crafting_table_slots,
         on_inventory_update_broadcast_equipment_update,
 
         on_player_animation_broadcast_animation,
@@ -80,8 +86,10 @@ pub fn build_event_handlers() -> EventHandlers {
         on_weather_change_broadcast_weather,
 
         on_chat_broadcast,
+        on_chat_send_to_console,
 
         on_entity_land_remove_falling_block,
+        on_scheduled_tick_spawn_falling_block,
 
         load_chunk_request,
 
@@ -97,6 +105,7 @@ pub fn build_event_handlers() -> EventHandlers {
         on_gamemode_update_send,
 
         on_health_update_send,
+        on_health_update_broadcast_hurt,
 
         on_player_death_scatter_inventory,
         on_player_death_mark_dead,
@@ -104,5 +113,10 @@ pub fn build_event_handlers() -> EventHandlers {
         on_chest_open_increment_viewers,
 
         on_chest_close_decrement_viewers,
-    }
+        on_crafting_table_close_return_items,
+        on_window_close_return_crafting_grid,
+    };
+
+    // Plugin handlers run after the server's own handlers.
+    feather_api::add_event_handlers(handlers)
 }
@@ -1,14 +1,18 @@
 //! Startup logic.
 
-use crate::{event_handlers, systems};
+use crate::{console, event_handlers, systems};
 use anyhow::Context;
+use feather_api::Plugins;
 use feather_core::anvil::level::{LevelData, LevelGeneratorType};
 use feather_core::util::ChunkPosition;
 use feather_server_chunk::{chunk_worker, ChunkWorkerHandle};
 use feather_server_config::DEFAULT_CONFIG_STR;
-use feather_server_network::NetworkIoManager;
+use feather_server_network::{NetworkIoManager, QueryInfo};
 use feather_server_packet_buffer::PacketBuffers;
-use feather_server_types::{task, BanInfo, Config, Game, Shared, ShutdownChannels};
+use feather_server_types::{
+    task, BanInfo, Config, Game, OpList, ProxyMode, Shared, ShutdownChannels, Whitelist,
+};
+use feather_server_wasm::{WasmPlugins, PLUGINS_DIR};
 use feather_server_worldgen::{
     ComposableGenerator, EmptyWorldGenerator, SuperflatWorldGenerator, WorldGenerator,
 };
@@ -20,7 +24,7 @@ use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use tokio::fs::File;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, UdpSocket};
 use tokio::{io, runtime};
 
 /// Intializes the server.
@@ -41,11 +45,28 @@ pub async fn init(
         .context("Failed to load configuration file `feather.toml`")?;
     set_up_logging(&config).context("Failed to initialize logging")?;
 
+    if config.proxy.proxy_mode == ProxyMode::Velocity && config.proxy.velocity_secret.is_empty() {
+        anyhow::bail!("`velocity_secret` must be set in `feather.toml` to use Velocity proxy mode");
+    }
+    if config.rcon.enabled && config.rcon.password.is_empty() {
+        anyhow::bail!("`password` must be set in the `rcon` section of `feather.toml` to use RCON");
+    }
+
     log::info!("Loading ban list");
     let ban_info = load_ban_info()
         .await
         .context("Failed to load ban list `bans.toml`")?;
 
+    log::info!("Loading whitelist");
+    let whitelist = load_whitelist(&config)
+        .await
+        .context("Failed to load whitelist `whitelist.json`")?;
+
+    log::info!("Loading operator list");
+    let ops = load_ops()
+        .await
+        .context("Failed to load operator list `ops.json`")?;
+
     log::info!("Loading world save");
     let level = load_level(&config)
         .await
@@ -58,6 +79,8 @@ pub async fn init(
             config: Arc::clone(&config),
             rng: Default::default(),
             player_count: Arc::new(Default::default()),
+            player_names: Arc::new(Default::default()),
+            plugins: Arc::new(Default::default()),
         }),
         chunk_map: Default::default(),
         tick_count: 0,
@@ -70,8 +93,10 @@ pub async fn init(
         resources: Arc::new(Default::default()), // we override this momentarily
         bump: Default::default(),
         game_rules: Default::default(),
+        scheduled_ticks: Default::default(),
     };
     task::init(runtime);
+    console::init(&mut world);
     let packet_buffers = Arc::new(PacketBuffers::new());
 
     log::info!("Queueing spawn chunks for loading");
@@ -87,6 +112,8 @@ pub async fn init(
     let networking_handle = create_networking_handle(
         Arc::clone(&config),
         Arc::clone(&ban_info),
+        Arc::clone(&whitelist),
+        Arc::clone(&ops),
         &game,
         Arc::clone(&packet_buffers),
     )
@@ -100,8 +127,13 @@ pub async fn init(
         networking_handle,
         packet_buffers,
         ban_info,
+        whitelist,
+        ops,
     );
 
+    log::info!("Enabling plugins");
+    feather_api::enable_plugins(&resources, &mut world);
+
     Ok((executor, resources, world))
 }
 
@@ -136,6 +168,31 @@ async fn load_ban_info() -> anyhow::Result<Arc<RwLock<BanInfo>>> {
     .map(Arc::new)
 }
 
+async fn load_whitelist(config: &Config) -> anyhow::Result<Arc<RwLock<Whitelist>>> {
+    const PATH: &str = "whitelist.json";
+
+    match File::open(PATH).await {
+        Ok(mut file) => Whitelist::load_from_file(&mut file).await,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Whitelist::default()),
+        Err(e) => Err(e.into()),
+    }
+    .map(|mut whitelist| {
+        whitelist.enabled = config.server.whitelist;
+        Arc::new(RwLock::new(whitelist))
+    })
+}
+
+async fn load_ops() -> anyhow::Result<Arc<RwLock<OpList>>> {
+    const PATH: &str = "ops.json";
+
+    match File::open(PATH).await {
+        Ok(mut file) => OpList::load_from_file(&mut file).await,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(OpList::default()),
+        Err(e) => Err(e.into()),
+    }
+    .map(|ops| Arc::new(RwLock::new(ops)))
+}
+
 fn set_up_logging(config: &Config) -> anyhow::Result<()> {
     use log::Level::*;
     let level = match config.log.level.as_str() {
@@ -249,16 +306,24 @@ fn create_cworker_handle(config: &Config, level: &LevelData) -> ChunkWorkerHandl
         _ => Arc::new(EmptyWorldGenerator {}),
     };
 
-    let (tx, rx) = chunk_worker::start(Path::new(&config.world.name), generator);
+    let (tx, rx, region_metrics) = chunk_worker::start(
+        Path::new(&config.world.name),
+        generator,
+        config.world.max_open_regions,
+        config.world.region_idle_timeout,
+    );
     ChunkWorkerHandle {
         sender: tx,
         receiver: rx,
+        region_metrics,
     }
 }
 
 async fn create_networking_handle(
     config: Arc<Config>,
     ban_info: Arc<RwLock<BanInfo>>,
+    whitelist: Arc<RwLock<Whitelist>>,
+    ops: Arc<RwLock<OpList>>,
     game: &Game,
     packet_buffers: Arc<PacketBuffers>,
 ) -> anyhow::Result<NetworkIoManager> {
@@ -273,10 +338,42 @@ async fn create_networking_handle(
 
     log::info!("Listening on {}", addr);
 
+    if config.query.enabled {
+        let query_addr = format!("{}:{}", config.server.address, config.query.port);
+        let query_socket = UdpSocket::bind(&query_addr)
+            .await
+            .context("failed to bind to query port")?;
+
+        log::info!("Listening for queries on {}", query_addr);
+
+        feather_server_network::start_query(
+            query_socket,
+            QueryInfo {
+                config: Arc::clone(&config),
+                player_count: Arc::clone(&game.player_count),
+                player_names: Arc::clone(&game.player_names),
+                plugins: Arc::clone(&game.plugins),
+            },
+        );
+    }
+
+    if config.rcon.enabled {
+        let rcon_addr = format!("{}:{}", config.server.address, config.rcon.port);
+        let rcon_listener = TcpListener::bind(&rcon_addr)
+            .await
+            .context("failed to bind to RCON port")?;
+
+        log::info!("Listening for RCON connections on {}", rcon_addr);
+
+        feather_server_network::start_rcon(rcon_listener, Arc::clone(&config));
+    }
+
     Ok(NetworkIoManager::start(
         socket,
         config,
         ban_info,
+        whitelist,
+        ops,
         Arc::clone(&game.player_count),
         Arc::new(server_icon),
         packet_buffers,
@@ -329,6 +426,8 @@ fn create_resources(
     networking_handle: NetworkIoManager,
     packet_buffers: Arc<PacketBuffers>,
     ban_info: Arc<RwLock<BanInfo>>,
+    whitelist: Arc<RwLock<Whitelist>>,
+    ops: Arc<RwLock<OpList>>,
 ) -> Arc<OwnedResources> {
     let resources = {
         let resources = resources
@@ -337,6 +436,9 @@ fn create_resources(
             .with(networking_handle)
             .with(packet_buffers)
             .with(ban_info)
+            .with(whitelist)
+            .with(ops)
+            .with(Plugins::default().with(WasmPlugins::new(PLUGINS_DIR)))
             .with(ShutdownChannels::new());
         Arc::new(resources)
     };
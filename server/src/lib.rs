@@ -4,7 +4,7 @@
 
 use feather_server_chunk::ChunkWorkerHandle;
 use feather_server_lighting::LightingWorkerHandle;
-use feather_server_types::{BanInfo, Game, ShutdownChannels, TPS};
+use feather_server_types::{BanInfo, Game, OpList, ShutdownChannels, Whitelist, TPS};
 use fecs::{Executor, OwnedResources, ResourcesProvider, World};
 use spin_sleep::LoopHelper;
 use std::ops::Deref;
@@ -13,6 +13,7 @@ use std::process::exit;
 use std::sync::{Arc, RwLock};
 use tokio::runtime;
 
+mod console;
 mod event_handlers;
 mod init;
 mod shutdown;
@@ -118,6 +119,8 @@ fn run_loop(state: &mut FullState) {
 }
 
 async fn shut_down(resources: &OwnedResources, world: &mut World) -> anyhow::Result<()> {
+    log::info!("Disabling plugins");
+    feather_api::disable_plugins(resources, world);
     log::info!("Disconnecting players");
     shutdown::disconnect_players(&world)?;
     log::info!("Shutting down workers");
@@ -137,6 +140,10 @@ async fn shut_down(resources: &OwnedResources, world: &mut World) -> anyhow::Res
     shutdown::save_player_data(&*resources.get::<Game>(), &world)?;
     log::info!("Saving ban list");
     shutdown::save_ban_list(&resources.get::<Arc<RwLock<BanInfo>>>()).await?;
+    log::info!("Saving whitelist");
+    shutdown::save_whitelist(&resources.get::<Arc<RwLock<Whitelist>>>()).await?;
+    log::info!("Saving operator list");
+    shutdown::save_ops(&resources.get::<Arc<RwLock<OpList>>>()).await?;
     log::info!("Waiting for tasks to finish");
     shutdown::wait_for_task_completion().await?;
 
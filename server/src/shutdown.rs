@@ -5,7 +5,7 @@ use feather_core::text::{TextRoot, TextValue};
 use feather_server_chunk::chunk_worker::Request;
 use feather_server_chunk::{save_chunk_at, ChunkWorkerHandle};
 use feather_server_lighting::LightingWorkerHandle;
-use feather_server_types::{tasks, BanInfo, Game, Network, Player};
+use feather_server_types::{tasks, BanInfo, Game, Network, OpList, Player, Whitelist};
 use fecs::{IntoQuery, Read, World};
 use std::sync::{Arc, RwLock};
 use tokio::fs::File;
@@ -97,3 +97,21 @@ pub async fn save_ban_list(ban_info: &Arc<RwLock<BanInfo>>) -> anyhow::Result<()
         Err(e) => Err(e.into()),
     }
 }
+
+pub async fn save_whitelist(whitelist: &Arc<RwLock<Whitelist>>) -> anyhow::Result<()> {
+    const PATH: &str = "whitelist.json";
+
+    match File::create(PATH).await {
+        Ok(mut file) => whitelist.read().unwrap().save_to_file(&mut file).await,
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn save_ops(ops: &Arc<RwLock<OpList>>) -> anyhow::Result<()> {
+    const PATH: &str = "ops.json";
+
+    match File::create(PATH).await {
+        Ok(mut file) => ops.read().unwrap().save_to_file(&mut file).await,
+        Err(e) => Err(e.into()),
+    }
+}
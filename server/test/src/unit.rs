@@ -14,8 +14,8 @@ use feather_server_chunk::{
 use feather_server_network::NewClientInfo;
 use feather_server_player::on_chunk_cross_update_chunks;
 use feather_server_types::{
-    ChunkCrossEvent, ChunkHolder, Game, Name, NetworkId, ServerToWorkerMessage, Shared, Uuid,
-    WorkerToServerMessage,
+    ChunkCrossEvent, ChunkHolder, Game, Name, NetworkId, OpList, ServerToWorkerMessage, Shared,
+    Uuid, WorkerToServerMessage,
 };
 use feather_server_util::on_chunk_cross_update_chunk_entities;
 use fecs::{
@@ -27,7 +27,7 @@ use std::borrow::Cow;
 use std::collections::HashMap;
 use std::net::{IpAddr, SocketAddr};
 use std::ops::Deref;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 
 struct TrackedPlayer {
     /// IO worker-side receiver
@@ -96,10 +96,14 @@ impl Test {
                 config: Arc::new(Default::default()),
                 rng: Default::default(),
                 player_count: Arc::new(Default::default()),
+                player_names: Arc::new(Default::default()),
+                plugins: Arc::new(Default::default()),
             }),
             game_rules: Default::default(),
+            scheduled_ticks: Default::default(),
         };
         resources.insert(cworker_handle);
+        resources.insert(Arc::new(RwLock::new(OpList::default())));
 
         let resources = Arc::new(resources);
         game.resources = resources;
@@ -205,6 +209,7 @@ impl Test {
                 gamemode: 1,
                 inventory: vec![],
                 held_item: 0,
+                recipe_book: Default::default(),
             },
             position,
             sender: server_tx,
@@ -365,6 +370,7 @@ impl ChunkWorkerTester {
             ChunkWorkerHandle {
                 receiver: rx,
                 sender: tx,
+                region_metrics: Default::default(),
             },
         )
     }
@@ -1,5 +1,6 @@
 mod marker;
 mod network;
+mod permissions;
 mod physics;
 mod serialize;
 
@@ -8,11 +9,13 @@ pub use serialize::*;
 
 pub use feather_core::inventory::Inventory;
 pub use network::{Network, ServerToWorkerMessage, WorkerToServerMessage};
+pub use permissions::{Permission, Permissions, MAX_OP_LEVEL};
 pub use physics::{AABBExt, Physics, PhysicsBuilder};
 pub use uuid::Uuid;
 
 use ahash::AHashSet;
 use dashmap::DashMap;
+use feather_core::anvil::player::RecipeBookNbt;
 use feather_core::text::Text;
 use feather_core::util::{ChunkPosition, Position};
 use fecs::Entity;
@@ -117,6 +120,54 @@ impl Default for OpenWindowCount {
         OpenWindowCount { count: 1 }
     }
 }
+
+/// A player's recipe book.
+#[derive(Debug, Clone, Default)]
+pub struct RecipeBook {
+    /// IDs of the recipes the player has unlocked.
+    pub known: AHashSet<String>,
+    /// IDs of unlocked recipes which the player has
+    /// not yet viewed in the recipe book.
+    pub to_be_displayed: AHashSet<String>,
+    pub crafting_book_open: bool,
+    pub crafting_filter_active: bool,
+    pub smelting_book_open: bool,
+    pub smelting_filter_active: bool,
+}
+
+impl RecipeBook {
+    /// Converts this recipe book into its representation
+    /// in the player data file.
+    pub fn to_nbt(&self) -> RecipeBookNbt {
+        let mut recipes: Vec<String> = self.known.iter().cloned().collect();
+        recipes.sort();
+        let mut to_be_displayed: Vec<String> = self.to_be_displayed.iter().cloned().collect();
+        to_be_displayed.sort();
+
+        RecipeBookNbt {
+            recipes,
+            to_be_displayed,
+            gui_open: self.crafting_book_open,
+            filtering_craftable: self.crafting_filter_active,
+            furnace_gui_open: self.smelting_book_open,
+            furnace_filtering_craftable: self.smelting_filter_active,
+        }
+    }
+}
+
+impl From<RecipeBookNbt> for RecipeBook {
+    fn from(nbt: RecipeBookNbt) -> Self {
+        Self {
+            known: nbt.recipes.into_iter().collect(),
+            to_be_displayed: nbt.to_be_displayed.into_iter().collect(),
+            crafting_book_open: nbt.gui_open,
+            crafting_filter_active: nbt.filtering_craftable,
+            smelting_book_open: nbt.furnace_gui_open,
+            smelting_filter_active: nbt.furnace_filtering_craftable,
+        }
+    }
+}
+
 /// Health of an entity. Measured in "half-hearts."
 #[derive(Copy, Clone, Debug)]
 pub struct Health(pub u32);
@@ -126,6 +177,19 @@ pub struct Health(pub u32);
 #[derive(Copy, Clone, Debug)]
 pub struct MaxHealth(pub u32);
 
+/// The tick on which an entity last attacked another,
+/// used to compute the attack cooldown.
+#[derive(Copy, Clone, Debug)]
+pub struct LastAttack(pub u64);
+
+/// The entity which last attacked an entity,
+/// and the tick on which it did so.
+#[derive(Copy, Clone, Debug)]
+pub struct LastAttacker {
+    pub entity: Entity,
+    pub tick: u64,
+}
+
 /// Stores the number of blocks fallen by an entity
 /// since the last time they were on_ground.
 #[derive(Default, Copy, Clone, Debug)]
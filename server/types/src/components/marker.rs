@@ -35,3 +35,10 @@ pub struct CanRespawn;
 /// Players with this component _should not be affected by gameplay actions_.
 /// They should not collect items, take damage, etc.
 pub struct Dead;
+
+/// Marks the entity representing the server console.
+///
+/// Commands typed into the console are executed with this
+/// entity as their sender, and messages sent to its
+/// `MessageReceiver` are printed to the terminal.
+pub struct Console;
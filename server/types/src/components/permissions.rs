@@ -0,0 +1,133 @@
+//! Permissions determining which commands and
+//! actions an entity may perform.
+
+use ahash::AHashMap;
+
+/// The highest operator permission level.
+pub const MAX_OP_LEVEL: u8 = 4;
+
+/// A permission node, such as `minecraft.command.stop`.
+///
+/// Entities are granted a permission if they have
+/// been granted its node (or a wildcard covering it) or if
+/// their operator level is at least the node's `level`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Permission {
+    /// Dot-separated name of the node.
+    pub node: &'static str,
+    /// The operator level at which the permission
+    /// is granted by default.
+    pub level: u8,
+}
+
+impl Permission {
+    pub const fn new(node: &'static str, level: u8) -> Self {
+        Self { node, level }
+    }
+}
+
+/// Component storing the permissions of a command sender.
+///
+/// Entities without this component have no permissions
+/// beyond those available at level 0.
+#[derive(Clone, Debug, Default)]
+pub struct Permissions {
+    /// Operator permission level, from 0 (not an operator)
+    /// to `MAX_OP_LEVEL`.
+    pub level: u8,
+    /// Nodes explicitly granted (`true`) or revoked (`false`),
+    /// overriding the operator level.
+    nodes: AHashMap<String, bool>,
+}
+
+impl Permissions {
+    /// Creates permissions with the given operator level
+    /// and no explicitly granted nodes.
+    pub fn with_level(level: u8) -> Self {
+        Self {
+            level,
+            nodes: AHashMap::new(),
+        }
+    }
+
+    /// Determines whether the permission is granted.
+    ///
+    /// The most specific node set through `grant` or `revoke`
+    /// takes precedence: `a.b.c` is checked first, then
+    /// `a.b.*`, `a.*` and `*`. If none is set, the
+    /// operator level decides.
+    pub fn has(&self, permission: Permission) -> bool {
+        if let Some(granted) = self.nodes.get(permission.node) {
+            return *granted;
+        }
+
+        let mut node = permission.node;
+        while let Some(end) = node.rfind('.') {
+            node = &node[..end];
+            if let Some(granted) = self.nodes.get(&format!("{}.*", node)) {
+                return *granted;
+            }
+        }
+        if let Some(granted) = self.nodes.get("*") {
+            return *granted;
+        }
+
+        self.level >= permission.level
+    }
+
+    /// Grants a node, which may end in `*`
+    /// to grant all nodes below it.
+    pub fn grant(&mut self, node: impl Into<String>) {
+        self.nodes.insert(node.into(), true);
+    }
+
+    /// Revokes a node, which may end in `*`
+    /// to revoke all nodes below it.
+    pub fn revoke(&mut self, node: impl Into<String>) {
+        self.nodes.insert(node.into(), false);
+    }
+
+    /// Removes a node previously passed to `grant`
+    /// or `revoke`, so that it is determined by the
+    /// operator level again.
+    pub fn unset(&mut self, node: &str) {
+        self.nodes.remove(node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STOP: Permission = Permission::new("minecraft.command.stop", 4);
+    const MSG: Permission = Permission::new("minecraft.command.msg", 0);
+
+    #[test]
+    fn levels() {
+        let permissions = Permissions::default();
+        assert!(permissions.has(MSG));
+        assert!(!permissions.has(STOP));
+
+        let permissions = Permissions::with_level(MAX_OP_LEVEL);
+        assert!(permissions.has(STOP));
+    }
+
+    #[test]
+    fn nodes() {
+        let mut permissions = Permissions::default();
+        permissions.grant("minecraft.command.*");
+        assert!(permissions.has(STOP));
+
+        permissions.revoke("minecraft.command.stop");
+        assert!(!permissions.has(STOP));
+        assert!(permissions.has(MSG));
+
+        permissions.unset("minecraft.command.stop");
+        permissions.unset("minecraft.command.*");
+        assert!(!permissions.has(STOP));
+
+        let mut permissions = Permissions::with_level(MAX_OP_LEVEL);
+        permissions.revoke("*");
+        assert!(!permissions.has(MSG));
+    }
+}
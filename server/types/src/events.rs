@@ -1,4 +1,4 @@
-use crate::Weather;
+use crate::{TickList, Weather};
 use feather_core::blocks::BlockId;
 use feather_core::inventory::SlotIndex;
 use feather_core::items::ItemStack;
@@ -6,6 +6,16 @@ use feather_core::util::{BlockPosition, ChunkPosition, ClientboundAnimation, Gam
 use fecs::Entity;
 use smallvec::SmallVec;
 
+/// Implemented by events triggered _before_ an action is
+/// performed, which handlers may cancel to prevent the action.
+pub trait Cancellable {
+    /// Returns whether a handler cancelled the action.
+    fn is_cancelled(&self) -> bool;
+
+    /// Cancels the action, or allows it again if `cancelled` is `false`.
+    fn set_cancelled(&mut self, cancelled: bool);
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct BlockUpdateEvent {
     /// Position of the updated block
@@ -30,6 +40,16 @@ pub enum BlockUpdateCause {
     Unknown,
 }
 
+/// Triggered when a scheduled tick is executed, if the block
+/// at its position is still of the kind which scheduled it.
+#[derive(Copy, Clone, Debug)]
+pub struct ScheduledTickEvent {
+    pub pos: BlockPosition,
+    /// The current block at `pos`.
+    pub block: BlockId,
+    pub list: TickList,
+}
+
 /// Triggered directly _before_ an entity is removed from the world.
 ///
 /// As such, components can still be accessed.
@@ -159,7 +179,8 @@ pub struct WindowOpenEvent {
 pub struct WindowCloseEvent {
     /// The player who closed the window
     pub player: Entity,
-    /// The entity whose inventory was closed
+    /// The entity whose inventory was closed, or
+    /// the player if they closed their own inventory
     pub closed: Entity,
 }
 
@@ -285,6 +306,95 @@ pub struct GamemodeUpdateEvent {
     pub new: Gamemode,
 }
 
+/// Implements `Cancellable` for pre-events with a `cancelled` field.
+macro_rules! cancellable {
+    ($($event:ident),*) => {
+        $(
+            impl Cancellable for $event {
+                fn is_cancelled(&self) -> bool {
+                    self.cancelled
+                }
+
+                fn set_cancelled(&mut self, cancelled: bool) {
+                    self.cancelled = cancelled;
+                }
+            }
+        )*
+    }
+}
+
+cancellable!(
+    BlockBreakPreEvent,
+    BlockPlacePreEvent,
+    ItemDropPreEvent,
+    ChatPreEvent,
+    PlayerMovePreEvent
+);
+
+// The pre-events below are triggered through `PluginEvents::trigger_pre`
+// rather than as ECS events, as their handlers may cancel them.
+
+/// Triggered before a player breaks a block. If cancelled,
+/// the block is resent to the player.
+#[derive(Copy, Clone, Debug)]
+pub struct BlockBreakPreEvent {
+    pub player: Entity,
+    pub pos: BlockPosition,
+    /// The block being broken.
+    pub block: BlockId,
+    pub cancelled: bool,
+}
+
+/// Triggered before a player places a block. Handlers may
+/// change the placed block. If cancelled, the block and the
+/// player's held item are resent to the player.
+///
+/// Blocks occupying more than one position, such as doors
+/// and beds, are placed as a whole or not at all.
+#[derive(Copy, Clone, Debug)]
+pub struct BlockPlacePreEvent {
+    pub player: Entity,
+    pub pos: BlockPosition,
+    /// The block to place.
+    pub block: BlockId,
+    pub cancelled: bool,
+}
+
+/// Triggered before a player drops an item stack. If
+/// cancelled, the slot is resent to the player.
+#[derive(Debug, Clone)]
+pub struct ItemDropPreEvent {
+    pub player: Entity,
+    /// The slot from which the item is dropped.
+    pub slot: SlotIndex,
+    /// The item stack in the slot, of which either
+    /// one item or the whole stack is dropped.
+    pub stack: ItemStack,
+    pub cancelled: bool,
+}
+
+/// Triggered before a chat message sent by a player
+/// is broadcast. Handlers may change the message.
+#[derive(Debug, Clone)]
+pub struct ChatPreEvent {
+    pub player: Entity,
+    /// The message as typed by the player.
+    pub message: String,
+    pub cancelled: bool,
+}
+
+/// Triggered before a player's position is updated from
+/// a movement packet. Handlers may change the new position.
+/// If cancelled or changed, the player is teleported to
+/// the resulting position.
+#[derive(Copy, Clone, Debug)]
+pub struct PlayerMovePreEvent {
+    pub player: Entity,
+    pub from: Position,
+    pub to: Position,
+    pub cancelled: bool,
+}
+
 /// Requests that a chunk be held for the given client.
 ///
 /// This is a "request"-type event: it has one handler defined
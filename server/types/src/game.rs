@@ -1,4 +1,6 @@
-use crate::{BlockUpdateCause, Network, ServerToWorkerMessage};
+use crate::{
+    BlockUpdateCause, Network, ScheduledTick, ScheduledTicks, ServerToWorkerMessage, TickList,
+};
 use crate::{
     BlockUpdateEvent, CanRespawn, Dead, EntityDeathEvent, EntityDespawnEvent, Health,
     HealthUpdateEvent, Name, PlayerLeaveEvent,
@@ -18,10 +20,11 @@ use rand::rngs::SmallRng;
 use rand::{Rng, SeedableRng};
 use smallvec::SmallVec;
 use std::cell::{RefCell, RefMut};
+use std::f64::consts::PI;
 use std::fmt::Display;
 use std::ops::{Deref, DerefMut};
 use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use thread_local::CachedThreadLocal;
 
 /// Resources which can be _shared_ between threads.
@@ -33,6 +36,10 @@ pub struct Shared {
     pub rng: CachedThreadLocal<RefCell<SmallRng>>,
     /// The server player count.
     pub player_count: Arc<AtomicU32>, // fixme: double Arc
+    /// The names of online players, in the order they joined.
+    pub player_names: Arc<RwLock<Vec<String>>>,
+    /// The names and versions of the enabled plugins.
+    pub plugins: Arc<RwLock<Vec<(String, String)>>>,
 }
 
 /// The `Game` resource, which acts as a central bus to bind together
@@ -66,6 +73,8 @@ pub struct Game {
     pub shared: Arc<Shared>,
     /// Gamrules
     pub game_rules: GameRules,
+    /// Block and liquid ticks scheduled in the loaded chunks.
+    pub scheduled_ticks: ScheduledTicks,
 }
 
 impl Deref for Game {
@@ -126,6 +135,58 @@ impl Game {
         result
     }
 
+    /// Schedules a tick for the block at `pos` in `delay` ticks.
+    /// A `ScheduledTickEvent` is triggered then if the block
+    /// is still of the same kind as `block`.
+    ///
+    /// Ticks with a lower priority are executed first when
+    /// scheduled for the same tick. Returns `false` if the block's
+    /// chunk is not loaded, as the tick could not be saved with it,
+    /// or if a tick is already scheduled for this position and block kind.
+    pub fn schedule_block_tick(
+        &mut self,
+        pos: BlockPosition,
+        block: BlockId,
+        delay: u64,
+        priority: i32,
+    ) -> bool {
+        self.schedule_tick(pos, block, TickList::Block, delay, priority)
+    }
+
+    /// Schedules a liquid tick, used for fluid flow.
+    /// See `schedule_block_tick`.
+    pub fn schedule_liquid_tick(
+        &mut self,
+        pos: BlockPosition,
+        block: BlockId,
+        delay: u64,
+        priority: i32,
+    ) -> bool {
+        self.schedule_tick(pos, block, TickList::Liquid, delay, priority)
+    }
+
+    fn schedule_tick(
+        &mut self,
+        pos: BlockPosition,
+        block: BlockId,
+        list: TickList,
+        delay: u64,
+        priority: i32,
+    ) -> bool {
+        if self.block_at(pos).is_none() {
+            return false;
+        }
+
+        let time = self.tick_count + delay;
+        self.scheduled_ticks.schedule(ScheduledTick {
+            pos,
+            block,
+            list,
+            time,
+            priority,
+        })
+    }
+
     /// Returns a bump allocator.
     pub fn bump(&self) -> &Bump {
         self.bump.get_or_default()
@@ -179,6 +240,11 @@ impl Game {
 
         log::info!("{} disconnected: {}", name.0, reason_console);
 
+        self.player_names
+            .write()
+            .unwrap()
+            .retain(|player_name| *player_name != name.0);
+
         drop(name);
         drop(network);
 
@@ -275,9 +341,8 @@ impl Game {
             if let Some(mut health) = world.try_get_mut::<Health>(entity) {
                 let old_health = health.0;
                 let should_kill = match health.0.checked_sub(damage) {
-                    Some(0) => true,
-                    None => {
-                        // below 0
+                    Some(0) | None => {
+                        // 0 or below
                         health.0 = 0;
                         true
                     }
@@ -410,6 +475,20 @@ impl Time {
     pub fn world_age(self) -> u64 {
         self.0
     }
+
+    /// Returns the amount by which sky light is reduced at
+    /// this time of day, from 0 at noon to 11 at midnight.
+    pub fn sky_darkness(self) -> u8 {
+        let mut angle = self.time_of_day() as f64 / 24_000.0 - 0.25;
+        if angle < 0.0 {
+            angle += 1.0;
+        }
+        let eased = 1.0 - ((angle * PI).cos() + 1.0) / 2.0;
+        angle += (eased - angle) / 3.0;
+
+        let brightness = ((angle * 2.0 * PI).cos() * 2.0 + 0.5).max(0.0).min(1.0);
+        ((1.0 - brightness) * 11.0) as u8
+    }
 }
 
 #[fecs::system]
@@ -7,13 +7,17 @@ mod components;
 mod events;
 mod game;
 mod misc;
+mod plugin_events;
 mod resources;
+mod scheduled_ticks;
 pub mod task;
 
 pub use components::*;
 pub use events::*;
 pub use misc::*;
+pub use plugin_events::*;
 pub use resources::*;
+pub use scheduled_ticks::*;
 
 // Constants
 /// The number of ticks executed per second.
@@ -0,0 +1,194 @@
+//! Storage of plugin event handlers.
+//!
+//! This lives here rather than in the plugin API so that
+//! subcrates can trigger pre-events without depending on it.
+
+use crate::*;
+use fecs::World;
+use std::any::{Any, TypeId};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// An event to which plugins may subscribe.
+pub trait PluginEvent: Send + Sync + 'static {}
+
+type Handler<E> = Box<dyn Fn(&E, &mut Game, &mut World) + Send + Sync>;
+type PreHandler<E> = Box<dyn Fn(&mut E, &mut Game, &mut World) + Send + Sync>;
+
+/// A `Vec` of handlers, which can be appended to another
+/// one without knowing the type of their event.
+trait HandlerList: Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Appends these handlers to `other`, which must be of the same type.
+    fn append_to(self: Box<Self>, other: &mut dyn HandlerList);
+}
+
+impl<T> HandlerList for Vec<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn append_to(mut self: Box<Self>, other: &mut dyn HandlerList) {
+        other
+            .as_any_mut()
+            .downcast_mut::<Vec<T>>()
+            .unwrap()
+            .append(&mut self);
+    }
+}
+
+/// Maps event types to their handlers.
+type HandlerMap = HashMap<TypeId, Box<dyn HandlerList>>;
+
+/// Resource storing the event handlers registered by plugins.
+///
+/// Handlers must not access this resource themselves,
+/// as it is borrowed while they run.
+#[derive(Default)]
+pub struct PluginEvents {
+    /// Maps event types to a `Vec<Handler<E>>`.
+    handlers: HandlerMap,
+    /// Maps pre-event types to a `Vec<PreHandler<E>>`.
+    pre_handlers: HandlerMap,
+}
+
+impl PluginEvents {
+    /// Adds a handler for events of type `E`.
+    pub fn subscribe<E>(
+        &mut self,
+        handler: impl Fn(&E, &mut Game, &mut World) + Send + Sync + 'static,
+    ) where
+        E: PluginEvent,
+    {
+        self.handlers
+            .entry(TypeId::of::<E>())
+            .or_insert_with(|| Box::new(Vec::<Handler<E>>::new()))
+            .as_any_mut()
+            .downcast_mut::<Vec<Handler<E>>>()
+            .unwrap()
+            .push(Box::new(handler));
+    }
+
+    /// Adds a handler for pre-events of type `E`.
+    pub fn subscribe_pre<E>(
+        &mut self,
+        handler: impl Fn(&mut E, &mut Game, &mut World) + Send + Sync + 'static,
+    ) where
+        E: PluginEvent + Cancellable,
+    {
+        self.pre_handlers
+            .entry(TypeId::of::<E>())
+            .or_insert_with(|| Box::new(Vec::<PreHandler<E>>::new()))
+            .as_any_mut()
+            .downcast_mut::<Vec<PreHandler<E>>>()
+            .unwrap()
+            .push(Box::new(handler));
+    }
+
+    /// Adds the handlers of `other`, after those already
+    /// subscribed to the same events.
+    pub fn merge(&mut self, other: PluginEvents) {
+        merge_handlers(&mut self.handlers, other.handlers);
+        merge_handlers(&mut self.pre_handlers, other.pre_handlers);
+    }
+
+    /// Returns whether any handler is subscribed to the pre-event `E`.
+    pub fn has_pre_handlers<E>(&self) -> bool
+    where
+        E: PluginEvent + Cancellable,
+    {
+        self.pre_handlers.contains_key(&TypeId::of::<E>())
+    }
+
+    /// Calls the handlers of `event`.
+    pub fn trigger<E>(&self, event: &E, game: &mut Game, world: &mut World)
+    where
+        E: PluginEvent,
+    {
+        if let Some(handlers) = self.handlers.get(&TypeId::of::<E>()) {
+            for handler in handlers.as_any().downcast_ref::<Vec<Handler<E>>>().unwrap() {
+                handler(event, game, world);
+            }
+        }
+    }
+
+    /// Calls the handlers of the pre-event `event`, which may
+    /// modify or cancel it. Handlers registered after the one which
+    /// cancelled the event still run, and may allow it again.
+    ///
+    /// Returns whether the action should be performed, i.e.
+    /// `false` if the event was cancelled.
+    pub fn trigger_pre<E>(&self, event: &mut E, game: &mut Game, world: &mut World) -> bool
+    where
+        E: PluginEvent + Cancellable,
+    {
+        if let Some(handlers) = self.pre_handlers.get(&TypeId::of::<E>()) {
+            for handler in handlers
+                .as_any()
+                .downcast_ref::<Vec<PreHandler<E>>>()
+                .unwrap()
+            {
+                handler(event, game, world);
+            }
+        }
+
+        !event.is_cancelled()
+    }
+}
+
+fn merge_handlers(handlers: &mut HandlerMap, other: HandlerMap) {
+    for (event, list) in other {
+        match handlers.entry(event) {
+            Entry::Occupied(mut entry) => list.append_to(entry.get_mut().as_mut()),
+            Entry::Vacant(entry) => {
+                entry.insert(list);
+            }
+        }
+    }
+}
+
+macro_rules! plugin_events {
+    ($($event:ident,)*) => {
+        $(impl PluginEvent for $event {})*
+    }
+}
+
+plugin_events! {
+    BlockUpdateEvent,
+    EntitySpawnEvent,
+    EntityDespawnEvent,
+    EntityDeathEvent,
+    PlayerPreJoinEvent,
+    PlayerJoinEvent,
+    PlayerLeaveEvent,
+    EntityLandEvent,
+    ItemDropEvent,
+    ItemCollectEvent,
+    InventoryUpdateEvent,
+    ItemDamageEvent,
+    WindowOpenEvent,
+    WindowCloseEvent,
+    HealthUpdateEvent,
+    PlayerAnimationEvent,
+    ChatEvent,
+    ChunkCrossEvent,
+    ChunkLoadEvent,
+    ChunkUnloadEvent,
+    WeatherChangeEvent,
+    GamemodeUpdateEvent,
+    BlockBreakPreEvent,
+    BlockPlacePreEvent,
+    ItemDropPreEvent,
+    ChatPreEvent,
+    PlayerMovePreEvent,
+}
@@ -2,7 +2,11 @@ use std::sync::{Arc, RwLock};
 
 pub use crate::game::*;
 pub use crate::task::*;
-pub use feather_server_config::{Ban, BanInfo, Config, ProxyMode};
+pub use feather_server_config::{
+    Ban, BanInfo, Config, Op, OpList, ProxyMode, Whitelist, WhitelistEntry,
+};
 pub type WrappedBanInfo = Arc<RwLock<BanInfo>>;
+pub type WrappedWhitelist = Arc<RwLock<Whitelist>>;
+pub type WrappedOpList = Arc<RwLock<OpList>>;
 
 pub use feather_server_packet_buffer::{PacketBuffer, PacketBuffers};
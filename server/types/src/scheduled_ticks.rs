@@ -0,0 +1,415 @@
+//! Scheduled block and liquid ticks, which let blocks such as
+//! repeaters, fluids and falling blocks update after a delay.
+//!
+//! Ticks are persisted with their chunk, in the `TileTicks`
+//! and `LiquidTicks` tags. Loaded ticks of blocks which no
+//! `ScheduledTickEvent` handler implements yet are not
+//! executed, but saved back unchanged.
+
+use ahash::AHashMap;
+use feather_core::anvil::region::{ScheduledBlockUpdate, ScheduledUpdates};
+use feather_core::blocks::{BlockId, BlockKind};
+use feather_core::util::{BlockPosition, ChunkPosition};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+/// The maximum number of scheduled ticks executed in one game tick,
+/// as in vanilla. Remaining ticks are executed on the following ticks.
+pub const MAX_SCHEDULED_TICKS_PER_TICK: usize = 65_536;
+
+/// The list a scheduled tick belongs to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TickList {
+    /// Saved as `TileTicks`.
+    Block,
+    /// Saved as `LiquidTicks`.
+    Liquid,
+}
+
+/// A tick scheduled for a block.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScheduledTick {
+    pub pos: BlockPosition,
+    /// The block which scheduled the tick. Only its kind
+    /// is compared with the block when the tick is executed.
+    pub block: BlockId,
+    pub list: TickList,
+    /// The value of `Game::tick_count` at which the tick is executed.
+    pub time: u64,
+    /// Ticks with a lower priority are executed first
+    /// when scheduled for the same time.
+    pub priority: i32,
+}
+
+impl ScheduledTick {
+    /// Converts a tick loaded from a chunk, whose delay is
+    /// relative to `time`. Returns `None` if the block is unknown.
+    pub fn from_update(update: &ScheduledBlockUpdate, list: TickList, time: u64) -> Option<Self> {
+        let block = match (list, update.name.as_ref()) {
+            // Liquid ticks store the fluid, which may be flowing.
+            (TickList::Liquid, "minecraft:flowing_water") => BlockId::water(),
+            (TickList::Liquid, "minecraft:flowing_lava") => BlockId::lava(),
+            (_, name) => BlockId::from_identifier(name)?,
+        };
+
+        Some(Self {
+            pos: BlockPosition::new(update.x, update.y, update.z),
+            block,
+            list,
+            // Overdue ticks are executed as soon as possible.
+            time: time + update.ticks_from_now.max(0) as u64,
+            priority: update.priority,
+        })
+    }
+
+    /// Converts the tick to be saved with its chunk,
+    /// with a delay relative to `time`.
+    pub fn to_update(&self, time: u64) -> ScheduledBlockUpdate {
+        let delay = self.time as i64 - time as i64;
+        let ticks_from_now = i32::try_from(delay).unwrap_or(if delay < 0 {
+            i32::min_value()
+        } else {
+            i32::max_value()
+        });
+
+        ScheduledBlockUpdate {
+            name: Cow::Borrowed(self.block.identifier()),
+            ticks_from_now,
+            priority: self.priority,
+            x: self.pos.x,
+            y: self.pos.y,
+            z: self.pos.z,
+        }
+    }
+
+    /// Returns whether a `ScheduledTickEvent` handler implements the
+    /// tick. Only falling blocks are implemented so far; this should
+    /// be extended as handlers for other blocks are added.
+    pub fn is_handled(&self) -> bool {
+        self.list == TickList::Block && self.block.can_fall()
+    }
+
+    fn entry(&self) -> TickEntry {
+        (self.pos, self.block.kind(), self.list)
+    }
+}
+
+/// Order in which ticks are executed: by time, then
+/// by priority, then in the order they were scheduled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct TickKey {
+    time: u64,
+    priority: i32,
+    id: u64,
+}
+
+/// A block can only have one pending tick of each kind in each list.
+type TickEntry = (BlockPosition, BlockKind, TickList);
+
+/// The queue of scheduled ticks in the loaded chunks.
+#[derive(Debug, Default)]
+pub struct ScheduledTicks {
+    queue: BTreeMap<TickKey, ScheduledTick>,
+    /// The pending ticks in each chunk.
+    chunks: AHashMap<ChunkPosition, AHashMap<TickEntry, TickKey>>,
+    /// Ticks loaded with each chunk which are not handled,
+    /// kept unchanged to be saved with the chunk.
+    kept: AHashMap<ChunkPosition, ScheduledUpdates>,
+    next_id: u64,
+}
+
+impl ScheduledTicks {
+    /// Schedules a tick.
+    ///
+    /// Like in vanilla, nothing is scheduled if a tick is already
+    /// pending for the same position, block kind and list;
+    /// returns whether the tick was scheduled.
+    pub fn schedule(&mut self, tick: ScheduledTick) -> bool {
+        let chunk = self.chunks.entry(tick.pos.chunk()).or_default();
+        let entry = tick.entry();
+        if chunk.contains_key(&entry) {
+            return false;
+        }
+
+        let key = TickKey {
+            time: tick.time,
+            priority: tick.priority,
+            id: self.next_id,
+        };
+        self.next_id += 1;
+
+        chunk.insert(entry, key);
+        self.queue.insert(key, tick);
+        true
+    }
+
+    /// Returns whether a tick is pending for the given
+    /// position, block kind and list.
+    pub fn is_scheduled(&self, pos: BlockPosition, kind: BlockKind, list: TickList) -> bool {
+        self.chunks
+            .get(&pos.chunk())
+            .map_or(false, |chunk| chunk.contains_key(&(pos, kind, list)))
+    }
+
+    /// Removes and returns the next tick due at or before `time`.
+    pub fn pop_due(&mut self, time: u64) -> Option<ScheduledTick> {
+        let key = *self.queue.keys().next()?;
+        if key.time > time {
+            return None;
+        }
+
+        let tick = self.queue.remove(&key).unwrap();
+        let chunk = tick.pos.chunk();
+        if let Some(entries) = self.chunks.get_mut(&chunk) {
+            entries.remove(&tick.entry());
+            if entries.is_empty() {
+                self.chunks.remove(&chunk);
+            }
+        }
+        Some(tick)
+    }
+
+    /// Returns whether any ticks are pending in the given chunk.
+    ///
+    /// Kept ticks are not included, as they do not change
+    /// until the chunk is unloaded.
+    pub fn has_ticks_in(&self, chunk: ChunkPosition) -> bool {
+        self.chunks.contains_key(&chunk)
+    }
+
+    /// Removes the pending and kept ticks in a chunk, which
+    /// should be done when it is unloaded.
+    pub fn remove_chunk(&mut self, chunk: ChunkPosition) {
+        if let Some(entries) = self.chunks.remove(&chunk) {
+            for key in entries.values() {
+                self.queue.remove(key);
+            }
+        }
+        self.kept.remove(&chunk);
+    }
+
+    /// Schedules the ticks loaded with a chunk, whose
+    /// delays are relative to `time`.
+    ///
+    /// Ticks which are not handled, including those of
+    /// unknown blocks, are kept to be saved unchanged.
+    pub fn load(&mut self, updates: &ScheduledUpdates, time: u64) {
+        let blocks = updates
+            .blocks
+            .iter()
+            .map(|update| (update, TickList::Block));
+        let liquids = updates
+            .liquids
+            .iter()
+            .map(|update| (update, TickList::Liquid));
+
+        for (update, list) in blocks.chain(liquids) {
+            match ScheduledTick::from_update(update, list, time) {
+                Some(tick) if tick.is_handled() => {
+                    self.schedule(tick);
+                }
+                _ => self.keep(update, list),
+            }
+        }
+    }
+
+    fn keep(&mut self, update: &ScheduledBlockUpdate, list: TickList) {
+        let chunk = BlockPosition::new(update.x, update.y, update.z).chunk();
+        let kept = self.kept.entry(chunk).or_default();
+        match list {
+            TickList::Block => kept.blocks.push(update.clone()),
+            TickList::Liquid => kept.liquids.push(update.clone()),
+        }
+    }
+
+    /// Returns the pending ticks in a chunk in the format in
+    /// which they are saved, with delays relative to `time`,
+    /// following the kept ticks.
+    pub fn save(&self, chunk: ChunkPosition, time: u64) -> ScheduledUpdates {
+        let mut updates = self.kept.get(&chunk).cloned().unwrap_or_default();
+
+        let mut keys: Vec<_> = match self.chunks.get(&chunk) {
+            Some(entries) => entries.values().copied().collect(),
+            None => return updates,
+        };
+        keys.sort_unstable();
+
+        for key in keys {
+            let tick = &self.queue[&key];
+            let list = match tick.list {
+                TickList::Block => &mut updates.blocks,
+                TickList::Liquid => &mut updates.liquids,
+            };
+            list.push(tick.to_update(time));
+        }
+
+        updates
+    }
+
+    /// Returns the number of pending ticks.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(x: i32, block: BlockId, time: u64, priority: i32) -> ScheduledTick {
+        ScheduledTick {
+            pos: BlockPosition::new(x, 64, 0),
+            block,
+            list: TickList::Block,
+            time,
+            priority,
+        }
+    }
+
+    #[test]
+    fn execution_order() {
+        let mut ticks = ScheduledTicks::default();
+        assert!(ticks.schedule(tick(0, BlockId::sand(), 5, 0)));
+        assert!(ticks.schedule(tick(1, BlockId::sand(), 2, 0)));
+        assert!(ticks.schedule(tick(2, BlockId::sand(), 5, -1)));
+        assert!(ticks.schedule(tick(3, BlockId::sand(), 5, 0)));
+
+        assert_eq!(ticks.pop_due(1), None);
+        assert_eq!(ticks.pop_due(4).map(|tick| tick.pos.x), Some(1));
+        assert_eq!(ticks.pop_due(4), None);
+
+        let order: Vec<_> = std::iter::from_fn(|| ticks.pop_due(5))
+            .map(|tick| tick.pos.x)
+            .collect();
+        assert_eq!(order, vec![2, 0, 3]);
+        assert!(ticks.is_empty());
+        assert!(!ticks.has_ticks_in(ChunkPosition::new(0, 0)));
+    }
+
+    #[test]
+    fn duplicates_are_ignored() {
+        let mut ticks = ScheduledTicks::default();
+        let pos = BlockPosition::new(0, 64, 0);
+        assert!(ticks.schedule(tick(0, BlockId::sand(), 5, 0)));
+        assert!(!ticks.schedule(tick(0, BlockId::sand(), 2, 0)));
+        assert!(ticks.is_scheduled(pos, BlockKind::Sand, TickList::Block));
+        assert!(!ticks.is_scheduled(pos, BlockKind::Sand, TickList::Liquid));
+
+        // A different block kind at the same position is a separate tick.
+        assert!(ticks.schedule(tick(0, BlockId::gravel(), 5, 0)));
+        assert_eq!(ticks.len(), 2);
+
+        // Once executed, the tick may be scheduled again.
+        ticks.pop_due(5);
+        assert!(ticks.schedule(tick(0, BlockId::sand(), 7, 0)));
+    }
+
+    #[test]
+    fn remove_chunk() {
+        let mut ticks = ScheduledTicks::default();
+        ticks.schedule(tick(0, BlockId::sand(), 5, 0));
+        ticks.schedule(tick(16, BlockId::sand(), 5, 0));
+
+        ticks.remove_chunk(ChunkPosition::new(0, 0));
+        assert_eq!(ticks.len(), 1);
+        assert!(!ticks.has_ticks_in(ChunkPosition::new(0, 0)));
+        assert_eq!(ticks.pop_due(5).map(|tick| tick.pos.x), Some(16));
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut ticks = ScheduledTicks::default();
+        ticks.schedule(tick(1, BlockId::sand(), 110, 0));
+        ticks.schedule(tick(0, BlockId::gravel(), 102, -1));
+        ticks.schedule(ScheduledTick {
+            list: TickList::Liquid,
+            ..tick(2, BlockId::water(), 105, 0)
+        });
+
+        let updates = ticks.save(ChunkPosition::new(0, 0), 100);
+        assert_eq!(updates.blocks.len(), 2);
+        assert_eq!(updates.blocks[0].name, "minecraft:gravel");
+        assert_eq!(updates.blocks[0].ticks_from_now, 2);
+        assert_eq!(updates.blocks[0].priority, -1);
+        assert_eq!(updates.blocks[1].ticks_from_now, 10);
+        assert_eq!(updates.liquids.len(), 1);
+        assert_eq!(updates.liquids[0].name, "minecraft:water");
+        assert_eq!(updates.liquids[0].x, 2);
+
+        let mut loaded = ScheduledTicks::default();
+        loaded.load(&updates, 0);
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(
+            loaded.pop_due(2).map(|tick| tick.block),
+            Some(BlockId::gravel())
+        );
+        assert_eq!(loaded.pop_due(5), None);
+        assert_eq!(
+            loaded.save(ChunkPosition::new(0, 0), 0).liquids,
+            updates.liquids
+        );
+    }
+
+    #[test]
+    fn unhandled_ticks_are_kept() {
+        let update = |name: &'static str, x| ScheduledBlockUpdate {
+            name: Cow::Borrowed(name),
+            ticks_from_now: 2,
+            priority: -1,
+            x,
+            y: 64,
+            z: 0,
+        };
+        let updates = ScheduledUpdates {
+            blocks: vec![
+                update("minecraft:repeater", 0),
+                update("minecraft:not_a_block", 1),
+                update("minecraft:sand", 2),
+            ],
+            liquids: vec![update("minecraft:flowing_water", 3)],
+        };
+
+        let mut ticks = ScheduledTicks::default();
+        ticks.load(&updates, 0);
+        assert_eq!(ticks.len(), 1);
+        assert_eq!(ticks.pop_due(2).map(|tick| tick.pos.x), Some(2));
+        assert_eq!(ticks.pop_due(100), None);
+
+        // The kept ticks are saved unchanged, even once time has passed.
+        let chunk = ChunkPosition::new(0, 0);
+        assert!(!ticks.has_ticks_in(chunk));
+        let saved = ticks.save(chunk, 100);
+        assert_eq!(saved.blocks, updates.blocks[..2]);
+        assert_eq!(saved.liquids, updates.liquids);
+
+        ticks.remove_chunk(chunk);
+        assert!(ticks.save(chunk, 100).blocks.is_empty());
+    }
+
+    #[test]
+    fn from_update() {
+        let update = ScheduledBlockUpdate {
+            name: Cow::Borrowed("minecraft:flowing_lava"),
+            ticks_from_now: -3,
+            priority: 0,
+            x: 1,
+            y: 2,
+            z: 3,
+        };
+        let tick = ScheduledTick::from_update(&update, TickList::Liquid, 50).unwrap();
+        assert_eq!(tick.block, BlockId::lava());
+        assert_eq!(tick.time, 50);
+        assert_eq!(tick.pos, BlockPosition::new(1, 2, 3));
+
+        let unknown = ScheduledBlockUpdate {
+            name: Cow::Borrowed("minecraft:not_a_block"),
+            ..update
+        };
+        assert!(ScheduledTick::from_update(&unknown, TickList::Block, 50).is_none());
+    }
+}
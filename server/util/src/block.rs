@@ -1,15 +1,16 @@
 //! Assorted functionality relating to blocks, including:
 //! * The block notify system, where a block update "notifies"
-//! adjacent blocks of the update. This is used for spawning
-//! falling blocks, for example.
+//! adjacent blocks of the update. This is used for breaking
+//! unsupported blocks, for example. Falling blocks instead
+//! schedule a block tick, see `FALLING_BLOCK_DELAY`.
 //!
 //! The block notify system works as follows: when a block
 //! is updated, `on_block_update_notify_adjacent` is called,
 //! which checks the blocks adjacent to the updated block.
 //! For each adjacent block, `notify_entity_for_block` is called
 //! which returns an `Option<EntityBuilder>` containing the components
-//! to create for the notify entity. For example, `Some(EntityBuilder::new().with(FallingBlockNotify)`
-//! could be returned for `Sand` and `Gravel` variants.
+//! to create for the notify entity. For example, `Some(EntityBuilder::new().with(BlockNotifySupportedBlock))`
+//! could be returned for blocks which need support, such as torches.
 //!
 //! `on_block_update_notify_adjacent` then creates an entity with those components.
 //! The "notify entity," in this case,
@@ -38,16 +39,15 @@ pub struct BlockNotifyPosition(pub BlockPosition);
 #[derive(Copy, Clone, Debug)]
 pub struct BlockNotifyBlock(pub BlockId);
 
-/// Marker component for block notify entities created for falling
-/// blocks, such as sand and gravel.
-#[derive(Copy, Clone, Debug)]
-pub struct BlockNotifyFallingBlock;
-
-/// Marker component for block notify entities created for falling
-/// blocks, such as sand and gravel.
+/// Marker component for block notify entities created for
+/// blocks which need support, such as torches and flowers.
 #[derive(Copy, Clone, Debug)]
 pub struct BlockNotifySupportedBlock;
 
+/// The delay in ticks, as in vanilla, before a falling
+/// block such as sand or gravel falls after being updated.
+pub const FALLING_BLOCK_DELAY: u64 = 2;
+
 /// Returns an `EntityBuilder` to create the block notify entity for
 /// the given block type.
 fn notify_entity_for_block(block: BlockId, pos: BlockPosition) -> Option<EntityBuilder> {
@@ -56,9 +56,7 @@ fn notify_entity_for_block(block: BlockId, pos: BlockPosition) -> Option<EntityB
         .with(BlockNotifyPosition(pos))
         .with(BlockNotifyBlock(block));
 
-    if block.can_fall() {
-        Some(builder.with(BlockNotifyFallingBlock))
-    } else if block.support_type().is_some() {
+    if block.support_type().is_some() {
         Some(builder.with(BlockNotifySupportedBlock))
     } else {
         None
@@ -66,29 +64,29 @@ fn notify_entity_for_block(block: BlockId, pos: BlockPosition) -> Option<EntityB
 }
 
 /// When a block is updated, spawns notify entities
-/// for adjacent blocks.
+/// for adjacent blocks and schedules a tick for
+/// adjacent falling blocks.
 #[fecs::event_handler]
 pub fn on_block_update_notify_adjacent(
     event: &BlockUpdateEvent,
     game: &mut Game,
     world: &mut World,
 ) {
-    adjacent_blocks(event.pos)
+    for adjacent_pos in adjacent_blocks(event.pos)
         .into_iter()
         .chain(iter::once(event.pos))
-        .filter_map(|adjacent_pos| {
-            if let Some(adjacent_block) = game.block_at(adjacent_pos) {
-                Some((adjacent_block, adjacent_pos))
-            } else {
-                None
-            }
-        })
-        .filter_map(|(adjacent_block, adjacent_pos)| {
-            notify_entity_for_block(adjacent_block, adjacent_pos)
-        })
-        .for_each(|builder| {
+    {
+        let adjacent_block = match game.block_at(adjacent_pos) {
+            Some(block) => block,
+            None => continue,
+        };
+
+        if adjacent_block.can_fall() {
+            game.schedule_block_tick(adjacent_pos, adjacent_block, FALLING_BLOCK_DELAY, 0);
+        } else if let Some(builder) = notify_entity_for_block(adjacent_block, adjacent_pos) {
             builder.build().spawn_in(world);
-        })
+        }
+    }
 }
 
 /// This checks whether a block of a specific BlockId
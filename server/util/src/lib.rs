@@ -14,6 +14,8 @@ mod time;
 pub use time::*;
 mod load;
 pub use load::*;
+mod scheduled_ticks;
+pub use scheduled_ticks::*;
 
 use feather_server_types::{Game, Uuid};
 use fecs::{Entity, World};
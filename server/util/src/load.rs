@@ -4,9 +4,15 @@ use feather_core::anvil::{
     entity::{EntityData, EntityDataKind},
 };
 use feather_server_types::{
-    BlockEntityLoaderFn, BlockEntityLoaderRegistration, EntityLoaderFn, EntityLoaderRegistration,
+    BlockEntityLoaderFn, BlockEntityLoaderRegistration, ComponentSerializer, EntityLoaderFn,
+    EntityLoaderRegistration, Game,
 };
-use fecs::EntityBuilder;
+use fecs::{EntityBuilder, EntityRef};
+
+/// Component storing the data of an entity which the server
+/// cannot load, such as an entity of an unknown kind. The entity
+/// is not simulated, but it is saved back unchanged.
+pub struct PreservedEntity(pub EntityData);
 
 /// Stores state for loading entities.
 pub struct EntityLoader {
@@ -43,10 +49,18 @@ impl EntityLoader {
 impl EntityLoader {
     /// Converts an `EntityData` into an `EntityBuilder`
     /// ready for spawning in a `World`.
-    pub fn load(&self, data: EntityData) -> Option<anyhow::Result<EntityBuilder>> {
-        self.loaders
-            .get(&EntityDataKind::from(&data))
-            .map(|loader| loader(data))
+    ///
+    /// Entities without a loader, or which their loader
+    /// fails to load, are kept as `PreservedEntity`s. An error
+    /// is only returned if the entity has no valid position.
+    pub fn load(&self, data: EntityData) -> anyhow::Result<EntityBuilder> {
+        match self.loaders.get(&EntityDataKind::from(&data)) {
+            Some(loader) => loader(data.clone()).or_else(|e| {
+                log::warn!("Failed to load entity, keeping it unchanged: {}", e);
+                preserve(data)
+            }),
+            None => preserve(data),
+        }
     }
 
     /// Converts a `BlockEntityData` into an `EntityBuilder`
@@ -57,3 +71,15 @@ impl EntityLoader {
             .map(|loader| loader(data))
     }
 }
+
+fn preserve(data: EntityData) -> anyhow::Result<EntityBuilder> {
+    let pos = data.read_position()?;
+    Ok(EntityBuilder::new()
+        .with(pos)
+        .with(PreservedEntity(data))
+        .with(ComponentSerializer(&serialize_preserved)))
+}
+
+fn serialize_preserved(_game: &Game, accessor: &EntityRef) -> EntityData {
+    accessor.get::<PreservedEntity>().0.clone()
+}
@@ -0,0 +1,46 @@
+//! Executes scheduled block and liquid ticks.
+
+use feather_server_types::{Game, ScheduledTickEvent, MAX_SCHEDULED_TICKS_PER_TICK};
+use fecs::World;
+
+/// System which executes the scheduled ticks due this tick,
+/// triggering a `ScheduledTickEvent` for each tick whose
+/// block has not changed kind since it was scheduled.
+#[fecs::system]
+pub fn run_scheduled_ticks(game: &mut Game, world: &mut World) {
+    let time = game.tick_count;
+
+    // Handlers may schedule new ticks, which are
+    // only executed on a later tick.
+    let mut due = Vec::new();
+    while due.len() < MAX_SCHEDULED_TICKS_PER_TICK {
+        match game.scheduled_ticks.pop_due(time) {
+            Some(tick) => due.push(tick),
+            None => break,
+        }
+    }
+
+    for tick in due {
+        // The chunk no longer contains the tick, so it has to be
+        // saved even if the tick does not change any block.
+        // Otherwise, the tick would be executed again
+        // every time the chunk is loaded.
+        if let Some(mut chunk) = game.chunk_map.chunk_at_mut(tick.pos.chunk()) {
+            chunk.set_modified();
+        }
+
+        let block = match game.block_at(tick.pos) {
+            Some(block) if block.kind() == tick.block.kind() => block,
+            _ => continue,
+        };
+
+        game.handle(
+            world,
+            ScheduledTickEvent {
+                pos: tick.pos,
+                block,
+                list: tick.list,
+            },
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! Host functions imported by plugins.
+
+use feather_core::blocks::BlockId;
+use feather_core::text::Text;
+use feather_core::util::BlockPosition;
+use feather_server_entity::spawning;
+use feather_server_types::{
+    BlockUpdateCause, ChatEvent, ChatPosition, EntitySpawnEvent, Game, MessageReceiver, NetworkId,
+    MAX_OP_LEVEL,
+};
+use fecs::{Entity, IntoQuery, Read, World};
+use std::convert::TryFrom;
+use wasmtime::{Caller, Extern, Linker, StoreLimits, Trap};
+
+/// Name of the module from which plugins import host functions.
+const MODULE: &str = "feather";
+
+/// Maximum length of a string passed to a host function.
+const MAX_STRING_LEN: i32 = 32 * 1024;
+
+/// Data stored alongside each plugin instance.
+pub struct HostState {
+    pub name: String,
+    pub limiter: StoreLimits,
+    /// Set while the plugin is being called.
+    pub call: Option<CallState>,
+    /// Whether the plugin is being enabled,
+    /// which is when it may register commands.
+    pub enabling: bool,
+    /// Names and operator levels of the registered commands.
+    pub commands: Vec<(String, u8)>,
+}
+
+impl HostState {
+    pub fn new(name: String, limiter: StoreLimits) -> Self {
+        Self {
+            name,
+            limiter,
+            call: None,
+            enabling: false,
+            commands: Vec::new(),
+        }
+    }
+}
+
+/// Access to the game state during a call into a plugin.
+///
+/// Like `LifetimelessMut` in the commands crate, this erases the
+/// lifetimes of the borrows, which cannot be stored in the `Store`.
+/// `WasmPlugin::with_call` sets it only for the duration of the call,
+/// during which the borrows it was created from are not used.
+#[derive(Copy, Clone)]
+pub struct CallState {
+    game: *mut Game,
+    world: *mut World,
+    sender: Option<Entity>,
+}
+
+impl CallState {
+    pub fn new(game: &mut Game, world: &mut World, sender: Option<Entity>) -> Self {
+        Self {
+            game,
+            world,
+            sender,
+        }
+    }
+}
+
+// The pointers are only dereferenced on the server
+// thread, during the call which set them.
+unsafe impl Send for HostState {}
+
+/// Adds the host functions to `linker`.
+pub fn add_to_linker(linker: &mut Linker<HostState>) -> anyhow::Result<()> {
+    linker.func_wrap(MODULE, "log", log)?;
+    linker.func_wrap(MODULE, "broadcast", broadcast)?;
+    linker.func_wrap(MODULE, "send_message", send_message)?;
+    linker.func_wrap(MODULE, "reply", reply)?;
+    linker.func_wrap(MODULE, "block_at", block_at)?;
+    linker.func_wrap(MODULE, "set_block_at", set_block_at)?;
+    linker.func_wrap(MODULE, "spawn_entity", spawn_entity)?;
+    linker.func_wrap(MODULE, "register_command", register_command)?;
+    Ok(())
+}
+
+fn log(mut caller: Caller<'_, HostState>, msg: i32, msg_len: i32) -> Result<(), Trap> {
+    let msg = read_str(&mut caller, msg, msg_len)?;
+    log::info!("[{}] {}", caller.data().name, msg);
+    Ok(())
+}
+
+fn broadcast(mut caller: Caller<'_, HostState>, msg: i32, msg_len: i32) -> Result<(), Trap> {
+    let msg = read_str(&mut caller, msg, msg_len)?;
+    with_game(&caller, |game, world, _| {
+        game.handle(
+            world,
+            ChatEvent {
+                message: Text::from(msg).into(),
+                position: ChatPosition::Chat,
+            },
+        );
+    })
+}
+
+fn send_message(
+    mut caller: Caller<'_, HostState>,
+    entity: i32,
+    msg: i32,
+    msg_len: i32,
+) -> Result<i32, Trap> {
+    let msg = read_str(&mut caller, msg, msg_len)?;
+    with_game(&caller, |_, world, _| {
+        let entity = find_entity(world, entity);
+        i32::from(entity.map_or(false, |entity| send(world, entity, msg)))
+    })
+}
+
+fn reply(mut caller: Caller<'_, HostState>, msg: i32, msg_len: i32) -> Result<i32, Trap> {
+    let msg = read_str(&mut caller, msg, msg_len)?;
+    with_game(&caller, |_, world, sender| {
+        i32::from(sender.map_or(false, |sender| send(world, sender, msg)))
+    })
+}
+
+fn block_at(caller: Caller<'_, HostState>, x: i32, y: i32, z: i32) -> Result<i32, Trap> {
+    with_game(&caller, |game, _, _| {
+        game.block_at(BlockPosition::new(x, y, z))
+            .map_or(-1, |block| i32::from(block.vanilla_id()))
+    })
+}
+
+fn set_block_at(
+    caller: Caller<'_, HostState>,
+    x: i32,
+    y: i32,
+    z: i32,
+    block: i32,
+) -> Result<i32, Trap> {
+    let block = block_from_id(block).ok_or_else(|| Trap::new("invalid block ID"))?;
+    with_game(&caller, |game, world, _| {
+        let pos = BlockPosition::new(x, y, z);
+        i32::from(game.set_block_at(world, pos, block, BlockUpdateCause::Unknown))
+    })
+}
+
+fn spawn_entity(
+    mut caller: Caller<'_, HostState>,
+    kind: i32,
+    kind_len: i32,
+    x: f64,
+    y: f64,
+    z: f64,
+) -> Result<i32, Trap> {
+    let kind = read_str(&mut caller, kind, kind_len)?;
+    with_game(&caller, |game, world, _| {
+        let pos = position!(x, y, z);
+        let builder = match spawning::create(&kind) {
+            Some(builder) => builder,
+            None => return -1,
+        };
+        if game.chunk_map.chunk_at(pos.chunk()).is_none() {
+            return -1;
+        }
+
+        let entity = builder.with(pos).build().spawn_in(world);
+        game.handle(world, EntitySpawnEvent { entity });
+        world.try_get::<NetworkId>(entity).map_or(-1, |id| id.0)
+    })
+}
+
+fn register_command(
+    mut caller: Caller<'_, HostState>,
+    name: i32,
+    name_len: i32,
+    level: i32,
+) -> Result<i32, Trap> {
+    let name = read_str(&mut caller, name, name_len)?;
+    let state = caller.data_mut();
+    if !state.enabling {
+        return Err(Trap::new(
+            "commands may only be registered in `feather_enable`",
+        ));
+    }
+
+    let level = level.max(0).min(i32::from(MAX_OP_LEVEL)) as u8;
+    state.commands.push((name, level));
+    Ok(state.commands.len() as i32 - 1)
+}
+
+/// Runs `f` with the game state of the current call.
+fn with_game<R>(
+    caller: &Caller<'_, HostState>,
+    f: impl FnOnce(&mut Game, &mut World, Option<Entity>) -> R,
+) -> Result<R, Trap> {
+    let call = caller
+        .data()
+        .call
+        .ok_or_else(|| Trap::new("host function called outside of a call"))?;
+
+    // SAFETY: see `CallState`.
+    let (game, world) = unsafe { (&mut *call.game, &mut *call.world) };
+    Ok(f(game, world, call.sender))
+}
+
+/// Reads a UTF-8 string from the plugin's memory.
+fn read_str(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> Result<String, Trap> {
+    if len < 0 || len > MAX_STRING_LEN {
+        return Err(Trap::new("invalid string length"));
+    }
+
+    let memory = match caller.get_export("memory") {
+        Some(Extern::Memory(memory)) => memory,
+        _ => return Err(Trap::new("plugin does not export `memory`")),
+    };
+    let mut buf = vec![0; len as usize];
+    memory
+        .read(&*caller, ptr as u32 as usize, &mut buf)
+        .map_err(|_| Trap::new("string out of bounds"))?;
+
+    String::from_utf8(buf).map_err(|_| Trap::new("string is not valid UTF-8"))
+}
+
+/// Returns the entity with the given network ID.
+fn find_entity(world: &World, id: i32) -> Option<Entity> {
+    <Read<NetworkId>>::query()
+        .iter_entities(world.inner())
+        .find(|(_, network_id)| network_id.0 == id)
+        .map(|(entity, _)| entity)
+}
+
+/// Sends a message to an entity, returning whether it can receive messages.
+fn send(world: &mut World, entity: Entity, msg: String) -> bool {
+    match world.try_get_mut::<MessageReceiver>(entity) {
+        Some(mut receiver) => {
+            receiver.send(msg);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Returns the block with the given vanilla ID, if valid.
+fn block_from_id(id: i32) -> Option<BlockId> {
+    let id = u16::try_from(id).ok()?;
+    // `from_vanilla_id` maps unknown IDs to air
+    // and panics on the highest ID.
+    if id == u16::max_value() {
+        return None;
+    }
+    let block = BlockId::from_vanilla_id(id);
+    if block.vanilla_id() == id {
+        Some(block)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_from_id() {
+        assert_eq!(block_from_id(0), Some(BlockId::air()));
+        assert_eq!(block_from_id(1), Some(BlockId::from_vanilla_id(1)));
+        assert_eq!(block_from_id(-1), None);
+        assert_eq!(block_from_id(60_000), None);
+        assert_eq!(block_from_id(65_535), None);
+        assert_eq!(block_from_id(1 << 20), None);
+    }
+}
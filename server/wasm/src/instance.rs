@@ -0,0 +1,317 @@
+//! Instances of WebAssembly plugins.
+
+use crate::host::{CallState, HostState};
+use anyhow::Context;
+use feather_server_config::Plugins as PluginLimits;
+use feather_server_types::Game;
+use fecs::{Entity, World};
+use wasmtime::{Config, Engine, Instance, Linker, Module, Store, StoreLimitsBuilder, Val};
+
+/// Maximum number of elements in a plugin's table.
+const MAX_TABLE_ELEMENTS: u32 = 10_000;
+
+/// Creates the engine used to compile plugins,
+/// which counts the fuel used by each call.
+pub fn create_engine() -> anyhow::Result<Engine> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    Engine::new(&config)
+}
+
+/// A loaded plugin with its own store and memory.
+pub struct WasmPlugin {
+    store: Store<HostState>,
+    instance: Instance,
+    /// Fuel available to each call.
+    fuel: u64,
+}
+
+impl WasmPlugin {
+    /// Instantiates a plugin module.
+    pub fn new(
+        linker: &Linker<HostState>,
+        module: &Module,
+        name: String,
+        limits: &PluginLimits,
+    ) -> anyhow::Result<Self> {
+        let limiter = StoreLimitsBuilder::new()
+            .memory_size(limits.max_memory_mib as usize * 1024 * 1024)
+            .table_elements(MAX_TABLE_ELEMENTS)
+            .instances(1)
+            .memories(1)
+            .tables(1)
+            .build();
+        let mut store = Store::new(module.engine(), HostState::new(name, limiter));
+        store.limiter(|state| &mut state.limiter);
+
+        // Fuel for the module's start function, if any.
+        store.add_fuel(limits.fuel)?;
+        let instance = linker.instantiate(&mut store, module)?;
+
+        Ok(Self {
+            store,
+            instance,
+            fuel: limits.fuel,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.store.data().name
+    }
+
+    /// Returns the names and operator levels of the commands
+    /// registered by the plugin, indexed by their IDs.
+    pub fn commands(&self) -> &[(String, u8)] {
+        &self.store.data().commands
+    }
+
+    /// Calls `feather_enable`, during which the plugin may register commands.
+    pub fn enable(&mut self, game: &mut Game, world: &mut World) -> anyhow::Result<()> {
+        self.store.data_mut().enabling = true;
+        let result = self.call(game, world, None, "feather_enable", &[]);
+        self.store.data_mut().enabling = false;
+        result
+    }
+
+    /// Calls the exported function `name`, if the plugin exports it.
+    ///
+    /// The function may access `game` and `world` through
+    /// host functions. `sender` is the sender of the command
+    /// being handled, if any.
+    pub fn call(
+        &mut self,
+        game: &mut Game,
+        world: &mut World,
+        sender: Option<Entity>,
+        name: &str,
+        params: &[Val],
+    ) -> anyhow::Result<()> {
+        self.with_call(game, world, sender, |store, instance| {
+            if let Some(func) = instance.get_func(&mut *store, name) {
+                func.call(&mut *store, params, &mut [])?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Calls `feather_on_command` to run the command with the given ID.
+    pub fn call_command(
+        &mut self,
+        game: &mut Game,
+        world: &mut World,
+        sender: Entity,
+        command: i32,
+        sender_id: i32,
+        args: &str,
+    ) -> anyhow::Result<()> {
+        self.with_call(game, world, Some(sender), |store, instance| {
+            let (args, args_len) = write_str(store, instance, args)?;
+            let func = instance
+                .get_func(&mut *store, "feather_on_command")
+                .context("plugin does not export `feather_on_command`")?;
+            func.call(
+                &mut *store,
+                &[
+                    Val::I32(command),
+                    Val::I32(sender_id),
+                    Val::I32(args),
+                    Val::I32(args_len),
+                ],
+                &mut [],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Runs `f` with the plugin's fuel refilled and with
+    /// access to `game` and `world` from host functions.
+    fn with_call<R>(
+        &mut self,
+        game: &mut Game,
+        world: &mut World,
+        sender: Option<Entity>,
+        f: impl FnOnce(&mut Store<HostState>, &Instance) -> anyhow::Result<R>,
+    ) -> anyhow::Result<R> {
+        let remaining = self.store.consume_fuel(0)?;
+        self.store.add_fuel(self.fuel.saturating_sub(remaining))?;
+
+        self.store.data_mut().call = Some(CallState::new(game, world, sender));
+        let result = f(&mut self.store, &self.instance);
+        self.store.data_mut().call = None;
+
+        result
+    }
+}
+
+/// Copies a string into memory allocated by the
+/// plugin's `feather_alloc`, returning its pointer and length.
+fn write_str(
+    store: &mut Store<HostState>,
+    instance: &Instance,
+    s: &str,
+) -> anyhow::Result<(i32, i32)> {
+    let alloc = instance
+        .get_func(&mut *store, "feather_alloc")
+        .context("plugin does not export `feather_alloc`")?;
+    let memory = instance
+        .get_memory(&mut *store, "memory")
+        .context("plugin does not export `memory`")?;
+
+    let mut ptr = [Val::I32(0)];
+    alloc.call(&mut *store, &[Val::I32(s.len() as i32)], &mut ptr)?;
+    let ptr = ptr[0]
+        .i32()
+        .context("`feather_alloc` did not return a pointer")?;
+
+    memory.write(&mut *store, ptr as u32 as usize, s.as_bytes())?;
+    Ok((ptr, s.len() as i32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host;
+    use feather_core::blocks::BlockId;
+    use feather_core::util::BlockPosition;
+    use feather_test_framework::Test;
+
+    fn load(wat: &str, limits: &PluginLimits) -> anyhow::Result<WasmPlugin> {
+        let engine = create_engine()?;
+        let mut linker = Linker::new(&engine);
+        host::add_to_linker(&mut linker)?;
+        let module = Module::new(&engine, wat)?;
+        WasmPlugin::new(&linker, &module, String::from("test"), limits)
+    }
+
+    #[test]
+    fn set_block_at() {
+        let mut test = Test::new();
+        let mut plugin = load(
+            r#"(module
+                (import "feather" "set_block_at" (func $set_block_at (param i32 i32 i32 i32) (result i32)))
+                (memory (export "memory") 1)
+                (func (export "feather_enable")
+                    (drop (call $set_block_at (i32.const 1) (i32.const 64) (i32.const 2) (i32.const 1)))))"#,
+            &PluginLimits::default(),
+        )
+        .unwrap();
+
+        plugin.enable(&mut test.game, &mut test.world).unwrap();
+        assert_eq!(
+            test.game.block_at(BlockPosition::new(1, 64, 2)),
+            Some(BlockId::from_vanilla_id(1))
+        );
+    }
+
+    #[test]
+    fn register_command() {
+        let mut test = Test::new();
+        let mut plugin = load(
+            r#"(module
+                (import "feather" "register_command" (func $register_command (param i32 i32 i32) (result i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "heal")
+                (func (export "feather_enable")
+                    (drop (call $register_command (i32.const 0) (i32.const 4) (i32.const 2))))
+                (func (export "feather_disable")
+                    (drop (call $register_command (i32.const 0) (i32.const 4) (i32.const 2)))))"#,
+            &PluginLimits::default(),
+        )
+        .unwrap();
+
+        plugin.enable(&mut test.game, &mut test.world).unwrap();
+        assert_eq!(plugin.commands(), &[(String::from("heal"), 2)]);
+
+        // Commands may only be registered while enabling.
+        assert!(plugin
+            .call(
+                &mut test.game,
+                &mut test.world,
+                None,
+                "feather_disable",
+                &[]
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn fuel_limit() {
+        let mut test = Test::new();
+        let limits = PluginLimits {
+            fuel: 1000,
+            ..Default::default()
+        };
+        let mut plugin = load(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "spin") (param $n i32)
+                    (loop $spin
+                        (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+                        (br_if $spin (local.get $n))))
+                (func (export "feather_enable") (loop br 0)))"#,
+            &limits,
+        )
+        .unwrap();
+
+        assert!(plugin.enable(&mut test.game, &mut test.world).is_err());
+
+        // Fuel is refilled before each call.
+        for _ in 0..2 {
+            plugin
+                .call(
+                    &mut test.game,
+                    &mut test.world,
+                    None,
+                    "spin",
+                    &[Val::I32(100)],
+                )
+                .unwrap();
+        }
+        assert!(plugin
+            .call(
+                &mut test.game,
+                &mut test.world,
+                None,
+                "spin",
+                &[Val::I32(1000)]
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn memory_limit() {
+        let limits = PluginLimits {
+            max_memory_mib: 1,
+            ..Default::default()
+        };
+        assert!(load(r#"(module (memory (export "memory") 16))"#, &limits).is_ok());
+        assert!(load(r#"(module (memory (export "memory") 17))"#, &limits).is_err());
+    }
+
+    #[test]
+    fn table_limit() {
+        let mut test = Test::new();
+        let grow = |elements: u32| {
+            format!(
+                r#"(module
+                    (table $table 1 funcref)
+                    (func (export "feather_enable")
+                        (if (i32.eq (table.grow $table (ref.null func) (i32.const {})) (i32.const -1))
+                            (then unreachable))))"#,
+                elements
+            )
+        };
+
+        let mut plugin = load(&grow(100), &PluginLimits::default()).unwrap();
+        assert!(plugin.enable(&mut test.game, &mut test.world).is_ok());
+
+        let mut plugin = load(&grow(MAX_TABLE_ELEMENTS), &PluginLimits::default()).unwrap();
+        assert!(plugin.enable(&mut test.game, &mut test.world).is_err());
+
+        assert!(load(
+            r#"(module (table 1 funcref) (table 1 funcref))"#,
+            &PluginLimits::default()
+        )
+        .is_err());
+    }
+}
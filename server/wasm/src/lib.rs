@@ -0,0 +1,289 @@
+//! Host for plugins compiled to WebAssembly, which are loaded
+//! at startup from `.wasm` files in the `plugins` directory.
+//!
+//! Each plugin runs in its own sandbox: it can only affect the server
+//! through the host functions below, its memory is limited to
+//! `max_memory_mib` and each call into it may use at most `fuel`
+//! units of fuel (see the `plugins` section of `feather.toml`).
+//! A plugin which exceeds its fuel traps, and the call is aborted.
+//!
+//! # Exports
+//! A plugin must export its `memory`. The following functions
+//! are called if the plugin exports them:
+//! * `feather_enable()` when the server starts. Commands
+//! may only be registered during this call.
+//! * `feather_disable()` when the server shuts down.
+//! * `feather_alloc(len: i32) -> i32`, which must return a pointer
+//! to `len` bytes of memory. Required to receive commands, as the
+//! host writes the command arguments there.
+//! * `feather_on_command(command: i32, sender: i32, args: i32, args_len: i32)`
+//! when a command registered by the plugin is run. `command`
+//! is the ID returned by `register_command`.
+//! * `feather_on_player_join(player: i32)` and
+//! `feather_on_player_leave(player: i32)`.
+//! * `feather_on_block_update(x: i32, y: i32, z: i32, old: i32, new: i32)`
+//! when a block changes, except through the plugin's own calls.
+//!
+//! Entities are identified by their network ID; senders without
+//! one, such as the console, are passed as `-1`. Blocks are
+//! identified by their vanilla state ID. Strings are passed
+//! as a pointer and length of UTF-8 bytes.
+//!
+//! # Imports
+//! The host provides these functions in the `feather` module:
+//! * `log(msg: i32, msg_len: i32)` logs a message.
+//! * `broadcast(msg: i32, msg_len: i32)` sends a chat message to all players.
+//! * `send_message(entity: i32, msg: i32, msg_len: i32) -> i32`
+//! sends a message to an entity, returning `1` if it was sent.
+//! * `reply(msg: i32, msg_len: i32) -> i32` sends a message to
+//! the sender of the command being handled.
+//! * `block_at(x: i32, y: i32, z: i32) -> i32` returns the
+//! block at a position, or `-1` if its chunk is not loaded.
+//! * `set_block_at(x: i32, y: i32, z: i32, block: i32) -> i32`
+//! sets a block, returning `1` on success.
+//! * `spawn_entity(kind: i32, kind_len: i32, x: f64, y: f64, z: f64) -> i32`
+//! spawns a mob, such as `minecraft:zombie`, returning its
+//! network ID or `-1` on failure.
+//! * `register_command(name: i32, name_len: i32, level: i32) -> i32`
+//! registers a command, requiring the given operator level,
+//! and returns its ID.
+
+#[macro_use]
+extern crate feather_core;
+
+mod host;
+mod instance;
+
+use feather_api::{Plugin, PluginContext};
+use feather_server_commands::CommandCtx;
+use feather_server_types::{
+    BlockUpdateEvent, Game, NetworkId, Permission, PlayerJoinEvent, PlayerLeaveEvent,
+};
+use fecs::{Entity, World};
+use instance::WasmPlugin;
+use parking_lot::Mutex;
+use std::collections::HashSet;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Arc;
+use wasmtime::{Engine, Linker, Module, Val};
+
+/// Directory from which plugins are loaded.
+pub const PLUGINS_DIR: &str = "plugins";
+
+type SharedPlugin = Arc<Mutex<WasmPlugin>>;
+
+/// Native plugin which loads and runs the WebAssembly plugins.
+pub struct WasmPlugins {
+    dir: PathBuf,
+    plugins: Vec<SharedPlugin>,
+}
+
+impl WasmPlugins {
+    /// Creates a host loading plugins from `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            plugins: Vec::new(),
+        }
+    }
+
+    /// Returns the paths of the `.wasm` files in the plugin
+    /// directory, which need not exist.
+    fn plugin_paths(&self) -> anyhow::Result<Vec<PathBuf>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut paths = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().map_or(false, |ext| ext == "wasm") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+}
+
+impl Plugin for WasmPlugins {
+    fn name(&self) -> &str {
+        "wasm"
+    }
+
+    fn version(&self) -> &str {
+        env!("CARGO_PKG_VERSION")
+    }
+
+    fn enable(&mut self, ctx: &mut PluginContext) -> anyhow::Result<()> {
+        let limits = ctx.game.config.plugins.clone();
+        let engine = instance::create_engine()?;
+        let mut linker = Linker::new(&engine);
+        host::add_to_linker(&mut linker)?;
+
+        for path in self.plugin_paths()? {
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            log::info!("Loading WebAssembly plugin {}", name);
+            let plugin = Module::from_file(&engine, &path)
+                .and_then(|module| WasmPlugin::new(&linker, &module, name.clone(), &limits))
+                .and_then(|mut plugin| {
+                    plugin.enable(ctx.game, ctx.world)?;
+                    Ok(plugin)
+                });
+            let plugin = match plugin {
+                Ok(plugin) => Arc::new(Mutex::new(plugin)),
+                Err(e) => {
+                    log::error!("Failed to load WebAssembly plugin {}: {:?}", name, e);
+                    continue;
+                }
+            };
+
+            if let Err(e) = register_commands(ctx, &plugin) {
+                log::error!(
+                    "Failed to register the commands of WebAssembly plugin {}: {:?}",
+                    name,
+                    e
+                );
+                disable_plugin(ctx.game, ctx.world, &plugin);
+                continue;
+            }
+            self.plugins.push(plugin);
+        }
+
+        subscribe_events(ctx, &self.plugins);
+        Ok(())
+    }
+
+    fn disable(&mut self, game: &mut Game, world: &mut World) {
+        for plugin in self.plugins.drain(..) {
+            disable_plugin(game, world, &plugin);
+        }
+    }
+}
+
+/// Calls the `feather_disable` export of a plugin.
+fn disable_plugin(game: &mut Game, world: &mut World, plugin: &SharedPlugin) {
+    let mut plugin = plugin.lock();
+    if let Err(e) = plugin.call(game, world, None, "feather_disable", &[]) {
+        log::warn!("Failed to disable plugin {}: {:?}", plugin.name(), e);
+    }
+}
+
+/// Registers the commands a plugin registered while being enabled.
+/// If one of them cannot be registered, none of them are.
+fn register_commands(ctx: &mut PluginContext, plugin: &SharedPlugin) -> anyhow::Result<()> {
+    let (name, commands) = {
+        let plugin = plugin.lock();
+        (plugin.name().to_owned(), plugin.commands().to_vec())
+    };
+
+    let mut names = HashSet::new();
+    for (command, _) in &commands {
+        if !names.insert(command.as_str()) {
+            anyhow::bail!("command `{}` is registered twice", command);
+        }
+        ctx.check_raw_command(command)?;
+    }
+
+    for (id, (command, level)) in commands.into_iter().enumerate() {
+        // Permission nodes are static, but commands are
+        // only registered once, so leaking them is fine.
+        let node = Box::leak(format!("{}.command.{}", name, command).into_boxed_str());
+        let permission = Permission::new(node, level);
+
+        let plugin = Arc::clone(plugin);
+        ctx.register_raw_command(
+            &command,
+            permission,
+            Box::new(move |ctx: &mut CommandCtx, args: &str| {
+                let sender = ctx.sender;
+                let sender_id = network_id(&ctx.world, sender);
+                let mut plugin = plugin
+                    .try_lock()
+                    .ok_or_else(|| anyhow::anyhow!("The plugin is busy."))?;
+                plugin.call_command(
+                    &mut ctx.game,
+                    &mut ctx.world,
+                    sender,
+                    id as i32,
+                    sender_id,
+                    args,
+                )?;
+                Ok(None)
+            }),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Forwards events to the plugins which export a handler for them.
+fn subscribe_events(ctx: &mut PluginContext, plugins: &[SharedPlugin]) {
+    let handlers = plugins.to_vec();
+    ctx.on(move |event: &PlayerJoinEvent, game, world| {
+        let player = network_id(world, event.player);
+        call_all(
+            &handlers,
+            game,
+            world,
+            "feather_on_player_join",
+            &[Val::I32(player)],
+        );
+    });
+
+    let handlers = plugins.to_vec();
+    ctx.on(move |event: &PlayerLeaveEvent, game, world| {
+        let player = network_id(world, event.player);
+        call_all(
+            &handlers,
+            game,
+            world,
+            "feather_on_player_leave",
+            &[Val::I32(player)],
+        );
+    });
+
+    let handlers = plugins.to_vec();
+    ctx.on(move |event: &BlockUpdateEvent, game, world| {
+        let params = [
+            Val::I32(event.pos.x),
+            Val::I32(event.pos.y),
+            Val::I32(event.pos.z),
+            Val::I32(i32::from(event.old.vanilla_id())),
+            Val::I32(i32::from(event.new.vanilla_id())),
+        ];
+        call_all(&handlers, game, world, "feather_on_block_update", &params);
+    });
+}
+
+/// Calls an exported function of each plugin, skipping plugins
+/// which are already running, i.e. whose own host calls
+/// triggered the event.
+fn call_all(
+    plugins: &[SharedPlugin],
+    game: &mut Game,
+    world: &mut World,
+    name: &str,
+    params: &[Val],
+) {
+    for plugin in plugins {
+        if let Some(mut plugin) = plugin.try_lock() {
+            if let Err(e) = plugin.call(game, world, None, name, params) {
+                log::warn!("Plugin {} failed to run `{}`: {:?}", plugin.name(), name, e);
+            }
+        }
+    }
+}
+
+/// Returns the network ID of an entity, or -1 if it has none.
+fn network_id(world: &World, entity: Entity) -> i32 {
+    world.try_get::<NetworkId>(entity).map_or(-1, |id| id.0)
+}